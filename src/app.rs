@@ -5,30 +5,34 @@ use std::sync::{
     atomic::{AtomicBool, Ordering}};
 use std::rc::Rc;
 use std::cell::RefCell;
+use std::collections::HashMap;
 
 use crossterm::event::{self, poll, Event, KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{backend::CrosstermBackend, Terminal};
 
-use crate::numeric::calculator::Calculator;
+use crate::numeric::calculator::{Calculator, FormulaCache, LARGE_SHEET_CELLS};
+use crate::util::{display_width, letters_from_col};
 use crate::transaction::clipboard::Clipboard;
 use crate::mode::command::{Command, CommandHandler};
-use crate::mode::insert::InsertHandler;
+use crate::mode::insert::{InsertHandler, TabMove};
 use crate::input::{
     is_escape, KeyBuffer, KeyBufferResult, KeyResult, NavigationHandler
 };
 use crate::mode::search::SearchHandler;
 use crate::mode::Mode;
-use crate::plugin::PluginManager;
+use crate::plugin::{PluginManager, PluginPrompt};
+use crate::table::operations::{ReplaceConfirm, ReplaceMatch};
 use crate::table::{
-    table::Table, rowmanager::FilterState};
+    table::Table, rowmanager::FilterState, sort::SortSpec};
 use crate::transaction::history::History;
 use crate::transaction::transaction::Transaction;
 use crate::ui;
-use crate::fileio::FileIO;
+use crate::fileio::{FileIO, BACKGROUND_SAVE_ROWS};
 use crate::mode::visual::{VisualType, VisualHandler};
-use crate::config::AppConfig;
-use crate::mode::normal::NormalHandler;
-use crate::viewstate::{ViewState, PendingOp};
+use crate::config::{AppConfig, FileHistory, FileSettings, Settings};
+use crate::mode::normal::{NormalContext, NormalHandler};
+use crate::viewstate::{BackgroundOutcome, ViewState, PendingOp};
+use crate::buffer::Buffer;
 
 pub struct App {
     pub table: Table,
@@ -40,9 +44,25 @@ pub struct App {
     #[allow(dead_code)]
     pub config: Rc<RefCell<AppConfig>>,
     pub dirty: bool,
+    pub(crate) edits: u64,  // Changes made so far, to tell whether a background save is still current
     pub calling_mode: Option<Mode>,
     pub should_quit: bool,
     pub header_mode: bool,
+    pub(crate) last_sort: Option<SortSpec>,  // Parameters of the most recent sort, for :sort!
+    pub(crate) running_sort: Option<SortSpec>,  // Background sort that becomes last_sort once it reorders rows
+    pub diff_cols: Option<(usize, usize)>,  // Columns compared by :diff, highlighted where they differ
+    pub marks: HashMap<char, (usize, usize)>,  // Cursor positions stored with m{char}, as (row, col)
+    pub(crate) hlsearch: bool,  // Highlight search matches (off with :set nohls)
+    pub(crate) relativenumber: bool,  // Number gutter rows by distance from the cursor
+    pub(crate) remember_file: bool,  // Save per-file settings on quit (off with :set noremember)
+    pub(crate) grow: bool,  // Moving down/right past the edge adds a row/column (:set grow on)
+    pub(crate) paste_progress: usize,  // Pastes of at least this many cells show a progress bar
+    pub(crate) save_progress: usize,  // Tables of at least this many rows are saved in the background
+    pub(crate) read_only: bool,  // Refuse every edit to the active buffer (--read-only); :w! or :fork turns it off
+    pub(crate) default_delimiter: Option<u8>,  // Delimiter from --delimiter or the config, used by :e
+    // Open files; the active one's slot is None while its state lives in the fields above
+    pub(crate) buffers: Vec<Option<Buffer>>,
+    pub(crate) active_buffer: usize,
     // Mode handlers
     key_buffer: KeyBuffer,
     pub(crate) nav_handler: NavigationHandler,
@@ -52,6 +72,9 @@ pub struct App {
     normal_handler: NormalHandler,
     // Plugin system
     pub(crate) plugin_manager: PluginManager,
+    pub(crate) plugin_prompt: Option<PluginPrompt>,  // Plugin waiting for the answer typed in prompt mode
+    pub(crate) replace_confirm: Option<ReplaceConfirm>,  // Matches of :s///c still being answered
+    pub(crate) formula_cache: Option<FormulaCache>,  // Formula values re-evaluated after edits on a large sheet
 }
 
 impl App {
@@ -63,6 +86,7 @@ impl App {
 
         let config = Rc::new(RefCell::new(AppConfig::new()));
         let key_buffer = KeyBuffer::new(config.clone());
+        let read_only = file_io.is_read_only();
 
         Self {
             table,
@@ -73,9 +97,24 @@ impl App {
             file_io,
             config,
             dirty: false,
+            edits: 0,
             calling_mode: None,
             should_quit: false,
             header_mode: true,
+            last_sort: None,
+            running_sort: None,
+            diff_cols: None,
+            marks: HashMap::new(),
+            hlsearch: true,
+            relativenumber: false,
+            remember_file: true,
+            grow: false,
+            paste_progress: 50_000,
+            save_progress: BACKGROUND_SAVE_ROWS,
+            read_only,
+            default_delimiter: None,
+            buffers: vec![None],
+            active_buffer: 0,
             key_buffer,
             nav_handler: NavigationHandler::new(),
             search_handler: SearchHandler::new(),
@@ -83,12 +122,69 @@ impl App {
             command_handler: CommandHandler::new(),
             normal_handler: NormalHandler::new(),
             plugin_manager,
+            plugin_prompt: None,
+            replace_confirm: None,
+            formula_cache: None,
+        }
+    }
+
+    /// Apply settings loaded from the config file, returning warnings for unusable values
+    pub fn apply_settings(&mut self, settings: Settings) -> Vec<String> {
+        use crate::ui::style::Theme;
+        let mut warnings = Vec::new();
+        if let Some(name) = &settings.theme {
+            match Theme::by_name(name) {
+                Some(theme) => self.view_state.style.set_theme(name, theme),
+                None => warnings.push(format!("Unknown theme in config: {}", name)),
+            }
+        }
+        if let Some(grid) = settings.grid {
+            self.view_state.style.theme.show_grid = grid;
+        }
+        if settings.precision.is_some() {
+            self.view_state.precision = settings.precision;
+        }
+        self.config.borrow_mut().settings = settings;
+        warnings
+    }
+
+    /// Restore settings remembered from a previous session with this file
+    /// The cursor is only restored if the table is still large enough
+    pub fn restore_file_settings(&mut self, settings: &FileSettings) {
+        self.header_mode = settings.header_mode;
+        if settings.cursor_row < self.table.row_count() && settings.cursor_col < self.table.col_count() {
+            self.view_state.view.cursor_row = settings.cursor_row;
+            self.view_state.view.cursor_col = settings.cursor_col;
+            self.view_state.view.scroll_to_cursor();
+        }
+    }
+
+    /// Record the delimiter, header mode and cursor of the current file for next time
+    pub fn remember_file_settings(&self) {
+        let Some(file) = &self.file_io.file_path else { return; };
+        if !self.remember_file {
+            return;
+        }
+        let path = FileHistory::path();
+        let mut history = FileHistory::load(&path);
+        history.remember(file, FileSettings {
+            delimiter: self.file_io.delimiter(),
+            header_mode: self.header_mode,
+            cursor_row: self.view_state.view.cursor_row,
+            cursor_col: self.view_state.view.cursor_col,
+        });
+        if let Err(e) = history.save(&path) {
+            tracing::warn!(error = %e, "Failed to save file history");
         }
     }
 
     // Accessor methods for UI
-    pub fn search_pattern(&self) -> Option<&String> {
-        self.search_handler.pattern.as_ref()
+    pub fn is_search_match(&self, col: usize, cell: &str) -> bool {
+        self.hlsearch && self.search_handler.is_match(col, cell)
+    }
+
+    pub fn is_current_match(&self, row: usize, col: usize) -> bool {
+        self.hlsearch && self.search_handler.current_match() == Some((row, col))
     }
 
     pub fn edit_buffer(&self) -> &str {
@@ -103,6 +199,23 @@ impl App {
         &self.command_handler.buffer
     }
 
+    pub fn prompt_question(&self) -> &str {
+        self.plugin_prompt.as_ref().map(|p| p.question.as_str()).unwrap_or("")
+    }
+
+    /// Question shown while confirming replacements
+    pub fn confirm_question(&self) -> String {
+        match self.confirm_match() {
+            Some(m) => format!("Replace \"{}\" with \"{}\"? (y/n/a/q)", m.found, m.replacement),
+            None => String::new(),
+        }
+    }
+
+    /// The match :s///c is asking about, highlighted in its cell
+    pub fn confirm_match(&self) -> Option<&ReplaceMatch> {
+        self.replace_confirm.as_ref().and_then(|c| c.current())
+    }
+
     pub fn search_buffer(&self) -> &str {
         &self.search_handler.buffer
     }
@@ -116,17 +229,32 @@ impl App {
         self.view_state.view.move_to_top();
     }
 
+    /// Move the cursor to the cell touched by an undone/redone transaction
+    fn move_cursor_to_edit(&mut self, cursor: Option<(usize, usize)>) {
+        if let Some((row, col)) = cursor {
+            self.view_state.view.cursor_row = row;
+            self.view_state.view.cursor_col = col;
+        }
+        self.view_state.view.clamp_cursor(&self.table);
+        self.view_state.view.scroll_to_cursor();
+    }
+
     /// Execute a pending operation (called after render so progress is visible)
     fn execute_pending_op(&mut self, op: PendingOp) {
         match op {
             PendingOp::Undo => {
+                let cursor = self.history.peek_undo().and_then(|t| t.affected_cursor());
                 if let Some(inverse) = self.history.undo() {
                     // Handle filter state if this is a SetFilter transaction
                     if let Some(filter_state) = inverse.filter_state() {
                         self.restore_filter_state(filter_state);
                     }
+                    if let Some(delimiter) = inverse.delimiter() {
+                        self.file_io.set_delimiter(delimiter);
+                    }
                     inverse.apply(&mut self.table);
-                    self.view_state.view.clamp_cursor(&self.table);
+                    self.remap_positions(&inverse);
+                    self.move_cursor_to_edit(cursor);
                     self.view_state.message = Some("Undo".to_string());
                 }
                 self.view_state.clear_progress();
@@ -137,8 +265,12 @@ impl App {
                     if let Some(filter_state) = txn.filter_state() {
                         self.restore_filter_state(filter_state);
                     }
+                    if let Some(delimiter) = txn.delimiter() {
+                        self.file_io.set_delimiter(delimiter);
+                    }
                     txn.apply(&mut self.table);
-                    self.view_state.view.clamp_cursor(&self.table);
+                    self.remap_positions(&txn);
+                    self.move_cursor_to_edit(txn.affected_cursor());
                     self.view_state.message = Some("Redo".to_string());
                 }
                 self.view_state.clear_progress();
@@ -169,15 +301,24 @@ impl App {
                 self.view_state.clear_progress();
                 let _ = formula_count; // Suppress unused warning
             }
+            PendingOp::ColFormula { col, template } => {
+                self.fill_column_formula(col, &template);
+                self.view_state.clear_progress();
+            }
+            PendingOp::Paste { txn, message } => {
+                self.execute(txn);
+                self.view_state.view.clamp_cursor(&self.table);
+                if message.is_some() {
+                    self.view_state.message = message;
+                }
+                self.view_state.clear_progress();
+            }
         }
     }
 
     pub fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, shutdown: Arc<AtomicBool>) -> io::Result<()> {
         while !self.should_quit && !shutdown.load(Ordering::Relaxed) {
-            // Check for completed background operations
-            let (msg, is_dirty) = self.view_state.poll_background_result(&mut self.table, &mut self.history);
-            self.view_state.message = msg;
-            self.dirty |= is_dirty;
+            self.poll_background();
 
             terminal.draw(|f| ui::ui::render(f, self, self.view_state.row_manager.clone()))?;
 
@@ -190,20 +331,80 @@ impl App {
             if poll(Duration::from_millis(16))? {
                 if let Event::Key(key) = event::read()? {
                     self.view_state.message = None;
+                    if key.code == KeyCode::Esc {
+                        if let Some(message) = self.view_state.cancel_background() {
+                            self.view_state.message = Some(message);
+                            continue;
+                        }
+                    }
                     self.handle_key(key);
                 }
             }
         }
+        // Let a save still in flight finish rather than leave a half-written file
+        self.view_state.wait_for_background_save();
         Ok(())
     }
 
+    /// Check for a completed background operation and apply its outcome
+    pub(crate) fn poll_background(&mut self) {
+        let (msg, outcome) = self.view_state.poll_background_result(&mut self.table, &mut self.history);
+        if msg.is_some() {
+            self.view_state.message = msg;
+        }
+        match outcome {
+            BackgroundOutcome::Unchanged => {}
+            BackgroundOutcome::Changed => {
+                self.mark_dirty();
+                if let Some(spec) = self.running_sort.take() {
+                    self.last_sort = Some(spec);
+                }
+                // A finished background sort was just recorded as the latest undo step
+                if let Some(txn) = self.history.peek_undo().cloned() {
+                    self.remap_positions(&txn);
+                    self.view_state.view.clamp_cursor(&self.table);
+                }
+            }
+            BackgroundOutcome::Saved { edits } => {
+                self.file_io.mark_saved();
+                // Changes made while the file was being written are still unsaved
+                if edits == self.edits {
+                    self.dirty = false;
+                }
+                self.view_state.message = Some(format!("Saved to {}", self.file_io.file_name()));
+            }
+        }
+    }
+
+    /// Note an unsaved change
+    pub(crate) fn mark_dirty(&mut self) {
+        self.dirty = true;
+        self.edits += 1;
+    }
+
     // === Transaction helpers ===
 
+    /// Report that an edit was refused; returns true in read-only mode
+    pub(crate) fn refuse_read_only(&mut self) -> bool {
+        if self.read_only {
+            self.view_state.message = Some("Read-only mode".to_string());
+        }
+        self.read_only
+    }
+
     /// Execute a transaction, record it in history, and mark dirty
+    /// In read-only mode only filter changes (and undo/redo of them) go through
     pub(crate) fn execute(&mut self, txn: Transaction) {
+        let changes_table = !matches!(txn, Transaction::SetFilter { .. } | Transaction::Undo | Transaction::Redo);
+        if changes_table && self.refuse_read_only() {
+            return;
+        }
+        // Undo and redo don't mark the buffer dirty but still change what a save would write
+        self.edits += 1;
         if matches!(txn, Transaction::Undo) {
             // Check if undo is large before executing
             if let Some(txn) = self.history.peek_undo() {
+                let cursor = txn.affected_cursor();
                 if txn.is_large() {
                     let size = txn.estimated_size();
                     self.view_state.start_progress("Undoing", size);
@@ -213,8 +414,12 @@ impl App {
                     if let Some(filter_state) = inverse.filter_state() {
                         self.restore_filter_state(filter_state);
                     }
+                    if let Some(delimiter) = inverse.delimiter() {
+                        self.file_io.set_delimiter(delimiter);
+                    }
                     inverse.apply(&mut self.table);
-                    self.view_state.view.clamp_cursor(&self.table);
+                    self.remap_positions(&inverse);
+                    self.move_cursor_to_edit(cursor);
                     self.view_state.message = Some("Undo".to_string());
                 }
             } else {
@@ -232,26 +437,87 @@ impl App {
                     if let Some(filter_state) = txn.filter_state() {
                         self.restore_filter_state(filter_state);
                     }
+                    if let Some(delimiter) = txn.delimiter() {
+                        self.file_io.set_delimiter(delimiter);
+                    }
                     txn.apply(&mut self.table);
-                    self.view_state.view.clamp_cursor(&self.table);
+                    self.remap_positions(&txn);
+                    self.move_cursor_to_edit(txn.affected_cursor());
                     self.view_state.message = Some("Redo".to_string());
                 }
             } else {
                 self.view_state.message = Some("Cannot redo.".to_string());
             }
         } else {
-            txn.apply(&mut self.table);
+            let txn = txn.apply_effective(&mut self.table);
+            self.remap_positions(&txn);
             self.history.record(txn);
             self.dirty = true;
         }
     }
 
+    /// Move marks and the filter's rows along with the cells they point at
+    /// Marks on deleted rows/columns are dropped
+    pub(crate) fn remap_positions(&mut self, txn: &Transaction) {
+        self.marks = self.marks
+            .drain()
+            .filter_map(|(name, pos)| txn.remap_position(pos).map(|pos| (name, pos)))
+            .collect();
+        let view = &mut self.view_state.view;
+        view.hidden_cols = view.hidden_cols
+            .drain()
+            .filter_map(|col| txn.remap_col(col))
+            .collect();
+        self.diff_cols = self.diff_cols.and_then(|(a, b)| Some((txn.remap_col(a)?, txn.remap_col(b)?)));
+        txn.remap_filter(&mut self.view_state.row_manager.borrow_mut());
+    }
+
     /// Execute and return to normal mode
     pub(crate) fn execute_and_finish(&mut self, txn: Transaction) {
         self.execute(txn);
+        // Deleting a selection can leave the cursor past the end or on a hidden row
+        self.view_state.view.clamp_cursor(&self.table);
         self.finish_edit();
     }
 
+    /// Execute a paste, deferring a large one until after the next render so its progress bar shows
+    pub(crate) fn execute_paste(&mut self, txn: Transaction, message: Option<String>) {
+        let size = txn.estimated_size();
+        if size >= self.paste_progress && !self.read_only {
+            self.view_state.start_progress("Pasting", size);
+            self.view_state.pending_op = Some(PendingOp::Paste { txn, message });
+            return;
+        }
+        self.execute(txn);
+        if message.is_some() {
+            self.view_state.message = message;
+        }
+    }
+
+    /// Re-evaluate the formulas that depend on an edited cell, for display only
+    fn recalc_dependents(&mut self, row: usize, col: usize) {
+        let calc = Calculator::with_plugins(&self.table, self.header_mode, &self.plugin_manager);
+        // The cache is updated in place only if this edit is the one change since it was current
+        let cache = match self.formula_cache.take() {
+            Some(cache) if cache.edits + 1 == self.edits => Ok(cache),
+            _ => calc.build_cache(),
+        };
+        let updated = cache.and_then(|mut cache| {
+            let count = calc.update_cache(&mut cache, row, col)?;
+            Ok((cache, count))
+        });
+        match updated {
+            Ok((mut cache, count)) => {
+                cache.edits = self.edits;
+                self.formula_cache = Some(cache);
+                if count > 0 {
+                    self.view_state.message = Some(format!("Recalculated {} dependent formula(s)", count));
+                }
+            }
+            Err(e) => self.view_state.message = Some(format!("{}", e)),
+        }
+    }
+
     /// Return to normal mode and update column widths
     pub(crate) fn finish_edit(&mut self) {
         self.mode = Mode::Normal;
@@ -261,7 +527,8 @@ impl App {
 
     fn handle_key(&mut self, key: KeyEvent) {
         // If canvas is visible, handle canvas-specific keys first
-        if self.view_state.canvas.visible {
+        // (a popup edit in insert mode takes its keys through the insert handler)
+        if self.view_state.canvas.visible && self.mode != Mode::Insert {
             match key.code {
                 KeyCode::Char('q') | KeyCode::Esc => {
                     self.view_state.canvas.hide();
@@ -298,11 +565,13 @@ impl App {
             Mode::VisualRow => self.handle_visual_mode(key, VisualType::Row),
             Mode::VisualCol => self.handle_visual_mode(key, VisualType::Col),
             Mode::Search => self.handle_search_mode(key),
+            Mode::Prompt => self.handle_prompt_mode(key),
+            Mode::Confirm => self.handle_confirm_mode(key),
         }
     }
 
     fn handle_visual_mode(&mut self, key: KeyEvent, visual_type: VisualType) {
-        let handler = VisualHandler::new(visual_type);
+        let handler = VisualHandler::new(visual_type, self.header_mode);
         let result = handler.handle_key(
             key,
             &mut self.view_state.view,
@@ -315,6 +584,10 @@ impl App {
     }
 
     fn handle_normal_mode(&mut self, key: KeyEvent) {
+        if is_escape(key) && self.diff_cols.take().is_some() {
+            self.view_state.message = Some("Diff cleared".to_string());
+        }
+
         // Process through key buffer for sequences
         match self.key_buffer.process(key) {
             KeyBufferResult::Action(action, count) => {
@@ -326,18 +599,27 @@ impl App {
                 return;
             }
             KeyBufferResult::Fallthrough(key, count) => {
+                let before = (self.view_state.view.cursor_row, self.view_state.view.cursor_col);
+                let plain = !key.modifiers.contains(KeyModifiers::CONTROL);
                 // Process as single key
                 //self.handle_normal_key(key, count);
                 let result = self.normal_handler.handle_key(key, 
                                                             &mut self.view_state.view,
                                                             &mut self.table,
                                                             count,
-                                                            &self.nav_handler,
-                                                            self.view_state.row_manager.borrow().is_filtered,
-                                                            &mut self.clipboard,
-                                                            &mut self.search_handler
-                                                            );
+                                                            NormalContext {
+                                                                nav_handler: &self.nav_handler,
+                                                                is_filtered: self.view_state.row_manager.borrow().is_filtered,
+                                                                header_mode: self.header_mode,
+                                                                clipboard: &mut self.clipboard,
+                                                                search_handler: &mut self.search_handler,
+                                                            });
                 self.process_key_result(result);
+                match key.code {
+                    KeyCode::Down if plain => self.grow_if_stuck(before, true),
+                    KeyCode::Right if plain => self.grow_if_stuck(before, false),
+                    _ => {}
+                }
             }
         }
     }
@@ -347,7 +629,7 @@ impl App {
         match result {
             KeyResult::Finish => {
                 if self.search_handler.pattern.is_some() {
-                    if let Some(msg) = self.search_handler.perform_search(&self.table) {
+                    if let Some(msg) = self.search_handler.perform_search(&self.table, None) {
                         self.view_state.message = Some(msg);
                     }
                     if let Some(msg) = self.search_handler.goto_next(&mut self.view_state.view) {
@@ -361,26 +643,204 @@ impl App {
         }
     }
 
+    /// Answer the current match of :s///c: y replaces it, n skips it, a replaces it and
+    /// all the rest, q or Esc stops; accepted replacements are applied together at the end
+    fn handle_confirm_mode(&mut self, key: KeyEvent) {
+        let Some(confirm) = self.replace_confirm.as_mut() else {
+            self.mode = Mode::Normal;
+            return;
+        };
+        match key.code {
+            _ if is_escape(key) => confirm.stop(),
+            KeyCode::Char('y') => confirm.answer(true),
+            KeyCode::Char('n') => confirm.answer(false),
+            KeyCode::Char('a') => confirm.accept_rest(),
+            KeyCode::Char('q') => confirm.stop(),
+            _ => return,
+        }
+        self.step_replace_confirm();
+    }
+
+    /// Collect the answer to a plugin prompt and run the plugin again with it
+    fn handle_prompt_mode(&mut self, key: KeyEvent) {
+        if is_escape(key) {
+            self.mode = Mode::Normal;
+            self.command_handler.buffer.clear();
+            self.plugin_prompt = None;
+            self.plugin_manager.clear_prompt_results();
+            self.view_state.message = Some("Cancelled".to_string());
+            return;
+        }
+
+        if let Some(answer) = self.command_handler.handle_key(key) {
+            self.mode = Mode::Normal;
+            if let Some(prompt) = self.plugin_prompt.take() {
+                self.plugin_manager.set_prompt_result(&prompt.question, answer);
+                self.execute_plugin(&prompt.plugin, &prompt.args);
+            }
+        }
+    }
+
+    /// Show the insert buffer in the canvas popup, or close the popup once editing ends
+    pub(crate) fn refresh_edit_popup(&mut self) {
+        if !self.insert_handler.multiline {
+            return;
+        }
+        let canvas = &mut self.view_state.canvas;
+        if self.mode != Mode::Insert {
+            canvas.hide();
+            return;
+        }
+        canvas.clear();
+        canvas.set_title(format!(
+            "Edit {}{}",
+            letters_from_col(self.view_state.view.cursor_col),
+            self.view_state.view.cursor_row + 1
+        ));
+        canvas.add_editor(self.insert_handler.buffer.clone(), self.insert_handler.cursor);
+        canvas.show();
+    }
+
     fn handle_insert_mode(&mut self, key: KeyEvent) {
+        if self.refuse_read_only() {
+            self.mode = Mode::Normal;
+            self.refresh_edit_popup();
+            return;
+        }
         let res  = self.insert_handler.handle_key(key, &self.view_state.view);
 
         match res {
             KeyResult::ExecuteAndFinish(txn) => {
+                let edited = match &txn {
+                    Transaction::SetCell { row, col, old_value, .. } => Some((*row, *col, display_width(old_value))),
+                    _ => None,
+                };
+                if let Some((_, col, _)) = edited {
+                    // Drop any growth from typing; the commit widens the column again if needed
+                    self.table.set_col_width(col, self.insert_handler.old_width);
+                }
+                // In append mode Enter commits together with opening the next row
+                let opened_row = matches!(txn, Transaction::Batch(_));
+                // A visual `c` edit undoes together with the clear before it; re-applying the
+                // clear is harmless as the cells are still empty
+                let txn = match self.insert_handler.change.then(|| self.history.pop_last()).flatten() {
+                    Some(clear) => Transaction::Batch(vec![clear, txn]),
+                    None => txn,
+                };
                 self.execute_and_finish(txn);
 
-                if self.insert_handler.old_width > self.insert_handler.buffer.len() {
-                    self.table.recompute_col_widths(); 
+                if let Some((row, col, old_len)) = edited {
+                    self.table.maybe_shrink_col_width(col, old_len);
+                    if self.table.row_count() * self.table.col_count() >= LARGE_SHEET_CELLS {
+                        self.recalc_dependents(row, col);
+                    }
+                }
+
+                if let Some(step) = self.insert_handler.tab_move.take() {
+                    self.edit_adjacent_cell(step);
+                } else if self.insert_handler.append && opened_row {
+                    // Continue in the row just opened below
+                    self.start_append_row(self.view_state.view.cursor_row + 1);
+                } else {
+                    self.insert_handler.append = false;
                 }
             }
             KeyResult::Finish => {
                 self.mode = Mode::Normal;
                 self.calling_mode = None;
                 self.table.update_col_width(self.view_state.view.cursor_col, self.insert_handler.old_width);
+                if self.insert_handler.append {
+                    self.insert_handler.append = false;
+                    self.drop_appended_row();
+                }
             }
             _default => {
                 self.table.expand_col_width(self.view_state.view.cursor_col, self.insert_handler.buffer.len());
             }
         }
+
+        self.refresh_edit_popup();
+    }
+
+    /// With :set grow on, add a row (down) or column (right) when a move left the cursor
+    /// at `before` because it was already at the edge, and move into it
+    pub(crate) fn grow_if_stuck(&mut self, before: (usize, usize), down: bool) {
+        let view = &self.view_state.view;
+        if !self.grow || (view.cursor_row, view.cursor_col) != before || self.refuse_read_only() {
+            return;
+        }
+        if down {
+            if self.view_state.row_manager.borrow().is_filtered {
+                self.view_state.message = Some("Adding rows is forbidden in filtered views.".to_string());
+                return;
+            }
+            let idx = self.table.row_count();
+            self.execute(Transaction::InsertRow { idx });
+            self.view_state.view.cursor_row = idx;
+        } else {
+            let idx = self.table.col_count();
+            self.execute(Transaction::InsertCol { idx });
+            self.view_state.view.cursor_col = idx;
+        }
+        self.view_state.view.scroll_to_cursor();
+    }
+
+    /// Move to the next/previous cell after a Tab commit and edit it
+    /// Tab past the last cell opens a new row; at either end of a filtered view editing stops
+    fn edit_adjacent_cell(&mut self, step: TabMove) {
+        let moved = match step {
+            TabMove::Next => self.view_state.view.next_cell(&self.table),
+            TabMove::Prev => self.view_state.view.prev_cell(&self.table),
+        };
+        if !moved {
+            if step == TabMove::Prev || self.view_state.row_manager.borrow().is_filtered {
+                self.insert_handler.append = false;
+                return;
+            }
+            let row = self.table.row_count();
+            self.execute(Transaction::InsertRow { idx: row });
+            self.view_state.view.cursor_row = row;
+            self.view_state.view.cursor_col = 0;
+            self.view_state.view.snap_to_visible_col(&self.table);
+            self.view_state.view.scroll_to_cursor();
+        }
+        self.process_key_result(KeyResult::SwitchMode(Mode::Insert));
+    }
+
+    /// Remove the row opened for appending when its edit is cancelled before anything was typed
+    /// The row insert is taken back out of the history, so undo doesn't meet an empty row
+    fn drop_appended_row(&mut self) {
+        let row = self.view_state.view.cursor_row;
+        let opened_here = match self.history.peek_undo() {
+            Some(Transaction::InsertRow { idx }) => *idx == row,
+            Some(Transaction::Batch(txns)) => matches!(txns.last(), Some(Transaction::InsertRow { idx }) if *idx == row),
+            _ => false,
+        };
+        let Some(data) = self.table.get_row_cloned(row) else { return; };
+        if !opened_here || data.iter().any(|cell| !cell.is_empty()) {
+            return;
+        }
+        // Keep the commit the row was opened with (Enter in append mode), drop only the insert
+        if let Some(Transaction::Batch(mut txns)) = self.history.pop_last() {
+            txns.pop();
+            self.history.record(Transaction::Batch(txns));
+        }
+        let txn = Transaction::DeleteRow { idx: row, data };
+        txn.apply(&mut self.table);
+        self.remap_positions(&txn);
+        self.edits += 1;
+        self.view_state.view.cursor_row = row.saturating_sub(1);
+        self.view_state.view.clamp_cursor(&self.table);
+        self.view_state.view.scroll_to_cursor();
+    }
+
+    /// Put the cursor in the first cell of row and edit it in append mode
+    pub(crate) fn start_append_row(&mut self, row: usize) {
+        self.view_state.view.cursor_row = row;
+        self.view_state.view.cursor_col = 0;
+        self.view_state.view.scroll_to_cursor();
+        self.process_key_result(KeyResult::SwitchMode(Mode::Insert));
+        self.insert_handler.append = self.mode == Mode::Insert;
     }
 
     fn handle_command_mode(&mut self, key: KeyEvent) {
@@ -392,6 +852,7 @@ impl App {
         }
 
         if let Some(cmd_str) = self.command_handler.handle_key(key) {
+            self.command_handler.history.push(&cmd_str);
             if let Some(cmd) = Command::parse(&cmd_str) {
                 self.execute_command(cmd);
             }
@@ -401,3 +862,6 @@ impl App {
         }
     }
 }
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,1346 @@
+use super::App;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::fileio::FileIO;
+use crate::mode::Mode;
+use crate::mode::command::Command;
+use crate::table::table::Table;
+use crate::transaction::transaction::Transaction;
+
+fn make_app(read_only: bool) -> App {
+    let table = Table::new(vec![
+        vec!["a".to_string(), "b".to_string()],
+        vec!["1".to_string(), "2".to_string()],
+    ]);
+    App::new(table, FileIO::new(None, None, read_only).unwrap())
+}
+
+fn press(app: &mut App, code: KeyCode) {
+    app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+}
+
+fn edit_first_cell(app: &mut App) {
+    press(app, KeyCode::Char('i'));
+    press(app, KeyCode::Char('x'));
+    press(app, KeyCode::Enter);
+}
+
+#[test]
+fn test_insert_edits_cell() {
+    let mut app = make_app(false);
+    edit_first_cell(&mut app);
+    assert_ne!(app.table.get_cell(0, 0).unwrap(), "a");
+    assert!(app.dirty);
+}
+
+#[test]
+fn test_read_only_insert_leaves_table_unchanged() {
+    let mut app = make_app(true);
+    edit_first_cell(&mut app);
+
+    assert_eq!(app.table.get_cell(0, 0).unwrap(), "a");
+    assert_eq!(app.mode, Mode::Normal);
+    assert!(!app.dirty);
+    assert_eq!(app.view_state.message.as_deref(), Some("Read-only mode"));
+}
+
+#[test]
+fn test_read_only_refuses_transactions() {
+    let mut app = make_app(true);
+    app.execute(Transaction::SetCell {
+        row: 0,
+        col: 0,
+        old_value: "a".to_string(),
+        new_value: "z".to_string(),
+    });
+
+    assert_eq!(app.table.get_cell(0, 0).unwrap(), "a");
+    assert!(!app.history.can_undo());
+}
+
+#[test]
+fn test_force_write_turns_read_only_off() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.csv");
+    let table = Table::new(vec![vec!["a".to_string()]]);
+    let mut app = App::new(table, FileIO::new(Some(path.clone()), None, true).unwrap());
+
+    app.execute_command(Command::Write);
+    assert!(!path.exists());
+
+    app.execute_command(Command::ForceWrite);
+    assert!(!app.read_only);
+    assert!(path.exists());
+    assert!(app.view_state.message.as_deref().unwrap().starts_with("Read-only mode turned off"));
+}
+
+#[test]
+fn test_partial_file_loads_more_and_refuses_plain_save() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.csv");
+    std::fs::write(&path, "h\n1\n2\n3\n4\n").unwrap();
+    let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+    file_io.set_max_rows(Some(3));
+    let table = file_io.load_table().unwrap().table;
+    let mut app = App::new(table, file_io);
+
+    edit_first_cell(&mut app);
+    app.execute_command(Command::Write);
+    assert!(app.view_state.message.as_deref().unwrap().starts_with("Only 3 rows"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "h\n1\n2\n3\n4\n");
+
+    app.execute_command(Command::parse("loadmore").unwrap());
+    assert!(app.view_state.message.as_deref().unwrap().ends_with("undo history cleared"));
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["hx", "1", "2", "3", "4"]);
+    assert!(!app.file_io.is_partial());
+    assert!(!app.history.can_undo());
+
+    app.execute_command(Command::Write);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "hx\n1\n2\n3\n4\n");
+    app.execute_command(Command::parse("loadmore").unwrap());
+    assert_eq!(app.view_state.message.as_deref(), Some("The whole file is already loaded"));
+}
+
+#[test]
+fn test_loadmore_refuses_file_changed_on_disk() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.csv");
+    std::fs::write(&path, "h\n1\n2\n").unwrap();
+    let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+    file_io.set_max_rows(Some(2));
+    let table = file_io.load_table().unwrap().table;
+    let mut app = App::new(table, file_io);
+
+    std::fs::write(&path, "other\n").unwrap();
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(std::time::SystemTime::UNIX_EPOCH).unwrap();
+    app.execute_command(Command::parse("loadmore").unwrap());
+    assert!(app.view_state.message.as_deref().unwrap().starts_with("File changed on disk"));
+    assert_eq!(app.table.row_count(), 2);
+    assert!(app.file_io.is_partial());
+}
+
+/// Poll until the background operation has finished
+fn finish_background(app: &mut App) {
+    while app.view_state.background_busy() {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        app.poll_background();
+    }
+}
+
+#[test]
+fn test_large_save_runs_in_background() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("big.csv");
+    let rows: Vec<Vec<String>> = (0..50_000).map(|i| vec![i.to_string()]).collect();
+    let mut app = App::new(Table::new(rows), FileIO::new(Some(path.clone()), None, false).unwrap());
+
+    // An edit made while the file is written keeps the buffer dirty
+    edit_first_cell(&mut app);
+    app.execute_command(Command::Write);
+    assert!(app.view_state.background_busy() && app.dirty);
+    app.execute_command(Command::Write);
+    assert_eq!(app.view_state.message.as_deref(), Some("Wait for the running operation to finish"));
+    press(&mut app, KeyCode::Char('j'));
+    edit_first_cell(&mut app);
+    finish_background(&mut app);
+    assert!(app.dirty);
+    assert!(std::fs::read_to_string(&path).unwrap().starts_with("0x\n1\n"));
+
+    app.execute_command(Command::Write);
+    finish_background(&mut app);
+    assert!(!app.dirty);
+    assert!(app.view_state.progress.is_none());
+    assert_eq!(app.view_state.message.as_deref(), Some(format!("Saved to {}", app.file_io.file_name()).as_str()));
+    assert!(std::fs::read_to_string(&path).unwrap().starts_with("0x\n1x\n"));
+}
+
+#[test]
+fn test_write_refused_after_file_changed_on_disk() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.csv");
+    std::fs::write(&path, "a,b\n1,2\n").unwrap();
+    let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+    let table = file_io.load_table().unwrap().table;
+    let mut app = App::new(table, file_io);
+    edit_first_cell(&mut app);
+
+    // Another program rewrites the file
+    std::fs::write(&path, "c,d\n3,4\n5,6\n").unwrap();
+    let file = std::fs::File::options().write(true).open(&path).unwrap();
+    file.set_modified(std::time::SystemTime::UNIX_EPOCH).unwrap();
+
+    app.execute_command(Command::Write);
+    assert_eq!(app.view_state.message.as_deref(), Some("File changed on disk; :w! to overwrite or :e! to reload"));
+    assert!(app.dirty);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "c,d\n3,4\n5,6\n");
+
+    app.execute_command(Command::Reload);
+    assert!(!app.dirty);
+    assert_eq!(app.table.row_count(), 3);
+    assert_eq!(app.table.get_cell(0, 0).unwrap(), "c");
+    assert_eq!(app.view_state.message.as_deref(), Some(format!("Reloaded {}", path.display()).as_str()));
+
+    edit_first_cell(&mut app);
+    app.execute_command(Command::Write);
+    assert!(!app.dirty);
+    assert!(std::fs::read_to_string(&path).unwrap().starts_with("cx,d\n"));
+}
+
+#[test]
+fn test_reload_resets_view_state_like_a_new_buffer() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.csv");
+    std::fs::write(&path, "a,b,c\n3,2,1\n1,2,3\n").unwrap();
+    let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+    let table = file_io.load_table().unwrap().table;
+    let mut app = App::new(table, file_io);
+
+    app.execute_command(Command::parse("sort").unwrap());
+    assert!(app.last_sort.is_some());
+    app.marks.insert('a', (2, 1));
+    app.execute_command(Command::SetColType(0, Some(crate::util::ColumnType::Text)));
+    app.view_state.view.hidden_cols.insert(2);
+    app.view_state.view.frozen_cols = 1;
+
+    app.execute_command(Command::Reload);
+    assert!(app.last_sort.is_none());
+    assert!(app.marks.is_empty());
+    assert_eq!(app.table.declared_type(0), None);
+    assert!(app.view_state.view.hidden_cols.is_empty());
+    assert_eq!(app.view_state.view.frozen_cols, 0);
+}
+
+#[test]
+fn test_edit_on_large_sheet_shows_dependents_without_overwriting() {
+    let mut rows: Vec<Vec<String>> = vec![vec!["n".to_string(), "double".to_string()]];
+    rows.push(vec!["1".to_string(), "=A2*2".to_string()]);
+    rows.extend((0..25_000).map(|_| vec!["0".to_string(), String::new()]));
+    let mut app = App::new(Table::new(rows), FileIO::new(None, None, false).unwrap());
+
+    press(&mut app, KeyCode::Char('j'));
+    for code in [KeyCode::Char('i'), KeyCode::Backspace, KeyCode::Char('7'), KeyCode::Enter] {
+        press(&mut app, code);
+    }
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "7");
+    assert_eq!(app.table.get_cell(1, 1).unwrap(), "=A2*2");
+    let cache = app.formula_cache.as_ref().unwrap();
+    assert_eq!(cache.edits, app.edits);
+    assert_eq!(cache.value(1, 1), Some("14"));
+
+    // The edit is a single undo step, after which the value is no longer shown
+    press(&mut app, KeyCode::Char('u'));
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "1");
+    assert!(!app.history.can_undo());
+    assert_ne!(app.formula_cache.as_ref().unwrap().edits, app.edits);
+}
+
+#[test]
+fn test_append_adds_one_undo_step_per_row() {
+    let mut app = make_app(false);
+    app.execute_command(Command::Append);
+    assert_eq!(app.table.row_count(), 3);
+    assert_eq!(app.mode, Mode::Insert);
+
+    for name in ["x", "y"] {
+        for c in name.chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+    }
+    assert_eq!(app.table.get_cell(2, 0).unwrap(), "x");
+    assert_eq!(app.table.get_cell(3, 0).unwrap(), "y");
+    assert_eq!(app.table.row_count(), 5);
+    assert_eq!((app.view_state.view.cursor_row, app.view_state.view.cursor_col), (4, 0));
+
+    // Esc drops the empty row opened for the next entry
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.mode, Mode::Normal);
+    assert!(!app.insert_handler.append);
+    assert_eq!(app.table.row_count(), 4);
+    assert_eq!(app.view_state.view.cursor_row, 3);
+
+    app.execute(Transaction::Undo);
+    assert_eq!(app.table.row_count(), 4);
+    assert_eq!(app.table.get_cell(3, 0).unwrap(), "");
+}
+
+#[test]
+fn test_append_cancelled_without_input_leaves_no_row() {
+    let mut app = make_app(false);
+    app.execute_command(Command::Append);
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.table.row_count(), 2);
+    assert!(!app.history.can_undo());
+
+    // A row with something typed into it is kept
+    app.execute_command(Command::Append);
+    press(&mut app, KeyCode::Char('x'));
+    press(&mut app, KeyCode::Tab);
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.table.row_count(), 3);
+}
+
+#[test]
+fn test_append_refused_when_filtered() {
+    let mut app = make_app(false);
+    app.view_state.row_manager.borrow_mut().is_filtered = true;
+    app.execute_command(Command::Append);
+    assert_eq!(app.table.row_count(), 2);
+    assert_eq!(app.mode, Mode::Normal);
+}
+
+#[test]
+fn test_tab_commits_and_moves_to_next_cell() {
+    let mut app = make_app(false);
+    press(&mut app, KeyCode::Char('i'));
+    press(&mut app, KeyCode::Char('x'));
+    press(&mut app, KeyCode::Tab);
+    assert_eq!(app.table.get_cell(0, 0).unwrap(), "ax");
+    assert_eq!(app.mode, Mode::Insert);
+    assert_eq!(app.view_state.view.cursor_col, 1);
+    assert_eq!(app.edit_buffer(), "b");
+
+    // Wraps to the first column of the next row
+    press(&mut app, KeyCode::Tab);
+    assert_eq!((app.view_state.view.cursor_row, app.view_state.view.cursor_col), (1, 0));
+
+    press(&mut app, KeyCode::BackTab);
+    assert_eq!((app.view_state.view.cursor_row, app.view_state.view.cursor_col), (0, 1));
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.mode, Mode::Normal);
+}
+
+#[test]
+fn test_tab_at_last_cell_opens_row() {
+    let mut app = make_app(false);
+    app.view_state.view.cursor_row = 1;
+    app.view_state.view.cursor_col = 1;
+    press(&mut app, KeyCode::Char('i'));
+    press(&mut app, KeyCode::Tab);
+
+    assert_eq!(app.table.row_count(), 3);
+    assert_eq!((app.view_state.view.cursor_row, app.view_state.view.cursor_col), (2, 0));
+    assert_eq!(app.mode, Mode::Insert);
+}
+
+fn set_grow(app: &mut App, value: &str) {
+    app.execute_command(Command::SetOption { name: "grow".to_string(), value: value.to_string() });
+}
+
+#[test]
+fn test_grow_off_stops_at_last_column() {
+    let mut app = make_app(false);
+    app.view_state.view.cursor_col = 1;
+    press(&mut app, KeyCode::Right);
+    assert_eq!(app.table.col_count(), 2);
+    assert_eq!(app.view_state.view.cursor_col, 1);
+}
+
+#[test]
+fn test_grow_adds_column_and_row_at_edges() {
+    let mut app = make_app(false);
+    set_grow(&mut app, "on");
+    app.view_state.view.cursor_col = 1;
+
+    press(&mut app, KeyCode::Right);
+    assert_eq!(app.table.col_count(), 3);
+    assert_eq!(app.view_state.view.cursor_col, 2);
+    // Inside the table moves are unchanged
+    press(&mut app, KeyCode::Char('h'));
+    press(&mut app, KeyCode::Char('l'));
+    assert_eq!(app.table.col_count(), 3);
+    press(&mut app, KeyCode::Char('l'));
+    assert_eq!(app.table.col_count(), 4);
+
+    app.view_state.view.cursor_row = 1;
+    press(&mut app, KeyCode::Down);
+    assert_eq!(app.table.row_count(), 3);
+    assert_eq!(app.view_state.view.cursor_row, 2);
+}
+
+#[test]
+fn test_grow_respects_read_only_and_filters() {
+    let mut app = make_app(true);
+    set_grow(&mut app, "on");
+    app.view_state.view.cursor_col = 1;
+    press(&mut app, KeyCode::Right);
+    assert_eq!(app.table.col_count(), 2);
+
+    let mut app = make_app(false);
+    set_grow(&mut app, "on");
+    app.view_state.row_manager.borrow_mut().is_filtered = true;
+    app.view_state.row_manager.borrow_mut().active_rows = vec![0, 1];
+    app.view_state.view.cursor_row = 1;
+    press(&mut app, KeyCode::Down);
+    assert_eq!(app.table.row_count(), 2);
+}
+
+#[test]
+fn test_mark_set_and_jump() {
+    let mut app = make_app(false);
+    app.view_state.view.cursor_row = 1;
+    app.view_state.view.cursor_col = 1;
+    press(&mut app, KeyCode::Char('m'));
+    press(&mut app, KeyCode::Char('a'));
+    assert_eq!(app.marks.get(&'a'), Some(&(1, 1)));
+
+    app.view_state.view.cursor_row = 0;
+    app.view_state.view.cursor_col = 0;
+    press(&mut app, KeyCode::Char('`'));
+    press(&mut app, KeyCode::Char('a'));
+    assert_eq!((app.view_state.view.cursor_row, app.view_state.view.cursor_col), (1, 1));
+
+    press(&mut app, KeyCode::Char('`'));
+    press(&mut app, KeyCode::Char('b'));
+    assert_eq!(app.view_state.message.as_deref(), Some("Mark b not set"));
+}
+
+#[test]
+fn test_marks_follow_row_changes() {
+    let mut app = make_app(false);
+    app.marks.insert('a', (1, 0));
+    app.marks.insert('b', (0, 1));
+
+    app.execute(Transaction::InsertRow { idx: 0 });
+    assert_eq!(app.marks.get(&'a'), Some(&(2, 0)));
+
+    let data = app.table.get_row_cloned(1).unwrap();
+    app.execute(Transaction::DeleteRow { idx: 1, data });
+    assert_eq!(app.marks.get(&'b'), None);
+    assert_eq!(app.marks.get(&'a'), Some(&(1, 0)));
+
+    app.execute(Transaction::Undo);
+    assert_eq!(app.marks.get(&'a'), Some(&(2, 0)));
+}
+
+#[test]
+fn test_insert_commit_shrinks_column_width() {
+    let table = Table::new(vec![
+        vec!["a fairly long value".to_string()],
+        vec!["abc".to_string()],
+    ]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    assert_eq!(app.table.col_widths()[0], 19);
+
+    press(&mut app, KeyCode::Char('i'));
+    for _ in 0..19 {
+        press(&mut app, KeyCode::Backspace);
+    }
+    press(&mut app, KeyCode::Char('x'));
+    press(&mut app, KeyCode::Enter);
+
+    assert_eq!(app.table.get_cell(0, 0).unwrap(), "x");
+    assert_eq!(app.table.col_widths()[0], 3);
+}
+
+#[test]
+fn test_search_highlight_marks_current_match_and_toggles() {
+    let mut app = make_app(false);
+    app.execute_command(Command::SearchCol("re:^\\d$".to_string()));
+    assert_eq!((app.view_state.view.cursor_row, app.view_state.view.cursor_col), (1, 0));
+    // The next match after the last row wraps to the top
+    app.execute_command(Command::SearchCol("re:\\w".to_string()));
+    assert!(app.is_search_match(0, "1"));
+    assert!(app.is_current_match(0, 0));
+    assert!(!app.is_current_match(1, 0));
+
+    app.execute_command(Command::SetOption { name: "nohls".to_string(), value: String::new() });
+    assert!(!app.is_search_match(0, "a"));
+    assert!(!app.is_current_match(0, 0));
+
+    app.execute_command(Command::SetOption { name: "hls".to_string(), value: String::new() });
+    assert!(app.is_search_match(0, "a"));
+}
+
+#[test]
+fn test_register_prefix_routes_cell_yank() {
+    let mut app = make_app(false);
+    for c in ['"', 'b', 'y', 'y'] {
+        press(&mut app, KeyCode::Char(c));
+    }
+    assert_eq!(app.view_state.message.as_deref(), Some("Cell yanked"));
+    assert_eq!(app.clipboard.registers.get(&'b').unwrap().data, vec![vec!["a".to_string()]]);
+}
+
+#[test]
+fn test_visual_change_clears_selection_and_edits_top_left() {
+    let mut app = make_app(false);
+    for c in ['l', 'v', 'j', 'h', 'c'] {
+        press(&mut app, KeyCode::Char(c));
+    }
+    assert_eq!(app.mode, Mode::Insert);
+    assert_eq!((app.view_state.view.cursor_row, app.view_state.view.cursor_col), (0, 0));
+    for row in 0..2 {
+        for col in 0..2 {
+            assert_eq!(app.table.get_cell(row, col).unwrap(), "");
+        }
+    }
+
+    // Esc keeps the typed value
+    press(&mut app, KeyCode::Char('z'));
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(app.table.get_cell(0, 0).unwrap(), "z");
+    assert_eq!(app.clipboard.unnamed.as_ref().unwrap().data, vec![vec!["a", "b"], vec!["1", "2"]]);
+
+    // One undo restores the selection as it was before `c`
+    app.execute(Transaction::Undo);
+    assert_eq!(app.table.get_row_cloned(0).unwrap(), vec!["a", "b"]);
+    assert_eq!(app.table.get_row_cloned(1).unwrap(), vec!["1", "2"]);
+    assert!(!app.history.can_undo());
+}
+
+fn ctrl(app: &mut App, c: char) {
+    app.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+}
+
+#[test]
+fn test_ctrl_a_and_ctrl_x_bump_current_cell() {
+    let mut app = make_app(false);
+    press(&mut app, KeyCode::Char('j'));
+    press(&mut app, KeyCode::Char('5'));
+    ctrl(&mut app, 'a');
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "6");
+    ctrl(&mut app, 'x');
+    ctrl(&mut app, 'x');
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "4");
+
+    // Non-numeric cells are left alone
+    press(&mut app, KeyCode::Char('k'));
+    ctrl(&mut app, 'a');
+    assert_eq!(app.table.get_cell(0, 0).unwrap(), "a");
+
+    // So are formulas, whose first number is a reference
+    app.table.set_cell(1, 0, "=B1".to_string());
+    press(&mut app, KeyCode::Char('j'));
+    ctrl(&mut app, 'a');
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "=B1");
+}
+
+#[test]
+fn test_visual_case_change_skips_filtered_rows() {
+    let mut app = filtered_app(&["ab", "cd", "ae"], "filter =~ a");
+    assert_eq!(active_rows(&app), vec![0, 1, 3]);
+    app.view_state.view.cursor_row = 1;
+    press(&mut app, KeyCode::Char('V'));
+    app.view_state.view.cursor_row = 3;
+    for c in ['g', 'U'] {
+        press(&mut app, KeyCode::Char(c));
+    }
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["n", "AB", "cd", "AE"]);
+}
+
+#[test]
+fn test_visual_format_skips_filtered_rows() {
+    let mut app = filtered_app(&["0.5", "2", "0.25"], "filter < 1");
+    assert_eq!(active_rows(&app), vec![0, 1, 3]);
+    press(&mut app, KeyCode::Char('v'));
+    press(&mut app, KeyCode::Char('G'));
+    for c in ['f', '%'] {
+        press(&mut app, KeyCode::Char(c));
+    }
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["n", "50%", "2", "25%"]);
+}
+
+#[test]
+fn test_visual_fill_paste_refused_in_filtered_view() {
+    let mut app = filtered_app(&["5", "1", "6"], "filter > 3");
+    press(&mut app, KeyCode::Char('y'));
+    press(&mut app, KeyCode::Char('y'));
+    for c in ['v', 'G', 'p'] {
+        press(&mut app, KeyCode::Char(c));
+    }
+    assert_eq!(app.view_state.message.as_deref(), Some("Fill paste is forbidden in filtered views."));
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["n", "5", "1", "6"]);
+}
+
+#[test]
+fn test_visual_ctrl_a_skips_filtered_rows() {
+    let mut app = filtered_app(&["1", "5", "2", "7", "9"], "filter > 3");
+    assert_eq!(active_rows(&app), vec![0, 2, 4, 5]);
+    app.view_state.view.cursor_row = 1;
+    press(&mut app, KeyCode::Char('V'));
+    app.view_state.view.cursor_row = 5;
+    ctrl(&mut app, 'a');
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["n", "1", "6", "2", "8", "10"]);
+}
+
+#[test]
+fn test_visual_g_ctrl_a_numbers_a_series() {
+    let table = Table::new(vec![
+        vec!["0".to_string()],
+        vec!["0".to_string()],
+        vec!["n/a".to_string()],
+        vec!["0".to_string()],
+        vec!["=A1".to_string()],
+    ]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    for c in ['v', 'G', 'g'] {
+        press(&mut app, KeyCode::Char(c));
+    }
+    ctrl(&mut app, 'a');
+    assert_eq!(app.mode, Mode::Normal);
+    let col: Vec<&str> = app.table.col_iter(0).collect();
+    assert_eq!(col, vec!["1", "2", "n/a", "3", "=A1"]);
+}
+
+fn load_ask_plugin(app: &mut App) {
+    let script = r#"
+        return {
+            name = "ask",
+            run = function()
+                tabular.set_cell(2, 2, "early")
+                local value = tabular.read_input("Value:", "7")
+                if not value then
+                    return
+                end
+                tabular.set_cell(2, 1, value)
+            end
+        }
+    "#;
+    app.plugin_manager.register_plugin(script).unwrap();
+}
+
+#[test]
+fn test_plugin_prompt_reruns_with_answer() {
+    let mut app = make_app(false);
+    load_ask_plugin(&mut app);
+    app.execute_plugin("ask", &[]);
+    assert_eq!(app.mode, Mode::Prompt);
+    assert_eq!(app.prompt_question(), "Value:");
+    assert_eq!(app.command_buffer(), "7");
+    // The run that asked is discarded
+    assert_eq!(app.table.get_cell(1, 1).unwrap(), "2");
+
+    press(&mut app, KeyCode::Char('5'));
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "75");
+    assert_eq!(app.table.get_cell(1, 1).unwrap(), "early");
+
+    // Answers are not reused by the next run
+    app.execute_plugin("ask", &[]);
+    assert_eq!(app.mode, Mode::Prompt);
+}
+
+#[test]
+fn test_plugin_prompt_escape_cancels() {
+    let mut app = make_app(false);
+    load_ask_plugin(&mut app);
+    app.execute_plugin("ask", &[]);
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(app.view_state.message.as_deref(), Some("Cancelled"));
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "1");
+    assert_eq!(app.table.get_cell(1, 1).unwrap(), "2");
+}
+
+fn load_rowsums_plugin(app: &mut App) {
+    let script = r#"
+        return {
+            name = "rowsums",
+            run = function()
+                local t = tabular.table
+                for row = 1, t:rows() do
+                    local sum = 0
+                    for col = 1, t:cols() - 1 do
+                        sum = sum + (tonumber(t:get(row, col)) or 0)
+                    end
+                    t:set(row, t:cols(), sum)
+                end
+            end
+        }
+    "#;
+    app.plugin_manager.register_plugin(script).unwrap();
+}
+
+#[test]
+fn test_plugin_table_handle_sets_cells_in_one_undo_step() {
+    let table = Table::new(vec![
+        vec!["1".to_string(), "2".to_string(), "".to_string()],
+        vec!["3".to_string(), "x".to_string(), "".to_string()],
+    ]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    load_rowsums_plugin(&mut app);
+    app.execute_plugin("rowsums", &[]);
+    let col: Vec<&str> = app.table.col_iter(2).collect();
+    assert_eq!(col, vec!["3", "3"]);
+
+    press(&mut app, KeyCode::Char('u'));
+    let col: Vec<&str> = app.table.col_iter(2).collect();
+    assert_eq!(col, vec!["", ""]);
+}
+
+#[test]
+fn test_plugin_table_handle_shares_cells_with_get_cell() {
+    let mut app = make_app(false);
+    let script = r#"
+        return {
+            name = "mirror",
+            run = function()
+                local t = tabular.table
+                tabular.set_cell(1, 1, "z")
+                t:set(2, 1, t:get(1, 1))
+                t:set(2, 2, tabular.get_cell(2, 1))
+            end
+        }
+    "#;
+    app.plugin_manager.register_plugin(script).unwrap();
+    app.execute_plugin("mirror", &[]);
+    assert_eq!(app.table.get_row_cloned(1).unwrap(), vec!["z", "z"]);
+}
+
+#[test]
+fn test_plugin_table_handle_sets_keep_their_order_with_other_actions() {
+    let table = Table::new(vec![vec!["a".to_string()], vec!["b".to_string()]]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    let script = r#"
+        return {
+            name = "order",
+            run = function()
+                tabular.table:set(2, 1, "x")
+                tabular.insert_row(1)
+            end
+        }
+    "#;
+    app.plugin_manager.register_plugin(script).unwrap();
+    app.execute_plugin("order", &[]);
+    // The set lands on the old second row, which the insert then moves down
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["", "a", "x"]);
+}
+
+#[test]
+fn test_plugin_table_handle_set_refused_when_read_only() {
+    let mut app = make_app(true);
+    load_rowsums_plugin(&mut app);
+    app.execute_plugin("rowsums", &[]);
+    assert_eq!(app.table.get_cell(0, 1).unwrap(), "b");
+    assert!(app.view_state.message.as_deref().unwrap().contains("read-only"));
+}
+
+fn load_require_plugin(app: &mut App) {
+    let script = r#"
+        return {
+            name = "require",
+            on_save = function(t)
+                local errors = {}
+                for row = 2, t:rows() do
+                    if t:get(row, 1) == "" then
+                        table.insert(errors, "row " .. row .. " has no " .. t:get(1, 1))
+                    end
+                end
+                return errors
+            end
+        }
+    "#;
+    app.plugin_manager.register_plugin(script).unwrap();
+}
+
+#[test]
+fn test_on_save_hook_blocks_save() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.csv");
+    let table = Table::new(vec![
+        vec!["id".to_string(), "name".to_string()],
+        vec!["".to_string(), "x".to_string()],
+    ]);
+    let mut app = App::new(table, FileIO::new(Some(path.clone()), None, false).unwrap());
+    load_require_plugin(&mut app);
+
+    app.execute_command(Command::WriteQuit);
+    assert!(!path.exists());
+    assert!(!app.should_quit);
+    assert_eq!(app.view_state.message.as_deref(), Some("Save blocked: row 2 has no id"));
+
+    app.table.set_cell(1, 0, "7".to_string());
+    app.execute_command(Command::Write);
+    assert!(path.exists());
+}
+
+#[test]
+fn test_on_load_hook_actions_are_applied() {
+    let mut app = make_app(false);
+    let script = r#"
+        return {
+            name = "trim",
+            on_load = function(t)
+                t:set(2, 1, "loaded")
+                tabular.set_message("checked " .. t:rows() .. " rows")
+            end
+        }
+    "#;
+    app.plugin_manager.register_plugin(script).unwrap();
+    let message = app.run_load_hooks();
+    assert_eq!(message.as_deref(), Some("checked 2 rows"));
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "loaded");
+    // The hook's edits are part of the loaded file, not an unsaved change
+    assert!(!app.dirty);
+    assert!(!app.history.can_undo());
+}
+
+#[test]
+fn test_reload_runs_on_load_hooks() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.csv");
+    std::fs::write(&path, "a,b\n1,2\n").unwrap();
+    let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+    let table = file_io.load_table().unwrap().table;
+    let mut app = App::new(table, file_io);
+    let script = r#"
+        return {
+            name = "mark",
+            on_load = function(t)
+                t:set(2, 2, "hooked")
+            end
+        }
+    "#;
+    app.plugin_manager.register_plugin(script).unwrap();
+
+    app.execute_command(Command::Reload);
+    assert_eq!(app.table.get_row(1).unwrap(), ["1", "hooked"]);
+    assert!(!app.dirty);
+    assert!(!app.history.can_undo());
+}
+
+#[test]
+fn test_col_formula_fills_data_rows_in_one_undo_step() {
+    let table = Table::new(vec![
+        vec!["qty".to_string(), "price".to_string(), "total".to_string(), "next".to_string()],
+        vec!["2".to_string(), "3".to_string(), "".to_string(), "=A2+1".to_string()],
+        vec!["4".to_string(), "5".to_string(), "old".to_string(), "=A3+1".to_string()],
+    ]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    app.execute_command(Command::parse("col-formula C =A{row}*D{row}").unwrap());
+    assert_eq!(app.table.get_row(0).unwrap(), ["qty", "price", "total", "next"]);
+    assert_eq!(app.table.get_row(1).unwrap(), ["2", "3", "6", "=A2+1"]);
+    assert_eq!(app.table.get_row(2).unwrap(), ["4", "5", "20", "=A3+1"]);
+
+    press(&mut app, KeyCode::Char('u'));
+    assert_eq!(app.table.get_row(1).unwrap(), ["2", "3", "", "=A2+1"]);
+    assert_eq!(app.table.get_row(2).unwrap(), ["4", "5", "old", "=A3+1"]);
+}
+
+#[test]
+fn test_col_formula_on_large_sheet_runs_as_pending_job() {
+    // Wide rather than long, so the test only evaluates a single formula
+    let mut header = vec!["n".to_string(), "double".to_string()];
+    header.resize(25_000, String::new());
+    let mut data = vec!["21".to_string()];
+    data.resize(25_000, String::new());
+    let mut app = App::new(Table::new(vec![header, data]), FileIO::new(None, None, false).unwrap());
+    app.execute_command(Command::parse("col-formula B =A{row}*2").unwrap());
+    assert!(app.view_state.progress.is_some());
+    assert_eq!(app.table.get_cell(1, 1).unwrap(), "");
+
+    let op = app.view_state.pending_op.take().expect("fill is pending");
+    app.execute_pending_op(op);
+    assert!(app.view_state.progress.is_none());
+    assert_eq!(app.table.get_cell(1, 1).unwrap(), "42");
+}
+
+fn alt(app: &mut App, c: char) {
+    app.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT));
+}
+
+fn column(app: &App, col: usize) -> Vec<String> {
+    app.table.get_col_cloned(col).unwrap()
+}
+
+#[test]
+fn test_alt_j_moves_visual_row_block_down() {
+    let table = Table::new(["a", "b", "c", "d"].iter().map(|s| vec![s.to_string()]).collect());
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    app.header_mode = false;
+    press(&mut app, KeyCode::Char('j'));
+    press(&mut app, KeyCode::Char('V'));
+    press(&mut app, KeyCode::Char('j'));
+
+    alt(&mut app, 'j');
+    assert_eq!(column(&app, 0), vec!["a", "d", "b", "c"]);
+    // The selection follows the block and it can keep moving
+    assert_eq!(app.mode, Mode::VisualRow);
+    assert_eq!(app.view_state.view.get_selection_bounds().0, 2);
+    assert_eq!(app.view_state.view.cursor_row, 3);
+    alt(&mut app, 'j');
+    assert_eq!(column(&app, 0), vec!["a", "d", "b", "c"]);
+
+    alt(&mut app, 'k');
+    alt(&mut app, 'k');
+    assert_eq!(column(&app, 0), vec!["b", "c", "a", "d"]);
+    press(&mut app, KeyCode::Esc);
+
+    press(&mut app, KeyCode::Char('u'));
+    press(&mut app, KeyCode::Char('u'));
+    assert_eq!(column(&app, 0), vec!["a", "d", "b", "c"]);
+}
+
+#[test]
+fn test_alt_k_moves_current_row_up() {
+    let mut app = make_app(false);
+    app.header_mode = false;
+    press(&mut app, KeyCode::Char('j'));
+    alt(&mut app, 'k');
+    assert_eq!(column(&app, 0), vec!["1", "a"]);
+    assert_eq!(app.view_state.view.cursor_row, 0);
+
+    let mut app = make_app(true);
+    app.header_mode = false;
+    alt(&mut app, 'j');
+    assert_eq!(column(&app, 0), vec!["a", "1"]);
+    assert_eq!(app.view_state.message.as_deref(), Some("Read-only mode"));
+}
+
+#[test]
+fn test_row_moves_leave_header_in_place() {
+    let table = Table::new(["h", "a", "b"].iter().map(|s| vec![s.to_string()]).collect());
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    press(&mut app, KeyCode::Char('j'));
+    alt(&mut app, 'k');
+    assert_eq!(column(&app, 0), vec!["h", "a", "b"]);
+    assert_eq!(app.view_state.view.cursor_row, 1);
+
+    // A block that includes the header can't move either way
+    press(&mut app, KeyCode::Char('V'));
+    press(&mut app, KeyCode::Char('k'));
+    alt(&mut app, 'j');
+    assert_eq!(column(&app, 0), vec!["h", "a", "b"]);
+    assert!(!app.history.can_undo());
+}
+
+#[test]
+fn test_alt_l_moves_visual_col_block_right() {
+    let table = Table::new(vec![["a", "b", "c"].iter().map(|s| s.to_string()).collect()]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    ctrl(&mut app, 'v');
+    press(&mut app, KeyCode::Char('l'));
+
+    alt(&mut app, 'l');
+    assert_eq!(app.table.get_row_cloned(0).unwrap(), vec!["c", "a", "b"]);
+    assert_eq!(app.mode, Mode::VisualCol);
+    assert_eq!(app.view_state.view.get_selection_bounds().2, 1);
+    press(&mut app, KeyCode::Esc);
+
+    // The cursor follows a single column back to the left
+    alt(&mut app, 'h');
+    assert_eq!(app.table.get_row_cloned(0).unwrap(), vec!["c", "b", "a"]);
+    assert_eq!(app.view_state.view.cursor_col, 1);
+}
+
+#[test]
+fn test_sample_filter_is_undoable() {
+    use crate::table::rowmanager::{FilterType, SampleSize};
+    let table = Table::new((0..20).map(|i| vec![i.to_string()]).collect());
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    app.execute_command(Command::Filter(FilterType::Sample { size: SampleSize::Rows(3), seed: Some(9) }));
+    assert_eq!(app.view_state.message.as_deref(), Some("Sampled 3 of 19 rows"));
+    assert_eq!(app.view_state.row_manager.borrow().active_rows.len(), 4);
+
+    press(&mut app, KeyCode::Char('u'));
+    assert!(!app.view_state.row_manager.borrow().is_filtered);
+}
+
+#[test]
+fn test_relative_row_navigation_clamps_at_the_ends() {
+    let table = Table::new((0..10).map(|i| vec![i.to_string()]).collect());
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    let go = |app: &mut App, input: &str| {
+        app.execute_command(Command::parse(input).unwrap());
+        app.view_state.view.cursor_row
+    };
+    assert_eq!(go(&mut app, "+3"), 3);
+    assert_eq!(go(&mut app, "-1"), 2);
+    assert_eq!(go(&mut app, "+100"), 9);
+    assert_eq!(go(&mut app, "-100"), 0);
+    assert_eq!(go(&mut app, "$"), 9);
+    assert_eq!(go(&mut app, "4"), 3);
+
+    // Filtered views count the rows that are shown
+    app.execute_command(Command::parse("filter > 4").unwrap());
+    assert_eq!(go(&mut app, "2"), 5);
+    assert_eq!(go(&mut app, "+2"), 7);
+    assert_eq!(go(&mut app, "$"), 9);
+    assert_eq!(go(&mut app, "-100"), 0);
+}
+
+fn run_command(app: &mut App, input: &str) {
+    press(app, KeyCode::Char(':'));
+    for c in input.chars() {
+        press(app, KeyCode::Char(c));
+    }
+    press(app, KeyCode::Enter);
+}
+
+#[test]
+fn test_up_recalls_earlier_commands() {
+    let mut app = make_app(false);
+    run_command(&mut app, "w");
+    run_command(&mut app, "sort");
+    run_command(&mut app, "sort");
+
+    press(&mut app, KeyCode::Char(':'));
+    press(&mut app, KeyCode::Char('x'));
+    press(&mut app, KeyCode::Up);
+    assert_eq!(app.command_handler.buffer, "sort");
+    // Repeats are stored once, so the second Up reaches :w
+    press(&mut app, KeyCode::Up);
+    assert_eq!(app.command_handler.buffer, "w");
+    press(&mut app, KeyCode::Up);
+    assert_eq!(app.command_handler.buffer, "w");
+
+    // Down walks back to the line being typed
+    press(&mut app, KeyCode::Down);
+    assert_eq!(app.command_handler.buffer, "sort");
+    press(&mut app, KeyCode::Down);
+    assert_eq!(app.command_handler.buffer, "x");
+    press(&mut app, KeyCode::Esc);
+
+    // A fresh prompt starts browsing from the newest entry again
+    press(&mut app, KeyCode::Char(':'));
+    press(&mut app, KeyCode::Up);
+    assert_eq!(app.command_handler.buffer, "sort");
+}
+
+#[test]
+fn test_replace_dry_run_records_nothing() {
+    let mut app = make_app(true);
+    app.execute_command(Command::parse("%s/[0-9]/x/gn").unwrap());
+    assert_eq!(app.view_state.message.as_deref(), Some("2 matches across 2 cells"));
+    assert!(!app.history.can_undo());
+    assert!(!app.dirty);
+}
+
+#[test]
+fn test_confirmed_replace_asks_for_each_match() {
+    let table = Table::new(vec![
+        vec!["cat".to_string(), "cat".to_string()],
+        vec!["cat".to_string(), "cat".to_string()],
+    ]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    app.execute_command(Command::parse("%s/cat/dog/gc").unwrap());
+    assert_eq!(app.mode, Mode::Confirm);
+    assert_eq!(app.confirm_question(), "Replace \"cat\" with \"dog\"? (y/n/a/q)");
+
+    press(&mut app, KeyCode::Char('y'));
+    press(&mut app, KeyCode::Char('n'));
+    // The cursor sits on the match being asked about
+    assert_eq!((app.view_state.view.cursor_row, app.view_state.view.cursor_col), (1, 0));
+    // Nothing is written until the answers are done
+    assert_eq!(app.table.get_cell(0, 0).unwrap(), "cat");
+    press(&mut app, KeyCode::Char('y'));
+    press(&mut app, KeyCode::Esc);
+
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(app.view_state.message.as_deref(), Some("2 replacement(s) made"));
+    assert_eq!(app.table.get_row_cloned(0).unwrap(), vec!["dog", "cat"]);
+    assert_eq!(app.table.get_row_cloned(1).unwrap(), vec!["dog", "cat"]);
+
+    // All accepted replacements undo together
+    press(&mut app, KeyCode::Char('u'));
+    assert_eq!(app.table.get_row_cloned(0).unwrap(), vec!["cat", "cat"]);
+    assert_eq!(app.table.get_row_cloned(1).unwrap(), vec!["cat", "cat"]);
+
+    app.execute_command(Command::parse("%s/cat/dog/gc").unwrap());
+    press(&mut app, KeyCode::Char('n'));
+    press(&mut app, KeyCode::Char('a'));
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(app.table.get_row_cloned(0).unwrap(), vec!["cat", "dog"]);
+    assert_eq!(app.table.get_row_cloned(1).unwrap(), vec!["dog", "dog"]);
+}
+
+#[test]
+fn test_undo_delete_of_only_column_restores_it() {
+    let table = Table::new(vec![vec!["x".to_string()], vec!["y".to_string()], vec!["z".to_string()]]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+
+    run_command(&mut app, "delcol");
+    assert_eq!(app.table.col_count(), 1);
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["", "", ""]);
+
+    press(&mut app, KeyCode::Char('u'));
+    assert_eq!(app.table.col_count(), 1);
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["x", "y", "z"]);
+
+    app.handle_key(KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL));
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["", "", ""]);
+}
+
+#[test]
+fn test_stale_cursor_does_not_panic() {
+    let mut app = make_app(false);
+    app.view_state.view.cursor_row = 9;
+
+    press(&mut app, KeyCode::Char('i'));
+    assert_eq!(app.mode, Mode::Normal);
+    assert_eq!(app.view_state.view.cursor_row, 1);
+
+    app.view_state.view.cursor_col = 7;
+    press(&mut app, KeyCode::Char('x'));
+    assert_eq!(app.view_state.view.cursor_col, 1);
+    assert_eq!(app.table.get_row(1).unwrap(), ["1", "2"]);
+}
+
+fn filtered_app(values: &[&str], filter: &str) -> App {
+    let mut rows = vec![vec!["n".to_string()]];
+    rows.extend(values.iter().map(|v| vec![v.to_string()]));
+    let mut app = App::new(Table::new(rows), FileIO::new(None, None, false).unwrap());
+    app.execute_command(Command::parse(filter).unwrap());
+    app
+}
+
+fn active_rows(app: &App) -> Vec<usize> {
+    app.view_state.row_manager.borrow().active_rows.clone()
+}
+
+#[test]
+fn test_filtered_view_edits_and_deletes_shown_rows() {
+    let mut app = filtered_app(&["1", "2", "3", "4", "5", "6"], "filter > 3");
+    assert_eq!(active_rows(&app), vec![0, 4, 5, 6]);
+
+    // Cell edits are allowed and leave the filter alone
+    app.view_state.view.cursor_row = 5;
+    edit_first_cell(&mut app);
+    assert_eq!(app.table.get_cell(5, 0).unwrap(), "5x");
+    assert_eq!(active_rows(&app), vec![0, 4, 5, 6]);
+
+    // dd is refused, dr deletes the underlying row and the shown rows after it move up
+    app.view_state.view.cursor_row = 4;
+    press(&mut app, KeyCode::Char('d'));
+    press(&mut app, KeyCode::Char('d'));
+    assert_eq!(app.view_state.message.as_deref(), Some("Adding rows is forbidden in filtered views."));
+    assert_eq!(app.table.row_count(), 7);
+    press(&mut app, KeyCode::Char('d'));
+    press(&mut app, KeyCode::Char('r'));
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["n", "1", "2", "3", "5x", "6"]);
+    assert_eq!(active_rows(&app), vec![0, 4, 5]);
+    assert_eq!(app.view_state.view.cursor_row, 4);
+
+    press(&mut app, KeyCode::Char('u'));
+    assert_eq!(app.table.get_cell(4, 0).unwrap(), "4");
+    assert_eq!(active_rows(&app), vec![0, 4, 5, 6]);
+
+    // 2dr from the header deletes it and the next shown row, skipping the hidden ones
+    app.view_state.view.cursor_row = 0;
+    for c in ['2', 'd', 'r'] {
+        press(&mut app, KeyCode::Char(c));
+    }
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["1", "2", "3", "5x", "6"]);
+    assert_eq!(active_rows(&app), vec![3, 4]);
+    assert_eq!(app.view_state.view.cursor_row, 3);
+}
+
+#[test]
+fn test_filtered_view_survives_sort() {
+    let mut app = filtered_app(&["9", "1", "5", "2", "7"], "filter > 3");
+    assert_eq!(active_rows(&app), vec![0, 1, 3, 5]);
+
+    app.execute_command(Command::parse("sort").unwrap());
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["n", "1", "2", "5", "7", "9"]);
+    assert_eq!(active_rows(&app), vec![0, 3, 4, 5]);
+
+    press(&mut app, KeyCode::Char('u'));
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["n", "9", "1", "5", "2", "7"]);
+    assert_eq!(active_rows(&app), vec![0, 1, 3, 5]);
+}
+
+#[test]
+fn test_hidden_column_follows_column_changes() {
+    let table = Table::new(vec![
+        vec!["a".to_string(), "b".to_string(), "c".to_string()],
+    ]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    app.view_state.view.cursor_col = 2;
+    app.execute_command(Command::HideCol);
+
+    app.view_state.view.cursor_col = 0;
+    app.execute_command(Command::DeleteColumn);
+    assert_eq!(app.view_state.view.hidden_cols.iter().copied().collect::<Vec<_>>(), vec![1]);
+
+    // Undo puts the column back in its old place
+    press(&mut app, KeyCode::Char('u'));
+    assert_eq!(app.view_state.view.hidden_cols.iter().copied().collect::<Vec<_>>(), vec![2]);
+
+    app.execute_command(Command::Transpose);
+    assert!(app.view_state.view.hidden_cols.is_empty());
+}
+
+#[test]
+fn test_diff_columns_follow_column_changes() {
+    let table = Table::new(vec![
+        vec!["a".to_string(), "b".to_string(), "c".to_string()],
+    ]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    app.execute_command(Command::Diff(Some((1, 2))));
+
+    app.execute_command(Command::AddColumn);
+    assert_eq!(app.diff_cols, Some((2, 3)));
+
+    // Deleting a compared column ends the diff
+    app.view_state.view.cursor_col = 2;
+    app.execute_command(Command::DeleteColumn);
+    assert_eq!(app.diff_cols, None);
+}
+
+#[test]
+fn test_sort_that_changes_nothing_is_not_remembered() {
+    let mut app = make_app(false);
+    app.execute_command(Command::parse("sort C").unwrap());
+    assert_eq!(app.view_state.message.as_deref(), Some("Column C is out of range"));
+    app.execute_command(Command::parse("sort").unwrap());
+    assert_eq!(app.view_state.message.as_deref(), Some("Already sorted"));
+    app.execute_command(Command::SortReverse);
+    assert_eq!(app.view_state.message.as_deref(), Some("No previous sort"));
+    assert!(app.last_sort.is_none());
+}
+
+#[test]
+fn test_filtered_visual_delete_skips_hidden_rows() {
+    let mut app = filtered_app(&["5", "1", "6", "2", "7"], "filter > 3");
+    assert_eq!(active_rows(&app), vec![0, 1, 3, 5]);
+
+    app.view_state.view.cursor_row = 1;
+    press(&mut app, KeyCode::Char('V'));
+    press(&mut app, KeyCode::Char('j'));
+    press(&mut app, KeyCode::Char('j'));
+    press(&mut app, KeyCode::Char('d'));
+    press(&mut app, KeyCode::Char('d'));
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["n", "1", "2"]);
+    assert_eq!(active_rows(&app), vec![0]);
+    assert_eq!(app.view_state.view.cursor_row, 0);
+
+    press(&mut app, KeyCode::Char('u'));
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["n", "5", "1", "6", "2", "7"]);
+    assert_eq!(active_rows(&app), vec![0, 1, 3, 5]);
+
+    // Clearing a selection only touches the shown cells
+    app.view_state.view.cursor_row = 1;
+    press(&mut app, KeyCode::Char('v'));
+    press(&mut app, KeyCode::Char('j'));
+    press(&mut app, KeyCode::Char('x'));
+    assert_eq!(app.table.get_col_cloned(0).unwrap(), vec!["n", "", "1", "", "2", "7"]);
+}
+
+#[test]
+fn test_saveprogress_sets_background_save_threshold() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("small.csv");
+    let rows = vec![vec!["a".to_string()], vec!["1".to_string()]];
+    let mut app = App::new(Table::new(rows), FileIO::new(Some(path.clone()), None, false).unwrap());
+
+    app.execute_command(Command::Write);
+    assert!(!app.view_state.background_busy());
+
+    app.execute_command(Command::parse("set saveprogress 2").unwrap());
+    assert_eq!(app.view_state.message.as_deref(), Some("Background saves from 2 rows"));
+    app.execute_command(Command::Write);
+    assert!(app.view_state.background_busy());
+    finish_background(&mut app);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\n1\n");
+}
+
+#[test]
+fn test_large_paste_waits_for_render_with_progress() {
+    let mut app = make_app(false);
+    app.execute_command(Command::parse("set pasteprogress 2").unwrap());
+    assert_eq!(app.paste_progress, 2);
+
+    // yr then p: a two-cell row paste reaches the threshold and is deferred
+    press(&mut app, KeyCode::Char('y'));
+    press(&mut app, KeyCode::Char('r'));
+    app.view_state.view.cursor_row = 1;
+    press(&mut app, KeyCode::Char('p'));
+    assert!(app.view_state.progress.is_some());
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "1");
+
+    let op = app.view_state.pending_op.take().expect("paste is pending");
+    app.execute_pending_op(op);
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "a");
+    assert!(app.view_state.progress.is_none());
+    assert!(app.history.can_undo());
+
+    // Below the threshold a paste applies straight away
+    app.execute_command(Command::parse("set pasteprogress 10").unwrap());
+    press(&mut app, KeyCode::Char('u'));
+    press(&mut app, KeyCode::Char('p'));
+    assert!(app.view_state.pending_op.is_none());
+    assert_eq!(app.table.get_cell(1, 0).unwrap(), "a");
+}
+
+#[test]
+fn test_quit_refused_with_dirty_parked_buffer() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = dir.path().join("first.csv");
+    let second = dir.path().join("second.csv");
+    std::fs::write(&first, "a;b\n1;2\n").unwrap();
+    std::fs::write(&second, "c;d\n3;4\n").unwrap();
+    let mut app = App::new(Table::new(vec![vec!["a".to_string()]]), FileIO::new(Some(first), None, false).unwrap());
+    app.default_delimiter = Some(b';');
+
+    edit_first_cell(&mut app);
+    app.execute_command(Command::OpenBuffer { path: second.to_string_lossy().to_string(), force: false });
+    assert_eq!(app.buffer_number(), 1);
+
+    app.execute_command(Command::OpenBuffer { path: second.to_string_lossy().to_string(), force: true });
+    assert_eq!(app.buffer_number(), 2);
+    assert_eq!(app.table.get_cell(0, 1).unwrap(), "d");
+
+    app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+    assert!(!app.should_quit);
+    app.execute_command(Command::Quit);
+    app.execute_command(Command::WriteQuit);
+    assert!(!app.should_quit);
+    assert!(app.view_state.message.as_deref().unwrap().contains("buffer 1"));
+}
+
+#[test]
+fn test_read_only_is_per_buffer() {
+    let dir = tempfile::tempdir().unwrap();
+    let other = dir.path().join("other.csv");
+    std::fs::write(&other, "a,b\n1,2\n").unwrap();
+    let mut app = make_app(true);
+
+    app.execute_command(Command::OpenBuffer { path: other.to_string_lossy().to_string(), force: false });
+    assert!(!app.read_only);
+    app.execute_command(Command::CycleBuffer { forward: true, force: false });
+    assert!(app.read_only);
+}
+
+#[test]
+fn test_set_delimiter_undoes_with_the_cells() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.csv");
+    std::fs::write(&path, "a;b,c\n1;2,3\n").unwrap();
+    let mut file_io = FileIO::new(Some(path), Some(b','), false).unwrap();
+    let table = file_io.load_table().unwrap().table;
+    let mut app = App::new(table, file_io);
+
+    // Edited, so the cells are written out with commas and re-split; "x,y" is quoted, not split apart
+    app.execute(Transaction::SetCell { row: 1, col: 1, old_value: "3".to_string(), new_value: "x,y".to_string() });
+    app.execute_command(Command::SetDelimiter(b';'));
+    assert_eq!(app.file_io.delimiter(), b';');
+    assert_eq!(app.table.get_row_cloned(1).unwrap(), vec!["1", "2,\"x,y\""]);
+
+    app.execute(Transaction::Undo);
+    assert_eq!(app.file_io.delimiter(), b',');
+    assert_eq!(app.table.get_row_cloned(1).unwrap(), vec!["1;2", "x,y"]);
+    app.execute(Transaction::Redo);
+    assert_eq!(app.file_io.delimiter(), b';');
+}
+
+
+
+#[test]
+fn test_export_json_follows_header_mode() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.csv");
+    std::fs::write(&path, "1,2\n3,4\n").unwrap();
+    let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+    let table = file_io.load_table().unwrap().table;
+    let mut app = App::new(table, file_io);
+
+    app.execute_command(Command::parse("header").unwrap());
+    app.execute_command(Command::parse("export json").unwrap());
+    let exported = FileIO::load_json(&dir.path().join("data.json")).unwrap();
+    assert_eq!(exported.get_row_cloned(0).unwrap(), vec!["A", "B"]);
+    assert_eq!(exported.get_row_cloned(1).unwrap(), vec!["1", "2"]);
+    assert_eq!(exported.get_row_cloned(2).unwrap(), vec!["3", "4"]);
+}
@@ -0,0 +1,203 @@
+//! Multiple open files for App
+//!
+//! The active buffer lives directly in App's fields; the others are parked
+//! in `App::buffers` and swapped in when switched to.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::app::App;
+use crate::fileio::FileIO;
+use crate::table::rowmanager::RowManager;
+use crate::table::sort::SortSpec;
+use crate::table::table::Table;
+use crate::table::tableview::TableView;
+use crate::transaction::history::History;
+
+/// Per-file state that is parked while another buffer is active
+pub struct Buffer {
+    pub table: Table,
+    pub file_io: FileIO,
+    pub history: History,
+    pub view: TableView,
+    pub row_manager: Rc<RefCell<RowManager>>,
+    pub dirty: bool,
+    pub read_only: bool,
+    pub header_mode: bool,
+    pub last_sort: Option<SortSpec>,
+    pub marks: HashMap<char, (usize, usize)>,
+}
+
+impl Buffer {
+    pub fn new(table: Table, file_io: FileIO) -> Self {
+        let row_manager = Rc::new(RefCell::new(RowManager::new()));
+        let read_only = file_io.is_read_only();
+        Self {
+            table,
+            file_io,
+            history: History::new(),
+            view: TableView::new(row_manager.clone()),
+            row_manager,
+            dirty: false,
+            read_only,
+            header_mode: true,
+            last_sort: None,
+            marks: HashMap::new(),
+        }
+    }
+}
+
+impl App {
+    /// Number of open buffers, including the active one
+    pub fn buffer_count(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// 1-based position of the active buffer
+    pub fn buffer_number(&self) -> usize {
+        self.active_buffer + 1
+    }
+
+    /// Index of the first parked buffer with unsaved changes
+    pub fn dirty_parked_buffer(&self) -> Option<usize> {
+        self.buffers.iter().position(|b| b.as_ref().is_some_and(|b| b.dirty))
+    }
+
+    /// Why quitting now would lose work, if it would: unsaved changes in the active buffer or a parked one
+    pub fn unsaved_changes_message(&self) -> Option<String> {
+        if self.dirty {
+            Some("Unsaved changes! Use :q! to force quit".to_string())
+        } else {
+            self.dirty_parked_buffer()
+                .map(|idx| format!("Unsaved changes in buffer {}! Use :q! to force quit", idx + 1))
+        }
+    }
+
+    /// Load a file into a new buffer and make it active
+    /// Refuses to leave a buffer with unsaved changes unless forced
+    pub fn open_buffer(&mut self, path: PathBuf, force: bool) {
+        if self.dirty && !force {
+            self.view_state.message = Some("Unsaved changes! Use :w to save or :e! <path> to open anyway".to_string());
+            return;
+        }
+        if self.view_state.background_busy() {
+            self.view_state.message = Some("Wait for the running operation to finish".to_string());
+            return;
+        }
+        let max_rows = self.file_io.max_rows();
+        let backup = self.file_io.backup();
+        let loaded = FileIO::new(Some(path), self.default_delimiter, false).and_then(|mut file_io| {
+            file_io.set_max_rows(max_rows);
+            file_io.set_backup(backup);
+            let load_result = file_io.load_table()?;
+            Ok((file_io, load_result))
+        });
+        match loaded {
+            Ok((file_io, load_result)) => {
+                self.buffers.push(Some(Buffer::new(load_result.table, file_io)));
+                self.activate_buffer(self.buffers.len() - 1);
+                let mut messages = vec![self.buffer_label()];
+                messages.extend(load_result.warnings);
+                messages.extend(self.run_load_hooks());
+                self.view_state.message = Some(messages.join("; "));
+            }
+            Err(e) => self.view_state.message = Some(format!("Error opening file: {}", e)),
+        }
+    }
+
+    /// Load the active buffer's file again from disk, dropping unsaved changes and undo history
+    /// Marks, the last sort and hidden or frozen columns are dropped too, as in a newly opened
+    /// buffer, since they may not fit the file's new contents (the new table has no declared types)
+    pub fn reload_file(&mut self) {
+        if self.file_io.file_path.is_none() {
+            self.view_state.message = Some("No file to reload".to_string());
+            return;
+        }
+        if self.view_state.background_busy() {
+            self.view_state.message = Some("Wait for the running operation to finish".to_string());
+            return;
+        }
+        match self.file_io.load_table() {
+            Ok(load_result) => {
+                self.table = load_result.table;
+                self.history.clear();
+                self.dirty = false;
+                self.edits += 1;
+                self.diff_cols = None;
+                self.marks.clear();
+                self.last_sort = None;
+                self.view_state.view.hidden_cols.clear();
+                self.view_state.view.frozen_cols = 0;
+                *self.view_state.row_manager.borrow_mut() = RowManager::new();
+                self.view_state.view.clamp_cursor(&self.table);
+                self.view_state.view.scroll_to_cursor();
+                let mut messages = vec![format!("Reloaded {}", self.file_io.file_name())];
+                messages.extend(load_result.warnings);
+                messages.extend(self.run_load_hooks());
+                self.view_state.message = Some(messages.join("; "));
+            }
+            Err(e) => self.view_state.message = Some(format!("Error reloading: {}", e)),
+        }
+    }
+
+    /// Cycle to the next (or previous) buffer
+    /// Refuses to leave a buffer with unsaved changes unless forced
+    pub fn cycle_buffer(&mut self, forward: bool, force: bool) {
+        let count = self.buffers.len();
+        if count <= 1 {
+            self.view_state.message = Some("Only one buffer open".to_string());
+            return;
+        }
+        if self.dirty && !force {
+            self.view_state.message = Some("Unsaved changes! Use :w to save or add ! to switch anyway".to_string());
+            return;
+        }
+        if self.view_state.background_busy() {
+            self.view_state.message = Some("Wait for the running operation to finish".to_string());
+            return;
+        }
+        let next = if forward {
+            (self.active_buffer + 1) % count
+        } else {
+            (self.active_buffer + count - 1) % count
+        };
+        self.activate_buffer(next);
+        self.view_state.message = Some(self.buffer_label());
+    }
+
+    /// Status label for the active buffer, e.g. "[2/3] sales.csv"
+    pub fn buffer_label(&self) -> String {
+        let name = self.file_io.file_path
+            .as_ref()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| "[No File]".to_string());
+        format!("[{}/{}] {}", self.buffer_number(), self.buffer_count(), name)
+    }
+
+    /// Park the active buffer and swap in buffer idx
+    fn activate_buffer(&mut self, idx: usize) {
+        if idx == self.active_buffer {
+            return;
+        }
+        let Some(incoming) = self.buffers[idx].take() else { return; };
+        let outgoing = Buffer {
+            table: mem::replace(&mut self.table, incoming.table),
+            file_io: mem::replace(&mut self.file_io, incoming.file_io),
+            history: mem::replace(&mut self.history, incoming.history),
+            view: mem::replace(&mut self.view_state.view, incoming.view),
+            row_manager: mem::replace(&mut self.view_state.row_manager, incoming.row_manager),
+            dirty: mem::replace(&mut self.dirty, incoming.dirty),
+            read_only: mem::replace(&mut self.read_only, incoming.read_only),
+            header_mode: mem::replace(&mut self.header_mode, incoming.header_mode),
+            last_sort: mem::replace(&mut self.last_sort, incoming.last_sort),
+            marks: mem::replace(&mut self.marks, incoming.marks),
+        };
+        self.buffers[self.active_buffer] = Some(outgoing);
+        self.active_buffer = idx;
+        self.diff_cols = None;
+        self.formula_cache = None;
+    }
+}
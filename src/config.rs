@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
 use crate::input::SequenceAction;
 
 type ActionBuilder = fn(char) -> SequenceAction;
@@ -85,6 +89,7 @@ impl Default for CommandTable {
                 (KeySequence::Two('y', 'r'), SequenceAction::YankRow),
                 (KeySequence::Two('y', 'c'), SequenceAction::YankCol),
                 (KeySequence::Two('y', 'y'), SequenceAction::Yank),
+                (KeySequence::Two('y', 'p'), SequenceAction::DuplicateRow),
 
                 (KeySequence::One('j'), SequenceAction::MoveDown),
                 (KeySequence::One('k'), SequenceAction::MoveUp),
@@ -96,22 +101,214 @@ impl Default for CommandTable {
                 (KeySequence::Two('f', '$'), SequenceAction::FormatCurrency),
                 (KeySequence::Two('f', 'e'), SequenceAction::FormatScientific),
                 (KeySequence::Two('f', '%'), SequenceAction::FormatPercentage),
+
+                (KeySequence::Two('g', 'u'), SequenceAction::LowerCase),
+                (KeySequence::Two('g', 'U'), SequenceAction::UpperCase),
+                (KeySequence::Two('g', '~'), SequenceAction::ToggleCase),
             ]),
             wildcard_map: HashMap::from([
-                (KeySequence::One('^'), SequenceAction::SelectRegister as ActionBuilder)
+                (KeySequence::One('"'), SequenceAction::SelectRegister as ActionBuilder),
+                (KeySequence::One('m'), SequenceAction::SetMark as ActionBuilder),
+                (KeySequence::One('`'), SequenceAction::JumpToMark as ActionBuilder),
             ])
         }
     }
 }
 
+/// Directory holding tabular's config files (~/.config/tabular)
+fn config_dir() -> PathBuf {
+    if let Some(home) = std::env::var_os("HOME") {
+        PathBuf::from(home).join(".config/tabular")
+    } else {
+        PathBuf::from(".config/tabular")
+    }
+}
+
+/// View settings persisted in ~/.config/tabular/config.toml
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Option<String>,
+    pub grid: Option<bool>,
+    pub precision: Option<usize>,
+    pub delimiter: Option<String>,  // Default delimiter when none is given on the command line
+}
+
+impl Settings {
+    /// Location of the config file
+    pub fn path() -> PathBuf {
+        config_dir().join("config.toml")
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    /// Load settings from a file; a missing file gives the defaults
+    /// A malformed file also gives the defaults, along with a warning
+    pub fn load(path: &Path) -> (Self, Option<String>) {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (Self::default(), None),
+            Err(e) => return (Self::default(), Some(format!("Could not read {}: {}", path.display(), e))),
+        };
+        match Self::parse(&content) {
+            Ok(settings) => (settings, None),
+            Err(e) => (Self::default(), Some(format!("Ignoring malformed {}: {}", path.display(), e.trim()))),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = toml::to_string(self).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(path, content).map_err(|e| e.to_string())
+    }
+}
+
+/// Settings remembered for one file between sessions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileSettings {
+    pub delimiter: u8,
+    pub header_mode: bool,
+    pub cursor_row: usize,
+    pub cursor_col: usize,
+}
+
+/// Per-file settings keyed by absolute path, stored in ~/.config/tabular/files.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FileHistory {
+    files: BTreeMap<String, FileSettings>,
+}
+
+impl FileHistory {
+    pub fn path() -> PathBuf {
+        config_dir().join("files.json")
+    }
+
+    /// Load the history; a missing or unreadable file gives an empty history
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    /// Absolute form of a file path used as the history key
+    fn key(file: &Path) -> Option<String> {
+        std::fs::canonicalize(file)
+            .or_else(|_| std::path::absolute(file))
+            .ok()
+            .map(|p| p.display().to_string())
+    }
+
+    pub fn get(&self, file: &Path) -> Option<&FileSettings> {
+        self.files.get(&Self::key(file)?)
+    }
+
+    pub fn remember(&mut self, file: &Path, settings: FileSettings) {
+        if let Some(key) = Self::key(file) {
+            self.files.insert(key, settings);
+        }
+    }
+}
+
 pub struct AppConfig {
-    pub commands: CommandTable
+    pub commands: CommandTable,
+    pub settings: Settings,
 }
 
 impl AppConfig {
     pub fn new() -> Self {
         Self {
-            commands: CommandTable::default()
+            commands: CommandTable::default(),
+            settings: Settings::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_parse() {
+        let settings = Settings::parse("theme = \"dark\"\ngrid = true\nprecision = 2\ndelimiter = \"tab\"\n").unwrap();
+        assert_eq!(settings.theme.as_deref(), Some("dark"));
+        assert_eq!(settings.grid, Some(true));
+        assert_eq!(settings.precision, Some(2));
+        assert_eq!(settings.delimiter.as_deref(), Some("tab"));
+    }
+
+    #[test]
+    fn test_settings_missing_keys_default() {
+        let settings = Settings::parse("grid = false\n").unwrap();
+        assert_eq!(settings, Settings { grid: Some(false), ..Settings::default() });
+        assert_eq!(Settings::parse("").unwrap(), Settings::default());
+    }
+
+    #[test]
+    fn test_settings_malformed_falls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "precision = \"lots\"").unwrap();
+
+        let (settings, warning) = Settings::load(&path);
+        assert_eq!(settings, Settings::default());
+        assert!(warning.unwrap().contains("malformed"));
+
+        let (settings, warning) = Settings::load(&dir.path().join("missing.toml"));
+        assert_eq!(settings, Settings::default());
+        assert!(warning.is_none());
+    }
+
+    #[test]
+    fn test_file_history_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("sales.csv");
+        std::fs::write(&csv, "a;b\n1;2\n").unwrap();
+        let settings = FileSettings { delimiter: b';', header_mode: false, cursor_row: 1, cursor_col: 1 };
+
+        let mut history = FileHistory::default();
+        history.remember(&csv, settings.clone());
+        let path = dir.path().join("files.json");
+        history.save(&path).unwrap();
+
+        let history = FileHistory::load(&path);
+        assert_eq!(history.get(&csv), Some(&settings));
+        assert_eq!(history.get(&dir.path().join("other.csv")), None);
+    }
+
+    #[test]
+    fn test_file_history_ignores_garbage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("files.json");
+        std::fs::write(&path, "not json").unwrap();
+        assert!(FileHistory::load(&path).files.is_empty());
+        assert!(FileHistory::load(&dir.path().join("missing.json")).files.is_empty());
+    }
+
+    #[test]
+    fn test_settings_save_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tabular/config.toml");
+        let settings = Settings {
+            theme: Some("solarized-dark".to_string()),
+            grid: Some(true),
+            precision: None,
+            delimiter: Some(";".to_string()),
+        };
+        settings.save(&path).unwrap();
+        assert_eq!(Settings::load(&path), (settings, None));
+    }
+}
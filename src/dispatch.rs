@@ -4,29 +4,54 @@
 //! including command dispatch, plugin execution, sorting, and replace operations.
 
 use std::cmp;
+use std::sync::mpsc;
+use std::thread;
 
 use crate::app::App;
-use crate::viewstate::PendingOp;
-use crate::numeric::calculator::Calculator;
+use crate::viewstate::{BackgroundResult, PendingOp};
+use crate::numeric::calculator::{Calculator, LARGE_SHEET_CELLS, expand_row_template};
 use crate::mode::command::Command;
-use crate::mode::visual::SelectionInfo;
+use crate::mode::visual::{CaseOp, FormatOp, SelectionInfo, bump_amount, create_bump_txn, create_case_txn, create_format_txn};
 use crate::mode::Mode;
 use crate::input::{KeyResult, SequenceAction};
-use crate::plugin::{PluginAction, PluginContext};
+use crate::plugin::{PluginAction, PluginContext, PluginPrompt};
 use crate::table::SortDirection;
+use crate::table::sort::SortSpec;
+use crate::util::{ColumnType, display_width, letters_from_col};
+use crate::ui::ui::CellAlign;
+use crate::config::Settings;
+use crate::numeric::format::{format_default, parse_numeric};
+use crate::fileio::{delimiter_label, join_delimited, split_delimited, ExportFormat, LineEnding, Quoting};
 use crate::table::rowmanager::FilterType;
-use crate::table::operations::{sort_by_column, sort_by_row, replace};
+use crate::table::operations::{ReplaceConfirm, live_rows, delete_rows, find_replace_matches, sort_by_columns, sort_by_row, replace, aggregate, describe_column, group_by, duplicate_rows, dedup_rows, merge_cols, reverse_rows, reverse_cols, shuffle_rows, index_column, count_mismatches};
 use crate::transaction::transaction::Transaction;
 use crate::transaction::clipboard::RegisterContent;
 
 impl App {
     pub fn execute_sequence_action(&mut self, action: SequenceAction, count: usize) {
+        if action.modifies_table() && self.refuse_read_only() {
+            return;
+        }
         match action {
             SequenceAction::SelectRegister(reg) => {
                 if let Err(e) = self.clipboard.select_register(reg) {
                     self.view_state.message = Some(e);
                 }
             }
+            SequenceAction::SetMark(name) => {
+                let view = &self.view_state.view;
+                self.marks.insert(name, (view.cursor_row, view.cursor_col));
+                self.view_state.message = Some(format!("Mark {} set", name));
+            }
+            SequenceAction::JumpToMark(name) => match self.marks.get(&name) {
+                Some(&(row, col)) => {
+                    self.view_state.view.cursor_row = row;
+                    self.view_state.view.cursor_col = col;
+                    self.view_state.view.clamp_cursor(&self.table);
+                    self.view_state.view.scroll_to_cursor();
+                }
+                None => self.view_state.message = Some(format!("Mark {} not set", name)),
+            },
             SequenceAction::DeleteRow => {
                 let start_row = self.view_state.view.cursor_row;
                 let actual_count = count.min(self.table.row_count().saturating_sub(start_row));
@@ -36,7 +61,16 @@ impl App {
                 }
 
                 if self.view_state.row_manager.borrow().is_filtered {
-                    self.view_state.message = Some("Delete is forbidden in filtered views.".to_string());
+                    // Delete the shown rows from the cursor down, not the hidden ones in between
+                    let end_row = self.table.row_count() - 1;
+                    let live: Vec<usize> = live_rows(&self.view_state.view, start_row, end_row).into_iter().take(count).collect();
+                    if let Some((txn, rows)) = delete_rows(&self.table, &live) {
+                        self.clipboard.store_deleted(RegisterContent::from_rows(rows));
+                        self.execute(txn);
+                    }
+                    self.view_state.view.clamp_cursor(&self.table);
+                    let msg = if live.len() == 1 { "Row deleted".to_string() } else { format!("{} rows deleted", live.len()) };
+                    self.view_state.message = Some(msg);
                     return;
                 }
                 let rows = self.table.get_rows_cloned(start_row, actual_count);
@@ -82,6 +116,9 @@ impl App {
                 let msg = if actual_count == 1 { "Column deleted".to_string() } else { format!("{} columns deleted", actual_count) };
                 self.view_state.message = Some(msg);
             }
+            SequenceAction::DuplicateRow => {
+                self.duplicate_current_rows(count);
+            }
             SequenceAction::YankRow => {
                 let start_row = self.view_state.view.cursor_row;
                 let actual_count = count.min(self.table.row_count().saturating_sub(start_row));
@@ -93,7 +130,10 @@ impl App {
                     self.table.get_rows_cloned(start_row, actual_count)
                 };
                 if !rows.is_empty() {
-                    self.clipboard.yank_rows(rows);
+                    if let Err(e) = self.clipboard.yank_rows(rows) {
+                        self.view_state.message = Some(e);
+                        return;
+                    }
                 }
                 let msg = if actual_count == 1 { "Row yanked".to_string() } else { format!("{} rows yanked", actual_count) };
                 self.view_state.message = Some(msg);
@@ -111,15 +151,21 @@ impl App {
                     })
                     .collect();
                 if !cols.is_empty() {
-                    self.clipboard.yank_cols(cols);
+                    if let Err(e) = self.clipboard.yank_cols(cols) {
+                        self.view_state.message = Some(e);
+                        return;
+                    }
                 }
                 let msg = if actual_count == 1 { "Column yanked".to_string() } else { format!("{} columns yanked", actual_count) };
                 self.view_state.message = Some(msg);
             }
             SequenceAction::Yank => {
-                if let Some(row) = self.table.get_row_cloned(self.view_state.view.cursor_row) {
-                    self.clipboard.yank_span(vec![vec![row[self.view_state.view.cursor_col].clone()]]);
-                    self.view_state.message = Some("Row yanked".to_string());
+                let view = &self.view_state.view;
+                if let Some(value) = self.table.get_cell(view.cursor_row, view.cursor_col).cloned() {
+                    let msg = match self.clipboard.yank_cell(value) {
+                        Ok(msg) | Err(msg) => msg,
+                    };
+                    self.view_state.message = Some(msg);
                 }
             }
             SequenceAction::Delete => {
@@ -143,13 +189,42 @@ impl App {
             | SequenceAction::MoveUp
             | SequenceAction::MoveLeft
             | SequenceAction::MoveRight => {
+                let before = (self.view_state.view.cursor_row, self.view_state.view.cursor_col);
                 self.nav_handler.handle_sequence(action, count, &mut self.view_state.view, &self.table);
+                if matches!(action, SequenceAction::MoveDown | SequenceAction::MoveRight) {
+                    self.grow_if_stuck(before, action == SequenceAction::MoveDown);
+                }
+            }
+            SequenceAction::LowerCase
+            | SequenceAction::UpperCase
+            | SequenceAction::ToggleCase => {
+                let (row, col) = (self.view_state.view.cursor_row, self.view_state.view.cursor_col);
+                let op = CaseOp::from_action(action).expect("case action");
+                if let Some(txn) = create_case_txn(&self.table, &[row], (col, col), op) {
+                    self.execute(txn);
+                }
+            }
+            SequenceAction::Increment
+            | SequenceAction::Decrement
+            | SequenceAction::IncrementSeries
+            | SequenceAction::DecrementSeries => {
+                let (row, col) = (self.view_state.view.cursor_row, self.view_state.view.cursor_col);
+                let (delta, _) = bump_amount(action, count).expect("bump action");
+                if let Some(txn) = create_bump_txn(&self.table, &[row], (col, col), delta, false) {
+                    self.execute(txn);
+                }
             }
             SequenceAction::FormatDefault
             | SequenceAction::FormatCommas
             | SequenceAction::FormatCurrency
             | SequenceAction::FormatScientific
-            | SequenceAction::FormatPercentage => {}
+            | SequenceAction::FormatPercentage => {
+                let (row, col) = (self.view_state.view.cursor_row, self.view_state.view.cursor_col);
+                let op = FormatOp::from_action(action).expect("format action");
+                if let Some(txn) = create_format_txn(&self.table, &[row], (col, col), op) {
+                    self.execute(txn);
+                }
+            }
         }
     }
 
@@ -166,9 +241,20 @@ impl App {
                     self.calling_mode = prev_mode;
                     self.command_handler.start();
                 } else if mode == Mode::Insert {
-                    let current = crate::table::operations::current_cell(&self.view_state.view, &self.table).clone();
-                    let old_width = self.table.col_widths.lock().unwrap().get_col_width(self.view_state.view.cursor_col);
+                    if self.refuse_read_only() {
+                        self.mode = Mode::Normal;
+                        return;
+                    }
+                    let Some(current) = crate::table::operations::current_cell(&self.view_state.view, &self.table).cloned() else {
+                        // A stale cursor left outside the table; put it back rather than editing nothing
+                        self.view_state.view.clamp_cursor(&self.table);
+                        self.mode = Mode::Normal;
+                        self.view_state.message = Some("Cursor was outside the table".to_string());
+                        return;
+                    };
+                    let old_width = self.table.col_width(self.view_state.view.cursor_col);
                     self.insert_handler.start_edit(current, old_width);
+                    self.refresh_edit_popup();
                 } else if mode == Mode::Search {
                     self.search_handler.start_search();
                 }
@@ -179,14 +265,32 @@ impl App {
             KeyResult::ExecuteAndFinish(txn) => {
                 self.execute_and_finish(txn);
             }
+            KeyResult::ExecuteAndReport(txn, msg) => {
+                self.execute_and_finish(txn);
+                self.view_state.message = Some(msg);
+            }
+            KeyResult::Paste(txn, msg) => {
+                self.execute_paste(txn, msg);
+                self.finish_edit();
+            }
+            KeyResult::ExecuteAndEdit(txn) => {
+                self.execute_and_finish(txn);
+                self.process_key_result(KeyResult::SwitchMode(Mode::Insert));
+                self.insert_handler.change = self.mode == Mode::Insert;
+            }
             KeyResult::Message(msg) => {
                 self.view_state.message = Some(msg);
             }
+            KeyResult::FilterRows { start, end } => {
+                self.filter_to_rows(start, end);
+            }
+            KeyResult::InspectCell => {
+                self.inspect_cell();
+            }
             KeyResult::Quit => {
-                if self.dirty {
-                    self.view_state.message = Some("Unsaved changes! Use :q! to force quit".to_string());
-                } else {
-                    self.should_quit = true;
+                match self.unsaved_changes_message() {
+                    Some(message) => self.view_state.message = Some(message),
+                    None => self.should_quit = true,
                 }
             }
             KeyResult::ForceQuit => {
@@ -196,7 +300,49 @@ impl App {
     }
 
     pub fn execute_command(&mut self, cmd: Command) {
+        if cmd.modifies_table() && self.refuse_read_only() {
+            return;
+        }
         match cmd {
+            Command::Write | Command::ForceWrite | Command::WriteQuit | Command::WriteAs { .. }
+                if self.view_state.background_busy() =>
+            {
+                self.view_state.message = Some("Wait for the running operation to finish".to_string());
+            }
+            Command::Write | Command::WriteQuit if self.read_only => {
+                self.view_state.message = Some("Read-only mode (use :w! to save anyway)".to_string());
+            }
+            Command::Write | Command::WriteQuit if self.file_io.has_changed() => {
+                self.view_state.message = Some("File changed on disk; :w! to overwrite or :e! to reload".to_string());
+            }
+            Command::Write | Command::WriteQuit if self.file_io.is_partial() => {
+                self.view_state.message = Some(format!(
+                    "Only {} rows of {} are loaded; saving would drop the rest (:loadmore reads more, :w! saves just these)",
+                    self.table.row_count(),
+                    self.file_io.file_name()
+                ));
+            }
+            Command::Write | Command::ForceWrite | Command::WriteQuit | Command::WriteAs { .. } if self.refuse_by_save_hooks() => {}
+            Command::ForceWrite => {
+                let was_read_only = self.read_only;
+                let was_partial = self.file_io.is_partial();
+                self.read_only = false;
+                self.file_io.set_read_only(false);
+                match self.file_io.write(&self.table) {
+                    Ok(()) => {
+                        self.dirty = false;
+                        self.view_state.message = Some(if was_partial {
+                            format!("Saved only the {} loaded rows to {}; the rest of the file was dropped", self.table.row_count(), self.file_io.file_name())
+                        } else if was_read_only {
+                            format!("Read-only mode turned off; saved to {}", self.file_io.file_name())
+                        } else {
+                            format!("Saved to {}", self.file_io.file_name())
+                        });
+                    }
+                    Err(e) => self.view_state.message = Some(format!("Error saving: {}", e)),
+                }
+            }
+            Command::Write if self.table.row_count() >= self.save_progress => self.save_in_background(),
             Command::Write => {
                 match self.file_io.write(&mut self.table) {
                     Ok(()) => {
@@ -206,23 +352,47 @@ impl App {
                     Err(e) => self.view_state.message = Some(format!("Error saving: {}", e)),
                 }
             }
+            Command::WriteAs { path, force } => {
+                let was_partial = self.file_io.is_partial();
+                match self.file_io.save_as(&self.table, std::path::Path::new(&path), force) {
+                    Ok(path) if was_partial => {
+                        self.dirty = false;
+                        self.view_state.message = Some(format!(
+                            "Saved only the {} loaded rows to {}; the rest of the original file is not included",
+                            self.table.row_count(),
+                            path.display()
+                        ));
+                    }
+                    Ok(path) => {
+                        self.dirty = false;
+                        self.view_state.message = Some(format!("Saved to {}", path.display()));
+                    }
+                    Err(e) => self.view_state.message = Some(format!("Error saving: {}", e)),
+                }
+            }
             Command::Quit => {
-                if self.dirty {
-                    self.view_state.message = Some("Unsaved changes! Use :q! to force quit".to_string());
-                } else {
-                    self.should_quit = true;
+                match self.unsaved_changes_message() {
+                    Some(message) => self.view_state.message = Some(message),
+                    None => self.should_quit = true,
                 }
             }
             Command::ForceQuit => self.should_quit = true,
+            Command::OpenBuffer { path, force } => self.open_buffer(std::path::PathBuf::from(path), force),
+            Command::LoadMore => self.load_more_rows(),
+            Command::Reload => self.reload_file(),
+            Command::CycleBuffer { forward, force } => self.cycle_buffer(forward, force),
             Command::WriteQuit => {
                 match self.file_io.write(&mut self.table) {
                     Ok(()) => {
                         self.dirty = false;
-                        self.view_state.message = Some(format!("Saved to {}", self.file_io.file_name()));
+                        // Parked buffers aren't saved by :wq, so they still block quitting
+                        match self.unsaved_changes_message() {
+                            Some(message) => self.view_state.message = Some(message),
+                            None => self.should_quit = true,
+                        }
                     }
                     Err(e) => self.view_state.message = Some(format!("Error saving: {}", e)),
                 }
-                self.should_quit = true;
             }
             Command::AddColumn => {
                 let txn = Transaction::InsertCol { idx: self.view_state.view.cursor_col + 1 };
@@ -249,7 +419,7 @@ impl App {
             }
             Command::Calc => {
                 let cell_count = self.table.row_count() * self.table.col_count();
-                if cell_count >= 50_000 {
+                if cell_count >= LARGE_SHEET_CELLS {
                     self.view_state.start_progress("Calculating", cell_count);
                     self.view_state.pending_op = Some(PendingOp::Calc { formula_count: cell_count });
                 } else {
@@ -277,59 +447,59 @@ impl App {
                     }
                 }
             }
+            Command::ColFormula { col, template } => self.apply_column_formula(col, &template),
             Command::Grid => self.view_state.style.toggle_grid(),
-            Command::NavigateRow(row) => {
-                self.view_state.view.cursor_row = row;
-                self.view_state.view.clamp_cursor(&self.table);
+            Command::NavigateRow(row) => self.view_state.view.goto_row(row, &self.table),
+            Command::NavigateRowBy(offset) => {
+                let view = &mut self.view_state.view;
+                match offset.cmp(&0) {
+                    cmp::Ordering::Greater => view.move_down_n(offset.unsigned_abs(), &self.table),
+                    cmp::Ordering::Less => view.move_up_n(offset.unsigned_abs()),
+                    cmp::Ordering::Equal => {}
+                }
             }
+            Command::NavigateLastRow => self.view_state.view.move_to_bottom(&self.table),
             Command::NavigateCell(cell) => {
                 self.view_state.view.cursor_row = cell.row;
                 self.view_state.view.cursor_col = cell.col;
                 self.view_state.view.clamp_cursor(&self.table);
             }
-            Command::Sort | Command::SortDesc | Command::SortRow | Command::SortRowDesc
-                if self.view_state.row_manager.borrow().is_filtered =>
-            {
-                self.view_state.message = Some("Sort is forbidden in filtered views.".to_string());
-            }
-            Command::Sort => {
-                let res = sort_by_column(self.view_state.view.cursor_col,
-                                                 self.header_mode, 
-                                                 &mut self.table,
-                                                 &mut self.view_state,
-                                                 SortDirection::Ascending);
-                if let Some(txn) = res {
-                    self.history.record(txn);
-                    self.dirty = true;
-                }
-            }
-            Command::SortDesc => {
-                let res = sort_by_column(self.view_state.view.cursor_col,
-                                                 self.header_mode, 
-                                                 &mut self.table,
-                                                 &mut self.view_state,
-                                                 SortDirection::Descending);
-                if let Some(txn) = res {
-                    self.history.record(txn);
-                    self.dirty = true;
-                }
-            }
-            Command::SortRow => {
-                let res = sort_by_row(self.view_state.view.cursor_row, 
-                                                 &mut self.table,
-                                                 SortDirection::Ascending);
-                if let Some(txn) = res {
-                    self.history.record(txn);
-                    self.dirty = true;
-                }
-            }
-            Command::SortRowDesc => {
-                let res = sort_by_row(self.view_state.view.cursor_row,
-                                                 &mut self.table,
-                                                 SortDirection::Descending);
-                if let Some(txn) = res {
-                    self.history.record(txn);
-                    self.dirty = true;
+            Command::NavigateCol(col) => {
+                self.view_state.view.cursor_col = col;
+                self.view_state.view.clamp_cursor(&self.table);
+                self.view_state.view.scroll_to_cursor();
+            }
+            Command::SearchCol(pattern) => {
+                self.search_handler.pattern = Some(pattern);
+                let col = self.view_state.view.cursor_col;
+                self.view_state.message = self.search_handler.perform_search(&self.table, Some(col));
+                // Keep the match count in the message rather than the [i/n] position
+                self.search_handler.goto_next(&mut self.view_state.view);
+            }
+            Command::Sort => self.run_sort(SortSpec::by_column(self.view_state.view.cursor_col, SortDirection::Ascending, None)),
+            Command::SortDesc => self.run_sort(SortSpec::by_column(self.view_state.view.cursor_col, SortDirection::Descending, None)),
+            Command::SortMulti(keys) => self.run_sort(SortSpec::Rows { keys, as_type: None }),
+            Command::SortNatural(direction) => self.run_sort(SortSpec::by_column(self.view_state.view.cursor_col, direction, Some(ColumnType::Natural))),
+            Command::SortDate(direction) => self.run_sort(SortSpec::by_column(self.view_state.view.cursor_col, direction, Some(ColumnType::Date))),
+            Command::SortRow => self.run_sort(SortSpec::Cols { row: self.view_state.view.cursor_row, direction: SortDirection::Ascending }),
+            Command::SortRowDesc => self.run_sort(SortSpec::Cols { row: self.view_state.view.cursor_row, direction: SortDirection::Descending }),
+            Command::SortReverse => {
+                match self.last_sort.clone() {
+                    Some(spec) => self.run_sort(spec.reversed()),
+                    None => self.view_state.message = Some("No previous sort".to_string()),
+                }
+            }
+            Command::Replace(ref replace_cmd) if replace_cmd.confirm && !replace_cmd.count_only => {
+                match find_replace_matches(replace_cmd, &self.table, &self.view_state.view, self.calling_mode) {
+                    Ok(matches) if matches.is_empty() => {
+                        self.view_state.message = Some(format!("Pattern not found: {}", replace_cmd.pattern));
+                    }
+                    Ok(matches) => {
+                        self.replace_confirm = Some(ReplaceConfirm::new(matches));
+                        self.mode = Mode::Confirm;
+                        self.step_replace_confirm();
+                    }
+                    Err(e) => self.view_state.message = Some(e),
                 }
             }
             Command::Replace(ref replace_cmd) => {
@@ -346,7 +516,7 @@ impl App {
             Command::Theme(name) => {
                 use crate::ui::style::Theme;
                 if let Some(theme) = Theme::by_name(&name) {
-                    self.view_state.style.set_theme(theme);
+                    self.view_state.style.set_theme(&name, theme);
                     self.view_state.message = Some(format!("Theme set to '{}'", name));
                 } else {
                     self.view_state.message = Some(format!(
@@ -356,6 +526,143 @@ impl App {
                     ));
                 }
             }
+            Command::SetOption { name, value } => {
+                let message = match name.as_str() {
+                    "undolimit" => match value.parse::<usize>() {
+                        Ok(limit) => {
+                            self.history.set_limit(limit);
+                            format!("Undo limit set to {}", limit)
+                        }
+                        Err(_) => format!("Invalid undolimit: {} (expected a number)", value),
+                    },
+                    "eol" if value.is_empty() => {
+                        format!("Line endings: {}", self.file_io.line_ending().name())
+                    }
+                    "eol" => match LineEnding::from_name(&value) {
+                        Some(line_ending) => {
+                            if line_ending != self.file_io.line_ending() {
+                                self.file_io.set_line_ending(line_ending);
+                                self.mark_dirty();
+                            }
+                            format!("Line endings set to {}", line_ending.name())
+                        }
+                        None => format!("Invalid eol: {} (expected lf or crlf)", value),
+                    },
+                    "quoting" if value.is_empty() => {
+                        format!("Quoting: {}", self.file_io.quoting().name())
+                    }
+                    "quoting" => match Quoting::from_name(&value) {
+                        Some(quoting) => {
+                            if quoting != self.file_io.quoting() {
+                                self.file_io.set_quoting(quoting);
+                                self.mark_dirty();
+                            }
+                            format!("Quoting set to {}", quoting.name())
+                        }
+                        None => format!("Invalid quoting: {} (expected always, minimal or never)", value),
+                    },
+                    "align" if value.is_empty() => {
+                        format!("Alignment: {}", self.view_state.align.name())
+                    }
+                    "align" => match CellAlign::from_name(&value) {
+                        Some(align) => {
+                            self.view_state.align = align;
+                            format!("Alignment set to {}", align.name())
+                        }
+                        None => format!("Invalid align: {} (expected left, right or auto)", value),
+                    },
+                    "thousands" if value.is_empty() => {
+                        format!("Thousands separators: {}", if self.view_state.thousands { "on" } else { "off" })
+                    }
+                    "thousands" => match value.as_str() {
+                        "on" | "off" => {
+                            self.view_state.thousands = value == "on";
+                            format!("Thousands separators {}", value)
+                        }
+                        _ => format!("Invalid thousands: {} (expected on or off)", value),
+                    },
+                    "pasteprogress" if value.is_empty() => {
+                        format!("Paste progress from {} cells", self.paste_progress)
+                    }
+                    "pasteprogress" => match value.parse::<usize>() {
+                        Ok(cells) => {
+                            self.paste_progress = cells;
+                            format!("Paste progress from {} cells", cells)
+                        }
+                        Err(_) => format!("Invalid pasteprogress: {} (expected a number)", value),
+                    },
+                    "saveprogress" if value.is_empty() => {
+                        format!("Background saves from {} rows", self.save_progress)
+                    }
+                    "saveprogress" => match value.parse::<usize>() {
+                        Ok(rows) => {
+                            self.save_progress = rows;
+                            format!("Background saves from {} rows", rows)
+                        }
+                        Err(_) => format!("Invalid saveprogress: {} (expected a number)", value),
+                    },
+                    "maxrows" if value.is_empty() => match self.file_io.max_rows() {
+                        Some(max) => format!("Loading at most {} rows at a time", max),
+                        None => "Loading whole files".to_string(),
+                    },
+                    "maxrows" => match value.parse::<usize>() {
+                        Ok(max) => {
+                            self.file_io.set_max_rows((max > 0).then_some(max));
+                            if max > 0 {
+                                format!("Loading at most {} rows at a time", max)
+                            } else {
+                                "Loading whole files".to_string()
+                            }
+                        }
+                        Err(_) => format!("Invalid maxrows: {} (expected a number, 0 for no limit)", value),
+                    },
+                    "grow" if value.is_empty() => {
+                        format!("Grow at edges: {}", if self.grow { "on" } else { "off" })
+                    }
+                    "grow" => match value.as_str() {
+                        "on" | "off" => {
+                            self.grow = value == "on";
+                            format!("Grow at edges {}", value)
+                        }
+                        _ => format!("Invalid grow: {} (expected on or off)", value),
+                    },
+                    "save" => self.save_settings(),
+                    "hls" | "nohls" => {
+                        self.hlsearch = name == "hls";
+                        format!("Search highlighting {}", if self.hlsearch { "on" } else { "off" })
+                    }
+                    "clippaste" | "noclippaste" => {
+                        self.clipboard.clip_paste = name == "clippaste";
+                        if self.clipboard.clip_paste {
+                            "Visual pastes are clipped to the selection".to_string()
+                        } else {
+                            "Visual pastes may extend past the selection".to_string()
+                        }
+                    }
+                    "relativenumber" | "rnu" | "norelativenumber" | "nornu" => {
+                        self.relativenumber = !name.starts_with("no");
+                        format!("Relative row numbers {}", if self.relativenumber { "on" } else { "off" })
+                    }
+                    "backup" | "nobackup" => {
+                        self.file_io.set_backup(name == "backup");
+                        if self.file_io.backup() {
+                            format!("The first save will keep the original file as {}~", self.file_io.file_name())
+                        } else {
+                            "No backup is made before saving".to_string()
+                        }
+                    }
+                    "remember" | "noremember" => {
+                        self.remember_file = name == "remember";
+                        if self.remember_file {
+                            "Cursor and file settings will be remembered".to_string()
+                        } else {
+                            "File settings will not be remembered this session".to_string()
+                        }
+                    }
+                    _ => format!("Unknown option: {}", name),
+                };
+                self.view_state.message = Some(message);
+            }
             Command::ThemeList => {
                 use crate::ui::style::Theme;
                 self.view_state.message = Some(format!(
@@ -371,15 +678,329 @@ impl App {
             }
             Command::Fork => {
                 self.file_io = self.file_io.fork();
+                self.read_only = false;
                 let fname = self.file_io.file_name();
                 self.view_state.message = Some(format!("File forked successfully, you are now editing: {}", fname));
             }
+            Command::FreezeCols(n) => {
+                let n = n.min(self.table.col_count());
+                self.view_state.view.frozen_cols = n;
+                self.view_state.view.scroll_to_cursor();
+                self.view_state.message = if n == 0 {
+                    Some("Columns unfrozen".to_string())
+                } else {
+                    Some(format!("Froze {} column(s)", n))
+                };
+            }
+            Command::HideCol => {
+                let col = self.view_state.view.cursor_col;
+                let view = &mut self.view_state.view;
+                let shown = (0..self.table.col_count()).filter(|c| !view.is_col_hidden(*c)).count();
+                if shown <= 1 {
+                    self.view_state.message = Some("Cannot hide the last visible column".to_string());
+                } else {
+                    view.hidden_cols.insert(col);
+                    view.snap_to_visible_col(&self.table);
+                    view.scroll_to_cursor();
+                    self.view_state.message = Some(format!("Column {} hidden", letters_from_col(col)));
+                }
+            }
+            Command::SetColType(col, column_type) => {
+                if col >= self.table.col_count() {
+                    self.view_state.message = Some(format!("Column {} out of range", letters_from_col(col)));
+                } else {
+                    self.table.set_declared_type(col, column_type);
+                    self.view_state.message = Some(match column_type {
+                        Some(t) => format!("Column {} declared {}", letters_from_col(col), t.name()),
+                        None => format!("Column {} type detected automatically", letters_from_col(col)),
+                    });
+                }
+            }
+            Command::ShowColType => {
+                let col = self.view_state.view.cursor_col;
+                let (column_type, source) = match self.table.declared_type(col) {
+                    Some(t) => (t, "declared"),
+                    None => (self.table.probe_column_type(col, self.header_mode), "inferred"),
+                };
+                self.view_state.message = Some(format!("Column {}: {} ({})", letters_from_col(col), column_type.name(), source));
+            }
+            Command::Diff(None) => {
+                self.diff_cols = None;
+                self.view_state.message = Some("Diff cleared".to_string());
+            }
+            Command::Diff(Some((a, b))) => {
+                let col_count = self.table.col_count();
+                if a >= col_count || b >= col_count {
+                    let col = if a >= col_count { a } else { b };
+                    self.view_state.message = Some(format!("Column {} out of range", letters_from_col(col)));
+                } else {
+                    let mismatches = count_mismatches(&self.table, a, b, self.header_mode);
+                    self.diff_cols = Some((a, b));
+                    self.view_state.message = Some(format!(
+                        "{} mismatched rows between {} and {} (Esc or :diff off to clear)",
+                        mismatches, letters_from_col(a), letters_from_col(b)
+                    ));
+                }
+            }
+            Command::UnhideCols(None) => {
+                if self.view_state.view.hidden_cols.is_empty() {
+                    self.view_state.message = Some("No hidden columns".to_string());
+                } else {
+                    self.view_state.view.hidden_cols.clear();
+                    self.view_state.message = Some("Showing all columns".to_string());
+                }
+            }
+            Command::UnhideCols(Some(col)) => {
+                self.view_state.message = if self.view_state.view.hidden_cols.remove(&col) {
+                    Some(format!("Column {} shown", letters_from_col(col)))
+                } else {
+                    Some(format!("Column {} is not hidden", letters_from_col(col)))
+                };
+            }
+            Command::Duplicate(times) => {
+                self.duplicate_current_rows(times);
+            }
+            Command::Merge { .. } if self.calling_mode != Some(Mode::VisualCol) => {
+                self.view_state.message = Some("Select the columns to merge in visual column mode (Ctrl+v) first".to_string());
+            }
+            Command::Merge { separator, skip_empty } => {
+                let (_, _, start_col, end_col) = self.view_state.view.get_selection_bounds();
+                if start_col == end_col {
+                    self.view_state.message = Some("Select at least two columns to merge".to_string());
+                } else {
+                    let txn = merge_cols(&self.table, start_col, end_col, &separator, skip_empty);
+                    self.execute(txn);
+                    self.view_state.view.cursor_col = start_col;
+                    self.view_state.view.clamp_cursor(&self.table);
+                    self.view_state.view.scroll_to_cursor();
+                    self.view_state.message = Some(format!("Merged {} columns into {}", end_col - start_col + 1, letters_from_col(start_col)));
+                }
+            }
+            Command::Index { start, name } => {
+                let txn = index_column(&self.table, start, name.as_deref().unwrap_or("#"), self.header_mode);
+                self.execute(txn);
+                // Stay on the same data cell, now one column to the right
+                self.view_state.view.cursor_col += 1;
+                self.view_state.view.scroll_to_cursor();
+                self.view_state.message = Some("Index column added".to_string());
+            }
+            Command::Dedup(_) if self.view_state.row_manager.borrow().is_filtered => {
+                self.view_state.message = Some("Deleting rows is forbidden in filtered views.".to_string());
+            }
+            Command::Dedup(keys) => {
+                let keys = keys.unwrap_or_else(|| vec![self.view_state.view.cursor_col]);
+                if let Some(&col) = keys.iter().find(|&&col| col >= self.table.col_count()) {
+                    self.view_state.message = Some(format!("Column {} out of range", letters_from_col(col)));
+                } else if let Some(txn) = dedup_rows(&self.table, &keys, self.header_mode) {
+                    let removed = match &txn {
+                        Transaction::Batch(txns) => match txns.last() {
+                            Some(Transaction::DeleteRowsBulk { data, .. }) => data.len(),
+                            _ => 0,
+                        },
+                        _ => 0,
+                    };
+                    self.execute(txn);
+                    self.view_state.view.clamp_cursor(&self.table);
+                    self.view_state.view.scroll_to_cursor();
+                    self.view_state.message = Some(format!("Removed {} duplicate rows", removed));
+                } else {
+                    self.view_state.message = Some("No duplicate rows".to_string());
+                }
+            }
+            Command::DelimiterCandidates => {
+                self.view_state.message = Some(match self.file_io.delimiter_candidates() {
+                    Some(candidates) => {
+                        let scores: Vec<String> = candidates.iter()
+                            .map(|&(delim, score)| format!("{} {:.2}", delimiter_label(delim), score))
+                            .collect();
+                        format!("Delimiter candidates: {} (using {})", scores.join(", "), self.file_io.delimiter_name())
+                    }
+                    None => "No file to sample delimiters from".to_string(),
+                });
+            }
+            Command::SetDelimiter(_) if self.view_state.row_manager.borrow().is_filtered => {
+                self.view_state.message = Some("Changing the delimiter is forbidden in filtered views.".to_string());
+            }
+            Command::SetDelimiter(delim) if delim == self.file_io.delimiter() => {
+                self.view_state.message = Some(format!("Delimiter is already {}", self.file_io.delimiter_name()));
+            }
+            Command::SetDelimiter(delim) => {
+                // Re-split the file's text if nothing was edited since, otherwise rebuild
+                // the lines by writing the current cells with the old delimiter
+                let old_rows = self.table.clone_all_rows();
+                let old_delim = self.file_io.delimiter();
+                let source = if self.dirty { Ok(None) } else { self.file_io.read_source() };
+                let split = source
+                    .and_then(|source| match source {
+                        Some(text) => Ok(text),
+                        None => join_delimited(&old_rows, old_delim),
+                    })
+                    .and_then(|text| split_delimited(&text, delim));
+                match split {
+                    Ok(new_rows) => {
+                        // One undo step restores both the cells and the delimiter
+                        self.execute(Transaction::Batch(vec![
+                            Transaction::ReplaceRows { old_rows, new_rows },
+                            Transaction::SetDelimiter { old: old_delim, new: delim },
+                        ]));
+                        self.file_io.set_delimiter(delim);
+                        self.view_state.view.clamp_cursor(&self.table);
+                        self.view_state.view.scroll_to_cursor();
+                        self.view_state.message = Some(format!(
+                            "Delimiter set to {}: {} rows, {} cols",
+                            self.file_io.delimiter_name(), self.table.row_count(), self.table.col_count()
+                        ));
+                    }
+                    Err(e) => self.view_state.message = Some(format!("Error splitting with new delimiter: {}", e)),
+                }
+            }
+            Command::Append if self.view_state.row_manager.borrow().is_filtered => {
+                self.view_state.message = Some("Adding rows is forbidden in filtered views.".to_string());
+            }
+            Command::Append => {
+                let row = self.table.row_count();
+                self.execute(Transaction::InsertRow { idx: row });
+                self.start_append_row(row);
+            }
+            Command::Reverse { cols: false } if self.view_state.row_manager.borrow().is_filtered => {
+                self.view_state.message = Some("Reverse is forbidden in filtered views.".to_string());
+            }
+            Command::Reverse { cols } => {
+                let (txn, what) = if cols {
+                    (reverse_cols(&self.table), "columns")
+                } else {
+                    (reverse_rows(&self.table, self.header_mode), "rows")
+                };
+                self.view_state.message = Some(match txn {
+                    Some(txn) => {
+                        self.execute(txn);
+                        format!("Reversed {}", what)
+                    }
+                    None => "Nothing to reverse".to_string(),
+                });
+            }
+            Command::Shuffle(_) if self.view_state.row_manager.borrow().is_filtered => {
+                self.view_state.message = Some("Shuffle is forbidden in filtered views.".to_string());
+            }
+            Command::Shuffle(seed) => {
+                self.view_state.message = Some(match shuffle_rows(&self.table, self.header_mode, seed) {
+                    Some(txn) => {
+                        self.execute(txn);
+                        match seed {
+                            Some(seed) => format!("Shuffled rows (seed {})", seed),
+                            None => "Shuffled rows".to_string(),
+                        }
+                    }
+                    None => "Nothing to shuffle".to_string(),
+                });
+            }
+            Command::Transpose if self.view_state.row_manager.borrow().is_filtered => {
+                self.view_state.message = Some("Transpose is forbidden in filtered views.".to_string());
+            }
+            Command::Transpose => {
+                let (old_rows, old_types) = self.table.transpose();
+                let txn = Transaction::Transpose { old_rows, old_types };
+                self.remap_positions(&txn);
+                self.history.record(txn);
+                self.view_state.view.clamp_cursor(&self.table);
+                self.mark_dirty();
+                self.view_state.message = Some(format!(
+                    "Transposed to {} rows, {} cols", self.table.row_count(), self.table.col_count()
+                ));
+            }
+            Command::Export(format) => {
+                let res = match format {
+                    ExportFormat::Markdown => self.file_io.export_markdown(&self.table, self.header_mode),
+                    ExportFormat::Json => self.file_io.export_json(&self.table, self.header_mode),
+                };
+                match res {
+                    Ok(path) => self.view_state.message = Some(format!("Exported to {}", path.display())),
+                    Err(e) => self.view_state.message = Some(format!("Error exporting: {}", e)),
+                }
+            }
             Command::SysPaste => {
                 match self.clipboard.from_system() {
                     Ok(msg) => self.view_state.message = Some(msg),
                     Err(e) => self.view_state.message = Some(e),
                 }
             }
+            Command::PasteSystem => {
+                let (message, txn_opt) = self.clipboard.paste_system_as_transaction(
+                    self.view_state.view.cursor_row,
+                    self.view_state.view.cursor_col,
+                    &self.table,
+                );
+                self.view_state.message = Some(message.clone());
+                if let Some(txn) = txn_opt {
+                    self.execute_paste(txn, Some(message));
+                }
+            }
+            Command::Describe => {
+                let col = self.view_state.view.cursor_col;
+                let lines = describe_column(&self.table, col, &self.view_state.view, self.header_mode);
+                let title = match self.table.get_cell(0, col) {
+                    Some(name) if self.header_mode && !name.is_empty() => format!("Column {} ({})", letters_from_col(col), name),
+                    _ => format!("Column {}", letters_from_col(col)),
+                };
+                let canvas = &mut self.view_state.canvas;
+                canvas.clear();
+                canvas.set_title(title);
+                for (label, value) in lines {
+                    canvas.add_text(format!("{:<10}{}", label, value));
+                }
+                canvas.show();
+            }
+            Command::GroupBy { col, sum } => {
+                let col = col.unwrap_or(self.view_state.view.cursor_col);
+                if let Some(&out) = [Some(col), sum].iter().flatten().find(|&&c| c >= self.table.col_count()) {
+                    self.view_state.message = Some(format!("Column {} out of range", letters_from_col(out)));
+                    return;
+                }
+                let groups = group_by(&self.table, col, sum, &self.view_state.view, self.header_mode);
+                let width = groups.iter().map(|g| display_width(&g.value)).max().unwrap_or(0).max(5);
+                let canvas = &mut self.view_state.canvas;
+                canvas.clear();
+                canvas.set_title(format!("Group by {} ({} groups)", letters_from_col(col), groups.len()));
+                let mut header = format!("{:<width$}  {:>6}", "value", "count");
+                if let Some(sum) = sum {
+                    header.push_str(&format!("  sum of {}", letters_from_col(sum)));
+                }
+                canvas.add_header(header);
+                canvas.add_separator();
+                for group in groups {
+                    let pad = width - display_width(&group.value);
+                    let mut line = format!("{}{}  {:>6}", group.value, " ".repeat(pad), group.count);
+                    if let Some(total) = group.sum {
+                        line.push_str(&format!("  {}", format_default(&total.to_string()).unwrap_or_default()));
+                    }
+                    canvas.add_text(line);
+                }
+                canvas.show();
+            }
+            Command::Registers => {
+                let registers = self.clipboard.list_registers();
+                let canvas = &mut self.view_state.canvas;
+                canvas.clear();
+                canvas.set_title("Registers");
+                if registers.is_empty() {
+                    canvas.add_text("All registers are empty");
+                }
+                for (name, preview) in registers {
+                    canvas.add_text(format!("{}  {}", name, preview));
+                }
+                canvas.show();
+            }
+            Command::PasteTranspose => {
+                let (message, txn_opt) = self.clipboard.paste_transposed_as_transaction(
+                    self.view_state.view.cursor_row,
+                    self.view_state.view.cursor_col,
+                    &self.table,
+                );
+                self.view_state.message = Some(message.clone());
+                if let Some(txn) = txn_opt {
+                    self.execute_paste(txn, Some(message));
+                }
+            }
             Command::PluginList => {
                 let commands = self.plugin_manager.list_commands();
                 let functions = self.plugin_manager.list_functions();
@@ -410,6 +1031,13 @@ impl App {
             Command::Custom { name, args } => {
                 self.execute_plugin(&name, &args);
             }
+            Command::Aggregate(kind) => {
+                let msg = aggregate(kind, &self.table, &self.view_state.view, self.calling_mode, self.header_mode);
+                self.view_state.message = Some(msg);
+            }
+            Command::Invalid(msg) => {
+                self.view_state.message = Some(msg);
+            }
             Command::Unknown(s) => {
                 let parts: Vec<&str> = s.split_whitespace().collect();
                 if let Some(cmd_name) = parts.first() {
@@ -421,6 +1049,9 @@ impl App {
                 }
                 self.view_state.message = Some(format!("Unknown command: {}", s));
             }
+            Command::Filter(FilterType::Invert) if !self.view_state.row_manager.borrow().is_filtered => {
+                self.view_state.message = Some("No filter to invert".to_string());
+            }
             Command::Filter(filter_type) => {
                 let old_state = self.view_state.row_manager.borrow().snapshot();
                 self.view_state.view.move_to_top();
@@ -429,9 +1060,15 @@ impl App {
                     self.view_state.message = Some("Filter removed".to_string());
                 } else if let FilterType::PredicateFilter(pred) = filter_type {
                     let active_col = self.view_state.view.cursor_col;
-                    let column_type = self.table.probe_column_type(active_col, self.header_mode);
+                    let column_type = self.table.column_type(active_col, self.header_mode);
                     self.view_state.row_manager.borrow_mut().predicate_filter(&self.table, active_col, pred, column_type, self.header_mode);
                     self.view_state.message = Some("Filter applied".to_string());
+                } else if let FilterType::Sample { size, seed } = filter_type {
+                    let (kept, total) = self.view_state.row_manager.borrow_mut().sample(&self.table, size, seed, self.header_mode);
+                    self.view_state.message = Some(format!("Sampled {} of {} rows", kept, total));
+                } else if filter_type == FilterType::Invert {
+                    self.view_state.row_manager.borrow_mut().invert(&self.table, self.header_mode);
+                    self.view_state.message = Some("Filter inverted".to_string());
                 } else {
                     self.view_state.message = Some("Filter not recognized".to_string());
                 }
@@ -470,6 +1107,201 @@ impl App {
         self.calling_mode = None;
     }
 
+    /// Write the current theme, grid and precision to the config file
+    fn save_settings(&mut self) -> String {
+        let settings = Settings {
+            theme: Some(self.view_state.style.theme_name.clone()),
+            grid: Some(self.view_state.style.theme.show_grid),
+            precision: self.view_state.precision,
+            delimiter: self.config.borrow().settings.delimiter.clone(),
+        };
+        let path = Settings::path();
+        match settings.save(&path) {
+            Ok(()) => {
+                self.config.borrow_mut().settings = settings;
+                format!("Settings saved to {}", path.display())
+            }
+            Err(e) => format!("Failed to save settings: {}", e),
+        }
+    }
+
+    /// Open a read-only popup with the untruncated content of the current cell
+    fn inspect_cell(&mut self) {
+        let (row, col) = (self.view_state.view.cursor_row, self.view_state.view.cursor_col);
+        let content = self.table.get_cell(row, col).cloned().unwrap_or_default();
+
+        let canvas = &mut self.view_state.canvas;
+        canvas.clear();
+        canvas.set_title(format!("Cell {}{}", letters_from_col(col), row + 1));
+        for line in content.split('\n') {
+            canvas.add_text(line);
+        }
+        canvas.add_separator();
+        canvas.add_text(format!("Characters: {}", content.chars().count()));
+        canvas.add_text(format!("Bytes: {}", content.len()));
+        match parse_numeric(content.trim()) {
+            Some(value) => canvas.add_text(format!("Numeric: yes ({})", value)),
+            None => canvas.add_text("Numeric: no"),
+        }
+        canvas.show();
+    }
+
+    /// Show only the rows start..=end (plus the header) and return to normal mode
+    fn filter_to_rows(&mut self, start: usize, end: usize) {
+        let old_state = self.view_state.row_manager.borrow().snapshot();
+        self.view_state.row_manager.borrow_mut().filter_to_range(start, end, self.header_mode);
+        let new_state = self.view_state.row_manager.borrow().snapshot();
+        self.history.record(Transaction::SetFilter { old_state, new_state });
+
+        self.finish_edit();
+        let cursor_row = self.view_state.view.cursor_row;
+        let live_row = {
+            let row_manager = self.view_state.row_manager.borrow();
+            if row_manager.is_row_live(cursor_row) {
+                cursor_row
+            } else {
+                row_manager.get_successor(cursor_row)
+                    .or_else(|| row_manager.get_predecessor(cursor_row))
+                    .unwrap_or(0)
+            }
+        };
+        self.view_state.view.cursor_row = live_row;
+        self.view_state.view.scroll_to_cursor();
+        self.view_state.message = Some("Filter applied".to_string());
+    }
+
+    /// Show the next match of :s///c, or apply the accepted replacements as one undo step
+    /// once every match is answered
+    pub(crate) fn step_replace_confirm(&mut self) {
+        let Some(confirm) = self.replace_confirm.as_ref() else { return; };
+        if let Some(m) = confirm.current() {
+            self.view_state.view.cursor_row = m.row;
+            self.view_state.view.cursor_col = m.col;
+            self.view_state.view.scroll_to_cursor();
+            return;
+        }
+
+        let confirm = self.replace_confirm.take().expect("confirm state");
+        let count = confirm.accepted_count();
+        if let Some(txn) = confirm.into_transaction(&self.table) {
+            self.execute(txn);
+        }
+        self.mode = Mode::Normal;
+        self.view_state.message = Some(format!("{} replacement(s) made", count));
+    }
+
+    /// Sort the table and remember the parameters so `:sort!` can reverse them
+    fn run_sort(&mut self, spec: SortSpec) {
+        if self.view_state.background_busy() {
+            self.view_state.message = Some("Wait for the running operation to finish".to_string());
+            return;
+        }
+        let res = match &spec {
+            SortSpec::Rows { keys, as_type } => {
+                if let Some(&(col, _)) = keys.iter().find(|&&(col, _)| col >= self.table.col_count()) {
+                    self.view_state.message = Some(format!("Column {} is out of range", letters_from_col(col)));
+                    return;
+                }
+                sort_by_columns(keys, self.header_mode, *as_type, &mut self.table, &mut self.view_state)
+            }
+            SortSpec::Cols { row, direction } => sort_by_row(*row, &mut self.table, *direction),
+        };
+
+        if let Some(txn) = res {
+            self.remap_positions(&txn);
+            self.view_state.view.clamp_cursor(&self.table);
+            self.history.record(txn);
+            self.mark_dirty();
+            if let SortSpec::Rows { keys, .. } = &spec {
+                if keys.len() > 1 {
+                    self.view_state.message = Some(format!("Sorted by {} columns", keys.len()));
+                }
+            }
+            self.last_sort = Some(spec);
+        } else if self.view_state.bg_receiver.is_some() {
+            self.running_sort = Some(spec);
+        } else {
+            self.view_state.message = Some("Already sorted".to_string());
+        }
+    }
+
+    /// Write a large table on a background thread so the UI stays responsive
+    /// The buffer stays dirty until the save succeeds, and afterwards if it was edited meanwhile
+    /// The thread writes a snapshot of the table, cloned here on the UI thread, so the save
+    /// briefly holds the table in memory twice
+    fn save_in_background(&mut self) {
+        let mut writer = self.file_io.writer_copy();
+        let table = self.table.clone();
+        let edits = self.edits;
+        let progress = self.view_state.start_progress("Saving", table.row_count());
+        let (tx, rx) = mpsc::channel();
+        self.view_state.bg_receiver = Some(rx);
+        self.view_state.bg_cancel = None;
+
+        let handle = thread::spawn(move || {
+            let result = writer.write_with_progress(&table, Some(&progress)).map_err(|e| e.to_string());
+            let _ = tx.send(BackgroundResult::SaveComplete { result, edits });
+        });
+        self.view_state.bg_handle = Some(handle);
+        self.view_state.message = Some(format!("Saving {}...", self.file_io.file_name()));
+    }
+
+    /// Append the next batch of rows of a partly loaded file
+    /// The rows come from the file rather than an edit, so they are not an undo step; the history
+    /// is cleared because whole-table steps (transpose, sort) would no longer line up with them
+    fn load_more_rows(&mut self) {
+        if !self.file_io.is_partial() {
+            self.view_state.message = Some("The whole file is already loaded".to_string());
+            return;
+        }
+        if self.file_io.has_changed() {
+            self.view_state.message = Some("File changed on disk; :e! to reload it before loading more".to_string());
+            return;
+        }
+        if self.view_state.row_manager.borrow().is_filtered {
+            self.view_state.message = Some("Adding rows is forbidden in filtered views.".to_string());
+            return;
+        }
+        match self.file_io.load_more() {
+            Ok(Some(rows)) => {
+                let count = rows.len();
+                let widest = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+                if widest > self.table.col_count() {
+                    self.table.expand_columns(widest);
+                }
+                self.table.insert_rows_with_data_bulk(self.table.row_count(), rows);
+                self.history.clear();
+                let mut message = format!("Loaded {} more rows ({} total)", count, self.table.row_count());
+                if self.file_io.is_partial() {
+                    message.push_str(", more remain");
+                }
+                message.push_str("; undo history cleared");
+                self.view_state.message = Some(message);
+            }
+            Ok(None) => self.view_state.message = Some("The whole file is already loaded".to_string()),
+            Err(e) => self.view_state.message = Some(format!("Error loading: {}", e)),
+        }
+    }
+
+    /// Insert `times` copies of the current row (or selected rows) below and move onto the first copy
+    fn duplicate_current_rows(&mut self, times: usize) {
+        if self.view_state.row_manager.borrow().is_filtered {
+            self.view_state.message = Some("Adding rows is forbidden in filtered views.".to_string());
+            return;
+        }
+        if let Some((txn, first_copy)) = duplicate_rows(times, &self.table, &self.view_state.view, self.calling_mode) {
+            let count = match &txn {
+                Transaction::InsertRowsWithDataBulk { data, .. } => data.len(),
+                _ => 0,
+            };
+            self.execute(txn);
+            self.view_state.view.cursor_row = first_copy;
+            self.view_state.view.scroll_to_cursor();
+            let msg = if count == 1 { "Row duplicated".to_string() } else { format!("{} rows added", count) };
+            self.view_state.message = Some(msg);
+        }
+    }
+
     pub fn get_selection_info(&self) -> SelectionInfo {
         let mode = if self.mode == Mode::Command {
             self.calling_mode.unwrap_or(self.mode)
@@ -485,15 +1317,108 @@ impl App {
         }
     }
 
-    pub fn execute_plugin(&mut self, name: &str, args: &[String]) {
-        let ctx = PluginContext {
+    /// Stamp a formula into every data row of col and evaluate it, as one undo step
+    /// With a header row, row 0 keeps its header; large sheets are filled after the next render
+    /// so the progress bar shows, like :calc
+    fn apply_column_formula(&mut self, col: usize, template: &str) {
+        let start = if self.header_mode { 1 } else { 0 };
+        if start >= self.table.row_count() {
+            self.view_state.message = Some("No rows to fill".to_string());
+            return;
+        }
+        if col >= self.table.col_count() {
+            self.view_state.message = Some(format!("Column {} is outside the table", letters_from_col(col)));
+            return;
+        }
+
+        let cell_count = self.table.row_count() * self.table.col_count();
+        if cell_count >= LARGE_SHEET_CELLS {
+            self.view_state.start_progress("Calculating", cell_count);
+            self.view_state.pending_op = Some(PendingOp::ColFormula { col, template: template.to_string() });
+        } else {
+            self.fill_column_formula(col, template);
+        }
+    }
+
+    /// Write the formulas of :col-formula into col and replace them with their values
+    /// Other formulas in the sheet are evaluated only to feed these and are left as they are
+    pub(crate) fn fill_column_formula(&mut self, col: usize, template: &str) {
+        let start = if self.header_mode { 1 } else { 0 };
+        let mut stamped = self.table.clone();
+        let mut txns = Vec::new();
+        for row in start..self.table.row_count() {
+            let formula = expand_row_template(template, row);
+            let old_value = stamped.get_cell(row, col).cloned().unwrap_or_default();
+            stamped.set_cell(row, col, formula.clone());
+            txns.push(Transaction::SetCell { row, col, old_value, new_value: formula });
+        }
+
+        let calc = Calculator::with_plugins(&stamped, self.header_mode, &self.plugin_manager);
+        match calc.evaluate_all() {
+            Ok(updates) => {
+                for (row, _, new_value) in updates.into_iter().filter(|&(row, update_col, _)| update_col == col && row >= start) {
+                    let old_value = stamped.get_cell(row, col).cloned().unwrap_or_default();
+                    txns.push(Transaction::SetCell { row, col, old_value, new_value });
+                }
+                let count = self.table.row_count() - start;
+                self.execute(Transaction::Batch(txns));
+                self.view_state.message = Some(format!("Filled column {} with {} formula(s)", letters_from_col(col), count));
+            }
+            Err(e) => self.view_state.message = Some(format!("{}", e)),
+        }
+    }
+
+    fn plugin_context(&self, read_only: bool) -> PluginContext {
+        PluginContext {
             cursor_row: self.view_state.view.cursor_row,
             cursor_col: self.view_state.view.cursor_col,
             row_count: self.table.row_count(),
             col_count: self.table.col_count(),
-            selection: self.get_selection_info()
+            selection: self.get_selection_info(),
+            read_only,
+        }
+    }
+
+    /// Run the plugins' on_load hooks on a freshly loaded table, returning their message
+    /// Their edits become part of the loaded table: the buffer starts clean with nothing to undo
+    pub fn run_load_hooks(&mut self) -> Option<String> {
+        let ctx = self.plugin_context(self.read_only);
+        let table = &self.table;
+        let get_cell = |row: usize, col: usize| -> Option<String> {
+            table.get_cell(row, col).cloned()
         };
 
+        match self.plugin_manager.run_load_hooks(&ctx, get_cell) {
+            Ok(result) => {
+                self.apply_plugin_actions(result.actions);
+                self.history.clear();
+                self.dirty = false;
+                result.message
+            }
+            Err(e) => Some(format!("Plugin error: {}", e)),
+        }
+    }
+
+    /// Run the plugins' on_save hooks, showing their errors if they reject the save
+    fn refuse_by_save_hooks(&mut self) -> bool {
+        let ctx = self.plugin_context(true);
+        let table = &self.table;
+        let get_cell = |row: usize, col: usize| -> Option<String> {
+            table.get_cell(row, col).cloned()
+        };
+
+        match self.plugin_manager.run_save_hooks(&ctx, get_cell) {
+            Ok(()) => false,
+            Err(e) => {
+                self.view_state.message = Some(format!("Save blocked: {}", e));
+                true
+            }
+        }
+    }
+
+    pub fn execute_plugin(&mut self, name: &str, args: &[String]) {
+        let ctx = self.plugin_context(self.read_only);
+
         let table = &self.table;
         let get_cell = |row: usize, col: usize| -> Option<String> {
             table.get_cell(row, col).cloned()
@@ -501,78 +1426,19 @@ impl App {
 
         match self.plugin_manager.execute(name, args, &ctx, get_cell) {
             Ok(result) => {
-                let mut txns = Vec::new();
-                for action in result.actions {
-                    match action {
-                        PluginAction::SetCell { row, col, value } => {
-                            if let Some(old_value) = self.table.get_cell(row, col).cloned() {
-                                txns.push(Transaction::SetCell {
-                                    row,
-                                    col,
-                                    old_value,
-                                    new_value: value,
-                                });
-                            }
-                        }
-                        PluginAction::InsertRow { at } => {
-                            txns.push(Transaction::InsertRow { idx: at });
-                        }
-                        PluginAction::DeleteRow { at } => {
-                            let data = self.table.get_row_cloned(at).unwrap_or_default();
-                            txns.push(Transaction::DeleteRow { idx: at, data });
-                        }
-                        PluginAction::InsertCol { at } => {
-                            txns.push(Transaction::InsertCol { idx: at });
-                        }
-                        PluginAction::DeleteCol { at } => {
-                            let data = self.table.get_col_cloned(at).unwrap_or_default();
-                            txns.push(Transaction::DeleteCol { idx: at, data });
-                        }
-                        PluginAction::CanvasClear => {
-                            self.view_state.canvas.clear();
-                        }
-                        PluginAction::CanvasShow => {
-                            self.view_state.canvas.show();
-                        }
-                        PluginAction::CanvasHide => {
-                            self.view_state.canvas.hide();
-                        }
-                        PluginAction::CanvasSetTitle { title } => {
-                            self.view_state.canvas.set_title(title);
-                        }
-                        PluginAction::CanvasAddText { text } => {
-                            self.view_state.canvas.add_text(text);
-                        }
-                        PluginAction::CanvasAddHeader { text } => {
-                            self.view_state.canvas.add_header(text);
-                        }
-                        PluginAction::CanvasAddSeparator => {
-                            self.view_state.canvas.add_separator();
-                        }
-                        PluginAction::CanvasAddBlank => {
-                            self.view_state.canvas.add_blank();
-                        }
-                        PluginAction::CanvasAddStyledText { text, fg, bg, bold } => {
-                            self.view_state.canvas.add_styled_text(
-                                text,
-                                fg.map(|c| c.to_ratatui()),
-                                bg.map(|c| c.to_ratatui()),
-                                bold
-                            );
-                        }
-                        PluginAction::CanvasAddImage { rows, title } => {
-                            self.view_state.canvas.add_image(rows, title);
-                        }
-                        PluginAction::PromptRequest { question, default: _ } => {
-                            // TODO: Implement full prompt UI with deferred execution
-                            self.view_state.message = Some(format!("Prompt requested: {} (use :cmd args instead)", question));
-                        }
-                    }
-                }
-
-                if !txns.is_empty() {
-                    self.execute(Transaction::Batch(txns));
+                let prompt = result.actions.iter().find_map(|action| match action {
+                    PluginAction::PromptRequest { question, default } => Some((question.clone(), default.clone())),
+                    _ => None,
+                });
+                if let Some((question, default)) = prompt {
+                    // The plugin runs again once answered, so this run's other actions are dropped
+                    self.plugin_prompt = Some(PluginPrompt { plugin: name.to_string(), args: args.to_vec(), question });
+                    self.command_handler.buffer = default;
+                    self.mode = Mode::Prompt;
+                    return;
                 }
+                self.plugin_manager.clear_prompt_results();
+                self.apply_plugin_actions(result.actions);
 
                 if let Some(msg) = result.message {
                     self.view_state.message = Some(msg);
@@ -584,5 +1450,77 @@ impl App {
         }
     }
 
+    fn apply_plugin_actions(&mut self, actions: Vec<PluginAction>) {
+        let mut txns = Vec::new();
+        for action in actions {
+            match action {
+                PluginAction::SetCell { row, col, value } => {
+                    if let Some(old_value) = self.table.get_cell(row, col).cloned() {
+                        txns.push(Transaction::SetCell {
+                            row,
+                            col,
+                            old_value,
+                            new_value: value,
+                        });
+                    }
+                }
+                PluginAction::InsertRow { at } => {
+                    txns.push(Transaction::InsertRow { idx: at });
+                }
+                PluginAction::DeleteRow { at } => {
+                    let data = self.table.get_row_cloned(at).unwrap_or_default();
+                    txns.push(Transaction::DeleteRow { idx: at, data });
+                }
+                PluginAction::InsertCol { at } => {
+                    txns.push(Transaction::InsertCol { idx: at });
+                }
+                PluginAction::DeleteCol { at } => {
+                    let data = self.table.get_col_cloned(at).unwrap_or_default();
+                    txns.push(Transaction::DeleteCol { idx: at, data });
+                }
+                PluginAction::CanvasClear => {
+                    self.view_state.canvas.clear();
+                }
+                PluginAction::CanvasShow => {
+                    self.view_state.canvas.show();
+                }
+                PluginAction::CanvasHide => {
+                    self.view_state.canvas.hide();
+                }
+                PluginAction::CanvasSetTitle { title } => {
+                    self.view_state.canvas.set_title(title);
+                }
+                PluginAction::CanvasAddText { text } => {
+                    self.view_state.canvas.add_text(text);
+                }
+                PluginAction::CanvasAddHeader { text } => {
+                    self.view_state.canvas.add_header(text);
+                }
+                PluginAction::CanvasAddSeparator => {
+                    self.view_state.canvas.add_separator();
+                }
+                PluginAction::CanvasAddBlank => {
+                    self.view_state.canvas.add_blank();
+                }
+                PluginAction::CanvasAddStyledText { text, fg, bg, bold } => {
+                    self.view_state.canvas.add_styled_text(
+                        text,
+                        fg.map(|c| c.to_ratatui()),
+                        bg.map(|c| c.to_ratatui()),
+                        bold
+                    );
+                }
+                PluginAction::CanvasAddImage { rows, title } => {
+                    self.view_state.canvas.add_image(rows, title);
+                }
+                PluginAction::PromptRequest { .. } => {}  // Handled before any action runs
+            }
+        }
+
+        if !txns.is_empty() {
+            self.execute(Transaction::Batch(txns));
+        }
+    }
+
 }
 
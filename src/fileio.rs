@@ -1,92 +1,487 @@
 use std::path::{PathBuf, Path};
 use std::fs;
 use std::ffi::OsStr;
-use std::io::{self, BufRead, BufReader, BufWriter};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::time::SystemTime;
 
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use memmap2::Mmap;
+use tempfile::NamedTempFile;
+
 use crate::table::table::{Table, CHUNK_SIZE};
+use crate::ui::progress::Progress;
+use crate::util::letters_from_col;
 
 /// Detected file format
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FileFormat {
     Csv,
     Tsv,
+    Json,
 }
 
 impl FileFormat {
-    /// Detect format from file extension
+    /// Detect format from file extension, looking through a trailing `.gz`
     fn from_extension(path: &PathBuf) -> Option<Self> {
+        let path = strip_gz_extension(path);
         let ext = path.extension()?.to_str()?.to_lowercase();
         match ext.as_str() {
             "csv" => Some(FileFormat::Csv),
             "tsv" => Some(FileFormat::Tsv),
+            "json" => Some(FileFormat::Json),
             _ => None
         }
     }
 }
 
-/// Common delimiters to detect
-const CANDIDATE_DELIMITERS: &[u8] = &[b',', b'\t', b';', b'|'];
+/// Default for `:set saveprogress`: tables with at least this many rows are written in the background on `:w`
+pub const BACKGROUND_SAVE_ROWS: usize = 50_000;
 
-/// Detect the most likely delimiter by analyzing the first N lines
-fn detect_delimiter(path: &PathBuf, sample_lines: usize) -> Option<u8> {
-    let file = fs::File::open(path).ok()?;
-    let reader = BufReader::new(file);
+/// Magic bytes at the start of every gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 
-    let mut counts: Vec<Vec<usize>> = vec![Vec::new(); CANDIDATE_DELIMITERS.len()];
+/// True if the path ends in `.gz`
+fn has_gz_extension(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
 
-    for (line_idx, line_result) in reader.lines().enumerate() {
-        if line_idx >= sample_lines {
-            break;
+/// The path without a trailing `.gz` (data.csv.gz -> data.csv)
+fn strip_gz_extension(path: &Path) -> PathBuf {
+    if has_gz_extension(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// True if the file starts with the gzip magic bytes
+fn is_gzip_file(path: &Path) -> bool {
+    let mut magic = [0u8; 2];
+    fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .is_ok_and(|_| magic == GZIP_MAGIC)
+}
+
+/// Modification time and size of a file, None if it doesn't exist
+fn disk_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Where the backup of a file is kept: the same path with `~` appended (`data.csv~`)
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push("~");
+    PathBuf::from(name)
+}
+
+/// Open a file for reading, transparently decompressing gzip input
+fn open_input(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::with_capacity(1 << 20, fs::File::open(path)?); // 1 MB
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::with_capacity(1 << 20, MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Where output bytes go: straight into a new file, or into a temp file next to an
+/// existing one that replaces it once everything is written
+enum Sink {
+    New(fs::File),
+    Replace { temp: NamedTempFile, target: PathBuf },
+}
+
+impl Sink {
+    fn create(path: &Path) -> io::Result<Self> {
+        let Ok(metadata) = fs::metadata(path) else {
+            return Ok(Sink::New(fs::File::create(path)?));
+        };
+        // Replace the file a symlink points at, not the link itself
+        let target = fs::canonicalize(path)?;
+        let dir = target.parent().unwrap_or(Path::new("."));
+        let name = target.file_name().and_then(OsStr::to_str).unwrap_or("tabular");
+        let temp = tempfile::Builder::new()
+            .prefix(&format!(".{}.", name))
+            .suffix(".tmp")
+            .tempfile_in(dir)?;
+        fs::set_permissions(temp.path(), metadata.permissions())?;
+        Ok(Sink::Replace { temp, target })
+    }
+
+    /// Make the written bytes the file's content; a temp file is renamed over the original
+    /// (replacing an existing file works on Windows too)
+    fn commit(self) -> io::Result<()> {
+        match self {
+            Sink::New(file) => file.sync_all(),
+            Sink::Replace { temp, target } => {
+                temp.as_file().sync_all()?;
+                temp.persist(&target).map(|_| ()).map_err(|e| e.error)
+            }
         }
-        let line = match line_result {
-            Ok(l) => l,
-            Err(_) => continue,
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::New(file) => file.write(buf),
+            Sink::Replace { temp, .. } => temp.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::New(file) => file.flush(),
+            Sink::Replace { temp, .. } => temp.flush(),
+        }
+    }
+}
+
+/// Buffered file output, optionally gzip-compressed
+/// An existing file is only replaced by finish, so a save that fails or is interrupted
+/// part way leaves it untouched (the temp file is removed when the output is dropped)
+enum Output {
+    Plain(BufWriter<Sink>),
+    Gzip(GzEncoder<BufWriter<Sink>>),
+}
+
+impl Output {
+    fn create(path: &Path, gzip: bool) -> io::Result<Self> {
+        let writer = BufWriter::new(Sink::create(path)?);
+        if gzip {
+            Ok(Output::Gzip(GzEncoder::new(writer, Compression::default())))
+        } else {
+            Ok(Output::Plain(writer))
+        }
+    }
+
+    /// Flush everything, writing the gzip trailer if compressing, and commit the file
+    fn finish(self) -> io::Result<()> {
+        let writer = match self {
+            Output::Plain(w) => w,
+            Output::Gzip(w) => w.finish()?,
         };
+        writer.into_inner().map_err(|e| e.into_error())?.commit()
+    }
+}
 
-        // Count occurrences of each delimiter in this line
-        for (delim_idx, &delim) in CANDIDATE_DELIMITERS.iter().enumerate() {
-            let count = line.bytes().filter(|&b| b == delim).count();
-            counts[delim_idx].push(count);
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Plain(w) => w.write(buf),
+            Output::Gzip(w) => w.write(buf),
         }
     }
 
-    // Find the delimiter with the most consistent non-zero count
-    let mut best_delim: Option<u8> = None;
-    let mut best_score: f64 = 0.0;
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Plain(w) => w.flush(),
+            Output::Gzip(w) => w.flush(),
+        }
+    }
+}
 
-    for (delim_idx, line_counts) in counts.iter().enumerate() {
-        if line_counts.is_empty() {
-            continue;
+/// Line terminator used when writing files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    /// Parse a line ending name as typed after `:set eol`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "lf" | "unix" => Some(LineEnding::Lf),
+            "crlf" | "dos" => Some(LineEnding::Crlf),
+            _ => None
         }
+    }
 
-        // Calculate consistency: we want delimiters that appear the same number of times
-        // on each line (low variance) and appear at least once (non-zero mean)
-        let sum: usize = line_counts.iter().sum();
-        let mean = sum as f64 / line_counts.len() as f64;
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+        }
+    }
 
-        if mean < 1.0 {
-            // Delimiter doesn't appear consistently
-            continue;
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
         }
+    }
 
-        // Calculate variance
-        let variance: f64 = line_counts.iter()
-            .map(|&c| (c as f64 - mean).powi(2))
-            .sum::<f64>() / line_counts.len() as f64;
+    fn csv_terminator(&self) -> csv::Terminator {
+        match self {
+            LineEnding::Lf => csv::Terminator::Any(b'\n'),
+            LineEnding::Crlf => csv::Terminator::CRLF,
+        }
+    }
+}
 
-        // Score: higher mean and lower variance is better
-        // Use coefficient of variation (lower is more consistent)
-        let cv = if mean > 0.0 { variance.sqrt() / mean } else { f64::MAX };
-        let score = mean / (1.0 + cv);
+/// When fields are wrapped in quotes on write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quoting {
+    /// Quote every field
+    Always,
+    /// Quote only fields containing the delimiter, a quote or a line break (RFC 4180)
+    #[default]
+    Minimal,
+    /// Never quote (fields containing the delimiter will not survive a reload)
+    Never,
+}
+
+impl Quoting {
+    /// Parse a quoting policy name as typed after `:set quoting`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "always" => Some(Quoting::Always),
+            "minimal" => Some(Quoting::Minimal),
+            "never" => Some(Quoting::Never),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quoting::Always => "always",
+            Quoting::Minimal => "minimal",
+            Quoting::Never => "never",
+        }
+    }
+
+    fn quote_style(&self) -> csv::QuoteStyle {
+        match self {
+            Quoting::Always => csv::QuoteStyle::Always,
+            Quoting::Minimal => csv::QuoteStyle::Necessary,
+            Quoting::Never => csv::QuoteStyle::Never,
+        }
+    }
+}
+
+/// Format for one-way exports of the table (not reloaded by tabular)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+impl ExportFormat {
+    /// Parse an export format name as typed after `:export`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "json" => Some(ExportFormat::Json),
+            _ => None
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Escape a cell for use inside a Markdown table row
+fn escape_markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', "<br>")
+}
+
+/// Format a row of cells as a Markdown table row, padding to col_count cells
+fn markdown_row<S: AsRef<str>>(cells: &[S], col_count: usize) -> String {
+    let mut line = String::from("|");
+    for col in 0..col_count {
+        let cell = cells.get(col).map(|c| escape_markdown_cell(c.as_ref())).unwrap_or_default();
+        line.push(' ');
+        line.push_str(&cell);
+        line.push_str(" |");
+    }
+    line
+}
+
+/// Convert a JSON value into cell text, stringifying numbers, bools and nested values
+fn json_value_to_cell(val: serde_json::Value) -> String {
+    match val {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Object keys for each column: the header cell, or the column letter when there is no
+/// header or the cell is empty; a repeated key gets a suffix (`name`, `name_2`) so no column is lost
+fn json_keys(header: Option<&Vec<String>>, col_count: usize) -> Vec<String> {
+    let mut seen = HashSet::new();
+    (0..col_count)
+        .map(|col| {
+            let base = header
+                .and_then(|h| h.get(col))
+                .filter(|k| !k.is_empty())
+                .cloned()
+                .unwrap_or_else(|| letters_from_col(col));
+            let mut key = base.clone();
+            let mut n = 2;
+            while !seen.insert(key.clone()) {
+                key = format!("{}_{}", base, n);
+                n += 1;
+            }
+            key
+        })
+        .collect()
+}
+
+/// Write the table to path as a JSON array with one object per data row
+fn write_json_to(path: &Path, table: &Table, header_mode: bool, line_ending: LineEnding, gzip: bool) -> io::Result<()> {
+    let col_count = table.col_count();
+    let mut rows = table.rows_iter();
+
+    let header = if header_mode { rows.next() } else { None };
+    let keys = json_keys(header, col_count);
+
+    let objects: Vec<serde_json::Value> = rows
+        .map(|row| {
+            let map: serde_json::Map<String, serde_json::Value> = keys.iter()
+                .enumerate()
+                .map(|(col, key)| {
+                    let cell = row.get(col).cloned().unwrap_or_default();
+                    (key.clone(), serde_json::Value::String(cell))
+                })
+                .collect();
+            serde_json::Value::Object(map)
+        })
+        .collect();
+
+    // Newlines inside values are escaped, so every raw newline is formatting
+    let json = serde_json::to_string_pretty(&objects)
+        .map_err(io::Error::other)?
+        .replace('\n', line_ending.as_str());
+
+    let mut writer = Output::create(path, gzip)?;
+    write!(writer, "{}{}", json, line_ending.as_str())?;
+    writer.finish()
+}
+
+/// Common delimiters to detect
+const CANDIDATE_DELIMITERS: &[u8] = &[b',', b'\t', b';', b'|'];
+
+/// Parse a delimiter name (comma, tab, semicolon, pipe) or a single character
+pub fn delimiter_from_name(s: &str) -> Option<u8> {
+    match s.to_lowercase().as_str() {
+        "comma" | "," => Some(b','),
+        "tab" | "\\t" | "\t" => Some(b'\t'),
+        "semicolon" | ";" => Some(b';'),
+        "pipe" | "|" => Some(b'|'),
+        _ if s.len() == 1 => Some(s.as_bytes()[0]),
+        _ => None,
+    }
+}
+
+/// Human-readable name for a delimiter byte
+pub fn delimiter_label(delimiter: u8) -> &'static str {
+    match delimiter {
+        b',' => "comma",
+        b'\t' => "tab",
+        b';' => "semicolon",
+        b'|' => "pipe",
+        _ => "custom",
+    }
+}
+
+/// Reader settings shared by loading and re-splitting delimited text
+fn csv_reader_builder(delimiter: u8) -> csv::ReaderBuilder {
+    let mut builder = csv::ReaderBuilder::new();
+    builder
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::Fields);
+    builder
+}
+
+/// Split delimited text into rows, padding every row to the widest one
+pub fn split_delimited(text: &[u8], delimiter: u8) -> io::Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    for result in csv_reader_builder(delimiter).from_reader(text).records() {
+        let record = result.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        rows.push(record.iter().map(|s| s.to_string()).collect::<Vec<String>>());
+    }
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0).max(1);
+    if rows.is_empty() {
+        rows.push(Vec::new());
+    }
+    for row in rows.iter_mut() {
+        row.resize(width, String::new());
+    }
+    Ok(rows)
+}
+
+/// Join rows into delimited text, quoting cells that contain the delimiter, quotes or newlines
+pub fn join_delimited(rows: &[Vec<String>], delimiter: u8) -> io::Result<Vec<u8>> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_writer(Vec::new());
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    writer.into_inner().map_err(|e| io::Error::other(e.into_error()))
+}
 
-        if score > best_score {
-            best_score = score;
-            best_delim = Some(CANDIDATE_DELIMITERS[delim_idx]);
+/// Number of lines sampled for delimiter detection
+const DETECT_SAMPLE_LINES: usize = 30;
+
+/// Read the first `lines` lines of a file as raw bytes
+/// Plain files are sampled from a memory map, so only the prefix is paged in
+fn read_sample(path: &Path, lines: usize) -> Option<Vec<u8>> {
+    if !is_gzip_file(path) {
+        let file = fs::File::open(path).ok()?;
+        // SAFETY: the map is only read to copy out the prefix and dropped before returning
+        let map = unsafe { Mmap::map(&file).ok()? };
+        let len = map.split_inclusive(|&b| b == b'\n').take(lines).map(<[u8]>::len).sum();
+        return Some(map[..len].to_vec());
+    }
+    let mut reader = open_input(path).ok()?;
+    let mut sample = Vec::new();
+    for _ in 0..lines {
+        if reader.read_until(b'\n', &mut sample).ok()? == 0 {
+            break;
         }
     }
+    Some(sample)
+}
+
+/// Detect the most likely delimiter by analyzing the first lines of the file
+fn detect_delimiter(path: &Path) -> Option<u8> {
+    let sample = read_sample(path, DETECT_SAMPLE_LINES)?;
+    FileIO::detect_delimiter_scored(&sample)
+        .into_iter()
+        .find(|&(_, score)| score > 0.0)
+        .map(|(delim, _)| delim)
+}
 
-    best_delim
+/// Detect the dominant line ending from the start of the file
+/// Returns None if the sample has no line breaks
+fn detect_line_ending(path: &Path) -> Option<LineEnding> {
+    let mut sample = Vec::with_capacity(1 << 16);
+    open_input(path).ok()?.take(1 << 16).read_to_end(&mut sample).ok()?;
+
+    let newlines = sample.iter().filter(|&&b| b == b'\n').count();
+    let crlf = sample.windows(2).filter(|w| w == b"\r\n").count();
+    if newlines == 0 {
+        None
+    } else if crlf * 2 > newlines {
+        Some(LineEnding::Crlf)
+    } else {
+        Some(LineEnding::Lf)
+    }
 }
 
 /// determine the filename to write the fork() output to
@@ -94,12 +489,20 @@ pub fn next_fork_filename_suffix_wins(path: &Path) -> PathBuf {
     let parent = path.parent().unwrap_or(Path::new("."));
     let file_name = path.file_name().unwrap_or(OsStr::new("tabular_fork.csv")).to_string_lossy();
 
-    let (stem, ext) = if let Some(s) = file_name.strip_suffix(".csv") {
+    let (stem, ext) = if let Some(s) = file_name.strip_suffix(".csv.gz") {
+        (s, "csv.gz")
+    } else if let Some(s) = file_name.strip_suffix(".tsv.gz") {
+        (s, "tsv.gz")
+    } else if let Some(s) = file_name.strip_suffix(".json.gz") {
+        (s, "json.gz")
+    } else if let Some(s) = file_name.strip_suffix(".csv") {
         (s, "csv")
     } else if let Some(s) = file_name.strip_suffix(".tsv") {
         (s, "tsv")
+    } else if let Some(s) = file_name.strip_suffix(".json") {
+        (s, "json")
     } else {
-        panic!("expected .csv or .tsv filename");
+        panic!("expected .csv, .tsv or .json filename (optionally .gz)");
     };
 
     // Extract (header, start_n)
@@ -148,11 +551,23 @@ pub struct LoadResult {
 
 pub struct FileIO {
     pub file_path: Option<PathBuf>,
-    open_time: SystemTime,
+    /// Modification time and size of the file when it was last loaded or saved
+    disk_stamp: Option<(SystemTime, u64)>,
     format: Option<FileFormat>,
     delimiter: u8,
     max_dim: (usize, usize),
-    read_only: bool
+    read_only: bool,
+    line_ending: LineEnding,
+    quoting: Quoting,
+    /// The file is gzip-compressed (by extension or magic bytes) and is re-compressed on write
+    gzip: bool,
+    /// Stop loading after this many rows (--max-rows / :set maxrows); :loadmore reads the next batch
+    max_rows: Option<usize>,
+    /// Byte offset of the first row not loaded yet, while the file is only partly loaded
+    unread_offset: Option<u64>,
+    /// Copy the file to `<name>~` before the first save of the session (:set backup)
+    backup: bool,
+    backup_made: bool,
 }
 
 impl FileIO {
@@ -164,10 +579,13 @@ impl FileIO {
         // Determine delimiter: explicit > detected > extension-based > comma default
         let delimiter = if let Some(d) = delimiter {
             d
+        } else if format == Some(FileFormat::Json) {
+            // Only used if the table is later forked or written as delimited text
+            b','
         } else if let Some(ref path) = file_path {
             if path.exists() {
                 // Auto-detect from file content
-                detect_delimiter(path, 30).unwrap_or_else(|| {
+                detect_delimiter(path).unwrap_or_else(|| {
                     // Fall back to extension-based
                     match format {
                         Some(FileFormat::Tsv) => b'\t',
@@ -185,10 +603,26 @@ impl FileIO {
             b','
         };
 
-        let open_time = SystemTime::now();
+        let gzip = file_path.as_deref().is_some_and(|path| has_gz_extension(path) || is_gzip_file(path));
+
+        let disk_stamp = file_path.as_deref().and_then(disk_stamp);
 
         let max_dim = (50000000, 50000000);
-        Ok(Self { file_path, open_time, format, delimiter, max_dim, read_only })
+        Ok(Self {
+            file_path,
+            disk_stamp,
+            format,
+            delimiter,
+            max_dim,
+            read_only,
+            line_ending: LineEnding::default(),
+            quoting: Quoting::default(),
+            gzip,
+            max_rows: None,
+            unread_offset: None,
+            backup: false,
+            backup_made: false,
+        })
     }
 
     pub fn fork(&self) -> FileIO {
@@ -198,31 +632,193 @@ impl FileIO {
             _  => "tabular_fork.csv"
         };
         let fpath = self.file_path.clone().unwrap_or_else(|| PathBuf::from(default_fname));
+        let fork_path = next_fork_filename_suffix_wins(&fpath);
         FileIO {
-            file_path: Some(next_fork_filename_suffix_wins(&fpath)),
-            open_time: SystemTime::now(),
+            disk_stamp: disk_stamp(&fork_path),
+            file_path: Some(fork_path),
             format: self.format,
             delimiter: self.delimiter,
             max_dim: self.max_dim,
-            read_only: false
+            read_only: false,
+            line_ending: self.line_ending,
+            quoting: self.quoting,
+            gzip: self.gzip,
+            max_rows: self.max_rows,
+            unread_offset: None,
+            backup: self.backup,
+            backup_made: false,
         }
     }
 
+    /// Write the table to a new path and make it the current file, returning the absolute path
+    /// The delimiter is kept unless the new extension implies another format (.tsv, .csv, .json);
+    /// an existing file is only replaced with force
+    pub fn save_as(&mut self, table: &Table, path: &Path, force: bool) -> io::Result<PathBuf> {
+        let path = std::path::absolute(path)?;
+        if path.exists() && !force {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists (use :w! to overwrite)", path.display()),
+            ));
+        }
+
+        let format = FileFormat::from_extension(&path);
+        let delimiter = match format {
+            Some(FileFormat::Tsv) => b'\t',
+            Some(FileFormat::Csv) if self.delimiter == b'\t' => b',',
+            _ => self.delimiter,
+        };
+        let mut target = FileIO {
+            file_path: Some(path.clone()),
+            disk_stamp: None,
+            format,
+            delimiter,
+            max_dim: self.max_dim,
+            read_only: false,
+            line_ending: self.line_ending,
+            quoting: self.quoting,
+            gzip: has_gz_extension(&path),
+            max_rows: self.max_rows,
+            unread_offset: None,
+            backup: self.backup,
+            backup_made: false,
+        };
+        target.write(table)?;
+        *self = target;
+        Ok(path)
+    }
+
     /// Get the detected/configured delimiter
     #[allow(dead_code)]
     pub fn delimiter(&self) -> u8 {
         self.delimiter
     }
 
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Allow (or forbid) writing back to the file
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Change the delimiter used for subsequent saves
+    pub fn set_delimiter(&mut self, delimiter: u8) {
+        self.delimiter = delimiter;
+    }
+
+    /// Read the file's text again so it can be re-split with another delimiter
+    /// None for JSON, a partly loaded file or one that changed on disk since it was loaded or saved
+    pub fn read_source(&self) -> io::Result<Option<Vec<u8>>> {
+        let Some(path) = self.file_path.as_ref() else { return Ok(None); };
+        if self.format == Some(FileFormat::Json) || self.is_partial() || self.has_changed() || !path.exists() {
+            return Ok(None);
+        }
+        let mut buf = Vec::new();
+        open_input(path)?.read_to_end(&mut buf)?;
+        Ok(Some(buf))
+    }
+
+    pub fn backup(&self) -> bool {
+        self.backup
+    }
+
+    /// Copy the original file to `<name>~` before the first save (:set backup)
+    pub fn set_backup(&mut self, backup: bool) {
+        self.backup = backup;
+    }
+
+    /// Row cap for loading, None if the whole file is read
+    pub fn max_rows(&self) -> Option<usize> {
+        self.max_rows
+    }
+
+    /// Cap how many rows a load (or each :loadmore) reads; None reads everything
+    pub fn set_max_rows(&mut self, max_rows: Option<usize>) {
+        self.max_rows = max_rows;
+    }
+
+    /// The file has rows past the ones loaded so far
+    pub fn is_partial(&self) -> bool {
+        self.unread_offset.is_some()
+    }
+
+    /// Read the next batch of up to max_rows rows of a partly loaded file
+    /// Returns None if the file is already fully loaded, and an error if it changed
+    /// on disk since it was loaded (the saved offset no longer points past the loaded rows)
+    pub fn load_more(&mut self) -> io::Result<Option<Vec<Vec<String>>>> {
+        let (Some(offset), Some(path)) = (self.unread_offset, self.file_path.as_ref()) else {
+            return Ok(None);
+        };
+        if self.has_changed() {
+            return Err(io::Error::other("file changed on disk since it was loaded"));
+        }
+        let mut input = open_input(path)?;
+        // Skip what was already read; this also works through gzip, which can't seek
+        io::copy(&mut input.by_ref().take(offset), &mut io::sink())?;
+
+        let mut csv_reader = csv_reader_builder(self.delimiter).from_reader(input);
+        let cap = self.max_rows.unwrap_or(usize::MAX);
+        let mut rows = Vec::new();
+        let mut records = csv_reader.records();
+        self.unread_offset = None;
+        loop {
+            let position = records.reader().position().byte();
+            let Some(result) = records.next() else { break; };
+            if rows.len() == cap {
+                self.unread_offset = Some(offset + position);
+                break;
+            }
+            let record = result.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            rows.push(record.iter().map(|s| s.to_string()).collect());
+        }
+        Ok(Some(rows))
+    }
+
+    /// Score each candidate delimiter on a sample of the file, best first
+    /// A delimiter that appears the same number of times on every line (low variance
+    /// of the per-line count) with a high mean scores best; absent ones score 0
+    pub fn detect_delimiter_scored(sample: &[u8]) -> Vec<(u8, f64)> {
+        let lines: Vec<&[u8]> = sample
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        let mut scored: Vec<(u8, f64)> = CANDIDATE_DELIMITERS.iter().map(|&delim| {
+            if lines.is_empty() {
+                return (delim, 0.0);
+            }
+            let counts: Vec<usize> = lines.iter()
+                .map(|line| line.iter().filter(|&&b| b == delim).count())
+                .collect();
+            let mean = counts.iter().sum::<usize>() as f64 / counts.len() as f64;
+            if mean < 1.0 {
+                // Delimiter doesn't appear consistently
+                return (delim, 0.0);
+            }
+            let variance = counts.iter()
+                .map(|&c| (c as f64 - mean).powi(2))
+                .sum::<f64>() / counts.len() as f64;
+            // Coefficient of variation: lower is more consistent
+            let cv = variance.sqrt() / mean;
+            (delim, mean / (1.0 + cv))
+        }).collect();
+
+        // Stable sort keeps candidate order on ties
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored
+    }
+
+    /// Delimiter scores for the current file, or None if it can't be read
+    pub fn delimiter_candidates(&self) -> Option<Vec<(u8, f64)>> {
+        let sample = read_sample(self.file_path.as_deref()?, DETECT_SAMPLE_LINES)?;
+        Some(Self::detect_delimiter_scored(&sample))
+    }
+
     /// Get a human-readable name for the delimiter
     pub fn delimiter_name(&self) -> &'static str {
-        match self.delimiter {
-            b',' => "comma",
-            b'\t' => "tab",
-            b';' => "semicolon",
-            b'|' => "pipe",
-            _ => "custom",
-        }
+        delimiter_label(self.delimiter)
     }
 
     pub fn file_name(&self) -> String {
@@ -232,11 +828,28 @@ impl FileIO {
             .unwrap_or_default()
     }
 
-    #[allow(dead_code)]
     pub fn format(&self) -> Option<FileFormat> {
         self.format
     }
 
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// Override the line ending used by later writes and exports
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    pub fn quoting(&self) -> Quoting {
+        self.quoting
+    }
+
+    /// Set the quoting policy used by later CSV/TSV writes
+    pub fn set_quoting(&mut self, quoting: Quoting) {
+        self.quoting = quoting;
+    }
+
     /// Load table from file, returning warnings about any modifications
     pub fn load_table(&mut self) -> io::Result<LoadResult> {
         if self.file_path.is_none() {
@@ -246,8 +859,15 @@ impl FileIO {
             });
         }
 
+        if let Some(line_ending) = self.file_path.as_deref().and_then(detect_line_ending) {
+            self.line_ending = line_ending;
+        }
+        // Taken before reading, so a change made while loading still counts as a change
+        self.disk_stamp = self.file_path.as_deref().and_then(disk_stamp);
+
         match self.format {
             Some(FileFormat::Csv) | Some(FileFormat::Tsv) => self.read_csv(),
+            Some(FileFormat::Json) => self.read_json(),
             None => {
                 // Default to CSV for unknown extensions
                 self.read_csv()
@@ -257,29 +877,139 @@ impl FileIO {
 
     /// Write table to file
     pub fn write(&mut self, table: &Table) -> io::Result<()> {
-        if self.file_path.is_none() {
+        self.write_with_progress(table, None)
+    }
+
+    /// Write table to file, reporting the rows written so far to progress (delimited files only)
+    pub fn write_with_progress(&mut self, table: &Table, progress: Option<&Progress>) -> io::Result<()> {
+        let Some(path) = self.file_path.as_deref() else {
             return Err(io::Error::new(io::ErrorKind::NotFound, "No file path specified"));
+        };
+
+        if self.backup && !self.backup_made && !self.read_only && path.exists() {
+            fs::copy(path, backup_path(path))?;
         }
 
         match self.format {
-            Some(FileFormat::Csv) | Some(FileFormat::Tsv) => self.write_csv(table),
-            None => self.write_csv(table),
+            Some(FileFormat::Csv) | Some(FileFormat::Tsv) => self.write_csv(table, progress),
+            Some(FileFormat::Json) => self.write_json(table),
+            None => self.write_csv(table, progress),
+        }?;
+        self.mark_saved();
+        Ok(())
+    }
+
+    /// Record that the file on disk now holds exactly the table, e.g. after a save on another thread
+    pub fn mark_saved(&mut self) {
+        // Later saves leave the backup of the original file alone
+        self.backup_made |= self.backup;
+        self.disk_stamp = self.file_path.as_deref().and_then(disk_stamp);
+        self.unread_offset = None;
+    }
+
+    /// Copy of the settings needed to write the file, without the retained source text,
+    /// for saving on a background thread
+    pub fn writer_copy(&self) -> FileIO {
+        FileIO {
+            file_path: self.file_path.clone(),
+            disk_stamp: self.disk_stamp,
+            format: self.format,
+            delimiter: self.delimiter,
+            max_dim: self.max_dim,
+            read_only: self.read_only,
+            line_ending: self.line_ending,
+            quoting: self.quoting,
+            gzip: self.gzip,
+            max_rows: self.max_rows,
+            unread_offset: self.unread_offset,
+            backup: self.backup,
+            backup_made: self.backup_made,
         }
     }
 
-    #[allow(dead_code)]
+    /// Path an export of the given format is written to: the current file with its
+    /// extension swapped, or a default name in the working directory
+    pub fn export_path(&self, format: ExportFormat) -> PathBuf {
+        match self.file_path {
+            Some(ref path) => strip_gz_extension(path).with_extension(format.extension()),
+            None => PathBuf::from(format!("tabular_export.{}", format.extension())),
+        }
+    }
+
+    /// Export the table as a GitHub-flavored Markdown table, returning the written path
+    /// With header_mode the first row becomes the header, otherwise the header is left blank
+    pub fn export_markdown(&self, table: &Table, header_mode: bool) -> io::Result<PathBuf> {
+        let path = self.export_path(ExportFormat::Markdown);
+        let col_count = table.col_count();
+
+        let eol = self.line_ending.as_str();
+
+        let mut writer = Output::create(&path, false)?;
+
+        let mut rows = table.rows_iter();
+        let header = if header_mode {
+            markdown_row(rows.next().map(|r| r.as_slice()).unwrap_or(&[]), col_count)
+        } else {
+            markdown_row::<&str>(&[], col_count)
+        };
+        write!(writer, "{}{}", header, eol)?;
+        write!(writer, "|{}{}", "---|".repeat(col_count), eol)?;
+
+        for row in rows {
+            write!(writer, "{}{}", markdown_row(row, col_count), eol)?;
+        }
+
+        writer.finish()?;
+        Ok(path)
+    }
+
+    /// Export the table as a JSON array of objects, returning the written path
+    /// With header_mode row 0 supplies the keys, otherwise column letters are used
+    /// Refused in read-only mode, and when the export would overwrite the open file itself
+    pub fn export_json(&self, table: &Table, header_mode: bool) -> io::Result<PathBuf> {
+        if self.read_only {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "file opened in read-only mode (use ':fork' to save your work)"));
+        }
+        let path = self.export_path(ExportFormat::Json);
+        if self.file_path.as_deref() == Some(path.as_path()) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} is the open file; use :w to save it", path.display())));
+        }
+        write_json_to(&path, table, header_mode, self.line_ending, false)?;
+        Ok(path)
+    }
+
+    /// The file on disk was changed (or created or removed) by something else since it was
+    /// last loaded or saved
     pub fn has_changed(&self) -> bool {
-        self.has_mutated().unwrap_or(false)
+        self.file_path.as_deref().is_some_and(|path| disk_stamp(path) != self.disk_stamp)
     }
 
     // === CSV/TSV ===
 
+    /// Write rows as delimited text using this file's delimiter, line ending and quoting
+    /// Returns the writer once everything is flushed into it
+    pub fn write_delimited<'a, W: Write>(&self, rows: impl Iterator<Item = &'a [String]>, writer: W) -> io::Result<W> {
+        let mut csv_writer = csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .terminator(self.line_ending.csv_terminator())
+            .quote_style(self.quoting.quote_style())
+            .from_writer(writer);
+
+        for row in rows {
+            csv_writer
+                .write_record(row)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
+
+        csv_writer
+            .into_inner()
+            .map_err(|e| io::Error::other(e.into_error()))
+    }
+
     fn read_csv(&mut self) -> io::Result<LoadResult> {
         let path = self.file_path.as_ref().ok_or(io::ErrorKind::NotFound)?;
         let delim = self.delimiter;
 
-        self.open_time = SystemTime::now();
-
         // Check if file exists - if not, create empty table
         if !path.exists() {
             return Ok(LoadResult {
@@ -288,26 +1018,41 @@ impl FileIO {
             });
         }
 
-        let file = fs::File::open(path)?;
-        let reader = BufReader::with_capacity(1 << 20, file); // 1 MB
-
-        let mut has_padding = false;
+        // Plain files are parsed straight from a memory map; gzip input is streamed
+        let mapped = if !self.gzip {
+            let file = fs::File::open(path)?;
+            // SAFETY: the map is only read while parsing below and dropped before returning;
+            // a file truncated by another process meanwhile would fault, as with any mmap reader
+            Some(unsafe { Mmap::map(&file)? })
+        } else {
+            None
+        };
+        let reader: Box<dyn Read + '_> = match &mapped {
+            Some(map) => Box::new(&map[..]),
+            None => Box::new(open_input(path)?),
+        };
 
-        let mut csv_reader = csv::ReaderBuilder::new()
-            .delimiter(delim)
-            .has_headers(false)
-            .flexible(true)
-            .trim(csv::Trim::Fields)
-            .from_reader(reader);
+        let mut csv_reader = csv_reader_builder(delim).from_reader(reader);
 
         // Stream directly into chunks to avoid intermediate Vec allocation
+        let mut chunks: Vec<Vec<Vec<String>>> = Vec::new();
         let mut current_chunk: Vec<Vec<String>> = Vec::with_capacity(CHUNK_SIZE);
         let mut max_cols: usize = 0;
         let mut row_no: usize = 0;
-
-        let mut table = Table::from_empty();
-
-        for result in csv_reader.records() {
+        let mut first_row_len: Option<usize> = None;
+        let mut wide_rows: usize = 0;  // Rows longer than the first (header) row
+
+        let cap = self.max_rows.unwrap_or(usize::MAX);
+        let mut unread_offset = None;
+
+        let mut records = csv_reader.records();
+        loop {
+            let position = records.reader().position().byte();
+            let Some(result) = records.next() else { break; };
+            if row_no == cap {
+                unread_offset = Some(position);
+                break;
+            }
             let record = result.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
             let row: Vec<String> = record.iter().map(|s| s.to_string()).collect();
 
@@ -315,10 +1060,11 @@ impl FileIO {
                 return Err(io::Error::from(io::ErrorKind::FileTooLarge));
             }
 
-            // Track if padding will be needed (O(1) instead of O(n²))
-            if row.len() > max_cols {
-                max_cols = row.len();
-                has_padding = true;
+            max_cols = max_cols.max(row.len());
+            match first_row_len {
+                None => first_row_len = Some(row.len()),
+                Some(len) if row.len() > len => wide_rows += 1,
+                _ => {}
             }
 
             current_chunk.push(row);
@@ -326,77 +1072,154 @@ impl FileIO {
 
             // Flush full chunk
             if current_chunk.len() == CHUNK_SIZE {
-                //chunks.push(std::mem::take(&mut current_chunk));
-                table.add_chunk(std::mem::take(&mut current_chunk), max_cols);
+                chunks.push(std::mem::take(&mut current_chunk));
                 current_chunk = Vec::with_capacity(CHUNK_SIZE);
             }
         }
 
+        drop(records);
+        drop(csv_reader);
+
         // Push remaining rows
         if !current_chunk.is_empty() {
-            //chunks.push(current_chunk);
-            table.add_chunk(current_chunk, max_cols);
+            chunks.push(current_chunk);
         }
 
         // Handle empty file
-        if table.is_empty() {
-            //chunks.push(vec![vec![String::new()]]);
+        if chunks.is_empty() {
             max_cols = 1;
-            table.add_chunk(vec![vec![String::new()]], max_cols);
+            chunks.push(vec![vec![String::new()]]);
+        }
+
+        // Widths are computed when first drawn rather than scanned here
+        let mut table = Table::from_chunks_lazy(chunks, max_cols);
+
+        // Widen every row to the widest one so no cell is lost past the header's width
+        let padded_rows = table.rows_iter().filter(|row| row.len() < max_cols).count();
+        if padded_rows > 0 {
+            table.expand_columns(max_cols);
         }
 
         let mut warnings = Vec::new();
 
-        if has_padding {
-            warnings.push("Padded".to_string());
+        if self.gzip {
+            warnings.push("Decompressed gzip input".to_string());
+        }
+        if padded_rows > 0 {
+            let mut warning = format!("Padded {} rows to {} columns", padded_rows, max_cols);
+            if wide_rows > 0 {
+                warning.push_str(&format!(" ({} rows wider than the first row)", wide_rows));
+            }
+            warnings.push(warning);
+        }
+        if unread_offset.is_some() {
+            warnings.push(format!("Loaded the first {} rows (:loadmore reads more)", row_no));
         }
 
+        self.unread_offset = unread_offset;
+
         Ok(LoadResult {
             table,
             warnings
         })
     }
 
-    fn has_mutated(&self) -> io::Result<bool> {
-        match self.file_path.as_deref() {
-            Some(path) => {
-                let metadata = fs::metadata(path)?;
-                let last_modified: SystemTime = metadata.modified()?;
+    // === JSON ===
 
-                Ok(self.open_time.le(&last_modified))
-            },
-            None => {
-                Ok(false)
+    /// Load a JSON array of objects; the header row is the union of keys in first-seen order
+    pub fn load_json(path: &Path) -> io::Result<Table> {
+        let reader = open_input(path)?;
+        let value: serde_json::Value = serde_json::from_reader(reader)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let serde_json::Value::Array(items) = value else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "expected a JSON array of objects"));
+        };
+
+        if items.is_empty() {
+            return Ok(Table::new(vec![vec![String::new()]]));
+        }
+
+        let mut keys: Vec<String> = Vec::new();
+        let mut key_index: HashMap<String, usize> = HashMap::new();
+        let mut objects = Vec::with_capacity(items.len());
+
+        for item in items {
+            let serde_json::Value::Object(map) = item else {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "expected a JSON array of objects"));
+            };
+            for key in map.keys() {
+                if !key_index.contains_key(key) {
+                    key_index.insert(key.clone(), keys.len());
+                    keys.push(key.clone());
+                }
+            }
+            objects.push(map);
+        }
+
+        // An array of empty objects has no keys at all
+        if keys.is_empty() {
+            return Ok(Table::new(vec![vec![String::new()]]));
+        }
+
+        let mut rows: Vec<Vec<String>> = Vec::with_capacity(objects.len() + 1);
+        rows.push(keys.clone());
+        for map in objects {
+            let mut row = vec![String::new(); keys.len()];
+            for (key, val) in map {
+                row[key_index[&key]] = json_value_to_cell(val);
             }
+            rows.push(row);
         }
+
+        Ok(Table::new(rows))
     }
 
-    fn write_csv(&mut self, table: &Table) -> io::Result<()> {
+    fn read_json(&mut self) -> io::Result<LoadResult> {
+        let path = self.file_path.as_ref().ok_or(io::ErrorKind::NotFound)?;
+
+        if !path.exists() {
+            return Ok(LoadResult {
+                table: Table::new(vec![vec![String::new(); 5]; 10]),
+                warnings: vec![format!("New file: {}", path.display())],
+            });
+        }
+
+        let mut warnings = Vec::new();
+        if self.gzip {
+            warnings.push("Decompressed gzip input".to_string());
+        }
+
+        Ok(LoadResult {
+            table: Self::load_json(path)?,
+            warnings,
+        })
+    }
+
+    fn write_json(&mut self, table: &Table) -> io::Result<()> {
         if self.read_only {
             return Err(io::Error::new(io::ErrorKind::PermissionDenied, "file opened in read-only mode (use ':fork' to save your work)"));
         }
         let path = self.file_path.as_ref().ok_or(io::ErrorKind::NotFound)?;
-        let delim = self.delimiter;
 
-        let file = fs::File::create(path)?;
-        let writer = BufWriter::new(file);
-        let mut csv_writer = csv::WriterBuilder::new()
-            .delimiter(delim)
-            .from_writer(writer);
+        // JSON files always carry their keys in the first row
+        write_json_to(path, table, true, self.line_ending, self.gzip)
+    }
 
-        for row in table.rows_iter() {
-            csv_writer
-                .write_record(row)
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    fn write_csv(&mut self, table: &Table, progress: Option<&Progress>) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "file opened in read-only mode (use ':fork' to save your work)"));
         }
+        let path = self.file_path.as_ref().ok_or(io::ErrorKind::NotFound)?;
 
-        csv_writer
-            .flush()
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-        self.open_time = SystemTime::now();
-
-        Ok(())
+        let writer = Output::create(path, self.gzip)?;
+        let rows = table.rows_iter().enumerate().map(|(i, row)| {
+            if let Some(progress) = progress.filter(|_| i % 10000 == 0) {
+                progress.set(i);
+            }
+            row.as_slice()
+        });
+        self.write_delimited(rows, writer)?.finish()
     }
 }
 
@@ -405,11 +1228,11 @@ impl FileIO {
 mod tests {
     use super::*;
     use std::io::Write;
-    use tempfile::NamedTempFile;
 
     #[test]
     fn test_format_detection() {
         assert_eq!(FileFormat::from_extension(&PathBuf::from("test.csv")), Some(FileFormat::Csv));
+        assert_eq!(FileFormat::from_extension(&PathBuf::from("test.tsv.gz")), Some(FileFormat::Tsv));
         assert_eq!(FileFormat::from_extension(&PathBuf::from("test.tsv")), Some(FileFormat::Tsv));
         assert_eq!(FileFormat::from_extension(&PathBuf::from("test.txt")), None);
     }
@@ -429,6 +1252,35 @@ mod tests {
         assert!(result.warnings[0].contains("Padded"));
     }
 
+    #[test]
+    fn test_csv_wide_row_keeps_all_cells() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        writeln!(file, "a,b").unwrap();
+        writeln!(file, "1,2,3,4").unwrap();  // Wider than the header
+        writeln!(file, "5,6").unwrap();
+
+        let mut file_io = FileIO::new(Some(file.path().to_path_buf()), None, false).unwrap();
+        let result = file_io.load_table().unwrap();
+        let table = result.table;
+
+        assert_eq!(table.col_count(), 4);
+        assert_eq!(table.get_row_cloned(1).unwrap(), vec!["1", "2", "3", "4"]);
+        // Shorter rows are padded out to the full width
+        assert_eq!(table.get_row_cloned(0).unwrap(), vec!["a", "b", "", ""]);
+        assert_eq!(table.get_row_cloned(2).unwrap(), vec!["5", "6", "", ""]);
+        assert_eq!(result.warnings, vec!["Padded 2 rows to 4 columns (1 rows wider than the first row)"]);
+    }
+
+    #[test]
+    fn test_csv_rectangular_file_has_no_warning() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        writeln!(file, "a,b").unwrap();
+        writeln!(file, "1,2").unwrap();
+
+        let mut file_io = FileIO::new(Some(file.path().to_path_buf()), None, false).unwrap();
+        assert!(file_io.load_table().unwrap().warnings.is_empty());
+    }
+
     #[test]
     fn test_fork_filename_skips_existing_forks() {
         let dir = tempfile::tempdir().unwrap();
@@ -451,4 +1303,546 @@ mod tests {
         let next = next_fork_filename_suffix_wins(&base);
         assert_eq!(next, dir.path().join("data.1.csv"));
     }
+
+    #[test]
+    fn test_max_rows_loads_in_batches() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        write!(file, "h1,h2\n1,\"a\nb\"\n2,x\n3,y,wide\n4,z\n").unwrap();
+
+        let mut file_io = FileIO::new(Some(file.path().to_path_buf()), None, false).unwrap();
+        file_io.set_max_rows(Some(2));
+        let result = file_io.load_table().unwrap();
+        assert_eq!(result.table.row_count(), 2);
+        assert_eq!(result.table.get_cell(1, 1).unwrap(), "a\nb");
+        assert!(file_io.is_partial());
+        assert!(result.warnings.iter().any(|w| w.contains("first 2 rows")));
+
+        let rows = file_io.load_more().unwrap().unwrap();
+        assert_eq!(rows, vec![vec!["2", "x"], vec!["3", "y", "wide"]]);
+        assert!(file_io.is_partial());
+
+        // The last batch ends exactly at the end of the file
+        assert_eq!(file_io.load_more().unwrap().unwrap(), vec![vec!["4", "z"]]);
+        assert!(!file_io.is_partial());
+        assert!(file_io.load_more().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_load_more_refuses_changed_file() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        write!(file, "a\nb\nc\n").unwrap();
+
+        let mut file_io = FileIO::new(Some(file.path().to_path_buf()), None, false).unwrap();
+        file_io.set_max_rows(Some(1));
+        file_io.load_table().unwrap();
+        file.as_file().set_modified(std::time::SystemTime::UNIX_EPOCH).unwrap();
+
+        assert!(file_io.load_more().is_err());
+        assert!(file_io.is_partial());
+    }
+
+    #[test]
+    fn test_max_rows_not_partial_when_file_fits() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        write!(file, "a\nb\n").unwrap();
+
+        let mut file_io = FileIO::new(Some(file.path().to_path_buf()), None, false).unwrap();
+        file_io.set_max_rows(Some(2));
+        assert_eq!(file_io.load_table().unwrap().table.row_count(), 2);
+        assert!(!file_io.is_partial());
+    }
+
+    #[test]
+    fn test_backup_keeps_original_bytes_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        // Quoting and line endings a re-serialized table would not reproduce
+        let original = "\"a\" , b\r\n1,\"2\"\r\n";
+        fs::write(&path, original).unwrap();
+
+        let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+        let mut table = file_io.load_table().unwrap().table;
+        file_io.set_backup(true);
+        table.set_cell(1, 0, "edited".to_string());
+        file_io.write(&table).unwrap();
+
+        let backup = dir.path().join("data.csv~");
+        assert_eq!(fs::read_to_string(&backup).unwrap(), original);
+        assert_ne!(fs::read_to_string(&path).unwrap(), original);
+
+        // The second save of the session keeps the first backup
+        table.set_cell(1, 0, "again".to_string());
+        file_io.write(&table).unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), original);
+    }
+
+    #[test]
+    fn test_has_changed_detects_new_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        fs::write(&path, "a,b\n").unwrap();
+
+        let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+        let table = file_io.load_table().unwrap().table;
+        assert!(!file_io.has_changed());
+
+        // Same size, different mtime
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(std::time::SystemTime::UNIX_EPOCH).unwrap();
+        assert!(file_io.has_changed());
+
+        // Saving or reloading records the new state
+        file_io.write(&table).unwrap();
+        assert!(!file_io.has_changed());
+        file.set_modified(std::time::SystemTime::UNIX_EPOCH).unwrap();
+        file_io.load_table().unwrap();
+        assert!(!file_io.has_changed());
+    }
+
+    #[test]
+    fn test_no_backup_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        fs::write(&path, "a\n").unwrap();
+
+        let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+        file_io.write(&Table::new(vec![vec!["b".to_string()]])).unwrap();
+        assert!(!dir.path().join("data.csv~").exists());
+    }
+
+    #[test]
+    fn test_interrupted_write_leaves_original_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        fs::write(&path, "a,b\n1,2\n").unwrap();
+
+        // Dropped before finish, as when a save fails part way
+        let mut output = Output::create(&path, false).unwrap();
+        output.write_all(b"x,y\n").unwrap();
+        output.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a,b\n1,2\n");
+        drop(output);
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a,b\n1,2\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_replaces_file_keeping_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        fs::write(&path, "a\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+        file_io.write(&Table::new(vec![vec!["b".to_string()]])).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "b\n");
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(feature = "bench-tests")]
+    #[test]
+    fn test_load_million_rows_mapped() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        {
+            let mut writer = BufWriter::new(file.as_file_mut());
+            writeln!(writer, "id,value,note").unwrap();
+            for i in 0..1_000_000 {
+                writeln!(writer, "{},{}.25,padding", i, i * 2).unwrap();
+            }
+        }
+
+        let mut file_io = FileIO::new(Some(file.path().to_path_buf()), None, false).unwrap();
+        let table = file_io.load_table().unwrap().table;
+
+        assert_eq!(table.row_count(), 1_000_001);
+        assert_eq!(table.col_count(), 3);
+        assert_eq!(table.get_cell(1_000_000, 1).unwrap(), "1999998.25");
+    }
+
+    #[test]
+    fn test_export_markdown_with_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_io = FileIO::new(Some(dir.path().join("data.csv")), None, false).unwrap();
+        let table = Table::new(vec![
+            vec!["name".to_string(), "note".to_string()],
+            vec!["a".to_string(), "x|y".to_string()],
+        ]);
+
+        let path = file_io.export_markdown(&table, true).unwrap();
+        assert_eq!(path, dir.path().join("data.md"));
+        let contents = fs::read_to_string(path).unwrap();
+        assert_eq!(contents, "| name | note |\n|---|---|\n| a | x\\|y |\n");
+    }
+
+    #[test]
+    fn test_export_markdown_without_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_io = FileIO::new(Some(dir.path().join("data.csv")), None, false).unwrap();
+        let table = Table::new(vec![
+            vec!["1".to_string(), "2".to_string()],
+        ]);
+
+        let path = file_io.export_markdown(&table, false).unwrap();
+        let contents = fs::read_to_string(path).unwrap();
+        assert_eq!(contents, "|  |  |\n|---|---|\n| 1 | 2 |\n");
+    }
+
+    #[test]
+    fn test_crlf_round_trips_on_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        fs::write(&path, "a,b\r\n1,2\r\n").unwrap();
+
+        let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+        let mut table = file_io.load_table().unwrap().table;
+        assert_eq!(file_io.line_ending(), LineEnding::Crlf);
+        assert_eq!(table.get_row_cloned(1).unwrap(), vec!["1", "2"]);
+
+        table.set_cell(1, 1, "3".to_string());
+        file_io.write(&table).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a,b\r\n1,3\r\n");
+    }
+
+    #[test]
+    fn test_line_ending_override_and_exports() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        fs::write(&path, "a,b\n1,2\n").unwrap();
+
+        let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+        let table = file_io.load_table().unwrap().table;
+        assert_eq!(file_io.line_ending(), LineEnding::Lf);
+
+        file_io.set_line_ending(LineEnding::from_name("crlf").unwrap());
+        file_io.write(&table).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a,b\r\n1,2\r\n");
+
+        let md = fs::read_to_string(file_io.export_markdown(&table, true).unwrap()).unwrap();
+        assert_eq!(md, "| a | b |\r\n|---|---|\r\n| 1 | 2 |\r\n");
+        let json = fs::read_to_string(file_io.export_json(&table, true).unwrap()).unwrap();
+        assert!(json.ends_with("}\r\n]\r\n"));
+        assert!(!json.replace("\r\n", "").contains('\n'));
+    }
+
+    #[test]
+    fn test_minimal_quoting_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+        let table = Table::new(vec![
+            vec!["plain".to_string(), "a,b".to_string()],
+            vec!["say \"hi\"".to_string(), "line1\nline2".to_string()],
+        ]);
+
+        file_io.write(&table).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "plain,\"a,b\"\n\"say \"\"hi\"\"\",\"line1\nline2\"\n"
+        );
+
+        let reloaded = FileIO::new(Some(path), None, false).unwrap().load_table().unwrap().table;
+        assert_eq!(reloaded.row_count(), 2);
+        assert_eq!(reloaded.get_row_cloned(0).unwrap(), table.get_row_cloned(0).unwrap());
+        assert_eq!(reloaded.get_row_cloned(1).unwrap(), table.get_row_cloned(1).unwrap());
+    }
+
+    #[test]
+    fn test_quoting_policies() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+        let table = Table::new(vec![vec!["a".to_string(), "b c".to_string()]]);
+
+        file_io.set_quoting(Quoting::from_name("always").unwrap());
+        file_io.write(&table).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "\"a\",\"b c\"\n");
+
+        let table = Table::new(vec![vec!["x\"y".to_string()]]);
+        file_io.set_quoting(Quoting::Never);
+        file_io.write(&table).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "x\"y\n");
+    }
+
+    #[test]
+    fn test_gzip_csv_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv.gz");
+        let mut encoder = GzEncoder::new(fs::File::create(&path).unwrap(), Compression::default());
+        encoder.write_all(b"a;b\n1;2\n3;4\n").unwrap();
+        encoder.finish().unwrap();
+
+        let mut file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+        // Delimiter detection sees the decompressed text
+        assert_eq!(file_io.delimiter(), b';');
+        assert_eq!(file_io.format(), Some(FileFormat::Csv));
+
+        let result = file_io.load_table().unwrap();
+        assert!(result.warnings.contains(&"Decompressed gzip input".to_string()));
+        let mut table = result.table;
+        assert_eq!(table.get_row_cloned(1).unwrap(), vec!["1", "2"]);
+
+        table.set_cell(2, 1, "5".to_string());
+        file_io.write(&table).unwrap();
+
+        // Output is still gzip with the original delimiter
+        let mut text = String::new();
+        MultiGzDecoder::new(fs::File::open(&path).unwrap()).read_to_string(&mut text).unwrap();
+        assert_eq!(text, "a;b\n1;2\n3;5\n");
+        assert_eq!(file_io.export_path(ExportFormat::Markdown), dir.path().join("data.md"));
+    }
+
+    #[test]
+    fn test_gzip_detected_by_magic_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        let mut encoder = GzEncoder::new(fs::File::create(&path).unwrap(), Compression::default());
+        encoder.write_all(b"x,y\n").unwrap();
+        encoder.finish().unwrap();
+
+        let mut file_io = FileIO::new(Some(path), None, false).unwrap();
+        let table = file_io.load_table().unwrap().table;
+        assert_eq!(table.get_row_cloned(0).unwrap(), vec!["x", "y"]);
+    }
+
+    #[test]
+    fn test_fork_filename_keeps_gz_suffix() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("data.csv.gz");
+        assert_eq!(next_fork_filename_suffix_wins(&base), dir.path().join("data.1.csv.gz"));
+    }
+
+    #[test]
+    fn test_json_load_unions_keys_in_order() {
+        let mut file = NamedTempFile::with_suffix(".json").unwrap();
+        write!(file, r#"[{{"name":"Alice","age":30}},{{"name":"Bob","active":true}},{{"age":null}}]"#).unwrap();
+
+        let mut file_io = FileIO::new(Some(file.path().to_path_buf()), None, false).unwrap();
+        let table = file_io.load_table().unwrap().table;
+
+        assert_eq!(table.get_row_cloned(0).unwrap(), vec!["name", "age", "active"]);
+        assert_eq!(table.get_row_cloned(1).unwrap(), vec!["Alice", "30", ""]);
+        assert_eq!(table.get_row_cloned(2).unwrap(), vec!["Bob", "", "true"]);
+        assert_eq!(table.get_row_cloned(3).unwrap(), vec!["", "", ""]);
+    }
+
+    #[test]
+    fn test_json_load_empty_array() {
+        let mut file = NamedTempFile::with_suffix(".json").unwrap();
+        write!(file, "[]").unwrap();
+
+        let table = FileIO::load_json(file.path()).unwrap();
+        assert_eq!(table.row_count(), 1);
+        assert_eq!(table.col_count(), 1);
+        assert_eq!(table.get_cell(0, 0).unwrap(), "");
+    }
+
+    #[test]
+    fn test_json_load_rejects_non_array() {
+        let mut file = NamedTempFile::with_suffix(".json").unwrap();
+        write!(file, r#"{{"name":"Alice"}}"#).unwrap();
+
+        assert!(FileIO::load_json(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_io = FileIO::new(Some(dir.path().join("data.csv")), None, false).unwrap();
+        let table = Table::new(vec![
+            vec!["name".to_string(), "age".to_string()],
+            vec!["Alice".to_string(), "30".to_string()],
+            vec!["Bob".to_string(), "".to_string()],
+        ]);
+
+        let path = file_io.export_json(&table, true).unwrap();
+        assert_eq!(path, dir.path().join("data.json"));
+
+        let loaded = FileIO::load_json(&path).unwrap();
+        assert_eq!(loaded.get_row_cloned(0).unwrap(), vec!["name", "age"]);
+        assert_eq!(loaded.get_row_cloned(1).unwrap(), vec!["Alice", "30"]);
+        assert_eq!(loaded.get_row_cloned(2).unwrap(), vec!["Bob", ""]);
+    }
+
+    #[test]
+    fn test_json_export_without_header_uses_letters() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_io = FileIO::new(Some(dir.path().join("data.csv")), None, false).unwrap();
+        let table = Table::new(vec![
+            vec!["1".to_string(), "2".to_string()],
+        ]);
+
+        let path = file_io.export_json(&table, false).unwrap();
+        let loaded = FileIO::load_json(&path).unwrap();
+        assert_eq!(loaded.get_row_cloned(0).unwrap(), vec!["A", "B"]);
+        assert_eq!(loaded.get_row_cloned(1).unwrap(), vec!["1", "2"]);
+    }
+
+    #[test]
+    fn test_json_export_keeps_columns_with_repeated_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_io = FileIO::new(Some(dir.path().join("data.csv")), None, false).unwrap();
+        let table = Table::new(vec![
+            vec!["name".to_string(), "name".to_string(), "".to_string(), "name".to_string()],
+            vec!["a".to_string(), "b".to_string(), "c".to_string(), "d".to_string()],
+        ]);
+
+        let path = file_io.export_json(&table, true).unwrap();
+        let loaded = FileIO::load_json(&path).unwrap();
+        assert_eq!(loaded.get_row_cloned(0).unwrap(), vec!["name", "name_2", "C", "name_3"]);
+        assert_eq!(loaded.get_row_cloned(1).unwrap(), vec!["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_json_export_refuses_open_file_and_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.json");
+        fs::write(&path, "[{\"a\": \"1\"}]").unwrap();
+        let table = Table::new(vec![vec!["a".to_string()], vec!["2".to_string()]]);
+
+        let file_io = FileIO::new(Some(path.clone()), None, false).unwrap();
+        assert_eq!(file_io.export_json(&table, true).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[{\"a\": \"1\"}]");
+
+        let file_io = FileIO::new(Some(dir.path().join("data.csv")), None, true).unwrap();
+        assert_eq!(file_io.export_json(&table, true).unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[{\"a\": \"1\"}]");
+    }
+
+    #[test]
+    fn test_scored_detection_comma() {
+        let scored = FileIO::detect_delimiter_scored(b"name,age,city\nAlice,30,Paris\nBob,25,Rome\n");
+        assert_eq!(scored[0], (b',', 2.0));
+        assert!(scored[1..].iter().all(|&(_, score)| score == 0.0));
+    }
+
+    #[test]
+    fn test_scored_detection_tab() {
+        let scored = FileIO::detect_delimiter_scored(b"name\tnote\nAlice\thi, there\nBob\tok\n");
+        assert_eq!(scored[0].0, b'\t');
+        assert_eq!(scored[0].1, 1.0);
+    }
+
+    #[test]
+    fn test_scored_detection_semicolon() {
+        // Decimal commas appear on some lines only, so they score lower
+        let scored = FileIO::detect_delimiter_scored(b"item;price;qty\napple;1,50;3\npear;2;4\n");
+        assert_eq!(scored[0], (b';', 2.0));
+        assert_eq!(scored[1].0, b',');
+        assert!(scored[1].1 < scored[0].1);
+    }
+
+    #[test]
+    fn test_scored_detection_pipe() {
+        let scored = FileIO::detect_delimiter_scored(b"a|b|c|d\n1|2|3|4");
+        assert_eq!(scored[0], (b'|', 3.0));
+    }
+
+    #[test]
+    fn test_scored_detection_empty_sample() {
+        let scored = FileIO::detect_delimiter_scored(b"");
+        assert_eq!(scored.len(), CANDIDATE_DELIMITERS.len());
+        assert!(scored.iter().all(|&(_, score)| score == 0.0));
+    }
+
+    #[test]
+    fn test_load_picks_best_scored_delimiter() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        writeln!(file, "item;price;qty").unwrap();
+        writeln!(file, "apple;1,50;3").unwrap();
+
+        let file_io = FileIO::new(Some(file.path().to_path_buf()), None, false).unwrap();
+        assert_eq!(file_io.delimiter(), b';');
+        assert_eq!(file_io.delimiter_candidates().unwrap()[0].0, b';');
+    }
+
+    #[test]
+    fn test_read_sample_takes_mapped_prefix() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        write!(file, "a|b\n1|2\n3|4").unwrap();
+
+        assert_eq!(read_sample(file.path(), 2).unwrap(), b"a|b\n1|2\n");
+        assert_eq!(read_sample(file.path(), 5).unwrap(), b"a|b\n1|2\n3|4");
+        let empty = NamedTempFile::with_suffix(".csv").unwrap();
+        assert!(read_sample(empty.path(), 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_join_delimited_quotes_cells() {
+        let rows = vec![vec!["a;b".to_string(), "c".to_string()], vec!["1".to_string(), String::new()]];
+        let text = join_delimited(&rows, b';').unwrap();
+        assert_eq!(text, b"\"a;b\";c\n1;\n");
+        assert_eq!(split_delimited(&text, b';').unwrap(), rows);
+    }
+
+    #[test]
+    fn test_split_delimited_pads_rows() {
+        let rows = split_delimited(b"a;b;c\n1;2\n", b';').unwrap();
+        assert_eq!(rows, vec![vec!["a", "b", "c"], vec!["1", "2", ""]]);
+        assert_eq!(split_delimited(b"", b',').unwrap(), vec![vec![""]]);
+    }
+
+    #[test]
+    fn test_read_source_for_resplit() {
+        let mut file = NamedTempFile::with_suffix(".csv").unwrap();
+        writeln!(file, "a;b,c").unwrap();
+        writeln!(file, "1;2,3").unwrap();
+
+        let mut file_io = FileIO::new(Some(file.path().to_path_buf()), Some(b','), false).unwrap();
+        let table = file_io.load_table().unwrap().table;
+        assert_eq!(table.get_row_cloned(0).unwrap(), vec!["a;b", "c"]);
+
+        let rows = split_delimited(&file_io.read_source().unwrap().unwrap(), b';').unwrap();
+        assert_eq!(rows, vec![vec!["a", "b,c"], vec!["1", "2,3"]]);
+
+        file_io.set_delimiter(b';');
+        file_io.write(&Table::new(rows)).unwrap();
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), "a;b,c\n1;2,3\n");
+
+        fs::write(file.path(), "changed\n").unwrap();
+        file_io.mark_saved();
+        assert!(file_io.read_source().unwrap().is_some());
+        fs::write(file.path(), "changed again,\n").unwrap();
+        assert!(file_io.read_source().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_save_as_switches_file_and_delimiter() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_io = FileIO::new(Some(dir.path().join("data.csv")), Some(b';'), false).unwrap();
+        let table = Table::new(vec![vec!["a".to_string(), "b".to_string()]]);
+
+        // Same format keeps the delimiter
+        let path = file_io.save_as(&table, &dir.path().join("copy.csv"), false).unwrap();
+        assert!(path.is_absolute());
+        assert_eq!(file_io.file_path, Some(path.clone()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a;b\n");
+
+        // A .tsv extension switches to tabs
+        let path = file_io.save_as(&table, &dir.path().join("copy.tsv"), false).unwrap();
+        assert_eq!(file_io.delimiter(), b'\t');
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\tb\n");
+    }
+
+    #[test]
+    fn test_save_as_requires_force_to_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("existing.csv");
+        fs::write(&existing, "keep\n").unwrap();
+        let mut file_io = FileIO::new(Some(dir.path().join("data.csv")), None, false).unwrap();
+        let table = Table::new(vec![vec!["new".to_string()]]);
+
+        let err = file_io.save_as(&table, &existing, false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(file_io.file_path, Some(dir.path().join("data.csv")));
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "keep\n");
+
+        file_io.save_as(&table, &existing, true).unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "new\n");
+    }
 }
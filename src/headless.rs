@@ -0,0 +1,234 @@
+//! Non-interactive mode for shell pipelines
+//!
+//! `--to-stdout`, `--filter` and `--sort` load the file, apply the filter and sort
+//! given on the command line and print the remaining rows without starting the TUI.
+//! `--calc` evaluates the formulas and saves the file (or prints it with `--to-stdout`).
+
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::fileio::FileIO;
+use crate::mode::command::Command;
+use crate::numeric::calculator::Calculator;
+use crate::numeric::predicate::Predicate;
+use crate::plugin::PluginManager;
+use crate::table::SortDirection;
+use crate::table::rowmanager::{FilterType, RowManager};
+use crate::table::table::Table;
+use crate::util::{CalcError, letters_from_col, parse_col_letters};
+
+/// Exit code for invalid command line arguments
+pub const EXIT_USAGE: i32 = 1;
+/// Exit code when the file can't be read or the output can't be written
+pub const EXIT_IO: i32 = 2;
+/// Exit code when a formula can't be evaluated (circular or invalid reference, parse error)
+pub const EXIT_CALC: i32 = 3;
+
+/// What to do to the table before printing it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeadlessOptions {
+    pub filter: Option<(usize, Predicate)>,
+    pub sort: Option<Vec<(usize, SortDirection)>>,
+    pub calc: bool,       // Evaluate all formulas first
+    pub to_stdout: bool,  // Print the result; otherwise it is written back to the file
+}
+
+/// Parse a --filter argument: a column followed by a :filter condition, e.g. "B > 100"
+pub fn parse_filter(arg: &str) -> Result<(usize, Predicate), String> {
+    let Some((letters, condition)) = arg.trim().split_once(char::is_whitespace) else {
+        return Err(format!("Invalid filter: '{}' (expected a column and a condition, e.g. \"B > 100\")", arg));
+    };
+    let col = parse_col_letters(letters)
+        .ok_or_else(|| format!("Invalid filter column: '{}' (expected letters like C)", letters))?;
+    match Command::parse(&format!("filter {}", condition.trim())) {
+        Some(Command::Filter(FilterType::PredicateFilter(predicate))) => Ok((col, predicate)),
+        Some(Command::Invalid(msg)) => Err(msg),
+        _ => Err(format!("Invalid filter condition: '{}'", condition.trim())),
+    }
+}
+
+/// Parse a --sort argument: sort keys as for :sort, e.g. "A,C!"
+pub fn parse_sort(arg: &str) -> Result<Vec<(usize, SortDirection)>, String> {
+    match Command::parse(&format!("sort {}", arg.trim())) {
+        Some(Command::SortMulti(keys)) => Ok(keys),
+        _ => Err(format!("Invalid sort keys: '{}' (expected columns like A,C!)", arg)),
+    }
+}
+
+/// Sort the table and filter its rows, returning the indices of the rows to print
+/// The first row is treated as a header: it stays in place and is always printed
+pub fn select_rows(table: &mut Table, options: &HeadlessOptions) -> Result<Vec<usize>, String> {
+    let filter_col = options.filter.as_ref().map(|(col, _)| *col);
+    let sort_cols = options.sort.iter().flatten().map(|(col, _)| *col);
+    if let Some(col) = filter_col.into_iter().chain(sort_cols).find(|&col| col >= table.col_count()) {
+        return Err(format!("Column {} out of range", letters_from_col(col)));
+    }
+
+    if let Some(keys) = &options.sort {
+        if let Some(permutation) = table.get_multi_sort_permutation(keys, true, None) {
+            table.apply_row_permutation(&permutation);
+        }
+    }
+
+    let mut row_manager = RowManager::new();
+    if let Some((col, predicate)) = &options.filter {
+        let column_type = table.column_type(*col, true);
+        row_manager.predicate_filter(table, *col, predicate.clone(), column_type, true);
+    }
+
+    Ok((0..table.row_count()).filter(|&row| row_manager.is_row_live(row)).collect())
+}
+
+/// Replace every formula with its value, as :calc does, returning how many were evaluated
+pub fn calculate(table: &mut Table, plugin_manager: &PluginManager) -> Result<usize, CalcError> {
+    let updates = Calculator::with_plugins(table, true, plugin_manager).evaluate_all()?;
+    let count = updates.len();
+    for (row, col, value) in updates {
+        table.set_cell(row, col, value);
+    }
+    Ok(count)
+}
+
+/// Load the file, apply the options and print the result to stdout (or save it)
+/// Returns the process exit code
+pub fn run(file_path: Option<PathBuf>, delimiter: Option<u8>, read_only: bool, options: &HeadlessOptions) -> i32 {
+    let Some(path) = file_path else {
+        eprintln!("Error: a file is required with --to-stdout, --filter, --sort or --calc");
+        return EXIT_USAGE;
+    };
+    if !path.exists() {
+        eprintln!("Error: {}: no such file", path.display());
+        return EXIT_IO;
+    }
+
+    let loaded = FileIO::new(Some(path.clone()), delimiter, read_only || options.to_stdout).and_then(|mut file_io| {
+        let load_result = file_io.load_table()?;
+        Ok((file_io, load_result))
+    });
+    let (mut file_io, load_result) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path.display(), e);
+            return EXIT_IO;
+        }
+    };
+    for warning in &load_result.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let mut table = load_result.table;
+
+    if options.calc {
+        let mut plugin_manager = PluginManager::new();
+        let _ = plugin_manager.load_plugins();
+        if let Err(e) = calculate(&mut table, &plugin_manager) {
+            eprintln!("Error: {}", e);
+            return EXIT_CALC;
+        }
+    }
+
+    if !options.to_stdout {
+        return match file_io.write(&table) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Error writing {}: {}", path.display(), e);
+                EXIT_IO
+            }
+        };
+    }
+    let rows = match select_rows(&mut table, options) {
+        Ok(rows) => rows,
+        Err(msg) => {
+            eprintln!("Error: {}", msg);
+            return EXIT_USAGE;
+        }
+    };
+
+    let stdout = BufWriter::new(io::stdout().lock());
+    let written = file_io
+        .write_delimited(rows.iter().filter_map(|&row| table.get_row(row)), stdout)
+        .and_then(|mut writer| writer.flush());
+    match written {
+        // The reader went away early (e.g. `| head`); that's not an error
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("Error writing output: {}", e);
+            EXIT_IO
+        }
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::numeric::predicate::Op;
+
+    fn table() -> Table {
+        Table::new(vec![
+            vec!["name".to_string(), "score".to_string()],
+            vec!["bo".to_string(), "7".to_string()],
+            vec!["al".to_string(), "12".to_string()],
+            vec!["cy".to_string(), "3".to_string()],
+        ])
+    }
+
+    #[test]
+    fn test_parse_filter_and_sort() {
+        assert_eq!(parse_filter("B > 5"), Ok((1, Predicate::Comparator { op: Op::Gt, val: "5".to_string() })));
+        assert_eq!(parse_filter("a blank"), Ok((0, Predicate::Blank)));
+        assert!(parse_filter("B").is_err());
+        assert!(parse_filter("3 > 5").is_err());
+        assert!(parse_filter("B ~ 5").is_err());
+
+        assert_eq!(parse_sort("B!,A"), Ok(vec![(1, SortDirection::Descending), (0, SortDirection::Ascending)]));
+        assert!(parse_sort("natural").is_err());
+    }
+
+    #[test]
+    fn test_select_rows_sorts_then_filters_keeping_header() {
+        let mut table = table();
+        let options = HeadlessOptions {
+            filter: Some(parse_filter("B >= 5").unwrap()),
+            sort: Some(parse_sort("B!").unwrap()),
+            ..Default::default()
+        };
+        let rows = select_rows(&mut table, &options).unwrap();
+        let names: Vec<&str> = rows.iter().map(|&r| table.get_cell(r, 0).unwrap().as_str()).collect();
+        assert_eq!(names, vec!["name", "al", "bo"]);
+
+        let mut table = self::table();
+        let options = HeadlessOptions { filter: Some(parse_filter("C = x").unwrap()), ..Default::default() };
+        assert_eq!(select_rows(&mut table, &options), Err("Column C out of range".to_string()));
+    }
+
+    #[test]
+    fn test_write_delimited_to_buffer() {
+        let mut table = table();
+        let options = HeadlessOptions { filter: Some(parse_filter("A ! bo").unwrap()), ..Default::default() };
+        let rows = select_rows(&mut table, &options).unwrap();
+        let file_io = FileIO::new(None, Some(b';'), true).unwrap();
+        let out = file_io.write_delimited(rows.iter().filter_map(|&r| table.get_row(r)), Vec::new()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "name;score\nal;12\ncy;3\n");
+    }
+
+    #[test]
+    fn test_calculate_replaces_formulas_and_reports_errors() {
+        let plugin_manager = PluginManager::new();
+        let mut table = Table::new(vec![
+            vec!["a".to_string(), "b".to_string(), "total".to_string()],
+            vec!["2".to_string(), "3".to_string(), "=A2*B2".to_string()],
+            vec!["4".to_string(), "5".to_string(), "=C2+A3".to_string()],
+        ]);
+        assert_eq!(calculate(&mut table, &plugin_manager).ok(), Some(2));
+        assert_eq!(table.get_cell(1, 2).unwrap(), "6");
+        assert_eq!(table.get_cell(2, 2).unwrap(), "10");
+
+        let mut table = Table::new(vec![
+            vec!["x".to_string()],
+            vec!["=A3".to_string()],
+            vec!["=A2".to_string()],
+        ]);
+        assert!(matches!(calculate(&mut table, &plugin_manager), Err(CalcError::CircularReference(_))));
+        assert_eq!(table.get_cell(1, 0).unwrap(), "=A3");
+    }
+}
@@ -21,10 +21,20 @@ pub enum KeyResult {
     Execute(Transaction),
     /// Execute a transaction and return to normal mode
     ExecuteAndFinish(Transaction),
+    /// Execute a transaction, return to normal mode and show a message
+    ExecuteAndReport(Transaction, String),
+    /// Paste a transaction (with a progress bar when large), return to normal mode and show the message if any
+    Paste(Transaction, Option<String>),
+    /// Execute a transaction, then edit the cell under the cursor (visual `c`)
+    ExecuteAndEdit(Transaction),
     /// Return to normal mode
     Finish,
     /// Show a message
     Message(String),
+    /// Collapse the view to the rows start..=end (visual row "filter to selection")
+    FilterRows { start: usize, end: usize },
+    /// Show the full content of the current cell in a popup
+    InspectCell,
     /// Quit the application
     Quit,
     /// Force quit
@@ -37,6 +47,69 @@ pub fn is_escape(key: KeyEvent) -> bool {
         || (key.code == KeyCode::Char('[') && key.modifiers.contains(KeyModifiers::CONTROL))
 }
 
+/// Most entries kept in the command and search histories
+const INPUT_HISTORY_LIMIT: usize = 100;
+
+/// Previously entered lines for the command or search prompt, browsed with Up/Down
+pub struct InputHistory {
+    entries: Vec<String>,
+    position: Option<usize>,  // Entry being shown, None while editing a new line
+    draft: String,            // The new line, restored when browsing back past the newest entry
+}
+
+impl InputHistory {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            position: None,
+            draft: String::new(),
+        }
+    }
+
+    /// Remember an entered line, skipping blanks and repeats of the newest entry
+    pub fn push(&mut self, line: &str) {
+        self.position = None;
+        if line.trim().is_empty() || self.entries.last().is_some_and(|last| last == line) {
+            return;
+        }
+        if self.entries.len() == INPUT_HISTORY_LIMIT {
+            self.entries.remove(0);
+        }
+        self.entries.push(line.to_string());
+    }
+
+    /// Stop browsing, e.g. when the prompt is opened again
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+
+    /// Step to an older entry; current is the line being edited, kept to come back to
+    pub fn older(&mut self, current: &str) -> Option<String> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            }
+            Some(position) => position.saturating_sub(1),
+        };
+        self.position = Some(position);
+        Some(self.entries[position].clone())
+    }
+
+    /// Step to a newer entry, ending with the line that was being edited
+    pub fn newer(&mut self) -> Option<String> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            Some(self.entries[position + 1].clone())
+        } else {
+            self.position = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+}
+
 /// Actions resulting from key sequences
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub enum SequenceAction {
@@ -45,6 +118,7 @@ pub enum SequenceAction {
     DeleteCol,   // dc
     YankRow,     // yr
     YankCol,     // yc
+    DuplicateRow, // yp - insert copies of the current row below it
     Yank,        // yy (visual mode)
     Delete,      // dd (visual mode)
     // Motion actions (can have count)
@@ -52,14 +126,26 @@ pub enum SequenceAction {
     MoveUp,      // k
     MoveLeft,    // h
     MoveRight,   // l
-    // Format actions (visual mode)
+    // Format actions (current cell, or the selection in visual mode)
     FormatDefault,         // ff - reset to default number format
     FormatCommas,          // f, - add comma separators
     FormatCurrency,        // f$
     FormatScientific,      // fe
     FormatPercentage,      // f%
+    // Case actions (current cell, or the selection in visual mode)
+    LowerCase,             // gu
+    UpperCase,             // gU
+    ToggleCase,            // g~
+    // Number bumps (count is the amount)
+    Increment,             // Ctrl-A
+    Decrement,             // Ctrl-X
+    IncrementSeries,       // g Ctrl-A (visual mode: the nth cell changes by n times the count)
+    DecrementSeries,       // g Ctrl-X
     // Register selection
     SelectRegister(char),  // "x
+    // Marks
+    SetMark(char),         // mx - remember the cursor position as mark x
+    JumpToMark(char),      // `x - jump to mark x
 }
 
 impl SequenceAction {
@@ -72,6 +158,28 @@ impl SequenceAction {
                  SequenceAction::MoveRight
                  )
     }
+
+    /// True for actions that change the table (refused in read-only mode)
+    pub fn modifies_table(&self) -> bool {
+        matches!(self,
+                 SequenceAction::DeleteRow |
+                 SequenceAction::DeleteCol |
+                 SequenceAction::DuplicateRow |
+                 SequenceAction::Delete |
+                 SequenceAction::FormatDefault |
+                 SequenceAction::FormatCommas |
+                 SequenceAction::FormatCurrency |
+                 SequenceAction::FormatScientific |
+                 SequenceAction::FormatPercentage |
+                 SequenceAction::LowerCase |
+                 SequenceAction::UpperCase |
+                 SequenceAction::ToggleCase |
+                 SequenceAction::Increment |
+                 SequenceAction::Decrement |
+                 SequenceAction::IncrementSeries |
+                 SequenceAction::DecrementSeries
+                 )
+    }
 }
 
 /// Result of processing a key through the buffer
@@ -115,6 +223,20 @@ impl KeyBuffer {
         // Only buffer character keys (no modifiers except shift)
         let c = match key.code {
             KeyCode::Char(c) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => c,
+            // Ctrl-A / Ctrl-X bump numbers, after g as a series
+            KeyCode::Char(c @ ('a' | 'x'))
+                if key.modifiers == KeyModifiers::CONTROL && matches!(self.keys.as_slice(), [] | ['g']) =>
+            {
+                let action = match (c, self.keys.is_empty()) {
+                    ('a', true) => SequenceAction::Increment,
+                    ('a', false) => SequenceAction::IncrementSeries,
+                    (_, true) => SequenceAction::Decrement,
+                    (_, false) => SequenceAction::DecrementSeries,
+                };
+                let count = self.take_count();
+                self.keys.clear();
+                return KeyBufferResult::Action(action, count);
+            }
             _ => {
                 // Non-char key breaks any sequence
                 let count = self.take_count();
@@ -182,7 +304,7 @@ impl KeyBuffer {
     }
 
     fn is_valid_prefix(&self) -> bool {
-        matches!(self.keys.as_slice(), ['g'] | ['d'] | ['y'] | ['f'] | ['"'])
+        matches!(self.keys.as_slice(), ['g'] | ['d'] | ['y'] | ['f'] | ['"'] | ['m'] | ['`'])
     }
 }
 
@@ -220,8 +342,11 @@ impl NavigationHandler {
     /// Handle navigation keys, returns true if the key was handled
     pub fn handle(&self, key: KeyEvent, view: &mut TableView, table: &Table) -> bool {
         let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
 
         match key.code {
+            // Alt+hjkl move rows and columns instead
+            KeyCode::Char('h' | 'j' | 'k' | 'l') if alt => false,
             // Jump navigation: Ctrl+Arrow or Ctrl+hjkl
             KeyCode::Left if ctrl => { view.jump_left(table); true }
             KeyCode::Right if ctrl => { view.jump_right(table); true }
@@ -1,6 +1,8 @@
 mod app;
+mod buffer;
 mod dispatch;
 mod fileio;
+mod headless;
 mod input;
 mod mode;
 mod plugin;
@@ -27,16 +29,21 @@ use crossterm::{
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 use app::App;
-use fileio::FileIO;
+use fileio::{FileIO, FileFormat, delimiter_from_name};
+use config::{FileHistory, Settings};
+use headless::HeadlessOptions;
 
 /// Parse command line arguments
-/// Returns (file_path, delimiter)
-fn parse_args() -> (Option<PathBuf>, Option<u8>, bool, bool) {
+/// Returns (file_path, delimiter, fork, read_only, max_rows, headless options)
+/// Invalid arguments exit with headless::EXIT_USAGE
+fn parse_args() -> (Option<PathBuf>, Option<u8>, bool, bool, Option<usize>, Option<HeadlessOptions>) {
     let args: Vec<String> = std::env::args().collect();
     let mut file_path: Option<PathBuf> = None;
     let mut delimiter: Option<u8> = None;
     let mut fork = false;
     let mut read_only = false;
+    let mut max_rows: Option<usize> = None;
+    let mut headless: Option<HeadlessOptions> = None;
 
     let mut i = 1;
     while i < args.len() {
@@ -62,6 +69,42 @@ fn parse_args() -> (Option<PathBuf>, Option<u8>, bool, bool) {
                 read_only = true;
                 i += 1;
             }
+            "--max-rows" => {
+                match args.get(i + 1).and_then(|n| n.parse::<usize>().ok()) {
+                    Some(n) if n > 0 => max_rows = Some(n),
+                    _ => {
+                        eprintln!("Error: --max-rows requires a positive number");
+                        std::process::exit(1);
+                    }
+                }
+                i += 2;
+            }
+            "--to-stdout" => {
+                headless.get_or_insert_with(HeadlessOptions::default).to_stdout = true;
+                i += 1;
+            }
+            "--calc" => {
+                headless.get_or_insert_with(HeadlessOptions::default).calc = true;
+                i += 1;
+            }
+            "--filter" | "--sort" => {
+                let Some(value) = args.get(i + 1) else {
+                    eprintln!("Error: {} requires an argument", args[i]);
+                    std::process::exit(headless::EXIT_USAGE);
+                };
+                let options = headless.get_or_insert_with(HeadlessOptions::default);
+                options.to_stdout = true;
+                let parsed = if args[i] == "--filter" {
+                    headless::parse_filter(value).map(|filter| options.filter = Some(filter))
+                } else {
+                    headless::parse_sort(value).map(|keys| options.sort = Some(keys))
+                };
+                if let Err(msg) = parsed {
+                    eprintln!("Error: {}", msg);
+                    std::process::exit(headless::EXIT_USAGE);
+                }
+                i += 2;
+            }
             arg if arg.starts_with('-') => {
                 eprintln!("Unknown option: {}", arg);
                 std::process::exit(1);
@@ -73,18 +116,14 @@ fn parse_args() -> (Option<PathBuf>, Option<u8>, bool, bool) {
         }
     }
 
-    (file_path, delimiter, fork, read_only)
+    (file_path, delimiter, fork, read_only, max_rows, headless)
 }
 
 /// Parse a delimiter string into a byte
 fn parse_delimiter(s: &str) -> Option<u8> {
-    match s.to_lowercase().as_str() {
-        "comma" | "," => Some(b','),
-        "tab" | "\\t" | "\t" => Some(b'\t'),
-        "semicolon" | ";" => Some(b';'),
-        "pipe" | "|" => Some(b'|'),
-        _ if s.len() == 1 => Some(s.as_bytes()[0]),
-        _ => {
+    match delimiter_from_name(s) {
+        Some(delimiter) => Some(delimiter),
+        None => {
             eprintln!("Invalid delimiter: '{}'. Use comma, tab, semicolon, pipe, or a single character.", s);
             std::process::exit(1);
         }
@@ -128,9 +167,20 @@ fn print_help() {
     eprintln!("    -d, --delimiter <DELIM>  Set the field delimiter (comma, tab, semicolon, pipe, or char)");
     eprintln!("    -f, --fork               Fork the file by default");
     eprintln!("    --read-only              Read only mode");
+    eprintln!("    --max-rows <N>           Load only the first N rows of the file (:loadmore reads more)");
+    eprintln!("    --to-stdout              Print the file to stdout instead of opening the editor");
+    eprintln!("    --filter <\"C OP VAL\">    Print only rows where column C matches (implies --to-stdout)");
+    eprintln!("    --sort <KEYS>            Sort rows by columns, e.g. A,C! (implies --to-stdout)");
+    eprintln!("    --calc                   Evaluate all formulas and save the file (or print it with --to-stdout)");
     eprintln!("    -h, --help               Print this help message");
     eprintln!();
     eprintln!("If no delimiter is specified, it will be auto-detected from the file content.");
+    eprintln!();
+    eprintln!("EXIT CODES (--to-stdout, --calc):");
+    eprintln!("    0  Success");
+    eprintln!("    1  Invalid arguments (unknown option, bad delimiter, filter or sort keys)");
+    eprintln!("    2  The file could not be read or the output could not be written");
+    eprintln!("    3  A formula could not be evaluated (circular reference, parse error, ...)");
 }
 
 
@@ -152,13 +202,35 @@ fn main() -> io::Result<()> {
 
     install_panic_hook();
 
-    let (file_path, delimiter, fork, read_only) = parse_args();
+    let (file_path, delimiter, fork, read_only, max_rows, headless_options) = parse_args();
+
+    // Settings from ~/.config/tabular/config.toml (defaults if missing or malformed)
+    let (settings, settings_warning) = Settings::load(&Settings::path());
+    let mut config_warnings: Vec<String> = settings_warning.into_iter().collect();
+    // Settings remembered from the last session with this file
+    let remembered = file_path.as_deref()
+        .and_then(|path| FileHistory::load(&FileHistory::path()).get(path).cloned());
+    let config_delimiter = settings.delimiter.as_deref().and_then(|name| {
+        let parsed = delimiter_from_name(name);
+        if parsed.is_none() {
+            config_warnings.push(format!("Invalid delimiter in config: {}", name));
+        }
+        parsed
+    });
+    // Files opened later with :e have no remembered settings, only these
+    let default_delimiter = delimiter.or(config_delimiter);
+    let delimiter = delimiter.or(remembered.as_ref().map(|r| r.delimiter)).or(config_delimiter);
+
+    if let Some(options) = headless_options {
+        std::process::exit(headless::run(file_path, delimiter, read_only, &options));
+    }
 
     let mut file_io = if fork {
         (FileIO::new(file_path, delimiter, read_only)?).fork()
     } else {
         FileIO::new(file_path, delimiter, read_only)?
     };
+    file_io.set_max_rows(max_rows);
 
     let load_result = file_io.load_table().map_err(|e| {error!(error = %e, "Failed to load table"); e})?;
 
@@ -169,16 +241,23 @@ fn main() -> io::Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Show delimiter info if auto-detected (only when file exists and no explicit delimiter)
-    let delimiter_msg = if delimiter.is_none() && file_io.file_path.is_some() {
+    let delimiter_msg = if delimiter.is_none() && file_io.file_path.is_some() && file_io.format() != Some(FileFormat::Json) {
         Some(format!("Delimiter: {}", file_io.delimiter_name()))
     } else {
         None
     };
 
     let mut app = App::new(load_result.table, file_io);
+    app.default_delimiter = default_delimiter;
+    config_warnings.extend(app.apply_settings(settings));
+    if let Some(remembered) = &remembered {
+        app.restore_file_settings(remembered);
+    }
 
     // Show any warnings from loading (e.g., "New file", "Padded rows")
     let mut messages: Vec<String> = load_result.warnings;
+    messages.extend(config_warnings);
+    messages.extend(app.run_load_hooks());
     if let Some(msg) = delimiter_msg {
         messages.push(msg);
     }
@@ -187,6 +266,7 @@ fn main() -> io::Result<()> {
     }
 
     let result = app.run(&mut terminal, shutdown);
+    app.remember_file_settings();
 
     disable_raw_mode()?;
     execute!(io::stdout(), LeaveAlternateScreen)?;
@@ -13,6 +13,8 @@ pub enum Mode {
     VisualRow,
     VisualCol,
     Search,
+    Prompt,  // A plugin is asking for input
+    Confirm,  // Answering y/n/a/q for each match of :s///c
 }
 
 impl Mode {
@@ -25,6 +27,8 @@ impl Mode {
             Mode::VisualRow => "VISUAL (ROW)",
             Mode::VisualCol => "VISUAL (COL)",
             Mode::Search => "SEARCH",
+            Mode::Prompt => "PROMPT",
+            Mode::Confirm => "CONFIRM",
         }
     }
 
@@ -1,10 +1,12 @@
 use regex::Regex;
 use crossterm::event::{KeyCode, KeyEvent};
 
-use crate::util::{CellRef, parse_cell_ref};
-use crate::table::rowmanager::FilterType;
-use crate::numeric::predicate::parse_predicate;
-use crate::input::is_escape;
+use crate::util::{CellRef, ColumnType, parse_cell_ref, parse_col_letters};
+use crate::table::SortDirection;
+use crate::table::rowmanager::{FilterType, SampleSize};
+use crate::numeric::predicate::{Predicate, parse_predicate};
+use crate::input::{is_escape, InputHistory};
+use crate::fileio::{ExportFormat, delimiter_from_name};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ReplaceScope {
@@ -17,9 +19,32 @@ pub struct ReplaceCommand {
     pub pattern: String,
     pub replacement: String,
     pub global: bool,      // /g flag - replace all occurrences in each cell
+    pub regex: bool,       // s/ treats the pattern as a regex, sl/ as a literal string
+    pub smart_case: bool,  // /i flag - match ignoring case and give each replacement the match's case
+    pub count_only: bool,  // /n flag - report the matches without replacing anything
+    pub confirm: bool,     // /c flag - ask before each replacement
     pub scope: ReplaceScope,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggKind {
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggKind::Sum => "sum",
+            AggKind::Avg => "avg",
+            AggKind::Min => "min",
+            AggKind::Max => "max",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[allow(dead_code)]
 pub enum Command {
@@ -27,32 +52,102 @@ pub enum Command {
     Quit,
     ForceQuit,
     WriteQuit,
+    ForceWrite,     // Save even in read-only mode, turning it off
+    WriteAs { path: String, force: bool },  // Save to another path and keep editing it (force overwrites)
     AddColumn,
     DeleteColumn,
     ToggleHeader,
     Calc,
+    ColFormula { col: usize, template: String },  // Stamp a {row} formula into a column and evaluate it
     Sort,           // Sort rows by current column, ascending
     SortDesc,       // Sort rows by current column, descending
     SortRow,        // Sort columns by current row, ascending
     SortRowDesc,    // Sort columns by current row, descending
+    SortMulti(Vec<(usize, SortDirection)>),  // Sort rows by several columns, first key has priority
+    SortNatural(SortDirection),  // Sort rows by current column, comparing embedded numbers by value
+    SortDate(SortDirection),  // Sort rows by current column as dates
+    SortReverse,    // Repeat the last sort in the opposite direction
+    Transpose,      // Swap rows and columns of the whole table
+    Reverse { cols: bool },  // Reverse the order of the data rows (or of the columns)
+    Shuffle(Option<u64>),  // Randomly permute the data rows (a seed makes it reproducible)
+    Duplicate(usize),  // Insert N copies of the current row/selected rows below
+    Append,         // Add a row at the bottom and fill rows in one after another
+    Merge { separator: String, skip_empty: bool },  // Join the selected columns into the leftmost one
+    Index { start: usize, name: Option<String> },  // Insert a leftmost column numbering the rows
+    Dedup(Option<Vec<usize>>),  // Remove rows repeating an earlier row's key columns (None = current column)
+    FreezeCols(usize),  // Pin the first N columns at the left (0 unfreezes)
+    HideCol,        // Hide the current column from the display
+    UnhideCols(Option<usize>),  // Show a hidden column again (None shows all)
+    Diff(Option<(usize, usize)>),  // Highlight rows where two columns differ (None turns it off)
+    SetColType(usize, Option<ColumnType>),  // Declare a column's type (None = detect automatically)
+    ShowColType,    // Report the declared or inferred type of the current column
+    DelimiterCandidates,  // Report the detection score of each candidate delimiter
+    SetDelimiter(u8),  // Re-split the file with another delimiter and save with it
     Grid,
     Theme(String),  // Set theme by name
     ThemeList,      // List available themes
     Replace(ReplaceCommand),
     NavigateRow(usize),
+    NavigateRowBy(isize),  // Move the cursor N rows down (or up when negative)
+    NavigateLastRow,
     NavigateCell(CellRef),
+    NavigateCol(usize),  // Jump to a column by letter, keeping the current row
+    SearchCol(String),  // Search only the current column for a pattern
     Fork,
+    OpenBuffer { path: String, force: bool },  // Load another file into a new buffer
+    Reload,         // Load the current file again from disk, dropping unsaved changes
+    LoadMore,       // Read the next batch of rows of a partly loaded file
+    CycleBuffer { forward: bool, force: bool },  // Switch to the next/previous buffer
+    Export(ExportFormat),  // Write a copy of the table in another format
     Clip,           // Copy yank to system clipboard
     SysPaste,       // Yank from system clipboard
+    PasteSystem,    // Paste the system clipboard as a block at the cursor
+    Registers,      // Show register contents in the canvas overlay
+    PasteTranspose, // Paste the register with rows and columns swapped
     PluginList,     // List loaded plugins
     Precision(Option<usize>),  // Set display precision for numbers (None = auto)
+    SetOption { name: String, value: String },  // :set name value
+    Aggregate(AggKind),  // Report an aggregate over the current column or selection
+    Describe,       // Show summary statistics of the current column in the canvas
+    GroupBy { col: Option<usize>, sum: Option<usize> },  // Count rows per value of a column (None = current), optionally summing another
     Custom { name: String, args: Vec<String> },
     Filter(FilterType),
     Canvas,  // Debug command to show canvas overlay
+    Invalid(String),  // Recognized command with bad arguments, holds the error message
     Unknown(String),
 }
 
 impl Command {
+    /// True for commands that change the table (refused in read-only mode)
+    pub fn modifies_table(&self) -> bool {
+        matches!(self,
+            Command::AddColumn
+            | Command::DeleteColumn
+            | Command::Calc
+            | Command::ColFormula { .. }
+            | Command::Sort
+            | Command::SortDesc
+            | Command::SortRow
+            | Command::SortRowDesc
+            | Command::SortMulti(_)
+            | Command::SortNatural(_)
+            | Command::SortDate(_)
+            | Command::SortReverse
+            | Command::Transpose
+            | Command::Reverse { .. }
+            | Command::Shuffle(_)
+            | Command::Duplicate(_)
+            | Command::Append
+            | Command::Merge { .. }
+            | Command::Index { .. }
+            | Command::Dedup(_)
+            | Command::SetDelimiter(_)
+            | Command::Replace(ReplaceCommand { count_only: false, .. })
+            | Command::PasteTranspose
+            | Command::PasteSystem
+        )
+    }
+
     pub fn parse(input: &str) -> Option<Self> {
         let trimmed = input.trim();
 
@@ -61,24 +156,255 @@ impl Command {
             return Some(Command::Replace(replace_cmd));
         }
 
+        // Checked before absolute rows, which would accept a leading +
+        if trimmed == "$" {
+            return Some(Command::NavigateLastRow);
+        }
+        let offset = |n: &str| n.bytes().all(|b| b.is_ascii_digit()).then(|| n.parse::<isize>().ok()).flatten();
+        if let Some(offset) = trimmed.strip_prefix('+').and_then(offset) {
+            return Some(Command::NavigateRowBy(offset));
+        }
+        if let Some(offset) = trimmed.strip_prefix('-').and_then(offset) {
+            return Some(Command::NavigateRowBy(-offset));
+        }
+
         if let Ok(row_dest) = input.parse::<usize>() {
             // ":0" and ":1" both navigate to the first row (rows are 1-based)
             return Some(Command::NavigateRow(row_dest.saturating_sub(1)));
         }
 
+        if let Some(letters) = trimmed.strip_prefix("col ") {
+            let letters = letters.trim();
+            return match parse_col_letters(letters) {
+                Some(col) => Some(Command::NavigateCol(col)),
+                None => Some(Command::Invalid(format!("Invalid column: {} (expected letters like C or AA)", letters))),
+            };
+        }
+
+        // Checked before cell references so paths like "Q1.csv" aren't taken as cells
+        if let Some(path) = trimmed.strip_prefix("e ") {
+            return Some(Command::OpenBuffer { path: path.trim().to_string(), force: false });
+        }
+        if let Some(path) = trimmed.strip_prefix("e! ") {
+            return Some(Command::OpenBuffer { path: path.trim().to_string(), force: true });
+        }
+        if let Some(path) = trimmed.strip_prefix("w ") {
+            return Some(Command::WriteAs { path: path.trim().to_string(), force: false });
+        }
+        if let Some(path) = trimmed.strip_prefix("w! ") {
+            return Some(Command::WriteAs { path: path.trim().to_string(), force: true });
+        }
+        // Checked before cell references since the formula contains them
+        if let Some(args) = trimmed.strip_prefix("col-formula ") {
+            let (letters, template) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+            let template = template.trim();
+            return match parse_col_letters(letters) {
+                None => Some(Command::Invalid(format!("Invalid column: {} (expected letters like C or AA)", letters))),
+                Some(_) if !template.starts_with('=') => {
+                    Some(Command::Invalid("Usage: col-formula C =FORMULA (e.g. =A{row}*B{row})".to_string()))
+                }
+                Some(col) => Some(Command::ColFormula { col, template: template.to_string() }),
+            };
+        }
+        if let Some(pattern) = trimmed.strip_prefix("search col ") {
+            return Some(Command::SearchCol(pattern.trim().to_string()));
+        }
+
         let cell_re = Regex::new(r"[A-Z]+\d+").unwrap();
 
         if cell_re.is_match(input) {
             return Some(Command::NavigateCell(parse_cell_ref(input)?));
         }
 
+        match trimmed {
+            "sort natural" => return Some(Command::SortNatural(SortDirection::Ascending)),
+            "sortd natural" => return Some(Command::SortNatural(SortDirection::Descending)),
+            "sort date" => return Some(Command::SortDate(SortDirection::Ascending)),
+            "sortd date" => return Some(Command::SortDate(SortDirection::Descending)),
+            _ => {}
+        }
+
+        // Multi-column sort: sort A,B! (a trailing ! sorts that key descending)
+        if let Some(sort_args) = trimmed.strip_prefix("sort ") {
+            return Self::parse_sort_keys(sort_args).map(Command::SortMulti);
+        }
+
+        if let Some(n) = trimmed.strip_prefix("freeze cols ").and_then(|n| n.trim().parse::<usize>().ok()) {
+            return Some(Command::FreezeCols(n));
+        }
+
+        if let Some(letters) = trimmed.strip_prefix("unhide ") {
+            let letters = letters.trim();
+            return match parse_col_letters(letters) {
+                Some(col) => Some(Command::UnhideCols(Some(col))),
+                None => Some(Command::Invalid(format!("Invalid column: {} (expected letters like C or AA)", letters))),
+            };
+        }
+
+        // Column type declaration: type C numeric|text|natural|date|auto
+        if let Some(args) = trimmed.strip_prefix("type ") {
+            let mut parts = args.split_whitespace();
+            let (Some(letters), Some(name), None) = (parts.next(), parts.next(), parts.next()) else {
+                return Some(Command::Invalid("Usage: type C numeric|text|natural|date|auto".to_string()));
+            };
+            let Some(col) = parse_col_letters(letters) else {
+                return Some(Command::Invalid(format!("Invalid column: {} (expected letters like C or AA)", letters)));
+            };
+            if name == "auto" {
+                return Some(Command::SetColType(col, None));
+            }
+            return match ColumnType::from_name(name) {
+                Some(column_type) => Some(Command::SetColType(col, Some(column_type))),
+                None => Some(Command::Invalid(format!("Unknown column type: {} (expected numeric, text, natural, date or auto)", name))),
+            };
+        }
+
+        if let Some(args) = trimmed.strip_prefix("diff ") {
+            let args: Vec<&str> = args.split_whitespace().collect();
+            return match args.as_slice() {
+                ["off"] => Some(Command::Diff(None)),
+                [a, b] => match (parse_col_letters(a), parse_col_letters(b)) {
+                    (Some(a), Some(b)) => Some(Command::Diff(Some((a, b)))),
+                    _ => Some(Command::Invalid("Usage: diff C D (two column letters) or diff off".to_string())),
+                },
+                _ => Some(Command::Invalid("Usage: diff C D (two column letters) or diff off".to_string())),
+            };
+        }
+
+        // merge [skipempty] SEP, where SEP may be quoted to keep surrounding spaces
+        if trimmed == "merge" || trimmed.starts_with("merge ") {
+            let mut rest = input.trim_start()["merge".len()..].trim_start();
+            let skip_empty = match rest.strip_prefix("skipempty") {
+                Some(after) if after.is_empty() || after.starts_with(char::is_whitespace) => {
+                    rest = after.trim_start();
+                    true
+                }
+                _ => false,
+            };
+            let separator = match rest.trim_end().strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+                Some(quoted) => quoted,
+                None => rest.trim_end(),
+            };
+            return Some(Command::Merge { separator: separator.to_string(), skip_empty });
+        }
+
+        // index [START] [NAME]
+        if trimmed == "index" || trimmed.starts_with("index ") {
+            let mut start = 1;
+            let mut name = None;
+            for arg in trimmed["index".len()..].split_whitespace() {
+                match arg.parse::<usize>() {
+                    Ok(n) => start = n,
+                    Err(_) => name = Some(arg.to_string()),
+                }
+            }
+            return Some(Command::Index { start, name });
+        }
+
+        if let Some(cols) = trimmed.strip_prefix("dedup ") {
+            let keys: Option<Vec<usize>> = cols.split(',').map(|c| parse_col_letters(c.trim())).collect();
+            return match keys {
+                Some(keys) => Some(Command::Dedup(Some(keys))),
+                None => Some(Command::Invalid(format!("Invalid columns: {} (expected letters like A,C)", cols.trim()))),
+            };
+        }
+
+        if let Some(seed) = trimmed.strip_prefix("shuffle ") {
+            return match seed.trim().parse::<u64>() {
+                Ok(seed) => Some(Command::Shuffle(Some(seed))),
+                Err(_) => Some(Command::Invalid(format!("Invalid seed: {} (expected a whole number)", seed.trim()))),
+            };
+        }
+
+        // groupby C [sum D]
+        if let Some(args) = trimmed.strip_prefix("groupby ") {
+            let words: Vec<&str> = args.split_whitespace().collect();
+            let (col, sum) = match words.as_slice() {
+                [col] => (parse_col_letters(col), Some(None)),
+                [col, "sum", sum] => (parse_col_letters(col), parse_col_letters(sum).map(Some)),
+                _ => return Some(Command::Invalid("Usage: groupby [C] [sum D]".to_string())),
+            };
+            return match (col, sum) {
+                (Some(col), Some(sum)) => Some(Command::GroupBy { col: Some(col), sum }),
+                _ => Some(Command::Invalid(format!("Invalid columns: {} (expected letters like C)", args.trim()))),
+            };
+        }
+
+        if let Some(n) = trimmed.strip_prefix("dup ") {
+            return match n.trim().parse::<usize>() {
+                Ok(n) if n > 0 => Some(Command::Duplicate(n)),
+                _ => Some(Command::Invalid(format!("Invalid count: {} (expected a positive number)", n.trim()))),
+            };
+        }
+
+        if let Some(arg) = trimmed.strip_prefix("delim ") {
+            let arg = arg.trim();
+            if arg == "?" {
+                return Some(Command::DelimiterCandidates);
+            }
+            return match delimiter_from_name(arg) {
+                Some(delim) => Some(Command::SetDelimiter(delim)),
+                None => Some(Command::Invalid(format!("Unknown delimiter: {} (expected comma, tab, semicolon, pipe or one character)", arg))),
+            };
+        }
+
+        // Option assignment: set name value (or set name=value)
+        if let Some(args) = trimmed.strip_prefix("set ") {
+            let args = args.trim();
+            let (name, value) = args
+                .split_once('=')
+                .or_else(|| args.split_once(char::is_whitespace))
+                .unwrap_or((args, ""));
+            return Some(Command::SetOption {
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+            });
+        }
+
         // Check for theme command with argument
         if let Some(theme_name) = trimmed.strip_prefix("theme ") {
             return Some(Command::Theme(theme_name.trim().to_string()));
         }
 
+        if let Some(format) = trimmed.strip_prefix("export ").and_then(|f| ExportFormat::from_name(f.trim())) {
+            return Some(Command::Export(format));
+        }
+
+        // sample N[%] [seed S]
+        if trimmed == "sample" || trimmed.starts_with("sample ") {
+            let usage = || Some(Command::Invalid("Usage: sample N or sample N% (optionally followed by seed S)".to_string()));
+            let words: Vec<&str> = trimmed["sample".len()..].split_whitespace().collect();
+            let (amount, seed) = match words.as_slice() {
+                [amount] => (*amount, None),
+                [amount, "seed", seed] => match seed.parse::<u64>() {
+                    Ok(seed) => (*amount, Some(seed)),
+                    Err(_) => return Some(Command::Invalid(format!("Invalid seed: {} (expected a whole number)", seed))),
+                },
+                _ => return usage(),
+            };
+            let size = match amount.strip_suffix('%') {
+                Some(pct) => pct.parse::<usize>().ok().filter(|&p| p <= 100).map(SampleSize::Percent),
+                None => amount.parse::<usize>().ok().map(SampleSize::Rows),
+            };
+            return match size {
+                Some(size) => Some(Command::Filter(FilterType::Sample { size, seed })),
+                None => usage(),
+            };
+        }
         if let Some(filter_args) = trimmed.strip_prefix("filter ") {
-            let predicate = parse_predicate(filter_args.to_string());
+            if filter_args.trim() == "invert" {
+                return Some(Command::Filter(FilterType::Invert));
+            }
+            let predicate = match filter_args.trim() {
+                "blank" => Some(Predicate::Blank),
+                "notblank" => Some(Predicate::NotBlank),
+                _ => parse_predicate(filter_args.to_string()),
+            };
+            if let Some(Predicate::Regex(pattern)) = &predicate {
+                if let Err(e) = Regex::new(pattern) {
+                    return Some(Command::Invalid(format!("Invalid regex: {}", e)));
+                }
+            }
             match predicate {
                 Some(pred) => {
                     return Some(Command::Filter(FilterType::PredicateFilter(pred)));
@@ -106,6 +432,7 @@ impl Command {
 
         match trimmed {
             "w" => Some(Command::Write),
+            "w!" => Some(Command::ForceWrite),
             "q" => Some(Command::Quit),
             "q!" => Some(Command::ForceQuit),
             "wq" => Some(Command::WriteQuit),
@@ -115,26 +442,71 @@ impl Command {
             "calc" => Some(Command::Calc),
             "sort" => Some(Command::Sort),
             "fork" => Some(Command::Fork),
-            "sortd" | "sort!" => Some(Command::SortDesc),
+            "e" => Some(Command::Invalid("Usage: e <path>".to_string())),
+            "e!" => Some(Command::Reload),
+            "loadmore" => Some(Command::LoadMore),
+            "bn" => Some(Command::CycleBuffer { forward: true, force: false }),
+            "bn!" => Some(Command::CycleBuffer { forward: true, force: true }),
+            "bp" => Some(Command::CycleBuffer { forward: false, force: false }),
+            "bp!" => Some(Command::CycleBuffer { forward: false, force: true }),
+            "sortd" => Some(Command::SortDesc),
+            "sort!" => Some(Command::SortReverse),
             "sortr" => Some(Command::SortRow),
             "sortrd" | "sortr!" => Some(Command::SortRowDesc),
             "grid" => Some(Command::Grid),
+            "transpose" => Some(Command::Transpose),
+            "reverse" => Some(Command::Reverse { cols: false }),
+            "reverse cols" => Some(Command::Reverse { cols: true }),
+            "shuffle" => Some(Command::Shuffle(None)),
+            "dup" => Some(Command::Duplicate(1)),
+            "append" => Some(Command::Append),
+            "dedup" => Some(Command::Dedup(None)),
+            "unfreeze" => Some(Command::FreezeCols(0)),
+            "hide" => Some(Command::HideCol),
+            "unhide" => Some(Command::UnhideCols(None)),
+            "type" => Some(Command::ShowColType),
+            "delim" => Some(Command::Invalid("Usage: delim <char> or delim ?".to_string())),
             "theme" | "themes" => Some(Command::ThemeList),
             "clip" | "cp" => Some(Command::Clip),
             "sp" | "syspaste" => Some(Command::SysPaste),
+            "pt" => Some(Command::PasteTranspose),
+            "pp" => Some(Command::PasteSystem),
+            "reg" | "registers" => Some(Command::Registers),
             "plugins" => Some(Command::PluginList),
             "nofilter" => Some(Command::Filter(FilterType::Default)),
             "canvas" => Some(Command::Canvas),
+            "sum" => Some(Command::Aggregate(AggKind::Sum)),
+            "avg" => Some(Command::Aggregate(AggKind::Avg)),
+            "min" => Some(Command::Aggregate(AggKind::Min)),
+            "max" => Some(Command::Aggregate(AggKind::Max)),
+            "describe" => Some(Command::Describe),
+            "groupby" => Some(Command::GroupBy { col: None, sum: None }),
             _ => Some(Command::Unknown(trimmed.to_string())),
         }
     }
 
+    /// Parse a comma-separated list of sort keys like "A,C!,b"
+    /// Returns None if any key is not a column reference
+    fn parse_sort_keys(input: &str) -> Option<Vec<(usize, SortDirection)>> {
+        let mut keys = Vec::new();
+        for part in input.split(',') {
+            let part = part.trim();
+            let (letters, direction) = match part.strip_suffix('!') {
+                Some(letters) => (letters.trim(), SortDirection::Descending),
+                None => (part, SortDirection::Ascending),
+            };
+            keys.push((parse_col_letters(letters)?, direction));
+        }
+        Some(keys)
+    }
+
     /// Parse a substitute/replace command
     /// Formats: %s/old/new/g, s/old/new/g, %s/old/new, s/old/new
+    /// Using sl instead of s (e.g. %sl/old/new/g) matches the pattern literally
     fn parse_replace(input: &str) -> Option<ReplaceCommand> {
-        // Match %s/.../.../[g] or s/.../.../[g]
+        // Match %s/.../.../[flags] or s/.../.../[flags] (or sl in place of s)
         // Use a regex that handles the delimiter
-        let re = Regex::new(r"^(%)?s/([^/]*)/([^/]*)(/g)?$").unwrap();
+        let re = Regex::new(r"^(%)?s(l)?/([^/]*)/([^/]*)(?:/([gicn]*))?$").unwrap();
 
         if let Some(caps) = re.captures(input) {
             let scope = if caps.get(1).is_some() {
@@ -142,9 +514,14 @@ impl Command {
             } else {
                 ReplaceScope::Selection
             };
-            let pattern = caps.get(2).map(|m| m.as_str()).unwrap_or("").to_string();
-            let replacement = caps.get(3).map(|m| m.as_str()).unwrap_or("").to_string();
-            let global = caps.get(4).is_some();
+            let regex = caps.get(2).is_none();
+            let pattern = caps.get(3).map(|m| m.as_str()).unwrap_or("").to_string();
+            let replacement = caps.get(4).map(|m| m.as_str()).unwrap_or("").to_string();
+            let flags = caps.get(5).map(|m| m.as_str()).unwrap_or("");
+            let global = flags.contains('g');
+            let smart_case = flags.contains('i');
+            let count_only = flags.contains('n');
+            let confirm = flags.contains('c');
 
             if pattern.is_empty() {
                 return None;
@@ -154,6 +531,10 @@ impl Command {
                 pattern,
                 replacement,
                 global,
+                regex,
+                smart_case,
+                count_only,
+                confirm,
                 scope,
             });
         }
@@ -165,17 +546,20 @@ impl Command {
 /// Command mode handler
 pub struct CommandHandler {
     pub buffer: String,
+    pub history: InputHistory,  // Executed commands; prompt answers are not recorded
 }
 
 impl CommandHandler {
     pub fn new() -> Self {
         Self {
             buffer: String::new(),
+            history: InputHistory::new(),
         }
     }
 
     pub fn start(&mut self) {
         self.buffer.clear();
+        self.history.reset();
     }
 
     pub fn handle_key(&mut self, key: KeyEvent) -> Option<String> {
@@ -194,6 +578,18 @@ impl CommandHandler {
                 self.buffer.pop();
                 None
             }
+            KeyCode::Up => {
+                if let Some(line) = self.history.older(&self.buffer) {
+                    self.buffer = line;
+                }
+                None
+            }
+            KeyCode::Down => {
+                if let Some(line) = self.history.newer() {
+                    self.buffer = line;
+                }
+                None
+            }
             KeyCode::Char(c) => {
                 self.buffer.push(c);
                 None
@@ -3,7 +3,17 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use crate::input::{KeyResult, is_escape};
 use crate::transaction::transaction::Transaction;
 use crate::table::tableview::TableView;
-use crate::string::{get_word_start, get_word_end};
+use crate::string::{get_word_start, get_word_end, line_up, line_down};
+
+/// Cells longer than this many characters (or containing line breaks) are edited in a popup
+pub const POPUP_EDIT_THRESHOLD: usize = 40;
+
+/// Direction to move after committing with Tab or Shift-Tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabMove {
+    Next,
+    Prev,
+}
 
 /// Insert mode handler
 /// Note: cursor is a CHARACTER index, not a byte index
@@ -11,6 +21,14 @@ pub struct InsertHandler {
     pub buffer: String,
     /// Cursor position as character index (not byte index)
     pub cursor: usize,
+    /// Editing in the canvas popup: Enter inserts a line break, Ctrl+S commits and Esc cancels
+    pub multiline: bool,
+    /// Append mode (:append): Enter commits the cell and opens a new row below
+    pub append: bool,
+    /// Set when the edit was committed with Tab/Shift-Tab; the app moves and keeps editing
+    pub tab_move: Option<TabMove>,
+    /// Change mode (visual `c`): Esc commits the typed value instead of discarding it
+    pub change: bool,
 
     true_val: String,
     pub old_width: usize
@@ -22,6 +40,10 @@ impl InsertHandler {
             buffer: String::new(),
             true_val: String::new(),
             cursor: 0,
+            multiline: false,
+            append: false,
+            tab_move: None,
+            change: false,
             old_width: 0
         }
     }
@@ -30,26 +52,76 @@ impl InsertHandler {
         self.true_val = initial.clone();
         self.buffer = initial;
         self.cursor = crate::util::char_count(&self.buffer);
+        self.multiline = self.cursor > POPUP_EDIT_THRESHOLD || self.buffer.contains('\n');
+        self.change = false;
         self.old_width = old_width
     }
 
+    /// Write the buffer back into the cell being edited
+    fn commit(&self, view: &TableView) -> KeyResult {
+        let txn = Transaction::SetCell {
+            row: view.cursor_row,
+            col: view.cursor_col,
+            old_value: self.true_val.clone(),
+            new_value: self.buffer.clone(),
+        };
+        KeyResult::ExecuteAndFinish(txn)
+    }
+
+    /// Write the buffer back and insert an empty row below, as one undo step
+    fn commit_and_open_row(&self, view: &TableView) -> KeyResult {
+        let txn = Transaction::Batch(vec![
+            Transaction::SetCell {
+                row: view.cursor_row,
+                col: view.cursor_col,
+                old_value: self.true_val.clone(),
+                new_value: self.buffer.clone(),
+            },
+            Transaction::InsertRow { idx: view.cursor_row + 1 },
+        ]);
+        KeyResult::ExecuteAndFinish(txn)
+    }
+
     pub fn handle_key(&mut self, key: KeyEvent, view: &TableView) -> KeyResult {
         if is_escape(key) {
+            if self.change && self.buffer != self.true_val {
+                return self.commit(view);
+            }
             self.buffer = self.true_val.clone();
             return KeyResult::Finish;
         }
 
+        if self.multiline && key.code == KeyCode::Char('s') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return self.commit(view);
+        }
+
         if key.code == KeyCode::Enter {
-            let txn = Transaction::SetCell {
-                row: view.cursor_row,
-                col: view.cursor_col,
-                old_value: self.true_val.clone(),
-                new_value: self.buffer.clone(),
-            };
-            return KeyResult::ExecuteAndFinish(txn);
+            if self.multiline {
+                self.buffer = crate::util::insert_char_at(&self.buffer, self.cursor, '\n');
+                self.cursor += 1;
+                return KeyResult::Continue;
+            }
+            if self.append {
+                return self.commit_and_open_row(view);
+            }
+            return self.commit(view);
         }
 
         match key.code {
+            KeyCode::Tab => {
+                self.tab_move = Some(TabMove::Next);
+                return self.commit(view);
+            }
+            KeyCode::BackTab => {
+                self.tab_move = Some(TabMove::Prev);
+                return self.commit(view);
+            }
+            KeyCode::Up if self.multiline => {
+                self.cursor = line_up(&self.buffer, self.cursor);
+            }
+            KeyCode::Down if self.multiline => {
+                self.cursor = line_down(&self.buffer, self.cursor);
+            }
             KeyCode::Backspace => {
                 if self.cursor > 0 {
                     self.cursor -= 1;
@@ -11,6 +11,15 @@ use crate::mode::search::SearchHandler;
 pub struct NormalHandler {
 }
 
+/// App state a normal-mode key may read or change besides the table and view
+pub struct NormalContext<'a> {
+    pub nav_handler: &'a NavigationHandler,
+    pub is_filtered: bool,
+    pub header_mode: bool,
+    pub clipboard: &'a mut Clipboard,
+    pub search_handler: &'a mut SearchHandler,
+}
+
 impl NormalHandler {
     pub fn new() -> Self {
         Self {
@@ -22,15 +31,31 @@ impl NormalHandler {
                   view: &mut TableView,
                   table: &mut Table,
                   _count: usize, 
-                  nav_handler: &NavigationHandler,
-                  is_filtered: bool,
-                  clipboard: &mut Clipboard,
-                  search_handler: &mut SearchHandler
+                  ctx: NormalContext
                   ) -> KeyResult {
         // Handle navigation (hjkl already handled by KeyBuffer with count)
-        nav_handler.handle(key, view, table);
+        ctx.nav_handler.handle(key, view, table);
 
         match key.code {
+            KeyCode::Char(c @ ('j' | 'k')) if key.modifiers.contains(KeyModifiers::ALT) => {
+                if ctx.is_filtered {
+                    return KeyResult::Message("Moving rows is forbidden in filtered views.".to_string());
+                }
+                let down = c == 'j';
+                if let Some(txn) = crate::table::operations::move_rows(table, view.cursor_row, view.cursor_row, down, ctx.header_mode) {
+                    view.cursor_row = if down { view.cursor_row + 1 } else { view.cursor_row - 1 };
+                    view.scroll_to_cursor();
+                    return KeyResult::Execute(txn);
+                }
+            }
+            KeyCode::Char(c @ ('h' | 'l')) if key.modifiers.contains(KeyModifiers::ALT) => {
+                let right = c == 'l';
+                if let Some(txn) = crate::table::operations::move_cols(table, view.cursor_col, view.cursor_col, right) {
+                    view.cursor_col = if right { view.cursor_col + 1 } else { view.cursor_col - 1 };
+                    view.scroll_to_cursor();
+                    return KeyResult::Execute(txn);
+                }
+            }
             KeyCode::Char('i') => {
                 return KeyResult::SwitchMode(Mode::Insert);
             }
@@ -53,7 +78,7 @@ impl NormalHandler {
                 return KeyResult::Quit;
             }
             KeyCode::Char('o') => {
-                if is_filtered {
+                if ctx.is_filtered {
                     return KeyResult::Message("Adding rows is forbidden in filtered views.".to_string());
                 }
                 let txn = Transaction::InsertRow { idx: view.cursor_row + 1 };
@@ -63,7 +88,7 @@ impl NormalHandler {
                 return KeyResult::Execute(txn);
             }
             KeyCode::Char('O') => {
-                if is_filtered {
+                if ctx.is_filtered {
                     return KeyResult::Message("Adding rows is forbidden in filtered views.".to_string());
                 }
                 let txn = Transaction::InsertRow { idx: view.cursor_row };
@@ -72,13 +97,13 @@ impl NormalHandler {
                 return KeyResult::Execute(txn);
             }
             KeyCode::Char('p') => {
-                let (message, txn_opt) = clipboard.paste_as_transaction(
+                let (message, txn_opt) = ctx.clipboard.paste_as_transaction(
                     view.cursor_row,
                     view.cursor_col,
                     &table,
                 );
                 if let Some(txn) = txn_opt {
-                    return KeyResult::Execute(txn);
+                    return KeyResult::Paste(txn, None);
                 }
                 return KeyResult::Message(message);
             }
@@ -102,8 +127,11 @@ impl NormalHandler {
                 return KeyResult::Message(String::from("Cannot remove column."));
             }
             KeyCode::Char('x') => {
-                let old_value = crate::table::operations::current_cell(view, table).clone();
-                clipboard.store_deleted(RegisterContent{
+                let Some(old_value) = crate::table::operations::current_cell(view, table).cloned() else {
+                    view.clamp_cursor(table);
+                    return KeyResult::Message(String::from("Cannot clear cell."));
+                };
+                ctx.clipboard.store_deleted(RegisterContent{
                     data: vec![vec![old_value.clone()]],
                     anchor: PasteAnchor::Cursor
                 });
@@ -125,13 +153,16 @@ impl NormalHandler {
             KeyCode::Char('/') => {
                 return KeyResult::SwitchMode(Mode::Search);
             }
+            KeyCode::Char('K') => {
+                return KeyResult::InspectCell;
+            }
             KeyCode::Char('n') => {
-                if let Some(msg) = search_handler.goto_next(view) {
+                if let Some(msg) = ctx.search_handler.goto_next(view) {
                     return KeyResult::Message(msg);
                 }
             }
             KeyCode::Char('N') => {
-                if let Some(msg) = search_handler.goto_prev(view) {
+                if let Some(msg) = ctx.search_handler.goto_prev(view) {
                     return KeyResult::Message(msg);
                 }
             }
@@ -1,29 +1,38 @@
 use crossterm::event::{KeyCode, KeyEvent};
+use regex::{Regex, RegexBuilder};
 
 use crate::table::tableview::TableView;
 use crate::table::table::Table;
-use crate::input::{KeyResult, is_escape};
+use crate::input::{InputHistory, KeyResult, is_escape};
+use crate::util::letters_from_col;
 
 /// Search state and functionality
 pub struct SearchHandler {
     pub pattern: Option<String>,
+    pub regex: Option<Regex>,  // Compiled form of pattern, shared by search and highlighting
     pub matches: Vec<(usize, usize)>,
+    pub restrict_col: Option<usize>,  // Column the last search was limited to
     pub index: usize,
     pub buffer: String,
+    pub history: InputHistory,
 }
 
 impl SearchHandler {
     pub fn new() -> Self {
         Self {
             pattern: None,
+            regex: None,
             matches: Vec::new(),
+            restrict_col: None,
             index: 0,
             buffer: String::new(),
+            history: InputHistory::new(),
         }
     }
 
     pub fn start_search(&mut self) {
         self.buffer.clear();
+        self.history.reset();
     }
 
     pub fn handle_key(&mut self, key: KeyEvent) -> KeyResult {
@@ -36,6 +45,7 @@ impl SearchHandler {
             KeyCode::Enter => {
                 if !self.buffer.is_empty() {
                     self.pattern = Some(self.buffer.clone());
+                    self.history.push(&self.buffer);
                 }
                 KeyResult::Finish
             }
@@ -43,6 +53,18 @@ impl SearchHandler {
                 self.buffer.pop();
                 KeyResult::Continue
             }
+            KeyCode::Up => {
+                if let Some(line) = self.history.older(&self.buffer) {
+                    self.buffer = line;
+                }
+                KeyResult::Continue
+            }
+            KeyCode::Down => {
+                if let Some(line) = self.history.newer() {
+                    self.buffer = line;
+                }
+                KeyResult::Continue
+            }
             KeyCode::Char(c) => {
                 self.buffer.push(c);
                 KeyResult::Continue
@@ -51,27 +73,86 @@ impl SearchHandler {
         }
     }
 
-    pub fn perform_search(&mut self, table: &Table) -> Option<String> {
+    /// Compile a search pattern into a matcher
+    /// Patterns written as "re:<regex>" are regular expressions, anything else matches literally
+    /// Matching is case-insensitive unless the pattern ends in \C (\c forces insensitive, like vim)
+    pub fn compile_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+        let (pattern, case_insensitive) = if let Some(p) = pattern.strip_suffix("\\C") {
+            (p, false)
+        } else if let Some(p) = pattern.strip_suffix("\\c") {
+            (p, true)
+        } else {
+            (pattern, true)
+        };
+
+        let source = match pattern.strip_prefix("re:") {
+            Some(re) => re.to_string(),
+            None => regex::escape(pattern),
+        };
+
+        RegexBuilder::new(&source)
+            .case_insensitive(case_insensitive)
+            .build()
+    }
+
+    /// Whether a cell in column col matches the current search
+    pub fn is_match(&self, col: usize, cell: &str) -> bool {
+        if self.restrict_col.is_some_and(|c| c != col) {
+            return false;
+        }
+        self.regex.as_ref().map(|re| re.is_match(cell)).unwrap_or(false)
+    }
+
+    /// Position of the match last jumped to
+    pub fn current_match(&self) -> Option<(usize, usize)> {
+        self.matches.get(self.index).copied()
+    }
+
+    /// Collect the cells matching the pattern, only scanning restrict_col when given
+    pub fn perform_search(&mut self, table: &Table, restrict_col: Option<usize>) -> Option<String> {
         self.matches.clear();
         self.index = 0;
+        self.restrict_col = restrict_col;
 
         let pattern = self.pattern.as_ref()?;
-        let pattern_lower = pattern.to_lowercase();
 
-        for row in 0..table.row_count() {
-            for col in 0..table.col_count() {
-                if let Some(cell) = table.get_cell(row, col) {
-                    if cell.to_lowercase().contains(&pattern_lower) {
+        let regex = match Self::compile_pattern(pattern) {
+            Ok(re) => re,
+            Err(e) => {
+                // Drop the bad pattern so the cursor stays put
+                self.pattern = None;
+                self.regex = None;
+                return Some(format!("Invalid regex: {}", e));
+            }
+        };
+
+        match restrict_col {
+            Some(col) => {
+                for (row, cell) in table.col_iter(col).enumerate() {
+                    if regex.is_match(cell) {
                         self.matches.push((row, col));
                     }
                 }
             }
+            None => {
+                for row in 0..table.row_count() {
+                    for col in 0..table.col_count() {
+                        if let Some(cell) = table.get_cell(row, col) {
+                            if regex.is_match(cell) {
+                                self.matches.push((row, col));
+                            }
+                        }
+                    }
+                }
+            }
         }
+        self.regex = Some(regex);
 
-        if self.matches.is_empty() {
-            Some(format!("Pattern not found: {}", pattern))
-        } else {
-            Some(format!("{} match(es) found", self.matches.len()))
+        match (restrict_col, self.matches.len()) {
+            (None, 0) => Some(format!("Pattern not found: {}", pattern)),
+            (None, n) => Some(format!("{} match(es) found", n)),
+            (Some(col), 0) => Some(format!("Pattern not found in col {}: {}", letters_from_col(col), pattern)),
+            (Some(col), n) => Some(format!("{} matches in col {}", n, letters_from_col(col))),
         }
     }
 
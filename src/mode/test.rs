@@ -1,4 +1,15 @@
 use super::command::*;
+use super::search::SearchHandler;
+use crate::table::table::Table;
+use crate::table::tableview::TableView;
+use crate::table::rowmanager::RowManager;
+use std::rc::Rc;
+use std::cell::RefCell;
+use crate::table::SortDirection;
+use crate::fileio::ExportFormat;
+use crate::util::ColumnType;
+use super::visual::{create_drag_down_txn, create_drag_right_txn, create_case_txn, CaseOp, FillMode};
+use crate::transaction::transaction::Transaction;
 
 #[test]
 fn test_parse_basic_commands() {
@@ -10,18 +21,274 @@ fn test_parse_basic_commands() {
     assert_eq!(Command::parse("delcol"), Some(Command::DeleteColumn));
     assert_eq!(Command::parse("header"), Some(Command::ToggleHeader));
     assert_eq!(Command::parse("calc"), Some(Command::Calc));
+    assert_eq!(Command::parse("transpose"), Some(Command::Transpose));
+    assert_eq!(Command::parse("pt"), Some(Command::PasteTranspose));
 }
 
 #[test]
 fn test_parse_sort_commands() {
     assert_eq!(Command::parse("sort"), Some(Command::Sort));
     assert_eq!(Command::parse("sortd"), Some(Command::SortDesc));
-    assert_eq!(Command::parse("sort!"), Some(Command::SortDesc));
+    assert_eq!(Command::parse("sort!"), Some(Command::SortReverse));
     assert_eq!(Command::parse("sortr"), Some(Command::SortRow));
     assert_eq!(Command::parse("sortrd"), Some(Command::SortRowDesc));
     assert_eq!(Command::parse("sortr!"), Some(Command::SortRowDesc));
 }
 
+#[test]
+fn test_parse_replace_regex_flag() {
+    match Command::parse("%s/a(b)/$1/g") {
+        Some(Command::Replace(cmd)) => {
+            assert!(cmd.regex);
+            assert!(cmd.global);
+            assert_eq!(cmd.scope, ReplaceScope::All);
+            assert_eq!(cmd.pattern, "a(b)");
+            assert_eq!(cmd.replacement, "$1");
+        }
+        other => panic!("expected replace, got {:?}", other),
+    }
+
+    match Command::parse("sl/a.b/c") {
+        Some(Command::Replace(cmd)) => {
+            assert!(!cmd.regex);
+            assert!(!cmd.global);
+            assert_eq!(cmd.scope, ReplaceScope::Selection);
+        }
+        other => panic!("expected replace, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_replace_flags() {
+    match Command::parse("%s/color/colour/gi") {
+        Some(Command::Replace(cmd)) => {
+            assert!(cmd.global);
+            assert!(cmd.smart_case);
+        }
+        other => panic!("expected replace, got {:?}", other),
+    }
+    let dry_run = Command::parse("%s/a/b/gn").unwrap();
+    assert!(matches!(&dry_run, Command::Replace(cmd) if cmd.count_only));
+    assert!(!dry_run.modifies_table());
+    assert!(Command::parse("%s/a/b/g").unwrap().modifies_table());
+    match Command::parse("s/a/b/") {
+        Some(Command::Replace(cmd)) => {
+            assert!(!cmd.global);
+            assert!(!cmd.smart_case);
+            assert_eq!(cmd.replacement, "b");
+        }
+        other => panic!("expected replace, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_aggregate() {
+    assert_eq!(Command::parse("sum"), Some(Command::Aggregate(AggKind::Sum)));
+    assert_eq!(Command::parse("avg"), Some(Command::Aggregate(AggKind::Avg)));
+    assert_eq!(Command::parse("min"), Some(Command::Aggregate(AggKind::Min)));
+    assert_eq!(Command::parse("max"), Some(Command::Aggregate(AggKind::Max)));
+}
+
+#[test]
+fn test_parse_describe() {
+    assert_eq!(Command::parse("describe"), Some(Command::Describe));
+}
+
+#[test]
+fn test_parse_groupby() {
+    assert_eq!(Command::parse("groupby"), Some(Command::GroupBy { col: None, sum: None }));
+    assert_eq!(Command::parse("groupby B"), Some(Command::GroupBy { col: Some(1), sum: None }));
+    assert_eq!(Command::parse("groupby b sum D"), Some(Command::GroupBy { col: Some(1), sum: Some(3) }));
+    assert!(matches!(Command::parse("groupby B avg D"), Some(Command::Invalid(_))));
+    assert!(matches!(Command::parse("groupby 3"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_navigate_col() {
+    assert_eq!(Command::parse("col C"), Some(Command::NavigateCol(2)));
+    assert_eq!(Command::parse("col aa"), Some(Command::NavigateCol(26)));
+    assert!(matches!(Command::parse("col 3A"), Some(Command::Invalid(_))));
+    assert!(matches!(Command::parse("col B2"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_duplicate() {
+    assert_eq!(Command::parse("dup"), Some(Command::Duplicate(1)));
+    assert_eq!(Command::parse("dup 3"), Some(Command::Duplicate(3)));
+    assert!(matches!(Command::parse("dup 0"), Some(Command::Invalid(_))));
+    assert!(matches!(Command::parse("dup x"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_set_option() {
+    let expected = Some(Command::SetOption { name: "undolimit".to_string(), value: "50".to_string() });
+    assert_eq!(Command::parse("set undolimit 50"), expected);
+    assert_eq!(Command::parse("set undolimit=50"), expected);
+    assert_eq!(
+        Command::parse("set undolimit"),
+        Some(Command::SetOption { name: "undolimit".to_string(), value: String::new() })
+    );
+}
+
+#[test]
+fn test_parse_freeze() {
+    assert_eq!(Command::parse("freeze cols 2"), Some(Command::FreezeCols(2)));
+    assert_eq!(Command::parse("unfreeze"), Some(Command::FreezeCols(0)));
+    assert_eq!(Command::parse("freeze cols x"), Some(Command::Unknown("freeze cols x".to_string())));
+}
+
+#[test]
+fn test_parse_export() {
+    assert_eq!(Command::parse("export md"), Some(Command::Export(ExportFormat::Markdown)));
+    assert_eq!(Command::parse("export markdown"), Some(Command::Export(ExportFormat::Markdown)));
+    assert_eq!(Command::parse("export json"), Some(Command::Export(ExportFormat::Json)));
+    assert_eq!(Command::parse("export xls"), Some(Command::Unknown("export xls".to_string())));
+}
+
+#[test]
+fn test_parse_multi_sort() {
+    assert_eq!(
+        Command::parse("sort A,C"),
+        Some(Command::SortMulti(vec![(0, SortDirection::Ascending), (2, SortDirection::Ascending)]))
+    );
+    assert_eq!(
+        Command::parse("sort b!, aa"),
+        Some(Command::SortMulti(vec![(1, SortDirection::Descending), (26, SortDirection::Ascending)]))
+    );
+    assert_eq!(Command::parse("sort A,1"), None);
+    assert_eq!(Command::parse("sort A,,B"), None);
+}
+
+#[test]
+fn test_parse_regex_filter() {
+    use crate::numeric::predicate::Predicate;
+    use crate::table::rowmanager::FilterType;
+    assert_eq!(
+        Command::parse("filter =~ ^foo"),
+        Some(Command::Filter(FilterType::PredicateFilter(Predicate::Regex("^foo".to_string()))))
+    );
+    assert!(matches!(Command::parse("filter =~ (unclosed"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_blank_filter() {
+    use crate::numeric::predicate::Predicate;
+    use crate::table::rowmanager::FilterType;
+    assert_eq!(Command::parse("filter blank"), Some(Command::Filter(FilterType::PredicateFilter(Predicate::Blank))));
+    assert_eq!(Command::parse("filter notblank"), Some(Command::Filter(FilterType::PredicateFilter(Predicate::NotBlank))));
+    assert_eq!(Command::parse("filter invert"), Some(Command::Filter(FilterType::Invert)));
+}
+
+#[test]
+fn test_parse_hide_unhide() {
+    assert_eq!(Command::parse("hide"), Some(Command::HideCol));
+    assert_eq!(Command::parse("unhide"), Some(Command::UnhideCols(None)));
+    assert_eq!(Command::parse("unhide C"), Some(Command::UnhideCols(Some(2))));
+    assert!(matches!(Command::parse("unhide 3"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_column_type() {
+    assert_eq!(Command::parse("type"), Some(Command::ShowColType));
+    assert_eq!(Command::parse("type B numeric"), Some(Command::SetColType(1, Some(ColumnType::Numeric))));
+    assert_eq!(Command::parse("type AA text"), Some(Command::SetColType(26, Some(ColumnType::Text))));
+    assert_eq!(Command::parse("type C auto"), Some(Command::SetColType(2, None)));
+    assert!(matches!(Command::parse("type C colour"), Some(Command::Invalid(_))));
+    assert!(matches!(Command::parse("type numeric"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_delim_query() {
+    assert_eq!(Command::parse("delim ?"), Some(Command::DelimiterCandidates));
+}
+
+#[test]
+fn test_parse_delim() {
+    assert_eq!(Command::parse("delim ;"), Some(Command::SetDelimiter(b';')));
+    assert_eq!(Command::parse("delim tab"), Some(Command::SetDelimiter(b'\t')));
+    assert!(matches!(Command::parse("delim"), Some(Command::Invalid(_))));
+    assert!(matches!(Command::parse("delim ab"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_write_as() {
+    assert_eq!(Command::parse("w Q1.csv"), Some(Command::WriteAs { path: "Q1.csv".to_string(), force: false }));
+    assert_eq!(Command::parse("w! out.tsv"), Some(Command::WriteAs { path: "out.tsv".to_string(), force: true }));
+}
+
+#[test]
+fn test_parse_buffer_commands() {
+    assert_eq!(Command::parse("e data/Q1.csv"), Some(Command::OpenBuffer { path: "data/Q1.csv".to_string(), force: false }));
+    assert_eq!(Command::parse("e! data/Q1.csv"), Some(Command::OpenBuffer { path: "data/Q1.csv".to_string(), force: true }));
+    assert!(matches!(Command::parse("e"), Some(Command::Invalid(_))));
+    assert_eq!(Command::parse("bn"), Some(Command::CycleBuffer { forward: true, force: false }));
+    assert_eq!(Command::parse("bp!"), Some(Command::CycleBuffer { forward: false, force: true }));
+}
+
+#[test]
+fn test_parse_diff() {
+    assert_eq!(Command::parse("diff B D"), Some(Command::Diff(Some((1, 3)))));
+    assert_eq!(Command::parse("diff off"), Some(Command::Diff(None)));
+    assert!(matches!(Command::parse("diff B"), Some(Command::Invalid(_))));
+    assert!(matches!(Command::parse("diff B 4"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_reverse() {
+    assert_eq!(Command::parse("reverse"), Some(Command::Reverse { cols: false }));
+    assert_eq!(Command::parse("reverse cols"), Some(Command::Reverse { cols: true }));
+    assert!(Command::parse("reverse").unwrap().modifies_table());
+}
+
+#[test]
+fn test_parse_shuffle() {
+    assert_eq!(Command::parse("shuffle"), Some(Command::Shuffle(None)));
+    assert_eq!(Command::parse("shuffle 42"), Some(Command::Shuffle(Some(42))));
+    assert!(matches!(Command::parse("shuffle x"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_sample() {
+    use crate::table::rowmanager::{FilterType, SampleSize};
+    assert_eq!(Command::parse("sample 10"), Some(Command::Filter(FilterType::Sample { size: SampleSize::Rows(10), seed: None })));
+    assert_eq!(Command::parse("sample 10% seed 5"), Some(Command::Filter(FilterType::Sample { size: SampleSize::Percent(10), seed: Some(5) })));
+    assert!(matches!(Command::parse("sample"), Some(Command::Invalid(_))));
+    assert!(matches!(Command::parse("sample 150%"), Some(Command::Invalid(_))));
+    assert!(matches!(Command::parse("sample 10 seed x"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_dedup() {
+    assert_eq!(Command::parse("dedup"), Some(Command::Dedup(None)));
+    assert_eq!(Command::parse("dedup A,c"), Some(Command::Dedup(Some(vec![0, 2]))));
+    assert!(matches!(Command::parse("dedup A,1"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_parse_merge() {
+    let merge = |sep: &str, skip_empty| Some(Command::Merge { separator: sep.to_string(), skip_empty });
+    assert_eq!(Command::parse("merge ,"), merge(",", false));
+    assert_eq!(Command::parse("merge \" - \""), merge(" - ", false));
+    assert_eq!(Command::parse("merge skipempty ;"), merge(";", true));
+    assert_eq!(Command::parse("merge"), merge("", false));
+}
+
+#[test]
+fn test_parse_index() {
+    assert_eq!(Command::parse("index"), Some(Command::Index { start: 1, name: None }));
+    assert_eq!(Command::parse("index 0"), Some(Command::Index { start: 0, name: None }));
+    assert_eq!(Command::parse("index 0 row"), Some(Command::Index { start: 0, name: Some("row".to_string()) }));
+    assert_eq!(Command::parse("index order"), Some(Command::Index { start: 1, name: Some("order".to_string()) }));
+}
+
+#[test]
+fn test_parse_natural_sort() {
+    assert_eq!(Command::parse("sort natural"), Some(Command::SortNatural(SortDirection::Ascending)));
+    assert_eq!(Command::parse("sortd natural"), Some(Command::SortNatural(SortDirection::Descending)));
+    assert_eq!(Command::parse("sort date"), Some(Command::SortDate(SortDirection::Ascending)));
+    assert_eq!(Command::parse("sortd date"), Some(Command::SortDate(SortDirection::Descending)));
+}
+
 #[test]
 fn test_parse_navigate_row() {
     assert_eq!(Command::parse("1"), Some(Command::NavigateRow(0)));
@@ -30,6 +297,15 @@ fn test_parse_navigate_row() {
     assert_eq!(Command::parse("0"), Some(Command::NavigateRow(0)));
 }
 
+#[test]
+fn test_parse_relative_row_navigation() {
+    assert_eq!(Command::parse("+10"), Some(Command::NavigateRowBy(10)));
+    assert_eq!(Command::parse("-3"), Some(Command::NavigateRowBy(-3)));
+    assert_eq!(Command::parse("$"), Some(Command::NavigateLastRow));
+    assert_eq!(Command::parse("+x"), Some(Command::Unknown("+x".to_string())));
+    assert_eq!(Command::parse("+-3"), Some(Command::Unknown("+-3".to_string())));
+}
+
 #[test]
 fn test_parse_unknown() {
     assert_eq!(
@@ -78,3 +354,360 @@ fn test_parse_empty() {
     // Empty string should return Unknown with empty string
     assert_eq!(Command::parse(""), Some(Command::Unknown(String::new())));
 }
+
+// === Search tests ===
+
+fn search_table() -> Table {
+    Table::new(vec![
+        vec!["Name".to_string(), "Code".to_string()],
+        vec!["alice".to_string(), "A-100".to_string()],
+        vec!["Bob".to_string(), "B-2".to_string()],
+        vec!["a.c".to_string(), "X".to_string()],
+    ])
+}
+
+fn search(pattern: &str) -> (SearchHandler, Option<String>) {
+    let mut handler = SearchHandler::new();
+    handler.pattern = Some(pattern.to_string());
+    let msg = handler.perform_search(&search_table(), None);
+    (handler, msg)
+}
+
+#[test]
+fn test_search_plain_is_literal_and_case_insensitive() {
+    let (handler, _) = search("A.C");
+    assert_eq!(handler.matches, vec![(3, 0)]);
+}
+
+#[test]
+fn test_search_regex() {
+    let (handler, _) = search(r"re:^[A-Z]-\d{3}$");
+    assert_eq!(handler.matches, vec![(1, 1)]);
+}
+
+#[test]
+fn test_search_case_suffix() {
+    let (handler, _) = search(r"re:^b\C");
+    assert!(handler.matches.is_empty());
+
+    let (handler, _) = search(r"re:^b\c");
+    assert_eq!(handler.matches, vec![(2, 0), (2, 1)]);
+
+    let (handler, _) = search(r"bob\C");
+    assert!(handler.matches.is_empty());
+}
+
+#[test]
+fn test_search_history_is_kept_apart() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    let mut handler = SearchHandler::new();
+    let type_line = |handler: &mut SearchHandler, line: &str| {
+        handler.start_search();
+        for c in line.chars() {
+            handler.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        handler.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    };
+    type_line(&mut handler, "alice");
+    type_line(&mut handler, "bob");
+
+    handler.start_search();
+    handler.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+    handler.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+    assert_eq!(handler.buffer, "alice");
+
+    let mut commands = CommandHandler::new();
+    commands.start();
+    commands.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+    assert_eq!(commands.buffer, "");
+}
+
+#[test]
+fn test_search_restricted_to_column() {
+    let (whole, msg) = search("b");
+    assert_eq!(whole.matches, vec![(2, 0), (2, 1)]);
+    assert_eq!(msg.as_deref(), Some("2 match(es) found"));
+
+    let mut handler = SearchHandler::new();
+    handler.pattern = Some("b".to_string());
+    let msg = handler.perform_search(&search_table(), Some(1));
+    assert_eq!(handler.matches, vec![(2, 1)]);
+    assert_eq!(msg.as_deref(), Some("1 matches in col B"));
+    assert!(handler.is_match(1, "B-2"));
+    assert!(!handler.is_match(0, "Bob"));
+}
+
+#[test]
+fn test_parse_search_col() {
+    assert_eq!(Command::parse("search col A1"), Some(Command::SearchCol("A1".to_string())));
+}
+
+#[test]
+fn test_parse_col_formula() {
+    assert_eq!(
+        Command::parse("col-formula C =A{row}*B1"),
+        Some(Command::ColFormula { col: 2, template: "=A{row}*B1".to_string() })
+    );
+    assert!(matches!(Command::parse("col-formula C A{row}"), Some(Command::Invalid(_))));
+    assert!(matches!(Command::parse("col-formula 3 =A1"), Some(Command::Invalid(_))));
+}
+
+#[test]
+fn test_search_invalid_regex_keeps_cursor() {
+    let (mut handler, msg) = search("re:(unclosed");
+    assert!(msg.unwrap().starts_with("Invalid regex:"));
+    assert!(handler.pattern.is_none());
+
+    let mut view = TableView::new(Rc::new(RefCell::new(RowManager::new())));
+    view.cursor_row = 2;
+    view.cursor_col = 1;
+    assert_eq!(handler.goto_next(&mut view), None);
+    assert_eq!((view.cursor_row, view.cursor_col), (2, 1));
+}
+
+// === Drag fill tests ===
+
+fn drag_table(rows: Vec<Vec<&str>>) -> Table {
+    Table::new(rows.into_iter().map(|r| r.into_iter().map(|s| s.to_string()).collect()).collect())
+}
+
+/// Select the whole table (anchored at the top-left) and return the filled span
+fn drag(table: &Table, down: bool, mode: FillMode) -> Vec<Vec<String>> {
+    let mut view = TableView::new(Rc::new(RefCell::new(RowManager::new())));
+    view.support_row = 0;
+    view.support_col = 0;
+    view.cursor_row = table.row_count() - 1;
+    view.cursor_col = table.col_count() - 1;
+    let txn = if down {
+        create_drag_down_txn(&view, table, false, mode)
+    } else {
+        create_drag_right_txn(&view, table, false, mode)
+    };
+    match txn {
+        Transaction::SetSpan { new_data, .. } => new_data,
+        other => panic!("expected SetSpan, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_case_change_skips_formulas() {
+    let table = drag_table(vec![vec!["=sum(A2:A3)", "Total"]]);
+    assert_eq!(create_case_txn(&table, &[0], (0, 0), CaseOp::Upper), None);
+    assert_eq!(
+        create_case_txn(&table, &[0], (0, 1), CaseOp::Lower),
+        Some(Transaction::Batch(vec![Transaction::SetCell {
+            row: 0, col: 1, old_value: "Total".to_string(), new_value: "total".to_string(),
+        }]))
+    );
+}
+
+#[test]
+fn test_case_change_batch_for_2x2_selection() {
+    let table = drag_table(vec![
+        vec!["Alpha", "beta", "x"],
+        vec!["GAMMA", "42", "y"],
+    ]);
+    let set = |row, col, old: &str, new: &str| Transaction::SetCell {
+        row, col, old_value: old.to_string(), new_value: new.to_string(),
+    };
+
+    // "42" has no case and is left out of every batch
+    assert_eq!(
+        create_case_txn(&table, &[0, 1], (0, 1), CaseOp::Upper),
+        Some(Transaction::Batch(vec![set(0, 0, "Alpha", "ALPHA"), set(0, 1, "beta", "BETA")]))
+    );
+    assert_eq!(
+        create_case_txn(&table, &[0, 1], (0, 1), CaseOp::Lower),
+        Some(Transaction::Batch(vec![set(0, 0, "Alpha", "alpha"), set(1, 0, "GAMMA", "gamma")]))
+    );
+    assert_eq!(
+        create_case_txn(&table, &[0, 1], (0, 1), CaseOp::Toggle),
+        Some(Transaction::Batch(vec![
+            set(0, 0, "Alpha", "aLPHA"),
+            set(0, 1, "beta", "BETA"),
+            set(1, 0, "GAMMA", "gamma"),
+        ]))
+    );
+}
+
+#[test]
+fn test_case_change_unchanged_cells_give_no_txn() {
+    let table = drag_table(vec![vec!["abc", "123"]]);
+    assert_eq!(create_case_txn(&table, &[0], (0, 1), CaseOp::Lower), None);
+}
+
+#[test]
+fn test_drag_series_integer_step() {
+    let table = drag_table(vec![vec!["1"], vec!["2"], vec![""], vec![""], vec![""]]);
+    let filled: Vec<String> = drag(&table, true, FillMode::Series).into_iter().map(|r| r[0].clone()).collect();
+    assert_eq!(filled, vec!["1", "2", "3", "4", "5"]);
+
+    // Copy mode repeats the first cell
+    let copied: Vec<String> = drag(&table, true, FillMode::Copy).into_iter().map(|r| r[0].clone()).collect();
+    assert_eq!(copied, vec!["1", "1", "1", "1", "1"]);
+}
+
+#[test]
+fn test_drag_series_float_step_right() {
+    let table = drag_table(vec![vec!["0.5", "0.75", "", ""], vec!["10", "7", "", ""]]);
+    let filled = drag(&table, false, FillMode::Series);
+    assert_eq!(filled[0], vec!["0.5", "0.75", "1.00", "1.25"]);
+    assert_eq!(filled[1], vec!["10", "7", "4", "1"]);
+}
+
+#[test]
+fn test_drag_series_keeps_source_format() {
+    let table = drag_table(vec![
+        vec!["$1,000", "1.50", "15%", "-$10.00"],
+        vec!["$1,250", "1.75", "20%", "-$5.00"],
+        vec!["", "", "", ""],
+        vec!["", "", "", ""],
+        vec!["", "", "", ""],
+    ]);
+    let filled = drag(&table, true, FillMode::Series);
+    assert_eq!(filled[2], vec!["$1,500", "2.00", "25%", "$0.00"]);
+    assert_eq!(filled[3], vec!["$1,750", "2.25", "30%", "$5.00"]);
+    assert_eq!(filled[4], vec!["$2,000", "2.50", "35%", "$10.00"]);
+}
+
+#[test]
+fn test_drag_series_non_numeric_copies_verbatim() {
+    let table = drag_table(vec![
+        vec!["a", "1", "=A1"],
+        vec!["2", "x", "5"],
+        vec!["", "", ""],
+    ]);
+    let filled = drag(&table, true, FillMode::Series);
+    // No column starts with two numbers, so every column falls back to copying the first row
+    assert_eq!(filled[1], vec!["a", "1", "=A2"]);
+    assert_eq!(filled[2], vec!["a", "1", "=A3"]);
+}
+
+// === Popup (multi-line) insert tests ===
+
+fn press(handler: &mut super::insert::InsertHandler, code: crossterm::event::KeyCode, view: &TableView) -> crate::input::KeyResult {
+    handler.handle_key(crossterm::event::KeyEvent::new(code, crossterm::event::KeyModifiers::NONE), view)
+}
+
+#[test]
+fn test_short_cells_edit_inline() {
+    use super::insert::InsertHandler;
+    let view = TableView::new(Rc::new(RefCell::new(RowManager::new())));
+    let mut handler = InsertHandler::new();
+    handler.start_edit("short".to_string(), 5);
+    assert!(!handler.multiline);
+    assert!(matches!(press(&mut handler, crossterm::event::KeyCode::Enter, &view), crate::input::KeyResult::ExecuteAndFinish(_)));
+}
+
+#[test]
+fn test_popup_edit_inserts_line_breaks_and_commits_on_ctrl_s() {
+    use crossterm::event::KeyCode;
+    use super::insert::{InsertHandler, POPUP_EDIT_THRESHOLD};
+    let view = TableView::new(Rc::new(RefCell::new(RowManager::new())));
+    let mut handler = InsertHandler::new();
+    let long = "x".repeat(POPUP_EDIT_THRESHOLD + 1);
+    handler.start_edit(long.clone(), 10);
+    assert!(handler.multiline);
+
+    press(&mut handler, KeyCode::Enter, &view);
+    press(&mut handler, KeyCode::Char('y'), &view);
+    assert_eq!(handler.buffer, format!("{}\ny", long));
+
+    // Up keeps the column where possible, Down returns to the last line
+    press(&mut handler, KeyCode::Up, &view);
+    assert_eq!(handler.cursor, 1);
+    press(&mut handler, KeyCode::Down, &view);
+    assert_eq!(handler.cursor, long.len() + 2);
+
+    let save = crossterm::event::KeyEvent::new(KeyCode::Char('s'), crossterm::event::KeyModifiers::CONTROL);
+    match handler.handle_key(save, &view) {
+        crate::input::KeyResult::ExecuteAndFinish(Transaction::SetCell { old_value, new_value, .. }) => {
+            assert_eq!(old_value, long);
+            assert_eq!(new_value, format!("{}\ny", long));
+        }
+        _ => panic!("expected the popup edit to commit"),
+    }
+}
+
+#[test]
+fn test_popup_edit_for_cells_with_line_breaks() {
+    use super::insert::InsertHandler;
+    let view = TableView::new(Rc::new(RefCell::new(RowManager::new())));
+    let mut handler = InsertHandler::new();
+    handler.start_edit("a\nb".to_string(), 1);
+    assert!(handler.multiline);
+    // Esc closes the popup and drops the changes
+    press(&mut handler, crossterm::event::KeyCode::Char('c'), &view);
+    assert!(matches!(press(&mut handler, crossterm::event::KeyCode::Esc, &view), crate::input::KeyResult::Finish));
+    assert_eq!(handler.buffer, "a\nb");
+}
+
+#[test]
+fn test_k_inspects_cell_without_mutation() {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    let mut table = Table::new(vec![vec!["a very long cell".to_string()]]);
+    let mut view = TableView::new(Rc::new(RefCell::new(RowManager::new())));
+    let mut handler = super::normal::NormalHandler::new();
+    let result = handler.handle_key(
+        KeyEvent::new(KeyCode::Char('K'), KeyModifiers::NONE),
+        &mut view,
+        &mut table,
+        1,
+        super::normal::NormalContext {
+            nav_handler: &crate::input::NavigationHandler::new(),
+            is_filtered: false,
+            header_mode: true,
+            clipboard: &mut crate::transaction::clipboard::Clipboard::new(),
+            search_handler: &mut SearchHandler::new(),
+        },
+    );
+    assert!(matches!(result, crate::input::KeyResult::InspectCell));
+    assert_eq!(table.get_cell(0, 0).unwrap(), "a very long cell");
+}
+
+// === Format tests ===
+
+fn format_cells(op: super::visual::FormatOp) -> Table {
+    let mut table = Table::new(vec![
+        vec!["1234.5".to_string(), "price".to_string()],
+        vec!["0.25".to_string(), "$1,000".to_string()],
+    ]);
+    if let Some(txn) = super::visual::create_format_txn(&table, &[0, 1], (0, 1), op) {
+        txn.apply(&mut table);
+    }
+    table
+}
+
+#[test]
+fn test_format_txn_each_kind() {
+    use super::visual::FormatOp;
+
+    let t = format_cells(FormatOp::Currency);
+    assert_eq!(t.get_cell(0, 0).unwrap(), "$1,234.50");
+    assert_eq!(t.get_cell(1, 0).unwrap(), "$0.25");
+    assert_eq!(t.get_cell(0, 1).unwrap(), "price");
+
+    let t = format_cells(FormatOp::Percentage);
+    assert_eq!(t.get_cell(1, 0).unwrap(), "25%");
+
+    let t = format_cells(FormatOp::Commas);
+    assert_eq!(t.get_cell(0, 0).unwrap(), "1,234.5");
+
+    let t = format_cells(FormatOp::Scientific);
+    assert_eq!(t.get_cell(0, 0).unwrap(), "1.23e3");
+
+    let t = format_cells(FormatOp::Default);
+    assert_eq!(t.get_cell(1, 1).unwrap(), "1000");
+}
+
+#[test]
+fn test_format_txn_skips_text_and_unchanged_cells() {
+    use super::visual::{FormatOp, create_format_txn};
+
+    let table = Table::new(vec![vec!["abc".to_string(), "12".to_string()]]);
+    assert!(create_format_txn(&table, &[0], (0, 1), FormatOp::Default).is_none());
+    match create_format_txn(&table, &[0], (0, 1), FormatOp::Currency) {
+        Some(crate::transaction::transaction::Transaction::Batch(txns)) => assert_eq!(txns.len(), 1),
+        other => panic!("expected a batch, got {:?}", other),
+    }
+}
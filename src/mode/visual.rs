@@ -1,12 +1,13 @@
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::mode::Mode;
 use crate::transaction::transaction::Transaction;
 use crate::input::{KeyResult, KeyBufferResult, SequenceAction, is_escape, NavigationHandler, KeyBuffer};
 use crate::table::table::Table;
 use crate::table::tableview::TableView;
+use crate::table::operations::{clear_rows, delete_rows, live_rows, move_cols, move_rows};
 use crate::transaction::clipboard::{Clipboard, RegisterContent, PasteAnchor};
-use crate::numeric::format::{format_scientific, format_percentage, format_currency, format_commas, format_default };
+use crate::numeric::format::{format_scientific, format_percentage, format_currency, format_commas, format_default, parse_numeric, bump_number, NumberStyle};
 
 /// Selection information for visual mode
 #[derive(Clone, Debug, Default)]
@@ -36,14 +37,78 @@ pub enum FormatOp {
     Percentage,
 }
 
+impl FormatOp {
+    pub fn from_action(action: SequenceAction) -> Option<Self> {
+        match action {
+            SequenceAction::FormatDefault => Some(FormatOp::Default),
+            SequenceAction::FormatCommas => Some(FormatOp::Commas),
+            SequenceAction::FormatCurrency => Some(FormatOp::Currency),
+            SequenceAction::FormatScientific => Some(FormatOp::Scientific),
+            SequenceAction::FormatPercentage => Some(FormatOp::Percentage),
+            _ => None,
+        }
+    }
+
+    /// Reformat a numeric cell; None for cells that aren't numbers
+    pub fn apply(&self, cell: &str) -> Option<String> {
+        match self {
+            FormatOp::Default => format_default(cell),
+            FormatOp::Commas => format_commas(cell),
+            FormatOp::Currency => format_currency(cell, '$'),
+            FormatOp::Scientific => format_scientific(cell, 2),
+            FormatOp::Percentage => format_percentage(cell, 0),
+        }
+    }
+}
+
+/// Case change operations (gu, gU, g~)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CaseOp {
+    Lower,
+    Upper,
+    Toggle,
+}
+
+impl CaseOp {
+    pub fn from_action(action: SequenceAction) -> Option<Self> {
+        match action {
+            SequenceAction::LowerCase => Some(CaseOp::Lower),
+            SequenceAction::UpperCase => Some(CaseOp::Upper),
+            SequenceAction::ToggleCase => Some(CaseOp::Toggle),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, s: &str) -> String {
+        match self {
+            CaseOp::Lower => s.to_lowercase(),
+            CaseOp::Upper => s.to_uppercase(),
+            CaseOp::Toggle => s.chars()
+                .map(|c| if c.is_uppercase() { c.to_lowercase().to_string() } else { c.to_uppercase().to_string() })
+                .collect(),
+        }
+    }
+}
+
+/// How a drag fills the cells after the source
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FillMode {
+    /// Copy the first cell, translating cell references
+    Copy,
+    /// Continue the arithmetic sequence set by the first two cells
+    Series,
+}
+
 /// Unified visual mode handler
 pub struct VisualHandler {
     pub visual_type: VisualType,
+    /// Row 0 is the header and stays in place when rows are moved
+    pub header_mode: bool,
 }
 
 impl VisualHandler {
-    pub fn new(visual_type: VisualType) -> Self {
-        Self { visual_type }
+    pub fn new(visual_type: VisualType, header_mode: bool) -> Self {
+        Self { visual_type, header_mode }
     }
 
     /// Handle a key event in visual mode
@@ -88,6 +153,27 @@ impl VisualHandler {
                     SequenceAction::FormatPercentage => {
                         return self.handle_format(view, table, FormatOp::Percentage);
                     }
+                    SequenceAction::LowerCase
+                    | SequenceAction::UpperCase
+                    | SequenceAction::ToggleCase => {
+                        let (rows, cols) = self.live_selection(view, table);
+                        let op = CaseOp::from_action(action).expect("case action");
+                        return match create_case_txn(table, &rows, cols, op) {
+                            Some(txn) => KeyResult::ExecuteAndFinish(txn),
+                            None => KeyResult::Finish,
+                        };
+                    }
+                    SequenceAction::Increment
+                    | SequenceAction::Decrement
+                    | SequenceAction::IncrementSeries
+                    | SequenceAction::DecrementSeries => {
+                        let (rows, cols) = self.live_selection(view, table);
+                        let (delta, series) = bump_amount(action, count).expect("bump action");
+                        return match create_bump_txn(table, &rows, cols, delta, series) {
+                            Some(txn) => KeyResult::ExecuteAndFinish(txn),
+                            None => KeyResult::Finish,
+                        };
+                    }
                     SequenceAction::SelectRegister(reg) => {
                         if let Err(e) = clipboard.select_register(reg) {
                             return KeyResult::Message(e);
@@ -107,29 +193,102 @@ impl VisualHandler {
 
                 match key.code {
                     KeyCode::Char('x') => self.handle_clear(view, table, clipboard),
+                    KeyCode::Char('p') if view.row_manager.borrow().is_filtered => {
+                        // A fill would spread over the rows the filter hides
+                        KeyResult::Message("Fill paste is forbidden in filtered views.".to_string())
+                    }
+                    KeyCode::Char('p') => {
+                        let bounds = self.expanded_bounds(view, table);
+                        match clipboard.paste_fill_as_transaction(bounds, table) {
+                            (msg, Some(txn)) => KeyResult::Paste(txn, Some(msg)),
+                            (msg, None) => KeyResult::Message(msg),
+                        }
+                    }
+                    KeyCode::Char('c') if self.visual_type == VisualType::Cell => {
+                        self.handle_change(view, table, clipboard)
+                    }
                     KeyCode::Char(':') => KeyResult::SwitchMode(crate::mode::Mode::Command),
-                    KeyCode::Char('q') => self.handle_drag_down(view, table),
-                    KeyCode::Char('Q') => self.handle_drag_right(view, table),
+                    KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::ALT) => {
+                        self.handle_drag_down(view, table, FillMode::Series)
+                    }
+                    KeyCode::Char('Q') if key.modifiers.contains(KeyModifiers::ALT) => {
+                        self.handle_drag_right(view, table, FillMode::Series)
+                    }
+                    KeyCode::Char(c @ ('j' | 'k'))
+                        if key.modifiers.contains(KeyModifiers::ALT) && self.visual_type == VisualType::Row =>
+                    {
+                        self.handle_move_rows(view, table, c == 'j')
+                    }
+                    KeyCode::Char(c @ ('h' | 'l'))
+                        if key.modifiers.contains(KeyModifiers::ALT) && self.visual_type == VisualType::Col =>
+                    {
+                        self.handle_move_cols(view, table, c == 'l')
+                    }
+                    KeyCode::Char('F') if self.visual_type == VisualType::Row => {
+                        let (start, end, _, _) = view.get_selection_bounds();
+                        KeyResult::FilterRows { start, end }
+                    }
+                    KeyCode::Char('q') => self.handle_drag_down(view, table, FillMode::Copy),
+                    KeyCode::Char('Q') => self.handle_drag_right(view, table, FillMode::Copy),
                     _ => KeyResult::Continue,
                 }
             }
         }
     }
 
+    /// Move the selected rows one place down or up, keeping them selected
+    fn handle_move_rows(&self, view: &mut TableView, table: &Table, down: bool) -> KeyResult {
+        if view.row_manager.borrow().is_filtered {
+            return KeyResult::Message("Moving rows is forbidden in filtered views.".to_string());
+        }
+        let (start_row, end_row, _, _) = view.get_selection_bounds();
+        let Some(txn) = move_rows(table, start_row, end_row, down, self.header_mode) else {
+            return KeyResult::Continue;
+        };
+        if down {
+            view.cursor_row += 1;
+            view.support_row += 1;
+        } else {
+            view.cursor_row -= 1;
+            view.support_row -= 1;
+        }
+        view.scroll_to_cursor();
+        KeyResult::Execute(txn)
+    }
+
+    /// Move the selected columns one place right or left, keeping them selected
+    fn handle_move_cols(&self, view: &mut TableView, table: &Table, right: bool) -> KeyResult {
+        let (_, _, start_col, end_col) = view.get_selection_bounds();
+        let Some(txn) = move_cols(table, start_col, end_col, right) else {
+            return KeyResult::Continue;
+        };
+        if right {
+            view.cursor_col += 1;
+            view.support_col += 1;
+        } else {
+            view.cursor_col -= 1;
+            view.support_col -= 1;
+        }
+        view.scroll_to_cursor();
+        KeyResult::Execute(txn)
+    }
+
     fn handle_yank(&self, view: &mut TableView, table: &Table, clipboard: &mut Clipboard) -> KeyResult {
         let (start_row, end_row, start_col, end_col) = view.get_selection_bounds();
 
-        match self.visual_type {
+        let result = match self.visual_type {
             VisualType::Cell => {
                 if let Some(span) = table.get_span(start_row, end_row, start_col, end_col) {
                     if view.row_manager.borrow().is_filtered {
                         let good_rows = (start_row..=end_row).filter(|&i| view.row_manager.borrow().is_row_live(i))
                                             .map(|i| span[i.saturating_sub(start_row)].clone()).collect();
 
-                        clipboard.yank_span(good_rows);
+                        clipboard.yank_span(good_rows)
                     } else {
-                        clipboard.yank_span(span);
+                        clipboard.yank_span(span)
                     }
+                } else {
+                    Ok(())
                 }
             }
             VisualType::Row => {
@@ -144,9 +303,7 @@ impl VisualHandler {
                 } else {
                     table.get_rows_cloned(start_row, count)
                 };
-                if !rows.is_empty() {
-                    clipboard.yank_rows(rows);
-                }
+                if rows.is_empty() { Ok(()) } else { clipboard.yank_rows(rows) }
             }
             VisualType::Col => {
                 // Yank all selected columns
@@ -157,21 +314,44 @@ impl VisualHandler {
                             .collect()
                     })
                     .collect();
-                if !cols.is_empty() {
-                    clipboard.yank_cols(cols);
-                }
+                if cols.is_empty() { Ok(()) } else { clipboard.yank_cols(cols) }
             }
+        };
+        match result {
+            Ok(()) => KeyResult::Finish,
+            Err(e) => KeyResult::Message(e),
         }
-        KeyResult::Finish
     }
 
     fn handle_delete(&self, view: &TableView, table: &Table, clipboard: &mut Clipboard) -> KeyResult {
-        if view.row_manager.borrow().is_filtered {
-            return KeyResult::Message("Deleting rows is forbidden in filtered views.".to_string());
+        let filtered = view.row_manager.borrow().is_filtered;
+        if filtered && self.visual_type == VisualType::Col {
+            return KeyResult::Message("Deleting columns is forbidden in filtered views.".to_string());
         }
         let (start_row, end_row, start_col, end_col) = view.get_selection_bounds();
 
         match self.visual_type {
+            // Only the rows the filter shows are cleared or deleted
+            VisualType::Cell if filtered => {
+                let (txn, old_data) = clear_rows(table, &live_rows(view, start_row, end_row), start_col, end_col);
+                clipboard.store_deleted(RegisterContent {
+                    data: old_data,
+                    anchor: PasteAnchor::Cursor
+                });
+                KeyResult::ExecuteAndFinish(txn)
+            }
+            VisualType::Row if filtered => {
+                match delete_rows(table, &live_rows(view, start_row, end_row)) {
+                    Some((txn, rows)) => {
+                        clipboard.store_deleted(RegisterContent {
+                            data: rows,
+                            anchor: PasteAnchor::RowStart
+                        });
+                        KeyResult::ExecuteAndFinish(txn)
+                    }
+                    None => KeyResult::Finish,
+                }
+            }
             VisualType::Cell => {
                 // Clear cell contents
                 let old_data = table.get_span(start_row, end_row, start_col, end_col)
@@ -234,11 +414,19 @@ impl VisualHandler {
     }
 
     fn handle_clear(&self, view: &TableView, table: &Table, clipboard: &mut Clipboard) -> KeyResult {
+        let (start_row, end_row, start_col, end_col) = view.get_selection_bounds();
+
         if view.row_manager.borrow().is_filtered {
-            // probably ease up on this in the future
-            return KeyResult::Message("Deleting rows is forbidden in filtered views.".to_string());
+            // Clear only the rows the filter shows
+            let (start_row, end_row, start_col, end_col, anchor) = match self.visual_type {
+                VisualType::Cell => (start_row, end_row, start_col, end_col, PasteAnchor::Cursor),
+                VisualType::Row => (start_row, end_row, 0, table.col_count() - 1, PasteAnchor::RowStart),
+                VisualType::Col => (0, table.row_count() - 1, start_col, end_col, PasteAnchor::ColStart),
+            };
+            let (txn, old_data) = clear_rows(table, &live_rows(view, start_row, end_row), start_col, end_col);
+            clipboard.store_deleted(RegisterContent { data: old_data, anchor });
+            return KeyResult::ExecuteAndFinish(txn);
         }
-        let (start_row, end_row, start_col, end_col) = view.get_selection_bounds();
 
         let new_data;
         let old_data;
@@ -296,37 +484,50 @@ impl VisualHandler {
         KeyResult::ExecuteAndFinish(txn)
     }
 
-    fn handle_drag_down(&self, view: &TableView, table: &Table) -> KeyResult {
+    /// Clear the selection like `x`, then edit its top-left cell
+    fn handle_change(&self, view: &mut TableView, table: &Table, clipboard: &mut Clipboard) -> KeyResult {
+        let (start_row, _, start_col, _) = view.get_selection_bounds();
+        match self.handle_clear(view, table, clipboard) {
+            KeyResult::ExecuteAndFinish(txn) => {
+                view.cursor_row = start_row;
+                view.cursor_col = start_col;
+                KeyResult::ExecuteAndEdit(txn)
+            }
+            other => other,
+        }
+    }
+
+    fn handle_drag_down(&self, view: &TableView, table: &Table, mode: FillMode) -> KeyResult {
         if view.row_manager.borrow().is_filtered {
             return KeyResult::Message("Drag is forbidden in filtered views.".to_string());
         }
         match self.visual_type {
             VisualType::Cell | VisualType::Row => {
-                let txn = create_drag_down_txn(view, table, self.visual_type == VisualType::Row);
+                let txn = create_drag_down_txn(view, table, self.visual_type == VisualType::Row, mode);
                 KeyResult::ExecuteAndFinish(txn)
             }
             VisualType::Col => KeyResult::Continue, // Not applicable
         }
     }
 
-    fn handle_drag_right(&self, view: &TableView, table: &Table) -> KeyResult {
+    fn handle_drag_right(&self, view: &TableView, table: &Table, mode: FillMode) -> KeyResult {
         if view.row_manager.borrow().is_filtered {
             return KeyResult::Message("Drag is forbidden in filtered views.".to_string());
         }
         match self.visual_type {
             VisualType::Cell | VisualType::Col => {
-                let txn = create_drag_right_txn(view, table, self.visual_type == VisualType::Col);
+                let txn = create_drag_right_txn(view, table, self.visual_type == VisualType::Col, mode);
                 KeyResult::ExecuteAndFinish(txn)
             }
             VisualType::Row => KeyResult::Continue, // Not applicable
         }
     }
 
-    fn handle_format(&self, view: &TableView, table: &Table, op: FormatOp) -> KeyResult {
+    /// Selection bounds, expanded to full rows or columns in line-wise visual modes
+    fn expanded_bounds(&self, view: &TableView, table: &Table) -> (usize, usize, usize, usize) {
         let (sel_start_row, sel_end_row, sel_start_col, sel_end_col) = view.get_selection_bounds();
 
-        // Expand selection based on visual type
-        let (start_row, end_row, start_col, end_col) = match self.visual_type {
+        match self.visual_type {
             VisualType::Row => {
                 // Full rows
                 (sel_start_row, sel_end_row, 0, table.col_count().saturating_sub(1))
@@ -339,43 +540,110 @@ impl VisualHandler {
                 // Just the selected cells
                 (sel_start_row, sel_end_row, sel_start_col, sel_end_col)
             }
-        };
+        }
+    }
 
-        // Get the old data
-        let old_data = table.get_span(start_row, end_row, start_col, end_col)
-            .unwrap_or_default();
-
-        // Apply format to each cell
-        let new_data: Vec<Vec<String>> = old_data.iter()
-            .map(|row| {
-                row.iter()
-                    .map(|cell| {
-                        let formatted = match op {
-                            FormatOp::Default => format_default(cell),
-                            FormatOp::Commas => format_commas(cell),
-                            FormatOp::Currency => format_currency(cell, '$'),
-                            FormatOp::Scientific => format_scientific(cell, 2),
-                            FormatOp::Percentage => format_percentage(cell, 0),
-                        };
-                        // If formatting failed (non-numeric), keep original value
-                        formatted.unwrap_or_else(|| cell.clone())
-                    })
-                    .collect()
-            })
-            .collect();
+    /// Rows of the expanded selection that the filter shows, with its column bounds
+    fn live_selection(&self, view: &TableView, table: &Table) -> (Vec<usize>, (usize, usize)) {
+        let (start_row, end_row, start_col, end_col) = self.expanded_bounds(view, table);
+        (live_rows(view, start_row, end_row), (start_col, end_col))
+    }
 
-        let txn = Transaction::SetSpan {
-            row: start_row,
-            col: start_col,
-            old_data,
-            new_data,
-        };
-        KeyResult::ExecuteAndFinish(txn)
+    fn handle_format(&self, view: &TableView, table: &Table, op: FormatOp) -> KeyResult {
+        let (rows, cols) = self.live_selection(view, table);
+        match create_format_txn(table, &rows, cols, op) {
+            Some(txn) => KeyResult::ExecuteAndFinish(txn),
+            None => KeyResult::Finish,
+        }
+    }
+}
+
+/// Create a batch of SetCell transactions rewriting the numeric cells in
+/// the given rows and cols start..=end in another format; other cells are skipped
+/// Returns None when no cell changes
+pub fn create_format_txn(table: &Table, rows: &[usize], cols: (usize, usize), op: FormatOp) -> Option<Transaction> {
+    let mut txns = Vec::new();
+    for &row in rows {
+        for col in cols.0..=cols.1 {
+            let Some(old_value) = table.get_cell(row, col) else { continue; };
+            let Some(new_value) = op.apply(old_value) else { continue; };
+            if &new_value != old_value {
+                txns.push(Transaction::SetCell { row, col, old_value: old_value.clone(), new_value });
+            }
+        }
+    }
+    if txns.is_empty() {
+        None
+    } else {
+        Some(Transaction::Batch(txns))
+    }
+}
+
+/// Create a batch of SetCell transactions changing the case of the cells in
+/// the given rows and cols start..=end; cells whose text doesn't change are skipped
+/// Returns None when no cell changes
+pub fn create_case_txn(table: &Table, rows: &[usize], cols: (usize, usize), op: CaseOp) -> Option<Transaction> {
+    let mut txns = Vec::new();
+    for &row in rows {
+        for col in cols.0..=cols.1 {
+            let Some(old_value) = table.get_cell(row, col) else { continue; };
+            // Formulas are left alone, since case changes would break function names and references
+            if old_value.starts_with('=') {
+                continue;
+            }
+            let new_value = op.apply(old_value);
+            if &new_value != old_value {
+                txns.push(Transaction::SetCell { row, col, old_value: old_value.clone(), new_value });
+            }
+        }
+    }
+    if txns.is_empty() {
+        None
+    } else {
+        Some(Transaction::Batch(txns))
+    }
+}
+
+/// Amount and series flag for a Ctrl-A / Ctrl-X action repeated count times
+pub fn bump_amount(action: SequenceAction, count: usize) -> Option<(i64, bool)> {
+    let count = count as i64;
+    match action {
+        SequenceAction::Increment => Some((count, false)),
+        SequenceAction::Decrement => Some((-count, false)),
+        SequenceAction::IncrementSeries => Some((count, true)),
+        SequenceAction::DecrementSeries => Some((-count, true)),
+        _ => None,
+    }
+}
+
+/// Create a batch of SetCell transactions adding delta to the first number of each cell in
+/// the given rows (ascending) and cols start..=end; cells without a number are skipped
+/// As a series the nth changed cell (row by row) gets n * delta
+/// Returns None when no cell changes
+pub fn create_bump_txn(table: &Table, rows: &[usize], cols: (usize, usize), delta: i64, series: bool) -> Option<Transaction> {
+    let mut txns = Vec::new();
+    for &row in rows {
+        for col in cols.0..=cols.1 {
+            let Some(old_value) = table.get_cell(row, col) else { continue; };
+            // Formulas are left alone, since the first number is usually part of a reference
+            if old_value.starts_with('=') {
+                continue;
+            }
+            let step = if series { delta.saturating_mul(txns.len() as i64 + 1) } else { delta };
+            if let Some(new_value) = bump_number(old_value, step) {
+                txns.push(Transaction::SetCell { row, col, old_value: old_value.clone(), new_value });
+            }
+        }
+    }
+    if txns.is_empty() {
+        None
+    } else {
+        Some(Transaction::Batch(txns))
     }
 }
 
-/// Create a drag-down transaction (fill formula down)
-fn create_drag_down_txn(view: &TableView, table: &Table, whole_row: bool) -> Transaction {
+/// Create a drag-down transaction (fill formula down, or continue a numeric series)
+pub fn create_drag_down_txn(view: &TableView, table: &Table, whole_row: bool, mode: FillMode) -> Transaction {
     let (start_row, end_row, mut start_col, mut end_col) = view.get_selection_bounds();
     if whole_row {
         start_col = 0;
@@ -386,13 +654,17 @@ fn create_drag_down_txn(view: &TableView, table: &Table, whole_row: bool) -> Tra
         .unwrap_or_default();
 
     let mut new_data = old_data.clone();
-    for row_idx in 1..new_data.len() {
-        for col_idx in 0..new_data[row_idx].len() {
-            new_data[row_idx][col_idx] = crate::util::translate_references(
-                &new_data[0][col_idx],
-                row_idx as isize,
-                0,
-            );
+    for col_idx in 0..new_data.first().map_or(0, |r| r.len()) {
+        let series = match (mode, old_data.get(1)) {
+            (FillMode::Series, Some(second)) => Series::from_pair(&old_data[0][col_idx], &second[col_idx]),
+            _ => None,
+        };
+        let first_filled = if series.is_some() { 2 } else { 1 };
+        for (row_idx, row) in new_data.iter_mut().enumerate().skip(first_filled) {
+            row[col_idx] = match &series {
+                Some(series) => series.nth(row_idx),
+                None => crate::util::translate_references(&old_data[0][col_idx], row_idx as isize, 0),
+            };
         }
     }
 
@@ -404,8 +676,8 @@ fn create_drag_down_txn(view: &TableView, table: &Table, whole_row: bool) -> Tra
     }
 }
 
-/// Create a drag-right transaction (fill formula right)
-fn create_drag_right_txn(view: &TableView, table: &Table, whole_col: bool) -> Transaction {
+/// Create a drag-right transaction (fill formula right, or continue a numeric series)
+pub fn create_drag_right_txn(view: &TableView, table: &Table, whole_col: bool, mode: FillMode) -> Transaction {
     let (mut start_row, mut end_row, start_col, end_col) = view.get_selection_bounds();
     if whole_col {
         start_row = 0;
@@ -416,13 +688,18 @@ fn create_drag_right_txn(view: &TableView, table: &Table, whole_col: bool) -> Tr
         .unwrap_or_default();
 
     let mut new_data = old_data.clone();
-    for row_idx in 0..new_data.len() {
-        for col_idx in 1..new_data[row_idx].len() {
-            new_data[row_idx][col_idx] = crate::util::translate_references(
-                &new_data[row_idx][0],
-                0,
-                col_idx as isize,
-            );
+    for (row_idx, row) in new_data.iter_mut().enumerate() {
+        let source = &old_data[row_idx];
+        let series = match (mode, source.get(1)) {
+            (FillMode::Series, Some(second)) => Series::from_pair(&source[0], second),
+            _ => None,
+        };
+        let first_filled = if series.is_some() { 2 } else { 1 };
+        for (col_idx, cell) in row.iter_mut().enumerate().skip(first_filled) {
+            *cell = match &series {
+                Some(series) => series.nth(col_idx),
+                None => crate::util::translate_references(&source[0], 0, col_idx as isize),
+            };
         }
     }
 
@@ -433,3 +710,43 @@ fn create_drag_right_txn(view: &TableView, table: &Table, whole_col: bool) -> Tr
         new_data,
     }
 }
+
+/// Arithmetic sequence inferred from two numeric cells
+struct Series {
+    start: f64,
+    step: f64,
+    style: Option<NumberStyle>,
+    decimals: usize,
+}
+
+impl Series {
+    /// None unless both cells are numeric
+    /// Cells written the same way ("$1,000", "$1,250") continue in that format with
+    /// the decimals as typed; otherwise the series is plain numbers
+    fn from_pair(first: &str, second: &str) -> Option<Self> {
+        if let (Some((mut style, a)), Some((second_style, b))) = (NumberStyle::parse(first), NumberStyle::parse(second)) {
+            if style.same_units(&second_style) {
+                style.decimals = style.decimals.max(second_style.decimals);
+                return Some(Self { start: a, step: b - a, decimals: style.decimals, style: Some(style) });
+            }
+        }
+        let a = parse_numeric(first)?;
+        let b = parse_numeric(second)?;
+        let decimals = |n: f64| n.to_string().split_once('.').map_or(0, |(_, frac)| frac.len());
+        Some(Self {
+            start: a,
+            step: b - a,
+            style: None,
+            decimals: decimals(a).max(decimals(b)),
+        })
+    }
+
+    /// The value at position `n` (0 is the first cell), printed like the sources
+    fn nth(&self, n: usize) -> String {
+        let value = self.start + self.step * n as f64;
+        match &self.style {
+            Some(style) => style.format(value),
+            None => format!("{:.*}", self.decimals, value),
+        }
+    }
+}
@@ -12,6 +12,10 @@ pub enum CalcType {
 }
 
 impl CalcType {
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, CalcType::Int(_) | CalcType::Float(_))
+    }
+
     pub fn numeric_precedence(l: CalcType, r: CalcType) -> Result<(CalcType, CalcType), CalcError> {
         match (l,r) {
             (CalcType::Int(a), CalcType::Int(b)) => Ok((CalcType::Int(a), CalcType::Int(b))),
@@ -5,8 +5,35 @@ use crate::util::{CellRef, CalcError, col_from_letters, letters_from_col};
 use crate::numeric::parser::{self, Expr, ParseError};
 use crate::numeric::formula::{self as formula, ExprEvaluator};
 use crate::numeric::calctype::CalcType;
+use crate::numeric::format::parse_numeric;
 use crate::plugin::PluginManager;
 
+/// Sheets with at least this many cells are recalculated in the background on `:calc`
+/// and incrementally after an edit
+pub const LARGE_SHEET_CELLS: usize = 50_000;
+
+/// Cells referenced by each formula cell
+type Dependencies = HashMap<CellRef, HashSet<CellRef>>;
+
+/// Parsed formulas and their computed values, kept between edits on a large sheet
+/// so an edit re-parses only the edited cell and re-evaluates only its dependents.
+/// The values are for display; the table keeps the formula text until `:calc`.
+#[derive(Debug, Default)]
+pub struct FormulaCache {
+    formulas: HashMap<CellRef, Expr>,
+    dependencies: Dependencies,
+    dependents: Dependencies,  // Formulas that read each cell
+    values: HashMap<CellRef, String>,
+    pub edits: u64,  // App::edits the values are current for
+}
+
+impl FormulaCache {
+    /// Computed value of a formula cell, if it was re-evaluated
+    pub fn value(&self, row: usize, col: usize) -> Option<&str> {
+        self.values.get(&CellRef { row, col }).map(String::as_str)
+    }
+}
+
 
 /// Format a numeric value for display, removing unnecessary trailing zeros
 fn format_number(vt: CalcType) -> String {
@@ -45,6 +72,11 @@ impl From<ParseError> for CalcError {
     }
 }
 
+/// Formula for one row of a column formula: {row} becomes the 1-based row number
+pub fn expand_row_template(template: &str, row: usize) -> String {
+    template.replace("{row}", &(row + 1).to_string())
+}
+
 pub struct Calculator<'a> {
     table: &'a Table,
     skip_header: bool,
@@ -63,31 +95,12 @@ impl<'a> Calculator<'a> {
 
     /// Evaluate all formula cells and return updates as (row, col, value)
     pub fn evaluate_all(&self) -> Result<Vec<(usize, usize, String)>, CalcError> {
-        // Find all formula cells and parse them
-        let mut formulas: HashMap<CellRef, Expr> = HashMap::new();
-        for (row_idx, row) in self.table.rows_iter().enumerate() {
-            for (col_idx, cell) in row.iter().enumerate() {
-                if cell.starts_with('=') {
-                    let expr = parser::parse(cell)?;
-                    formulas.insert(
-                        CellRef { row: row_idx, col: col_idx },
-                        expr,
-                    );
-                }
-            }
-        }
+        let (formulas, dependencies) = self.parse_formulas()?;
 
         if formulas.is_empty() {
             return Ok(vec![]);
         }
 
-        // Build dependency graph
-        let mut dependencies: HashMap<CellRef, HashSet<CellRef>> = HashMap::new();
-        for (cell_ref, expr) in &formulas {
-            let refs = self.extract_cell_refs_from_expr(expr)?;
-            dependencies.insert(cell_ref.clone(), refs);
-        }
-
         // Check for circular references and get evaluation order
         let order = self.topological_sort(&formulas, &dependencies)?;
 
@@ -105,6 +118,128 @@ impl<'a> Calculator<'a> {
         Ok(updates)
     }
 
+    /// Parse every formula into a cache with no values yet
+    pub fn build_cache(&self) -> Result<FormulaCache, CalcError> {
+        let (formulas, dependencies) = self.parse_formulas()?;
+        let mut dependents: Dependencies = HashMap::new();
+        for (cell_ref, deps) in &dependencies {
+            for dep in deps {
+                dependents.entry(dep.clone()).or_default().insert(cell_ref.clone());
+            }
+        }
+        Ok(FormulaCache { formulas, dependencies, dependents, ..Default::default() })
+    }
+
+    /// Re-parse an edited cell into the cache and re-evaluate the formulas downstream of it
+    /// Returns how many formula values were updated
+    pub fn update_cache(&self, cache: &mut FormulaCache, row: usize, col: usize) -> Result<usize, CalcError> {
+        let changed = CellRef { row, col };
+        for dep in cache.dependencies.remove(&changed).into_iter().flatten() {
+            if let Some(readers) = cache.dependents.get_mut(&dep) {
+                readers.remove(&changed);
+            }
+        }
+        cache.formulas.remove(&changed);
+        cache.values.remove(&changed);
+
+        if let Some(text) = self.table.get_cell(row, col).filter(|text| text.starts_with('=')) {
+            let expr = parser::parse(text)?;
+            let refs = self.extract_cell_refs_from_expr(&expr)?;
+            for dep in &refs {
+                cache.dependents.entry(dep.clone()).or_default().insert(changed.clone());
+            }
+            cache.dependencies.insert(changed.clone(), refs);
+            cache.formulas.insert(changed.clone(), expr);
+        }
+
+        let updates = self.dependent_updates(cache, changed)?;
+        let count = updates.len();
+        for (row, col, value) in updates {
+            cache.values.insert(CellRef { row, col }, value);
+        }
+        Ok(count)
+    }
+
+    /// Values of the changed cell (if it is a formula) and every formula downstream of it
+    fn dependent_updates(&self, cache: &FormulaCache, changed: CellRef) -> Result<Vec<(usize, usize, String)>, CalcError> {
+        let FormulaCache { formulas, dependencies, dependents, .. } = cache;
+
+        // Walk downstream from the changed cell
+        let mut dirty: HashSet<CellRef> = HashSet::new();
+        if formulas.contains_key(&changed) {
+            dirty.insert(changed.clone());
+        }
+        let mut queue = vec![changed];
+        while let Some(cell) = queue.pop() {
+            for dependent in dependents.get(&cell).into_iter().flatten() {
+                if dirty.insert(dependent.clone()) {
+                    queue.push(dependent.clone());
+                }
+            }
+        }
+
+        if dirty.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Dirty formulas may read other formulas, which have to be evaluated first
+        let mut needed: HashSet<CellRef> = dirty.clone();
+        let mut queue: Vec<CellRef> = dirty.iter().cloned().collect();
+        while let Some(cell) = queue.pop() {
+            for dep in dependencies.get(&cell).into_iter().flatten() {
+                if formulas.contains_key(dep) && needed.insert(dep.clone()) {
+                    queue.push(dep.clone());
+                }
+            }
+        }
+
+        let formulas: HashMap<CellRef, Expr> = needed
+            .into_iter()
+            .map(|cell_ref| {
+                let expr = formulas[&cell_ref].clone();
+                (cell_ref, expr)
+            })
+            .collect();
+        let order = self.topological_sort(&formulas, dependencies)?;
+
+        let mut results: HashMap<CellRef, CalcType> = HashMap::new();
+        let mut updates: Vec<(usize, usize, String)> = Vec::new();
+
+        for cell_ref in order {
+            let value = self.evaluate_expr(&formulas[&cell_ref], &results)?;
+            results.insert(cell_ref.clone(), value.clone());
+            if dirty.contains(&cell_ref) {
+                updates.push((cell_ref.row, cell_ref.col, format_number(value)));
+            }
+        }
+
+        Ok(updates)
+    }
+
+    /// Parse every formula cell and collect the cells each one references
+    fn parse_formulas(&self) -> Result<(HashMap<CellRef, Expr>, Dependencies), CalcError> {
+        let mut formulas: HashMap<CellRef, Expr> = HashMap::new();
+        for (row_idx, row) in self.table.rows_iter().enumerate() {
+            for (col_idx, cell) in row.iter().enumerate() {
+                if cell.starts_with('=') {
+                    let expr = parser::parse(cell)?;
+                    formulas.insert(
+                        CellRef { row: row_idx, col: col_idx },
+                        expr,
+                    );
+                }
+            }
+        }
+
+        let mut dependencies: HashMap<CellRef, HashSet<CellRef>> = HashMap::new();
+        for (cell_ref, expr) in &formulas {
+            let refs = self.extract_cell_refs_from_expr(expr)?;
+            dependencies.insert(cell_ref.clone(), refs);
+        }
+
+        Ok((formulas, dependencies))
+    }
+
     /// Extract all cell references from a parsed expression
     fn extract_cell_refs_from_expr(&self, expr: &Expr) -> Result<HashSet<CellRef>, CalcError> {
         let mut refs = HashSet::new();
@@ -175,23 +310,27 @@ impl<'a> Calculator<'a> {
     }
 
     /// Topological sort with cycle detection
+    /// Cells are visited in row-major order so a reported cycle is deterministic
     fn topological_sort(
         &self,
         formulas: &HashMap<CellRef, Expr>,
         dependencies: &HashMap<CellRef, HashSet<CellRef>>,
     ) -> Result<Vec<CellRef>, CalcError> {
         let mut visited: HashSet<CellRef> = HashSet::new();
-        let mut in_stack: HashSet<CellRef> = HashSet::new();
+        let mut path: Vec<CellRef> = Vec::new();
         let mut order: Vec<CellRef> = Vec::new();
 
-        for cell_ref in formulas.keys() {
+        let mut cells: Vec<&CellRef> = formulas.keys().collect();
+        cells.sort();
+
+        for cell_ref in cells {
             if !visited.contains(cell_ref) {
                 self.dfs_topo(
                     cell_ref,
                     formulas,
                     dependencies,
                     &mut visited,
-                    &mut in_stack,
+                    &mut path,
                     &mut order,
                 )?;
             }
@@ -200,35 +339,41 @@ impl<'a> Calculator<'a> {
         Ok(order)
     }
 
+    /// Depth-first visit; `path` holds the chain of formulas currently being resolved
     fn dfs_topo(
         &self,
         cell: &CellRef,
         formulas: &HashMap<CellRef, Expr>,
         dependencies: &HashMap<CellRef, HashSet<CellRef>>,
         visited: &mut HashSet<CellRef>,
-        in_stack: &mut HashSet<CellRef>,
+        path: &mut Vec<CellRef>,
         order: &mut Vec<CellRef>,
     ) -> Result<(), CalcError> {
-        if in_stack.contains(cell) {
-            return Err(CalcError::CircularReference(cell_ref_to_name(cell)));
+        if let Some(start) = path.iter().position(|c| c == cell) {
+            // Report the cycle from its first cell back around to itself
+            let cycle: Vec<String> = path[start..].iter()
+                .chain(std::iter::once(cell))
+                .map(cell_ref_to_name)
+                .collect();
+            return Err(CalcError::CircularReference(cycle.join(" -> ")));
         }
 
         if visited.contains(cell) {
             return Ok(());
         }
 
-        in_stack.insert(cell.clone());
+        path.push(cell.clone());
         visited.insert(cell.clone());
 
         if let Some(deps) = dependencies.get(cell) {
+            let mut deps: Vec<&CellRef> = deps.iter().filter(|d| formulas.contains_key(d)).collect();
+            deps.sort();
             for dep in deps {
-                if formulas.contains_key(dep) {
-                    self.dfs_topo(dep, formulas, dependencies, visited, in_stack, order)?;
-                }
+                self.dfs_topo(dep, formulas, dependencies, visited, path, order)?;
             }
         }
 
-        in_stack.remove(cell);
+        path.pop();
         order.push(cell.clone());
 
         Ok(())
@@ -271,6 +416,26 @@ impl<'a> Calculator<'a> {
         CalcType::Str(trimmed.to_string())
     }
 
+    /// Value of a cell inside a range, or None for a blank cell (ranges skip blanks)
+    /// Formatted numbers such as "$1,234.56" or "15%" count as numbers here
+    fn get_range_value(
+        &self,
+        cell: &CellRef,
+        results: &HashMap<CellRef, CalcType>,
+    ) -> Option<CalcType> {
+        if results.contains_key(cell) {
+            return Some(self.get_cell_value(cell, results));
+        }
+        let content = self.table.get_cell(cell.row, cell.col)?.trim();
+        if content.is_empty() {
+            return None;
+        }
+        match self.get_cell_value(cell, results) {
+            CalcType::Str(_) => Some(parse_numeric(content).map_or_else(|| CalcType::Str(content.to_string()), CalcType::Float)),
+            val => Some(val),
+        }
+    }
+
     /// Evaluate an expression to CalcType
     fn evaluate_expr(&self, expr: &Expr, results: &HashMap<CellRef, CalcType>) -> Result<CalcType, CalcError> {
         match expr {
@@ -309,7 +474,7 @@ impl<'a> Calculator<'a> {
         }
     }
 
-    /// Expand a range expression to a Vec of f64 values
+    /// Expand a range expression to the values of its non-blank cells
     pub fn expand_range(&self, expr: &Expr, results: &HashMap<CellRef, CalcType>) -> Result<Vec<CalcType>, CalcError> {
         match expr {
             Expr::Range { start, end } => {
@@ -326,8 +491,7 @@ impl<'a> Calculator<'a> {
                     for r in row_min..=row_max {
                         for c in col_min..=col_max {
                             let cell = CellRef { row: r - 1, col: c };
-                            let val = self.get_cell_value(&cell, results);
-                            values.push(val);
+                            values.extend(self.get_range_value(&cell, results));
                         }
                     }
                     Ok(values)
@@ -342,8 +506,7 @@ impl<'a> Calculator<'a> {
                 for r in row_min..=row_max {
                     for c in 0..self.table.col_count() {
                         let cell = CellRef { row: r - 1, col: c };
-                        let val = self.get_cell_value(&cell, results);
-                        values.push(val)
+                        values.extend(self.get_range_value(&cell, results));
                     }
                 }
                 Ok(values)
@@ -358,8 +521,7 @@ impl<'a> Calculator<'a> {
                 for r in row_start..self.table.row_count() {
                     for c in col_min..=col_max {
                         let cell = CellRef { row: r, col: c };
-                        let val = self.get_cell_value(&cell, results);
-                        values.push(val);
+                        values.extend(self.get_range_value(&cell, results));
                     }
                 }
                 Ok(values)
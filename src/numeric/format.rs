@@ -70,6 +70,54 @@ pub fn parse_numeric(s: &str) -> Option<f64> {
     None
 }
 
+/// Parse a date into days since 1970-01-01
+///
+/// Handles ISO "YYYY-MM-DD", US "MM/DD/YYYY" and European "DD.MM.YYYY";
+/// month and day may have one or two digits. Impossible dates return None.
+pub fn parse_date(s: &str) -> Option<i64> {
+    let trimmed = s.trim();
+    let (sep, order) = if trimmed.contains('-') {
+        ('-', [0, 1, 2])  // year, month, day
+    } else if trimmed.contains('/') {
+        ('/', [2, 0, 1])
+    } else if trimmed.contains('.') {
+        ('.', [2, 1, 0])
+    } else {
+        return None;
+    };
+
+    let parts: Vec<&str> = trimmed.split(sep).collect();
+    if parts.len() != 3 || parts.iter().any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    let [year, month, day] = order.map(|i| parts[i]);
+    if year.len() != 4 || month.len() > 2 || day.len() > 2 {
+        return None;
+    }
+    let (year, month, day): (i64, u32, u32) = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let month_days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if day == 0 || day > month_days {
+        return None;
+    }
+
+    // Days from civil date (proleptic Gregorian calendar)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let shifted_month = (month as i64 + 9) % 12;  // March = 0
+    let day_of_year = (153 * shifted_month + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
 /// Format a number to its default representation (no formatting, just the number)
 /// Parses the input (which may have currency, commas, etc.) and outputs a plain number.
 pub fn format_default(val: &str) -> Option<String> {
@@ -92,21 +140,29 @@ pub fn format_commas(val: &str) -> Option<String> {
     // First verify it's a valid number
     let _ = parse_numeric(trimmed)?;
 
-    // Work with the string representation to preserve decimal places
-    let is_negative = trimmed.starts_with('-');
-    let without_sign = trimmed.trim_start_matches('-');
+    Some(group_thousands(trimmed))
+}
 
-    // Remove any existing commas
-    let clean: String = without_sign.chars().filter(|c| *c != ',').collect();
+/// Insert thousands separators into the integer part of a number
+/// (e.g. "-1234567.5" -> "-1,234,567.5", "$1234" -> "$1,234")
+/// Existing separators are regrouped; non-numeric text and scientific notation are returned unchanged.
+pub fn group_thousands(val: &str) -> String {
+    if parse_numeric(val).is_none() {
+        return val.to_string();
+    }
+    let Some((prefix, number, suffix)) = split_number(val) else {
+        return val.to_string();
+    };
+    if number.contains(['e', 'E']) {
+        return val.to_string();
+    }
 
-    // Split on decimal point
-    let (int_part, dec_part) = if let Some(dot_pos) = clean.find('.') {
-        (&clean[..dot_pos], Some(&clean[dot_pos + 1..]))
-    } else {
-        (clean.as_str(), None)
+    let clean: String = number.chars().filter(|c| *c != ',').collect();
+    let (int_part, dec_part) = match clean.split_once('.') {
+        Some((int_part, dec_part)) => (int_part, Some(dec_part)),
+        None => (clean.as_str(), None),
     };
 
-    // Format integer part with commas
     let with_commas: String = int_part
         .as_bytes()
         .rchunks(3)
@@ -115,23 +171,62 @@ pub fn format_commas(val: &str) -> Option<String> {
         .collect::<Vec<_>>()
         .join(",");
 
-    // Reconstruct the number
-    let result = match dec_part {
-        Some(d) if !d.is_empty() => format!("{}.{}", with_commas, d),
-        _ => with_commas,
-    };
+    match dec_part {
+        Some(d) => format!("{}{}.{}{}", prefix, with_commas, d, suffix),
+        None => format!("{}{}{}", prefix, with_commas, suffix),
+    }
+}
 
-    if is_negative {
-        Some(format!("-{}", result))
-    } else {
-        Some(result)
+/// How a number cell is written, so computed values can be printed the same way
+/// ("$1,200.50" -> symbol "$", grouped, two decimals; "15%" -> percent, no decimals)
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberStyle {
+    prefix: String,
+    suffix: String,
+    grouped: bool,
+    pub decimals: usize,
+}
+
+impl NumberStyle {
+    /// Split a numeric cell into its style and the value as written ("15%" -> 15, not 0.15)
+    /// Scientific notation and text that parse_numeric rejects give None
+    pub fn parse(val: &str) -> Option<(Self, f64)> {
+        parse_numeric(val)?;
+        let (prefix, number, suffix) = split_number(val.trim())?;
+        if number.contains(['e', 'E']) {
+            return None;
+        }
+        let negative = prefix.contains(['-', '(']);
+        let clean: String = number.chars().filter(|c| *c != ',').collect();
+        let magnitude: f64 = clean.parse().ok()?;
+        let style = Self {
+            prefix: prefix.chars().filter(|c| !"-+(".contains(*c)).collect(),
+            suffix: suffix.chars().filter(|c| *c != ')').collect(),
+            grouped: number.contains(','),
+            decimals: clean.split_once('.').map_or(0, |(_, frac)| frac.len()),
+        };
+        Some((style, if negative { -magnitude } else { magnitude }))
+    }
+
+    /// True if both styles decorate numbers the same way, so their values are in the same units
+    pub fn same_units(&self, other: &Self) -> bool {
+        self.prefix == other.prefix && self.suffix == other.suffix
+    }
+
+    /// Print value in this style; negatives get a leading '-' ("-$5.00")
+    pub fn format(&self, value: f64) -> String {
+        let digits = format!("{:.*}", self.decimals, value.abs());
+        let digits = if self.grouped { group_thousands(&digits) } else { digits };
+        let negative = value < 0.0 && digits.chars().any(|c| c.is_ascii_digit() && c != '0');
+        format!("{}{}{}{}", if negative { "-" } else { "" }, self.prefix, digits, self.suffix)
     }
 }
 
 /// Format as currency with symbol and thousands separators (e.g., 1234.56 -> $1,234.56)
+/// Like the other formats it accepts already formatted input ("15%", "$1,234.5")
 pub fn format_currency(val: &str, symbol: char) -> Option<String> {
     let trimmed = val.trim();
-    let n: f64 = trimmed.parse().ok()?;
+    let n = parse_numeric(trimmed)?;
 
     let is_negative = n < 0.0;
     let abs_n = n.abs();
@@ -162,7 +257,7 @@ pub fn format_currency(val: &str, symbol: char) -> Option<String> {
 /// Format in scientific notation (e.g., 0.00001234 -> 1.23e-5)
 pub fn format_scientific(val: &str, precision: usize) -> Option<String> {
     let trimmed = val.trim();
-    let n: f64 = trimmed.parse().ok()?;
+    let n = parse_numeric(trimmed)?;
 
     if n == 0.0 {
         return Some(format!("0.{}e0", "0".repeat(precision)));
@@ -174,7 +269,7 @@ pub fn format_scientific(val: &str, precision: usize) -> Option<String> {
 /// Format as percentage (e.g., 0.15 -> 15%)
 pub fn format_percentage(val: &str, decimals: usize) -> Option<String> {
     let trimmed = val.trim();
-    let n: f64 = trimmed.parse().ok()?;
+    let n = parse_numeric(trimmed)?;
 
     let pct = n * 100.0;
     if decimals == 0 {
@@ -185,28 +280,85 @@ pub fn format_percentage(val: &str, decimals: usize) -> Option<String> {
 }
 
 /// Format a number for display with optional precision.
-/// If precision is None, displays the number as-is.
-/// If precision is Some(n), displays exactly n decimal places for numeric values.
-/// Non-numeric values are returned unchanged.
+/// If precision is None, displays the value as-is.
+/// If precision is Some(n), numeric values (anything parse_numeric accepts) show exactly
+/// n decimal places; currency symbols, signs, parentheses, percent signs and thousands
+/// separators are kept around the reformatted number.
+/// Only the displayed text changes, the stored cell is never touched.
 pub fn format_display(val: &str, precision: Option<usize>) -> String {
     let precision = match precision {
         Some(p) => p,
         None => return val.to_string(),
     };
 
-    let trimmed = val.trim();
+    if parse_numeric(val).is_none() {
+        return val.to_string();
+    }
 
-    // Try to parse as a plain number (not formatted)
-    if let Ok(n) = trimmed.parse::<f64>() {
-        if n.is_nan() || n.is_infinite() {
-            return val.to_string();
-        }
-        if precision == 0 {
-            return format!("{}", n.round() as i64);
-        }
-        return format!("{:.prec$}", n, prec = precision);
+    let Some((prefix, number, suffix)) = split_number(val) else {
+        return val.to_string();
+    };
+    let grouped = number.contains(',');
+    let n = match number.replace(',', "").parse::<f64>() {
+        Ok(n) if n.is_finite() => n,
+        _ => return val.to_string(),
+    };
+
+    let formatted = if precision == 0 {
+        format!("{}", n.round() as i64)
+    } else {
+        format!("{:.prec$}", n, prec = precision)
+    };
+    let formatted = if grouped {
+        group_thousands(&formatted)
+    } else {
+        formatted
+    };
+
+    format!("{}{}{}", prefix, formatted, suffix)
+}
+
+/// Split a formatted number into (prefix, number, suffix), e.g. "($1,234.5)" -> ("($", "1,234.5", ")")
+/// The prefix may only hold signs, parentheses and currency symbols; the suffix only % and )
+fn split_number(val: &str) -> Option<(&str, &str, &str)> {
+    let start = val.find(|c: char| c.is_ascii_digit() || c == '.')?;
+    let end = val.rfind(|c: char| c.is_ascii_digit() || c == '.')? + 1;
+    let (prefix, suffix) = (&val[..start], &val[end..]);
+
+    let prefix_ok = prefix.chars().all(|c| c.is_whitespace() || "-+($€£¥".contains(c));
+    let suffix_ok = suffix.chars().all(|c| c.is_whitespace() || c == '%' || c == ')');
+    if prefix_ok && suffix_ok {
+        Some((prefix, &val[start..end], suffix))
+    } else {
+        None
     }
+}
 
-    // Not a number, return as-is
-    val.to_string()
+/// Add delta to the first integer in val (vim's Ctrl-A / Ctrl-X)
+/// A '-' directly before the digits counts as a sign unless it follows a letter or digit
+/// ("item-3" bumps 3, "-3" bumps -3). Zero-padded numbers keep their width.
+/// Returns None if val has no digits or the result overflows.
+pub fn bump_number(val: &str, delta: i64) -> Option<String> {
+    let bytes = val.as_bytes();
+    let start = bytes.iter().position(|b| b.is_ascii_digit())?;
+    let end = bytes[start..].iter().position(|b| !b.is_ascii_digit()).map_or(bytes.len(), |n| start + n);
+
+    let negative = start > 0
+        && bytes[start - 1] == b'-'
+        && (start == 1 || !bytes[start - 2].is_ascii_alphanumeric());
+    let digits = &val[start..end];
+    let magnitude: i128 = digits.parse().ok()?;
+    let value = if negative { -magnitude } else { magnitude };
+    let bumped = value.checked_add(delta as i128)?;
+
+    let width = if digits.len() > 1 && digits.starts_with('0') { digits.len() } else { 0 };
+    let sign_start = if negative { start - 1 } else { start };
+    Some(format!(
+        "{}{}{:0width$}{}",
+        &val[..sign_start],
+        if bumped < 0 { "-" } else { "" },
+        bumped.unsigned_abs(),
+        &val[end..],
+        width = width,
+    ))
 }
@@ -32,11 +32,8 @@ pub fn evaluate_function<E: ExprEvaluator>(
     match name {
         "SUM" => {
             require_args(name, args, 1)?;
-            let vals = evaluator.expand(&args[0], results)?;
-
-            if vals.len() == 0 {
-                return Err(CalcError::EvalError("Trying to use an array function on an empty array".to_string()));
-            }
+            // Non-numeric cells are skipped, so a range of text sums to 0
+            let vals = numeric_values(evaluator.expand(&args[0], results)?);
 
             Ok(vals.iter().try_fold(CalcType::Int(0), |acc, v| {
                 CalcType::bin_op(BinOp::Add, acc, v.clone())
@@ -44,10 +41,10 @@ pub fn evaluate_function<E: ExprEvaluator>(
         },
         "AVG" | "AVERAGE" => {
             require_args(name, args, 1)?;
-            let vals = evaluator.expand(&args[0], results)?;
+            let vals = numeric_values(evaluator.expand(&args[0], results)?);
 
             if vals.len() == 0 {
-                return Err(CalcError::EvalError("Trying to use an array function on an empty array".to_string()));
+                return Err(CalcError::EvalError(format!("{} has no numeric cells to average", name)));
             }
 
             let sum = vals.iter().try_fold(CalcType::Int(0), |acc, v| {
@@ -82,7 +79,7 @@ pub fn evaluate_function<E: ExprEvaluator>(
         },
         "COUNT" => {
             require_args(name, args, 1)?;
-            let vals = evaluator.expand(&args[0], results)?;
+            let vals = numeric_values(evaluator.expand(&args[0], results)?);
 
             Ok(CalcType::Int(vals.len() as i64))
         },
@@ -194,6 +191,11 @@ pub fn evaluate_function<E: ExprEvaluator>(
     }
 }
 
+/// Keep only the numeric values of an expanded range
+fn numeric_values(vals: Vec<CalcType>) -> Vec<CalcType> {
+    vals.into_iter().filter(|v| v.is_numeric()).collect()
+}
+
 fn require_args(name: &str, args: &[super::parser::Expr], expected: usize) -> Result<(), CalcError> {
     if args.len() != expected {
         Err(CalcError::EvalError(format!("{} requires {} argument(s), got {}", name, expected, args.len())))
@@ -36,6 +36,9 @@ pub enum Predicate {
         op: Op,
         val: String,
     },
+    Regex(String),  // Cell text matches the pattern (=~)
+    Blank,          // Cell is empty or whitespace-only
+    NotBlank,
     Not(Box<Predicate>),
     And(Box<Predicate>, Box<Predicate>),
     Or(Box<Predicate>, Box<Predicate>)
@@ -47,6 +50,15 @@ impl fmt::Display for Predicate {
             Predicate::Comparator { op, val } => {
                 write!(f, "{} {}", op, val)
             },
+            Predicate::Regex(pattern) => {
+                write!(f, "=~ {}", pattern)
+            },
+            Predicate::Blank => {
+                write!(f, "is blank")
+            },
+            Predicate::NotBlank => {
+                write!(f, "is not blank")
+            },
             Predicate::Not(pred) => {
                 write!(f, "NOT({})", pred)
             },
@@ -87,7 +99,22 @@ impl Predicate {
                     }
                 }
 
-                ColumnType::Text => {
+                ColumnType::Date => {
+                    let (Some(lhs), Some(rhs)) = (crate::numeric::format::parse_date(other), crate::numeric::format::parse_date(val)) else {
+                        return false;
+                    };
+
+                    match op {
+                        Op::Eq => lhs == rhs,
+                        Op::Ne => lhs != rhs,
+                        Op::Lt => lhs < rhs,
+                        Op::Le => lhs <= rhs,
+                        Op::Gt => lhs > rhs,
+                        Op::Ge => lhs >= rhs,
+                    }
+                }
+
+                ColumnType::Text | ColumnType::Natural => {
                     let lhs = other.trim().to_lowercase();
                     let rhs = val.trim().to_lowercase();
 
@@ -101,6 +128,12 @@ impl Predicate {
                     }
                 },
             },
+            // Compiles on every call; RowManager::predicate_filter compiles once per filter
+            Predicate::Regex(pattern) => {
+                Regex::new(pattern).is_ok_and(|re| re.is_match(other))
+            },
+            Predicate::Blank => other.trim().is_empty(),
+            Predicate::NotBlank => !other.trim().is_empty(),
             Predicate::Not(pred) => {
                 !pred.evaluate(other, col_type)
             },
@@ -119,7 +152,7 @@ pub fn parse_predicate(pred_string: String) -> Option<Predicate> {
     // Multi-char operators must come first: alternation is leftmost-first,
     // so "<" before "<=" would match "<" and corrupt the value with "=".
     // The value may contain spaces (e.g. text filters like `= New York`).
-    let pred_re = Regex::new(r"^\s*(<=|>=|!=|==|!|=|<|>)\s*(\S.*?)\s*$")
+    let pred_re = Regex::new(r"^\s*(<=|>=|!=|==|=~|!|=|<|>)\s*(\S.*?)\s*$")
         .expect("invalid regex");
 
     let caps = pred_re
@@ -128,6 +161,10 @@ pub fn parse_predicate(pred_string: String) -> Option<Predicate> {
     let op_str = &caps[1];
     let val = caps[2].to_string();
 
+    if op_str == "=~" {
+        return Some(Predicate::Regex(val));
+    }
+
     let op = match op_str {
         "=" | "==" => Op::Eq,
         "!" | "!=" => Op::Ne,
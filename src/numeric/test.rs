@@ -7,7 +7,7 @@ use super::parser::*;
 use super::predicate::*;
 
 use crate::table::table::Table;
-use crate::util::ColumnType;
+use crate::util::{CalcError, ColumnType};
 
 
 #[test]
@@ -261,6 +261,44 @@ fn test_format_commas() {
     assert_eq!(format_commas("abc"), None);
 }
 
+#[test]
+fn test_group_thousands() {
+    assert_eq!(group_thousands("1234567"), "1,234,567");
+    assert_eq!(group_thousands("-1234567"), "-1,234,567");
+    assert_eq!(group_thousands("1234567.891"), "1,234,567.891");
+    assert_eq!(group_thousands("-1234.50"), "-1,234.50");
+    assert_eq!(group_thousands("999"), "999");
+    assert_eq!(group_thousands("$1234.5"), "$1,234.5");
+    assert_eq!(group_thousands("12345%"), "12,345%");
+}
+
+#[test]
+fn test_group_thousands_already_grouped() {
+    assert_eq!(group_thousands("1,234,567"), "1,234,567");
+    assert_eq!(group_thousands("12,34,567"), "1,234,567");
+    assert_eq!(group_thousands(&group_thousands("-9876543.21")), "-9,876,543.21");
+}
+
+#[test]
+fn test_group_thousands_leaves_other_text() {
+    assert_eq!(group_thousands("abc"), "abc");
+    assert_eq!(group_thousands("2024 report"), "2024 report");
+    assert_eq!(group_thousands("1.5e10"), "1.5e10");
+    assert_eq!(group_thousands(""), "");
+}
+
+#[test]
+fn test_number_style_round_trip() {
+    for val in ["$1,234.50", "15%", "-$5", "0.750", "1,000,000", "€3"] {
+        let (style, value) = NumberStyle::parse(val).unwrap();
+        assert_eq!(style.format(value), val);
+    }
+    assert_eq!(NumberStyle::parse("15%").unwrap().1, 15.0);
+    assert_eq!(NumberStyle::parse("($12)").unwrap().1, -12.0);
+    assert_eq!(NumberStyle::parse("1.5e3"), None);
+    assert_eq!(NumberStyle::parse("abc"), None);
+}
+
 #[test]
 fn test_format_currency() {
     assert_eq!(format_currency("1234.56", '$'), Some("$1,234.56".to_string()));
@@ -271,6 +309,14 @@ fn test_format_currency() {
     assert_eq!(format_currency("abc", '$'), None);
 }
 
+#[test]
+fn test_format_from_formatted_input() {
+    assert_eq!(format_currency("$1,234.5", '$'), Some("$1,234.50".to_string()));
+    assert_eq!(format_currency("15%", '$'), Some("$0.15".to_string()));
+    assert_eq!(format_scientific("1,500", 1), Some("1.5e3".to_string()));
+    assert_eq!(format_percentage("15%", 0), Some("15%".to_string()));
+}
+
 #[test]
 fn test_format_currency_rounding_carry() {
     // Cents that round up to 100 must carry into the integer part
@@ -280,6 +326,33 @@ fn test_format_currency_rounding_carry() {
     assert_eq!(format_currency("999.995", '$'), Some("$1,000.00".to_string()));
 }
 
+#[test]
+fn test_format_display_plain_numbers() {
+    assert_eq!(format_display("3.14159", Some(2)), "3.14");
+    assert_eq!(format_display("2", Some(3)), "2.000");
+    assert_eq!(format_display("-7.5", Some(0)), "-8");
+    assert_eq!(format_display("1.5e3", Some(1)), "1500.0");
+    // No precision: the raw string is shown untouched
+    assert_eq!(format_display("3.14159", None), "3.14159");
+    assert_eq!(format_display("$1,234.5", None), "$1,234.5");
+}
+
+#[test]
+fn test_format_display_keeps_symbols() {
+    assert_eq!(format_display("$1,234.5", Some(2)), "$1,234.50");
+    assert_eq!(format_display("-$99.999", Some(2)), "-$100.00");
+    assert_eq!(format_display("($50)", Some(1)), "($50.0)");
+    assert_eq!(format_display("15.25%", Some(1)), "15.2%");
+    assert_eq!(format_display("1,234,567.891", Some(0)), "1,234,568");
+}
+
+#[test]
+fn test_format_display_leaves_text_alone() {
+    assert_eq!(format_display("abc", Some(2)), "abc");
+    assert_eq!(format_display("12 apples", Some(2)), "12 apples");
+    assert_eq!(format_display("", Some(2)), "");
+}
+
 #[test]
 fn test_format_scientific() {
     assert_eq!(format_scientific("1234", 2), Some("1.23e3".to_string()));
@@ -569,6 +642,21 @@ fn parse_predicate_with_whitespace() {
     assert_eq!(pred, Some(Predicate::Comparator { op: Op::Ge, val: "123".to_string() }));
 }
 
+#[test]
+fn parse_predicate_regex() {
+    let pred = parse_predicate("=~ ^foo\\d+".to_string());
+    assert_eq!(pred, Some(Predicate::Regex("^foo\\d+".to_string())));
+    assert_eq!(pred.unwrap().to_string(), "=~ ^foo\\d+");
+}
+
+#[test]
+fn evaluate_regex_predicate() {
+    let pred = Predicate::Regex("^a.c$".to_string());
+    assert!(pred.evaluate("abc", ColumnType::Text));
+    assert!(!pred.evaluate("abcd", ColumnType::Text));
+    assert!(Predicate::Regex("^1".to_string()).evaluate("123", ColumnType::Numeric));
+}
+
 // === parse_predicate tests for text values ===
 
 #[test]
@@ -751,3 +839,255 @@ fn display_predicate() {
     let not_pred = Predicate::Not(Box::new(pred.clone()));
     assert_eq!(format!("{}", not_pred), "NOT(>= 100)");
 }
+
+// === Range aggregate tests ===
+
+/// Evaluate a table and return the value written to (row, col)
+fn calc_value(table: &Table, row: usize, col: usize) -> String {
+    let results = Calculator::new(table, false).evaluate_all().unwrap();
+    results.into_iter()
+        .find(|&(r, c, _)| r == row && c == col)
+        .map(|(_, _, v)| v)
+        .unwrap()
+}
+
+#[test]
+fn test_range_functions_vertical() {
+    let table = make_table(vec![
+        vec!["1", "=SUM(A1:A4)"],
+        vec!["2", "=AVG(A1:A4)"],
+        vec!["n/a", "=COUNT(A1:A4)"],
+        vec!["$6", ""],
+    ]);
+    // The text cell is skipped by all three; formatted numbers count
+    assert_eq!(calc_value(&table, 0, 1), "9");
+    assert_eq!(calc_value(&table, 1, 1), "3");
+    assert_eq!(calc_value(&table, 2, 1), "3");
+}
+
+#[test]
+fn test_range_functions_horizontal() {
+    let table = make_table(vec![
+        vec!["1.5", "", "2.5", "x"],
+        vec!["=SUM(A1:D1)", "=AVG(A1:D1)", "=COUNT(A1:D1)", ""],
+    ]);
+    // Blank and text cells do not count toward the average
+    assert_eq!(calc_value(&table, 1, 0), "4");
+    assert_eq!(calc_value(&table, 1, 1), "2");
+    assert_eq!(calc_value(&table, 1, 2), "2");
+}
+
+#[test]
+fn test_range_functions_block() {
+    let table = make_table(vec![
+        vec!["1", "2", "a"],
+        vec!["3", "4", "b"],
+        vec!["=SUM(A1:B2)", "=AVG(A1:B2)", "=COUNT(A1:C2)"],
+    ]);
+    assert_eq!(calc_value(&table, 2, 0), "10");
+    assert_eq!(calc_value(&table, 2, 1), "2.5");
+    assert_eq!(calc_value(&table, 2, 2), "4");
+}
+
+#[test]
+fn test_formatted_numbers_only_parsed_in_ranges() {
+    let table = make_table(vec![
+        vec!["$1,000", "=SUM(A1:A1)", "=A1"],
+    ]);
+    assert_eq!(calc_value(&table, 0, 1), "1000");
+    // A plain reference keeps the cell's text
+    assert_eq!(calc_value(&table, 0, 2), "$1,000");
+}
+
+#[test]
+fn test_range_including_own_cell_is_rejected() {
+    let table = make_table(vec![
+        vec!["1"],
+        vec!["2"],
+        vec!["=SUM(A1:A3)"],
+    ]);
+    let err = Calculator::new(&table, false).evaluate_all().unwrap_err();
+    assert_eq!(err.to_string(), "Circular reference: A3 -> A3");
+}
+
+// === Dependency order tests ===
+
+#[test]
+fn test_circular_reference_reports_cycle() {
+    let table = make_table(vec![
+        vec!["=B1+1", "=C1", "=A1*2"],
+    ]);
+    let err = Calculator::new(&table, false).evaluate_all().unwrap_err();
+    assert_eq!(err.to_string(), "Circular reference: A1 -> B1 -> C1 -> A1");
+}
+
+#[test]
+fn test_dependency_chain_evaluates_in_order() {
+    // Each formula depends on the one to its right, ending at a plain value
+    let table = make_table(vec![
+        vec!["=B1*2", "=C1+1", "=D1-3", "=E1^2", "4"],
+    ]);
+    assert_eq!(calc_value(&table, 0, 3), "16");
+    assert_eq!(calc_value(&table, 0, 2), "13");
+    assert_eq!(calc_value(&table, 0, 1), "14");
+    assert_eq!(calc_value(&table, 0, 0), "28");
+}
+
+// === Incremental evaluation tests ===
+
+/// Values re-evaluated after `row`, `col` changed, from a freshly built cache
+fn dependents_of(table: &Table, row: usize, col: usize) -> Result<Vec<(usize, usize, String)>, CalcError> {
+    let calc = Calculator::new(table, false);
+    let mut cache = calc.build_cache()?;
+    calc.update_cache(&mut cache, row, col)?;
+    let mut updates = Vec::new();
+    for r in 0..table.row_count() {
+        for c in 0..table.col_count() {
+            if let Some(value) = cache.value(r, c) {
+                updates.push((r, c, value.to_string()));
+            }
+        }
+    }
+    Ok(updates)
+}
+
+#[test]
+fn test_dependents_of_only_updates_downstream() {
+    let table = make_table(vec![
+        vec!["1", "=A1*2", "=B1+1"],
+        vec!["5", "=A2*2", ""],
+    ]);
+    assert_eq!(dependents_of(&table, 0, 0).unwrap(), vec![
+        (0, 1, "2".to_string()),
+        (0, 2, "3".to_string()),
+    ]);
+}
+
+#[test]
+fn test_dependents_of_matches_evaluate_all() {
+    // C2 reads B1, which is not downstream of A2 but still has to be evaluated
+    let table = make_table(vec![
+        vec!["1", "=A1+10", ""],
+        vec!["2", "=A2*3", "=B1+B2"],
+    ]);
+    let all = Calculator::new(&table, false).evaluate_all().unwrap();
+    let updates = dependents_of(&table, 1, 0).unwrap();
+    assert_eq!(updates, vec![
+        (1, 1, "6".to_string()),
+        (1, 2, "17".to_string()),
+    ]);
+    for update in &updates {
+        assert!(all.contains(update));
+    }
+}
+
+#[test]
+fn test_dependents_of_unreferenced_cell_is_empty() {
+    let table = make_table(vec![
+        vec!["1", "=A1*2"],
+        vec!["5", ""],
+    ]);
+    assert!(dependents_of(&table, 1, 0).unwrap().is_empty());
+}
+
+#[test]
+fn test_dependents_of_edited_formula_includes_itself() {
+    let table = make_table(vec![
+        vec!["4", "=A1+1", "=B1*2"],
+    ]);
+    assert_eq!(dependents_of(&table, 0, 1).unwrap(), vec![
+        (0, 1, "5".to_string()),
+        (0, 2, "10".to_string()),
+    ]);
+}
+
+#[test]
+fn test_dependents_of_reports_cycle() {
+    let table = make_table(vec![
+        vec!["1", "=A1+C1", "=B1"],
+    ]);
+    let err = dependents_of(&table, 0, 0).unwrap_err();
+    assert_eq!(err.to_string(), "Circular reference: B1 -> C1 -> B1");
+}
+
+#[test]
+fn test_update_cache_follows_edited_formula() {
+    let mut table = make_table(vec![
+        vec!["1", "=A1*2", "=B1+1"],
+        vec!["5", "", ""],
+    ]);
+    let mut cache = Calculator::new(&table, false).build_cache().unwrap();
+
+    table.set_cell(0, 0, "3".to_string());
+    assert_eq!(Calculator::new(&table, false).update_cache(&mut cache, 0, 0).unwrap(), 2);
+    assert_eq!(cache.value(0, 1), Some("6"));
+    assert_eq!(cache.value(0, 2), Some("7"));
+
+    // B1 now reads A2, so A1 no longer has dependents
+    table.set_cell(0, 1, "=A2*2".to_string());
+    Calculator::new(&table, false).update_cache(&mut cache, 0, 1).unwrap();
+    assert_eq!(cache.value(0, 1), Some("10"));
+    assert_eq!(cache.value(0, 2), Some("11"));
+    table.set_cell(0, 0, "4".to_string());
+    assert_eq!(Calculator::new(&table, false).update_cache(&mut cache, 0, 0).unwrap(), 0);
+
+    // Overwriting a formula with text drops its value
+    table.set_cell(0, 2, "done".to_string());
+    Calculator::new(&table, false).update_cache(&mut cache, 0, 2).unwrap();
+    assert_eq!(cache.value(0, 2), None);
+}
+
+#[test]
+fn bump_number_plain_integers() {
+    assert_eq!(bump_number("41", 1).as_deref(), Some("42"));
+    assert_eq!(bump_number("10", -3).as_deref(), Some("7"));
+    assert_eq!(bump_number("007", 1).as_deref(), Some("008"));
+    assert_eq!(bump_number("099", 1).as_deref(), Some("100"));
+}
+
+#[test]
+fn bump_number_negatives() {
+    assert_eq!(bump_number("-5", 2).as_deref(), Some("-3"));
+    assert_eq!(bump_number("-1", 3).as_deref(), Some("2"));
+    assert_eq!(bump_number("2", -5).as_deref(), Some("-3"));
+    // A dash after a word is not a sign
+    assert_eq!(bump_number("item-3", 1).as_deref(), Some("item-4"));
+    assert_eq!(bump_number("x = -3 units", -1).as_deref(), Some("x = -4 units"));
+}
+
+#[test]
+fn bump_number_non_numeric_is_none() {
+    assert_eq!(bump_number("", 1), None);
+    assert_eq!(bump_number("abc", 1), None);
+    assert_eq!(bump_number("99999999999999999999999999999999999999999", 1), None);
+}
+
+#[test]
+fn test_expand_row_template() {
+    assert_eq!(expand_row_template("=A{row}*B{row}", 0), "=A1*B1");
+    assert_eq!(expand_row_template("=SUM(A1:A{row})", 9), "=SUM(A1:A10)");
+    assert_eq!(expand_row_template("=A1", 4), "=A1");
+}
+
+#[test]
+fn test_parse_date_formats() {
+    assert_eq!(parse_date("1970-01-01"), Some(0));
+    assert_eq!(parse_date("2000-03-01"), Some(11_017));
+    assert_eq!(parse_date("2023-1-5"), parse_date("2023-01-05"));
+    assert_eq!(parse_date("01/05/2023"), parse_date("2023-01-05"));
+    assert_eq!(parse_date("05.01.2023"), parse_date("2023-01-05"));
+    assert_eq!(parse_date("1969-12-31"), Some(-1));
+    assert_eq!(parse_date(" 2024-02-29 "), Some(19_782));
+}
+
+#[test]
+fn test_parse_date_rejects_non_dates() {
+    assert_eq!(parse_date("2023-02-29"), None);
+    assert_eq!(parse_date("2023-13-01"), None);
+    assert_eq!(parse_date("2023-00-10"), None);
+    assert_eq!(parse_date("23-01-05"), None);
+    assert_eq!(parse_date("2023-01"), None);
+    assert_eq!(parse_date("1.5"), None);
+    assert_eq!(parse_date("-5"), None);
+    assert_eq!(parse_date("2023-01-05x"), None);
+}
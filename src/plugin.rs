@@ -1,4 +1,4 @@
-use mlua::{Lua, Result as LuaResult, Function, Value};
+use mlua::{AnyUserData, Lua, Result as LuaResult, Function, UserData, UserDataMethods, Value};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
@@ -21,6 +21,8 @@ pub struct PluginManager {
     commands: HashMap<String, String>, // command_name -> script content
     functions: HashMap<String, String>, // function_name -> script content (uppercase keys)
     prompt_results: HashMap<String, String>, // question -> answer for deferred prompts
+    load_hooks: Vec<String>, // command plugins defining on_load, in load order
+    save_hooks: Vec<String>, // command plugins defining on_save, in load order
 }
 
 pub struct PluginContext {
@@ -29,6 +31,7 @@ pub struct PluginContext {
     pub row_count: usize,
     pub col_count: usize,
     pub selection: SelectionInfo,
+    pub read_only: bool,
 }
 
 #[derive(Clone)]
@@ -54,6 +57,53 @@ pub enum PluginAction {
     PromptRequest { question: String, default: String },
 }
 
+/// A plugin run waiting for the user to answer a prompt; it runs again with the answer
+pub struct PluginPrompt {
+    pub plugin: String,
+    pub args: Vec<String>,
+    pub question: String,
+}
+
+/// The table handed to plugins as `tabular.table`; `set` queues a SetCell
+/// Reads and writes go through the same cell store as get_cell/set_cell, so no second copy is kept
+struct TableHandle {
+    store: mlua::Table,
+    row_count: usize,
+    col_count: usize,
+    read_only: bool,
+    actions: mlua::Table,  // The run's action list, so sets keep their place among other calls
+}
+
+impl UserData for TableHandle {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("rows", |_, this, ()| Ok(this.row_count));
+        methods.add_method("cols", |_, this, ()| Ok(this.col_count));
+
+        // get(row, col) - 1-indexed, empty string outside the table
+        methods.add_method("get", |_, this, (row, col): (usize, usize)| {
+            let key = format!("{}:{}", row.wrapping_sub(1), col.wrapping_sub(1));
+            Ok(this.store.get::<Option<String>>(key)?.unwrap_or_default())
+        });
+
+        // set(row, col, value) - 1-indexed, visible to later get calls
+        methods.add_method("set", |lua, this, (row, col, value): (usize, usize, String)| {
+            if this.read_only {
+                return Err(mlua::Error::RuntimeError("Table is read-only".to_string()));
+            }
+            if row == 0 || col == 0 || row > this.row_count || col > this.col_count {
+                return Err(mlua::Error::RuntimeError(format!("Cell ({}, {}) is outside the table", row, col)));
+            }
+            this.store.set(format!("{}:{}", row - 1, col - 1), value.as_str())?;
+            let action = lua.create_table()?;
+            action.set("type", "set_cell")?;
+            action.set("row", row)?;
+            action.set("col", col)?;
+            action.set("value", value)?;
+            this.actions.push(action)
+        });
+    }
+}
+
 pub struct PluginResult {
     pub actions: Vec<PluginAction>,
     pub message: Option<String>
@@ -67,17 +117,17 @@ impl PluginManager {
             commands: HashMap::new(),
             functions: HashMap::new(),
             prompt_results: HashMap::new(),
+            load_hooks: Vec::new(),
+            save_hooks: Vec::new(),
         }
     }
 
     /// Store a prompt result for use in the next plugin execution
-    #[allow(dead_code)]
     pub fn set_prompt_result(&mut self, question: &str, answer: String) {
         self.prompt_results.insert(question.to_string(), answer);
     }
 
     /// Clear all prompt results
-    #[allow(dead_code)]
     pub fn clear_prompt_results(&mut self) {
         self.prompt_results.clear();
     }
@@ -110,7 +160,7 @@ impl PluginManager {
         Ok(loaded)
     }
 
-    fn register_plugin(&mut self, script: &str) -> LuaResult<Option<String>> {
+    pub(crate) fn register_plugin(&mut self, script: &str) -> LuaResult<Option<String>> {
         let chunk = self.lua.load(script);
         let result: Value = chunk.eval().map_err(|e| {error!(error=%e, "Failed to load plugin"); e} )?;
 
@@ -134,6 +184,12 @@ impl PluginManager {
                     }
                     PluginType::Command => {
                         self.commands.insert(plugin_name.clone(), script.to_string());
+                        if table.get::<Function>("on_load").is_ok() {
+                            self.load_hooks.push(plugin_name.clone());
+                        }
+                        if table.get::<Function>("on_save").is_ok() {
+                            self.save_hooks.push(plugin_name.clone());
+                        }
                     }
                 }
                 return Ok(Some(plugin_name));
@@ -240,7 +296,7 @@ impl PluginManager {
                 args: &[String],
                 ctx: &PluginContext,
                 get_cell: impl Fn(usize, usize) -> Option<String>,
-            ) -> LuaResult<(mlua::Table, mlua::Table)> {
+            ) -> LuaResult<(mlua::Table, mlua::Table, AnyUserData)> {
         // Create the context table
         let ctx_table = self.lua.create_table()?;
         ctx_table.set("cursor_row", ctx.cursor_row + 1)?; // 1-indexed for Lua
@@ -278,11 +334,12 @@ impl PluginManager {
             for col in 0..ctx.col_count {
                 if let Some(val) = get_cell(row, col) {
                     let key = format!("{}:{}", row, col);
-                    cell_store.set(key, val)?;
+                    cell_store.set(key, val.as_str())?;
                 }
             }
         }
 
+
         // Create get_cell function (1-indexed from Lua)
         let store_for_get = cell_store.clone();
         let get_cell_fn = self.lua.create_function(move |_, (row, col): (usize, usize)| {
@@ -293,6 +350,15 @@ impl PluginManager {
         // Create actions table to collect results
         let actions_table = self.lua.create_table()?;
 
+        // Table handle for iterating; its sets are queued like set_cell calls
+        let table_handle = self.lua.create_userdata(TableHandle {
+            store: cell_store.clone(),
+            row_count: ctx.row_count,
+            col_count: ctx.col_count,
+            read_only: ctx.read_only,
+            actions: actions_table.clone(),
+        })?;
+
         // Create set_cell function that updates the shared cell store AND records the action
         let store_for_set = cell_store.clone();
         let actions_ref = actions_table.clone();
@@ -426,7 +492,7 @@ impl PluginManager {
             }
         })?;
 
-        // prompt(question, default) function - requests user input
+        // prompt(question, default) function - requests user input (also available as read_input)
         let prompt_results = self.prompt_results.clone();
         let prompt_actions_ref = actions_table.clone();
         let prompt_fn = self.lua.create_function(move |lua, (question, default): (String, Option<String>)| {
@@ -495,6 +561,7 @@ impl PluginManager {
         api.set("args", args_table)?;
         api.set("get_cell", get_cell_fn)?;
         api.set("set_cell", set_cell_fn)?;
+        api.set("table", table_handle.clone())?;
         api.set("insert_row", insert_row_fn)?;
         api.set("delete_row", delete_row_fn)?;
         api.set("insert_col", insert_col_fn)?;
@@ -504,13 +571,14 @@ impl PluginManager {
         api.set("get_selection", get_selection_fn)?;
         api.set("get_range", get_range_fn)?;
         api.set("get_column_type", get_column_type_fn)?;
+        api.set("read_input", prompt_fn.clone())?;
         api.set("prompt", prompt_fn)?;
         api.set("save_data", save_data_fn)?;
         api.set("load_data", load_data_fn)?;
 
         self.lua.globals().set("tabular", api)?;
 
-        Ok((actions_table, message_table))
+        Ok((actions_table, message_table, table_handle))
     }
 
     pub fn execute(
@@ -528,7 +596,7 @@ impl PluginManager {
             }),
         };
 
-        let (actions_table, message_table) = self.build_api(args, ctx, get_cell)?;
+        let (actions_table, message_table, _) = self.build_api(args, ctx, get_cell)?;
 
         // Load and execute the plugin
         let chunk = self.lua.load(script);
@@ -540,22 +608,86 @@ impl PluginManager {
             }
         }
 
-        // Collect actions from Lua
-        let mut actions = Vec::new();
-        for i in 1..=actions_table.len()? {
-            if let Ok(action) = actions_table.get::<mlua::Table>(i) {
+        collect_result(&actions_table, &message_table)
+    }
+
+    /// Call the on_load(table) hook of every plugin defining one, merging their actions
+    pub fn run_load_hooks(
+        &self,
+        ctx: &PluginContext,
+        get_cell: impl Fn(usize, usize) -> Option<String> + Copy,
+    ) -> LuaResult<PluginResult> {
+        let mut merged = PluginResult { actions: vec![], message: None };
+        for name in &self.load_hooks {
+            let (actions_table, message_table, table_handle) = self.build_api(&[], ctx, get_cell)?;
+            self.call_hook(name, "on_load", &table_handle)?;
+            let result = collect_result(&actions_table, &message_table)?;
+            merged.actions.extend(result.actions);
+            merged.message = result.message.or(merged.message);
+        }
+        Ok(merged)
+    }
 
-                if let Some(plugin_action) = match_action(action) {
-                    actions.push(plugin_action);
+    /// Call the on_save(table) hook of every plugin defining one with a read-only table
+    /// A hook rejects the save by returning false, a message or a list of messages
+    pub fn run_save_hooks(
+        &self,
+        ctx: &PluginContext,
+        get_cell: impl Fn(usize, usize) -> Option<String> + Copy,
+    ) -> Result<(), String> {
+        let mut errors = Vec::new();
+        for name in &self.save_hooks {
+            let verdict = self.build_api(&[], ctx, get_cell)
+                .and_then(|(_, _, table_handle)| self.call_hook(name, "on_save", &table_handle));
+            match verdict {
+                Ok(Value::Nil) | Ok(Value::Boolean(true)) => {}
+                Ok(Value::Boolean(false)) => errors.push(format!("rejected by {}", name)),
+                Ok(Value::Table(list)) => {
+                    errors.extend(list.sequence_values::<String>().flatten());
                 }
+                Ok(Value::String(msg)) => errors.push(msg.to_string_lossy()),
+                Ok(_) => errors.push(format!("rejected by {}", name)),
+                Err(e) => errors.push(format!("error in {}: {}", name, e)),
             }
         }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    fn call_hook(&self, name: &str, hook: &str, table_handle: &AnyUserData) -> LuaResult<Value> {
+        let Some(script) = self.commands.get(name) else { return Ok(Value::Nil); };
+        let plugin: Value = self.lua.load(script).eval()?;
+        match plugin {
+            Value::Table(table) => match table.get::<Function>(hook) {
+                Ok(hook_fn) => hook_fn.call::<Value>(table_handle.clone())
+                    .map_err(|e| {error!(error=%e, hook, "Error in Lua plugin hook"); e} ),
+                Err(_) => Ok(Value::Nil),
+            },
+            _ => Ok(Value::Nil),
+        }
+    }
+}
 
-        // Get message if set
-        let message: Option<String> = message_table.get("msg").ok();
+/// Gather the actions and message a plugin run queued
+fn collect_result(actions_table: &mlua::Table, message_table: &mlua::Table) -> LuaResult<PluginResult> {
+    // Collect actions from Lua
+    let mut actions = Vec::new();
+    for i in 1..=actions_table.len()? {
+        if let Ok(action) = actions_table.get::<mlua::Table>(i) {
 
-        Ok(PluginResult { actions, message })
+            if let Some(plugin_action) = match_action(action) {
+                actions.push(plugin_action);
+            }
+        }
     }
+
+    // Get message if set
+    let message: Option<String> = message_table.get("msg").ok();
+
+    Ok(PluginResult { actions, message })
 }
 
 fn match_action(action: mlua::Table) -> Option<PluginAction> {
@@ -79,3 +79,42 @@ pub fn get_word_end(s: &String, idx: usize) -> usize{
 
     char_count.saturating_sub(1)
 }
+
+/// Line and column (both in characters) of a character index in a multi-line string
+fn line_position(s: &str, idx: usize) -> (usize, usize) {
+    let before: Vec<char> = s.chars().take(idx).collect();
+    let line = before.iter().filter(|&&c| c == '\n').count();
+    let col = before.iter().rev().take_while(|&&c| c != '\n').count();
+    (line, col)
+}
+
+/// Character index at the given line and column, clamping the column to the line length
+fn index_at(s: &str, line: usize, col: usize) -> usize {
+    let mut idx = 0;
+    for (i, text) in s.split('\n').enumerate() {
+        let len = text.chars().count();
+        if i == line {
+            return idx + col.min(len);
+        }
+        idx += len + 1;
+    }
+    idx.saturating_sub(1)
+}
+
+/// Move a cursor to the same column on the previous line
+pub fn line_up(s: &str, idx: usize) -> usize {
+    match line_position(s, idx) {
+        (0, _) => idx,
+        (line, col) => index_at(s, line - 1, col),
+    }
+}
+
+/// Move a cursor to the same column on the next line
+pub fn line_down(s: &str, idx: usize) -> usize {
+    let (line, col) = line_position(s, idx);
+    if line + 1 >= s.split('\n').count() {
+        idx
+    } else {
+        index_at(s, line + 1, col)
+    }
+}
@@ -60,10 +60,6 @@ impl ColumnWidths {
         self.col_widths_dirty = false;
     } 
 
-    pub fn get_col_width(&mut self, col_idx: usize) -> usize {
-        self.col_widths[col_idx]
-    }
-
     pub fn col_widths(&mut self, table: &Table, parallel: bool) -> &[usize] {
         if self.col_widths_dirty {
             self.recompute(table, parallel);
@@ -81,6 +77,21 @@ impl ColumnWidths {
         }
     }
 
+    /// Width of one column, or None while a full recompute is pending
+    pub fn width_of(&self, col: usize) -> Option<usize> {
+        if self.col_widths_dirty {
+            return None;
+        }
+        self.col_widths.get(col).copied()
+    }
+
+    /// Overwrite one column's width (may shrink it)
+    pub fn set_col_width(&mut self, col: usize, width: usize) {
+        if let Some(w) = self.col_widths.get_mut(col) {
+            *w = cmp::min(width, self.max_col_width);
+        }
+    }
+
     pub fn insert_at(&mut self, idx: usize, col_size: usize) {
         if idx <= self.col_widths.len() {
             self.col_widths.insert(idx, col_size);
@@ -1,4 +1,9 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
+use regex::{Captures, Regex, RegexBuilder};
+use rand::seq::SliceRandom;
 use std::thread;
 
 use crate::table::table::Table;
@@ -6,16 +11,16 @@ use crate::viewstate::BackgroundResult;
 use crate::table::tableview::TableView;
 use crate::table::SortDirection;
 use crate::transaction::transaction::Transaction;
-use crate::util::ColumnType;
+use crate::util::{seeded_rng, ColumnType};
 use crate::viewstate::ViewState;
 use crate::mode::Mode;
-use crate::mode::command::{ReplaceCommand, ReplaceScope};
+use crate::mode::command::{AggKind, ReplaceCommand, ReplaceScope};
+use crate::numeric::format::{format_default, parse_date, parse_numeric};
 
 // === Cell Access ===
-/// Get current cell content
-pub fn current_cell<'a>(view: &TableView, table: &'a Table) -> &'a String {
+/// Get current cell content, or None if the cursor is outside the table
+pub fn current_cell<'a>(view: &TableView, table: &'a Table) -> Option<&'a String> {
     table.get_cell(view.cursor_row, view.cursor_col)
-        .expect("Cursor should be within bounds")
 }
 
 
@@ -26,6 +31,35 @@ pub fn current_cell<'a>(view: &TableView, table: &'a Table) -> &'a String {
 pub enum SortKey {
     Numeric(f64),
     Text(String),
+    Natural(Vec<NaturalChunk>),
+    Date(Option<i64>),  // Days since 1970; cells that aren't dates sort last
+}
+
+/// Piece of a natural sort key: a run of digits or a run of other characters
+/// Digit runs order before text, and by value among themselves
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NaturalChunk {
+    Number { digits: usize, value: String },  // Leading zeros stripped, compared by length first
+    Text(String),
+}
+
+/// Split a lowercased string into alternating text and digit chunks
+fn natural_chunks(cell: &str) -> Vec<NaturalChunk> {
+    let mut chunks = Vec::new();
+    let mut rest = cell;
+    while let Some(first) = rest.chars().next() {
+        let is_digit = first.is_ascii_digit();
+        let end = rest.find(|c: char| c.is_ascii_digit() != is_digit).unwrap_or(rest.len());
+        let (run, tail) = rest.split_at(end);
+        if is_digit {
+            let value = run.trim_start_matches('0').to_string();
+            chunks.push(NaturalChunk::Number { digits: value.len(), value });
+        } else {
+            chunks.push(NaturalChunk::Text(run.to_string()));
+        }
+        rest = tail;
+    }
+    chunks
 }
 
 impl Eq for SortKey {}
@@ -42,6 +76,8 @@ impl Ord for SortKey {
                 }
             }
             (SortKey::Text(a), SortKey::Text(b)) => a.cmp(b),
+            (SortKey::Natural(a), SortKey::Natural(b)) => a.cmp(b),
+            (SortKey::Date(a), SortKey::Date(b)) => (a.is_none(), a).cmp(&(b.is_none(), b)),
             _ => std::cmp::Ordering::Equal,
         }
     }
@@ -53,57 +89,98 @@ impl PartialOrd for SortKey {
     }
 }
 
-pub fn sort_by_column(sort_col: usize, skip_header: bool, table: &mut Table, view_state: &mut ViewState, direction: SortDirection) -> Option<Transaction> {
+impl SortKey {
+    /// Build the sort key for a cell given the probed type of its column
+    pub fn from_cell(cell: &str, sort_type: ColumnType) -> Self {
+        match sort_type {
+            ColumnType::Numeric => {
+                SortKey::Numeric(parse_numeric(cell.trim()).unwrap_or(f64::NAN))
+            }
+            ColumnType::Text => SortKey::Text(cell.trim().to_lowercase()),
+            ColumnType::Natural => SortKey::Natural(natural_chunks(&cell.trim().to_lowercase())),
+            ColumnType::Date => SortKey::Date(parse_date(cell)),
+        }
+    }
+}
+
+/// Compare two rows' sort keys in priority order
+/// Falls through to the next key only when the previous ones are equal
+pub fn compare_sort_keys(a: &[SortKey], b: &[SortKey], directions: &[SortDirection]) -> std::cmp::Ordering {
+    for ((key_a, key_b), direction) in a.iter().zip(b.iter()).zip(directions.iter()) {
+        let cmp = match direction {
+            SortDirection::Ascending => key_a.cmp(key_b),
+            SortDirection::Descending => key_a.cmp(key_b).reverse(),
+        };
+        if cmp != std::cmp::Ordering::Equal {
+            return cmp;
+        }
+    }
+    std::cmp::Ordering::Equal
+}
+
+/// Sort rows by several columns, earlier keys taking priority over later ones
+/// `as_type` requests a natural (text columns only) or date sort instead of the probed types
+pub fn sort_by_columns(keys: &[(usize, SortDirection)], skip_header: bool, as_type: Option<ColumnType>, table: &mut Table, view_state: &mut ViewState) -> Option<Transaction> {
     let row_count = table.row_count();
 
+    if keys.is_empty() {
+        return None;
+    }
+
     if row_count < 50_000 {
-        return sort_by_column_sync(sort_col, skip_header, table, direction);
+        return sort_by_columns_sync(keys, skip_header, as_type, table);
     }
 
-    let sort_type = table.probe_column_type(sort_col, skip_header);
-    let col_data: Vec<String> = (0..row_count)
-        .map(|row| {
-            table.get_cell(row, sort_col)
-                .cloned()
-                .unwrap_or_default()
+    let sort_types: Vec<ColumnType> = keys.iter()
+        .map(|&(col, _)| table.probe_sort_type(col, skip_header, as_type))
+        .collect();
+    let directions: Vec<SortDirection> = keys.iter().map(|&(_, dir)| dir).collect();
+    let col_data: Vec<Vec<String>> = keys.iter()
+        .map(|&(col, _)| {
+            (0..row_count)
+                .map(|row| {
+                    table.get_cell(row, col)
+                        .cloned()
+                        .unwrap_or_default()
+                })
+                .collect()
         })
         .collect();
 
     let progress = view_state.start_progress("Sorting", row_count);
     let (tx, rx) = mpsc::channel();
     view_state.bg_receiver = Some(rx);
+    let cancel = Arc::new(AtomicBool::new(false));
+    view_state.bg_cancel = Some(cancel.clone());
 
     let handle = thread::spawn(move || {
         let start_row = if skip_header { 1 } else { 0 };
-        let mut keyed: Vec<(usize, SortKey)> = Vec::with_capacity(row_count - start_row);
+        let mut keyed: Vec<(usize, Vec<SortKey>)> = Vec::with_capacity(row_count - start_row);
 
         for (i, row) in (start_row..row_count).enumerate() {
-            let key = match sort_type {
-                ColumnType::Numeric => {
-                    let val = crate::numeric::format::parse_numeric(col_data[row].trim())
-                        .unwrap_or(f64::NAN);
-                    SortKey::Numeric(val)
-                }
-                ColumnType::Text => {
-                    SortKey::Text(col_data[row].to_lowercase())
-                }
-            };
-            keyed.push((row, key));
+            let row_keys: Vec<SortKey> = col_data.iter()
+                .zip(sort_types.iter())
+                .map(|(data, &sort_type)| SortKey::from_cell(&data[row], sort_type))
+                .collect();
+            keyed.push((row, row_keys));
 
             if i % 10000 == 0 {
+                if cancel.load(Ordering::Relaxed) {
+                    return;
+                }
                 progress.set(i);
             }
         }
 
         progress.set(row_count / 2);
 
-        keyed.sort_unstable_by(|(idx_a, key_a), (idx_b, key_b)| {
-            let cmp = key_a.cmp(key_b);
-            match direction {
-                SortDirection::Ascending => cmp.then(idx_a.cmp(idx_b)),
-                SortDirection::Descending => cmp.reverse().then(idx_a.cmp(idx_b)),
-            }
+        keyed.sort_unstable_by(|(idx_a, keys_a), (idx_b, keys_b)| {
+            compare_sort_keys(keys_a, keys_b, &directions).then(idx_a.cmp(idx_b))
         });
+        // Cancelled while sorting: the result would be dropped anyway
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
 
         progress.set(row_count);
 
@@ -115,34 +192,24 @@ pub fn sort_by_column(sort_col: usize, skip_header: bool, table: &mut Table, vie
         permutation.extend(keyed.into_iter().map(|(row, _)| row));
 
         let already_sorted = permutation.iter().enumerate().all(|(i, &idx)| i == idx);
-        if already_sorted {
-            let _ = tx.send(BackgroundResult::SortComplete {
-                permutation: Vec::new(),
-                direction,
-                sort_type,
-                is_column_sort: false,
-            });
-        } else {
-            let _ = tx.send(BackgroundResult::SortComplete {
-                permutation,
-                direction,
-                sort_type,
-                is_column_sort: false,
-            });
-        }
+        let _ = tx.send(BackgroundResult::SortComplete {
+            permutation: if already_sorted { Vec::new() } else { permutation },
+            direction: directions[0],
+            sort_type: sort_types[0],
+            key_count: directions.len(),
+            is_column_sort: false,
+        });
     });
 
     view_state.bg_handle = Some(handle);
     None
 }
 
-fn sort_by_column_sync(sort_col: usize, skip_header: bool, table: &mut Table, direction: SortDirection) -> Option<Transaction> {
-    let permutation = match table.get_sort_permutation(sort_col, direction, skip_header) {
-        Some(p) => p,
-        None => {
-            return None;
-        }
-    };
+fn sort_by_columns_sync(keys: &[(usize, SortDirection)], skip_header: bool, as_type: Option<ColumnType>, table: &mut Table) -> Option<Transaction> {
+    let permutation = match keys {
+        [(sort_col, direction)] if as_type.is_none() => table.get_sort_permutation(*sort_col, *direction, skip_header),
+        _ => table.get_multi_sort_permutation(keys, skip_header, as_type),
+    }?;
 
     table.apply_row_permutation(&permutation);
     let txn = Transaction::PermuteRows { permutation };
@@ -165,46 +232,114 @@ pub fn sort_by_row(sort_row: usize, table: &mut Table, direction: SortDirection)
 }
 
 
-pub fn replace(cmd: ReplaceCommand, table: &mut Table, view: &mut TableView, calling_mode: Option<Mode>) -> (Option<Transaction>, Option<String>) {
+/// Row and column ranges covered by a visual selection, or None outside visual modes
+/// VisualRow spans every column and VisualCol spans every row
+fn visual_ranges(table: &Table, view: &TableView, calling_mode: Option<Mode>) -> Option<(std::ops::Range<usize>, std::ops::Range<usize>)> {
+    if !calling_mode.map_or(false, |x| x.is_visual()) {
+        return None;
+    }
+    let (start_row, end_row) = if calling_mode != Some(Mode::VisualCol) {
+        (std::cmp::min(view.cursor_row, view.support_row),
+            std::cmp::max(view.cursor_row, view.support_row))
+    } else {
+        (0, table.row_count()-1)
+    };
+    let (start_col, end_col) = if calling_mode != Some(Mode::VisualRow) {
+        (std::cmp::min(view.cursor_col, view.support_col),
+            std::cmp::max(view.cursor_col, view.support_col))
+    } else {
+        (0, table.col_count()-1)
+    };
+    Some((start_row..end_row + 1, start_col..end_col + 1))
+}
+
+/// Rows and columns a replace command covers; rows the filter hides are left out
+fn replace_ranges(cmd: &ReplaceCommand, table: &Table, view: &TableView, calling_mode: Option<Mode>) -> (Vec<usize>, std::ops::Range<usize>) {
     let (row_range, col_range) = match cmd.scope {
         ReplaceScope::All => {
             (0..table.row_count(), 0..table.col_count())
         }
         ReplaceScope::Selection => {
-            if calling_mode.map_or(false, |x| x.is_visual()) {
-                let (start_row, end_row) = if calling_mode != Some(Mode::VisualCol) {
-                    (std::cmp::min(view.cursor_row, view.support_row),
-                        std::cmp::max(view.cursor_row, view.support_row))
-                } else {
-                    (0, table.row_count()-1)
-                };
-                let (start_col, end_col) = if calling_mode != Some(Mode::VisualRow) {
-                    (std::cmp::min(view.cursor_col, view.support_col),
-                        std::cmp::max(view.cursor_col, view.support_col))
-                } else {
-                    (0, table.col_count()-1)
-                };
-                (start_row..end_row + 1, start_col..end_col + 1)
-            } else {
+            visual_ranges(table, view, calling_mode).unwrap_or(
                 (view.cursor_row..view.cursor_row+1, view.cursor_col..view.cursor_col+1)
-            }
+            )
         }
     };
+    let row_manager = view.row_manager.borrow();
+    (row_range.filter(|&row| row_manager.is_row_live(row)).collect(), col_range)
+}
+
+/// Compile the pattern of a replace command, None for plain literal matching
+/// The regex crate steps past empty matches, so patterns like "x*" terminate.
+/// Smart case needs a case-insensitive matcher even for literals
+fn compile_replace(cmd: &ReplaceCommand) -> Result<Option<Regex>, String> {
+    if !cmd.regex && !cmd.smart_case {
+        return Ok(None);
+    }
+    let source = if cmd.regex { cmd.pattern.clone() } else { regex::escape(&cmd.pattern) };
+    RegexBuilder::new(&source)
+        .case_insensitive(cmd.smart_case)
+        .build()
+        .map(Some)
+        .map_err(|e| format!("Invalid regex: {}", e))
+}
+
+/// Replacement text for one regex match: capture groups expanded for regex patterns,
+/// then given the match's case with /i
+fn expand_replacement(cmd: &ReplaceCommand, caps: &Captures) -> String {
+    let mut expanded = String::new();
+    if cmd.regex {
+        caps.expand(&cmd.replacement, &mut expanded);
+    } else {
+        expanded.push_str(&cmd.replacement);
+    }
+    if cmd.smart_case {
+        match_case(&caps[0], &expanded)
+    } else {
+        expanded
+    }
+}
+
+pub fn replace(cmd: ReplaceCommand, table: &mut Table, view: &mut TableView, calling_mode: Option<Mode>) -> (Option<Transaction>, Option<String>) {
+    let (rows, col_range) = replace_ranges(&cmd, table, view, calling_mode);
+
+    // Compile once up front
+    let regex = match compile_replace(&cmd) {
+        Ok(regex) => regex,
+        Err(e) => return (None, Some(e)),
+    };
+    let limit = if cmd.global { 0 } else { 1 };
 
     let mut replacements = 0;
     let mut txns: Vec<Transaction> = Vec::new();
     let mut found = false;
+    let mut match_count = 0;
 
-    for row in row_range.clone() {
+    for row in rows {
         for col in col_range.clone() {
-            if let Some(cell) = table.get_cell(row, col) {
+            if let Some(cell) = table.get_cell(row, col).filter(|_| cmd.count_only) {
+                // Dry run: count what the replace would change, stopping where it stops
+                found = true;
+                let per_cell = if cmd.global { usize::MAX } else { 1 };
+                let matches = match &regex {
+                    Some(re) => re.find_iter(cell).take(per_cell).count(),
+                    None => cell.matches(cmd.pattern.as_str()).take(per_cell).count(),
+                };
+                if matches > 0 {
+                    match_count += matches;
+                    replacements += 1;
+                }
+            } else if let Some(cell) = table.get_cell(row, col) {
                 found = true;
 
                 let old_value = cell.clone();
-                let new_value = if cmd.global {
-                    old_value.replace(&cmd.pattern, &cmd.replacement)
-                } else {
-                    old_value.replacen(&cmd.pattern, &cmd.replacement, 1)
+                let new_value = match (&regex, cmd.global) {
+                    (Some(re), _) if cmd.smart_case => re.replacen(&old_value, limit, |caps: &Captures| {
+                        expand_replacement(&cmd, caps)
+                    }).into_owned(),
+                    (Some(re), _) => re.replacen(&old_value, limit, cmd.replacement.as_str()).into_owned(),
+                    (None, true) => old_value.replace(&cmd.pattern, &cmd.replacement),
+                    (None, false) => old_value.replacen(&cmd.pattern, &cmd.replacement, 1),
                 };
 
                 if new_value != old_value {
@@ -227,6 +362,9 @@ pub fn replace(cmd: ReplaceCommand, table: &mut Table, view: &mut TableView, cal
         }
     }
 
+    if cmd.count_only {
+        return (None, Some(format!("{} matches across {} cells", match_count, replacements)));
+    }
     if txns.is_empty() {
         (None, Some(format!("Pattern not found: {}", cmd.pattern)))
 
@@ -234,3 +372,478 @@ pub fn replace(cmd: ReplaceCommand, table: &mut Table, view: &mut TableView, cal
         (Some(Transaction::Batch(txns)), Some(format!("{} replacement(s) made", replacements)))
     }
 }
+
+/// One match of a confirmed replace (:s///c) and the text that would replace it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplaceMatch {
+    pub row: usize,
+    pub col: usize,
+    pub range: std::ops::Range<usize>,  // Byte range of the match in the cell as it was
+    pub found: String,
+    pub replacement: String,
+}
+
+/// Find the matches a replace command would change, in reading order, skipping rows the
+/// filter hides; without /g only the first match of the first cell, as replace does
+pub fn find_replace_matches(cmd: &ReplaceCommand, table: &Table, view: &TableView, calling_mode: Option<Mode>) -> Result<Vec<ReplaceMatch>, String> {
+    let (rows, col_range) = replace_ranges(cmd, table, view, calling_mode);
+    let regex = compile_replace(cmd)?;
+    let per_cell = if cmd.global { usize::MAX } else { 1 };
+
+    let mut matches = Vec::new();
+    for row in rows {
+        for col in col_range.clone() {
+            let Some(cell) = table.get_cell(row, col) else { continue; };
+            let found: Vec<(std::ops::Range<usize>, String)> = match &regex {
+                Some(re) => re.captures_iter(cell)
+                    .take(per_cell)
+                    .map(|caps| (caps.get(0).unwrap().range(), expand_replacement(cmd, &caps)))
+                    .collect(),
+                None => cell.match_indices(cmd.pattern.as_str())
+                    .take(per_cell)
+                    .map(|(start, text)| (start..start + text.len(), cmd.replacement.clone()))
+                    .collect(),
+            };
+            matches.extend(found.into_iter()
+                .filter(|(range, replacement)| cell[range.clone()] != *replacement)
+                .map(|(range, replacement)| ReplaceMatch {
+                    row,
+                    col,
+                    found: cell[range.clone()].to_string(),
+                    range,
+                    replacement,
+                }));
+            if !cmd.global {
+                return Ok(matches);
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// Progress through the matches of a confirmed replace, one y/n answer at a time
+pub struct ReplaceConfirm {
+    matches: Vec<ReplaceMatch>,
+    index: usize,
+    accepted: Vec<usize>,
+}
+
+impl ReplaceConfirm {
+    pub fn new(matches: Vec<ReplaceMatch>) -> Self {
+        Self { matches, index: 0, accepted: Vec::new() }
+    }
+
+    /// The match waiting for an answer, None once every match was answered
+    pub fn current(&self) -> Option<&ReplaceMatch> {
+        self.matches.get(self.index)
+    }
+
+    /// Answer the current match and move to the next
+    pub fn answer(&mut self, replace: bool) {
+        if replace && self.index < self.matches.len() {
+            self.accepted.push(self.index);
+        }
+        self.index += 1;
+    }
+
+    /// Accept the current match and every one after it
+    pub fn accept_rest(&mut self) {
+        self.accepted.extend(self.index..self.matches.len());
+        self.index = self.matches.len();
+    }
+
+    /// Leave the remaining matches alone
+    pub fn stop(&mut self) {
+        self.index = self.matches.len();
+    }
+
+    /// Number of matches accepted so far
+    pub fn accepted_count(&self) -> usize {
+        self.accepted.len()
+    }
+
+    /// One Batch with a SetCell for each cell that has accepted matches
+    pub fn into_transaction(self, table: &Table) -> Option<Transaction> {
+        let mut txns: Vec<Transaction> = Vec::new();
+        let mut accepted = self.accepted.into_iter().map(|i| &self.matches[i]).peekable();
+        while let Some(first) = accepted.next() {
+            let (row, col) = (first.row, first.col);
+            let Some(old_value) = table.get_cell(row, col).cloned() else { continue; };
+            let mut new_value = String::new();
+            let mut last = 0;
+            let mut current = Some(first);
+            while let Some(m) = current {
+                new_value.push_str(&old_value[last..m.range.start]);
+                new_value.push_str(&m.replacement);
+                last = m.range.end;
+                current = accepted.next_if(|next| (next.row, next.col) == (row, col));
+            }
+            new_value.push_str(&old_value[last..]);
+            txns.push(Transaction::SetCell { row, col, old_value, new_value });
+        }
+        (!txns.is_empty()).then_some(Transaction::Batch(txns))
+    }
+}
+
+/// Give a replacement the case pattern of the text it replaces: all caps, Titlecase or
+/// lowercase; a mixed-case match leaves the replacement as written
+fn match_case(matched: &str, replacement: &str) -> String {
+    let letters: Vec<char> = matched.chars().filter(|c| c.is_alphabetic()).collect();
+    let upper = letters.iter().filter(|c| c.is_uppercase()).count();
+    if letters.len() > 1 && upper == letters.len() {
+        replacement.to_uppercase()
+    } else if upper == 0 {
+        replacement.to_lowercase()
+    } else if upper == 1 && letters[0].is_uppercase() {
+        let mut chars = replacement.chars();
+        chars.next()
+            .map(|first| first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect())
+            .unwrap_or_default()
+    } else {
+        replacement.to_string()
+    }
+}
+
+/// Rows the filter shows within start..=end
+pub fn live_rows(view: &TableView, start: usize, end: usize) -> Vec<usize> {
+    let row_manager = view.row_manager.borrow();
+    (start..=end).filter(|&row| row_manager.is_row_live(row)).collect()
+}
+
+/// Build a transaction deleting the given rows (ascending, e.g. the ones a filter shows)
+/// Returns it with the deleted rows for the register; None if there are no rows
+pub fn delete_rows(table: &Table, rows: &[usize]) -> Option<(Transaction, Vec<Vec<String>>)> {
+    let data: Vec<Vec<String>> = rows.iter().filter_map(|&row| table.get_row_cloned(row)).collect();
+    if data.is_empty() {
+        return None;
+    }
+    // Last row first, so the earlier indices are still valid when their turn comes
+    let txns = rows.iter().zip(data.iter()).rev()
+        .map(|(&idx, row)| Transaction::DeleteRow { idx, data: row.clone() })
+        .collect();
+    Some((Transaction::Batch(txns), data))
+}
+
+/// Build a transaction clearing columns start_col..=end_col of the given rows, leaving
+/// the rows in between alone; returns it with the old values for the register
+pub fn clear_rows(table: &Table, rows: &[usize], start_col: usize, end_col: usize) -> (Transaction, Vec<Vec<String>>) {
+    let old_data: Vec<Vec<String>> = rows.iter()
+        .map(|&row| (start_col..=end_col).map(|col| table.get_cell(row, col).cloned().unwrap_or_default()).collect())
+        .collect();
+    let txns = rows.iter().zip(old_data.iter())
+        .flat_map(|(&row, values)| {
+            values.iter().enumerate()
+                .filter(|(_, value)| !value.is_empty())
+                .map(move |(i, value)| Transaction::SetCell {
+                    row,
+                    col: start_col + i,
+                    old_value: value.clone(),
+                    new_value: String::new(),
+                })
+        })
+        .collect();
+    (Transaction::Batch(txns), old_data)
+}
+
+/// Build a transaction inserting `times` copies of the current row (or the rows of the
+/// visual selection) directly below it; returns the index of the first copy
+pub fn duplicate_rows(times: usize, table: &Table, view: &TableView, calling_mode: Option<Mode>) -> Option<(Transaction, usize)> {
+    let rows = match visual_ranges(table, view, calling_mode) {
+        Some((row_range, _)) if calling_mode != Some(Mode::VisualCol) => row_range,
+        _ => view.cursor_row..view.cursor_row + 1,
+    };
+    let source = table.get_rows_cloned(rows.start, rows.len());
+    if source.is_empty() || times == 0 {
+        return None;
+    }
+
+    let data: Vec<Vec<String>> = std::iter::repeat_n(&source, times).flatten().cloned().collect();
+    let idx = rows.start + source.len();
+    Some((Transaction::InsertRowsWithDataBulk { idx, data }, idx))
+}
+
+/// Build a permutation moving rows start..=end one place down (or up), swapping the
+/// block with its neighbour; None when the block is already at that edge of the table
+/// With skip_header the header row never moves: blocks including it or moving up into it are refused
+pub fn move_rows(table: &Table, start: usize, end: usize, down: bool, skip_header: bool) -> Option<Transaction> {
+    if skip_header && (start == 0 || (!down && start == 1)) {
+        return None;
+    }
+    block_shift(table.row_count(), start, end, down).map(|permutation| Transaction::PermuteRows { permutation })
+}
+
+/// Build a permutation moving columns start..=end one place right (or left); widths and
+/// declared types travel with their columns
+pub fn move_cols(table: &Table, start: usize, end: usize, right: bool) -> Option<Transaction> {
+    block_shift(table.col_count(), start, end, right).map(|permutation| Transaction::PermuteCols { permutation })
+}
+
+/// Build a permutation reversing the data rows, leaving the header row in place
+/// None when there are fewer than two rows to reverse
+pub fn reverse_rows(table: &Table, skip_header: bool) -> Option<Transaction> {
+    let start = if skip_header { 1 } else { 0 };
+    let row_count = table.row_count();
+    if row_count < start + 2 {
+        return None;
+    }
+    let permutation: Vec<usize> = (0..start).chain((start..row_count).rev()).collect();
+    Some(Transaction::PermuteRows { permutation })
+}
+
+/// Build a random permutation of the data rows, leaving the header row in place
+/// The same seed always gives the same order; without one the RNG is seeded from entropy
+pub fn shuffle_rows(table: &Table, skip_header: bool, seed: Option<u64>) -> Option<Transaction> {
+    let start = if skip_header { 1 } else { 0 };
+    let row_count = table.row_count();
+    if row_count < start + 2 {
+        return None;
+    }
+    let mut permutation: Vec<usize> = (0..row_count).collect();
+    permutation[start..].shuffle(&mut seeded_rng(seed));
+    Some(Transaction::PermuteRows { permutation })
+}
+
+/// Build a permutation reversing the columns, or None with fewer than two
+pub fn reverse_cols(table: &Table) -> Option<Transaction> {
+    let col_count = table.col_count();
+    if col_count < 2 {
+        return None;
+    }
+    Some(Transaction::PermuteCols { permutation: (0..col_count).rev().collect() })
+}
+
+/// Identity permutation of len entries with start..=end rotated one place forward or back
+fn block_shift(len: usize, start: usize, end: usize, forward: bool) -> Option<Vec<usize>> {
+    if end >= len || (forward && end + 1 == len) || (!forward && start == 0) {
+        return None;
+    }
+    let mut permutation: Vec<usize> = (0..len).collect();
+    if forward {
+        permutation[start..=end + 1].rotate_right(1);
+    } else {
+        permutation[start - 1..=end].rotate_left(1);
+    }
+    Some(permutation)
+}
+
+/// Aggregate the numeric cells of the visual selection, or of the cursor's column otherwise
+/// Hidden (filtered) rows and the header row are skipped, as are non-numeric cells
+pub fn aggregate(kind: AggKind, table: &Table, view: &TableView, calling_mode: Option<Mode>, header_mode: bool) -> String {
+    let row_manager = view.row_manager.borrow();
+    let is_counted = |row: usize| row_manager.is_row_live(row) && !(header_mode && row == 0);
+
+    let mut values: Vec<f64> = Vec::new();
+    let mut non_empty = 0;
+    let mut visit = |cell: &str| {
+        if cell.trim().is_empty() {
+            return;
+        }
+        non_empty += 1;
+        if let Some(n) = parse_numeric(cell) {
+            values.push(n);
+        }
+    };
+
+    match visual_ranges(table, view, calling_mode) {
+        Some((row_range, col_range)) => {
+            for row in row_range.filter(|&r| is_counted(r)) {
+                for col in col_range.clone() {
+                    visit(table.get_cell(row, col).map(|s| s.as_str()).unwrap_or(""));
+                }
+            }
+        }
+        None => {
+            for (row, cell) in table.col_iter(view.cursor_col).enumerate() {
+                if is_counted(row) {
+                    visit(cell);
+                }
+            }
+        }
+    }
+
+    if values.is_empty() {
+        return format!("{}: no numeric cells ({} skipped)", kind.name(), non_empty);
+    }
+
+    let result = match kind {
+        AggKind::Sum => values.iter().sum(),
+        AggKind::Avg => values.iter().sum::<f64>() / values.len() as f64,
+        AggKind::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+        AggKind::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+    };
+
+    format!("{}={} ({}/{} numeric)", kind.name(), format_default(&result.to_string()).unwrap_or_default(), values.len(), non_empty)
+}
+
+/// Summary statistics of a column, like pandas' describe(), as (label, value) lines
+/// Hidden (filtered) rows and the header row are skipped; numeric columns get min, max,
+/// mean, median and sample standard deviation, other columns their distinct and most common values
+pub fn describe_column(table: &Table, col: usize, view: &TableView, header_mode: bool) -> Vec<(&'static str, String)> {
+    let row_manager = view.row_manager.borrow();
+    let cells: Vec<&str> = table.col_iter(col)
+        .enumerate()
+        .filter(|&(row, _)| row_manager.is_row_live(row) && !(header_mode && row == 0))
+        .map(|(_, cell)| cell)
+        .collect();
+    let filled: Vec<&str> = cells.iter().copied().filter(|cell| !cell.trim().is_empty()).collect();
+    let mut values: Vec<f64> = filled.iter().filter_map(|cell| parse_numeric(cell)).collect();
+    let number = |n: f64| format_default(&n.to_string()).unwrap_or_default();
+
+    let mut lines = vec![
+        ("count", filled.len().to_string()),
+        ("numeric", values.len().to_string()),
+        ("blank", (cells.len() - filled.len()).to_string()),
+    ];
+
+    if table.column_type(col, header_mode) == ColumnType::Numeric && !values.is_empty() {
+        values.sort_by(|a, b| a.total_cmp(b));
+        let n = values.len();
+        let mean = values.iter().sum::<f64>() / n as f64;
+        let median = if n.is_multiple_of(2) { (values[n / 2 - 1] + values[n / 2]) / 2.0 } else { values[n / 2] };
+        lines.push(("min", number(values[0])));
+        lines.push(("max", number(values[n - 1])));
+        lines.push(("mean", number(mean)));
+        lines.push(("median", number(median)));
+        if n > 1 {
+            let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+            lines.push(("std", number(variance.sqrt())));
+        }
+    } else {
+        let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+        for (i, cell) in filled.iter().enumerate() {
+            counts.entry(cell).or_insert((0, i)).0 += 1;
+        }
+        lines.push(("distinct", counts.len().to_string()));
+        // Ties go to the value seen first
+        if let Some((value, (freq, _))) = counts.into_iter().max_by_key(|&(_, (freq, first))| (freq, std::cmp::Reverse(first))) {
+            lines.push(("top", value.to_string()));
+            lines.push(("freq", freq.to_string()));
+        }
+    }
+    lines
+}
+
+/// One group of :groupby: the key value, how many rows have it and, when asked for, the
+/// sum of another column over those rows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub value: String,
+    pub count: usize,
+    pub sum: Option<f64>,
+}
+
+/// Tally the live data rows per distinct value of col, most frequent first (ties keep the
+/// order values first appear in); empty cells form a "(blank)" group. With sum_col, its
+/// numeric cells are added up per group and other cells are ignored
+pub fn group_by(table: &Table, col: usize, sum_col: Option<usize>, view: &TableView, header_mode: bool) -> Vec<Group> {
+    let row_manager = view.row_manager.borrow();
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut groups: Vec<Group> = Vec::new();
+
+    for (row, cell) in table.col_iter(col).enumerate() {
+        if !row_manager.is_row_live(row) || (header_mode && row == 0) {
+            continue;
+        }
+        let key = if cell.trim().is_empty() { "(blank)" } else { cell };
+        let i = *index.entry(key).or_insert_with(|| {
+            groups.push(Group { value: key.to_string(), count: 0, sum: sum_col.map(|_| 0.0) });
+            groups.len() - 1
+        });
+        let group = &mut groups[i];
+        group.count += 1;
+        if let (Some(sum), Some(sum_col)) = (group.sum.as_mut(), sum_col) {
+            *sum += table.get_cell(row, sum_col).and_then(|c| parse_numeric(c)).unwrap_or(0.0);
+        }
+    }
+
+    // Stable, so equal counts stay in order of appearance
+    groups.sort_by_key(|g| std::cmp::Reverse(g.count));
+    groups
+}
+
+/// Build a transaction removing rows whose key columns repeat an earlier row's
+/// Kept rows move to the top in their original order and the duplicates are deleted from
+/// the end; empty key cells are compared like any other value
+/// Returns None when there is nothing to remove
+pub fn dedup_rows(table: &Table, keys: &[usize], skip_header: bool) -> Option<Transaction> {
+    let start = if skip_header { 1 } else { 0 };
+    let mut seen: HashSet<Vec<&str>> = HashSet::new();
+    let mut kept: Vec<usize> = (0..start).collect();
+    let mut dups: Vec<usize> = Vec::new();
+
+    for row in start..table.row_count() {
+        let key: Vec<&str> = keys.iter()
+            .map(|&col| table.get_cell(row, col).map(|s| s.as_str()).unwrap_or(""))
+            .collect();
+        if seen.insert(key) {
+            kept.push(row);
+        } else {
+            dups.push(row);
+        }
+    }
+
+    if dups.is_empty() {
+        return None;
+    }
+
+    let keep_count = kept.len();
+    let data: Vec<Vec<String>> = dups.iter()
+        .map(|&row| table.get_row_cloned(row).unwrap_or_default())
+        .collect();
+    kept.extend(dups);
+    Some(Transaction::Batch(vec![
+        Transaction::PermuteRows { permutation: kept },
+        Transaction::DeleteRowsBulk { idx: keep_count, data },
+    ]))
+}
+
+/// Build a transaction joining columns start..=end into the first of them, row by row,
+/// then deleting the others; with skip_empty, empty cells add no separator
+pub fn merge_cols(table: &Table, start_col: usize, end_col: usize, separator: &str, skip_empty: bool) -> Transaction {
+    let mut txns = Vec::new();
+    for row in 0..table.row_count() {
+        let parts: Vec<&str> = (start_col..=end_col)
+            .map(|col| table.get_cell(row, col).map(|s| s.as_str()).unwrap_or(""))
+            .filter(|cell| !skip_empty || !cell.is_empty())
+            .collect();
+        let new_value = parts.join(separator);
+        let old_value = table.get_cell(row, start_col).cloned().unwrap_or_default();
+        if new_value != old_value {
+            txns.push(Transaction::SetCell { row, col: start_col, old_value, new_value });
+        }
+    }
+    // Delete right to left so the remaining indices stay valid
+    for col in (start_col + 1..=end_col).rev() {
+        let data = table.get_col_cloned(col).unwrap_or_default();
+        txns.push(Transaction::DeleteCol { idx: col, data });
+    }
+    Transaction::Batch(txns)
+}
+
+/// Build a transaction inserting a leftmost column numbering the rows from `start`
+/// With a header row, the header cell gets `name` and numbering starts on the next row
+pub fn index_column(table: &Table, start: usize, name: &str, header_mode: bool) -> Transaction {
+    let rows = table.row_count();
+    let data: Vec<String> = if header_mode && rows > 0 {
+        std::iter::once(name.to_string())
+            .chain((start..).take(rows - 1).map(|n| n.to_string()))
+            .collect()
+    } else {
+        (start..).take(rows).map(|n| n.to_string()).collect()
+    };
+    Transaction::InsertColWithData { idx: 0, data }
+}
+
+/// Whether two columns hold different text in a row
+pub fn cells_differ(table: &Table, row: usize, a: usize, b: usize) -> bool {
+    let cell = |col| table.get_cell(row, col).map(|s| s.as_str()).unwrap_or("");
+    cell(a) != cell(b)
+}
+
+/// Count the rows where columns a and b differ, skipping the header row
+pub fn count_mismatches(table: &Table, a: usize, b: usize, skip_header: bool) -> usize {
+    let start = if skip_header { 1 } else { 0 };
+    (start..table.row_count())
+        .filter(|&row| cells_differ(table, row, a, b))
+        .count()
+}
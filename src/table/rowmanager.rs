@@ -1,13 +1,23 @@
 use std::collections::HashSet;
+use regex::Regex;
 
 use crate::table::table::Table;
 use crate::numeric::predicate::Predicate;
-use crate::util::{letters_from_col, ColumnType};
+use crate::util::{letters_from_col, seeded_rng, ColumnType};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum FilterType {
     Default,
-    PredicateFilter(Predicate)
+    PredicateFilter(Predicate),
+    Invert,  // Show exactly the rows the current filter hides
+    Sample { size: SampleSize, seed: Option<u64> },  // Show a random subset of the visible rows
+}
+
+/// How many rows :sample keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleSize {
+    Rows(usize),
+    Percent(usize),
 }
 
 /// Snapshot of filter state for undo/redo
@@ -106,7 +116,17 @@ impl RowManager {
             Box::new(0usize..table.row_count())
         };
 
-        self.active_rows = idxs.filter(|&i| predicate.evaluate(table.get_cell(i, col).unwrap(), col_type)).collect();
+        // Compile a regex predicate once rather than for every cell
+        let regex = match &predicate {
+            Predicate::Regex(pattern) => Regex::new(pattern).ok(),
+            _ => None,
+        };
+        let matches = |cell: &str| match &regex {
+            Some(re) => re.is_match(cell),
+            None => predicate.evaluate(cell, col_type),
+        };
+
+        self.active_rows = idxs.filter(|&i| matches(table.get_cell(i, col).unwrap())).collect();
 
         if keep_header && self.active_rows.first() != Some(&0usize) {
             self.active_rows.insert(0, 0usize);
@@ -118,6 +138,68 @@ impl RowManager {
         self.filter_string = format!("Filtered ({} {})", col_letter, predicate.to_string());
     }
 
+    /// Keep only the currently visible rows within start..=end, plus the header
+    pub fn filter_to_range(&mut self, start: usize, end: usize, keep_header: bool) {
+        let mut active_rows: Vec<usize> = (start..=end).filter(|&row| self.is_row_live(row)).collect();
+
+        if keep_header && active_rows.first() != Some(&0usize) {
+            active_rows.insert(0, 0usize);
+        }
+
+        self.active_row_set = active_rows.iter().cloned().collect();
+        self.active_rows = active_rows;
+        self.is_filtered = true;
+        self.filter_string = "Filtered (manual selection)".to_string();
+    }
+
+    /// Keep a random subset of the currently visible data rows, plus the header
+    /// Returns how many data rows were kept out of how many
+    pub fn sample(&mut self, table: &Table, size: SampleSize, seed: Option<u64>, keep_header: bool) -> (usize, usize) {
+        let start = if keep_header { 1 } else { 0 };
+        let live: Vec<usize> = (start..table.row_count()).filter(|&row| self.is_row_live(row)).collect();
+        let count = match size {
+            SampleSize::Rows(n) => n,
+            SampleSize::Percent(pct) => (live.len() * pct + 50) / 100,
+        }.min(live.len());
+
+        let mut active_rows: Vec<usize> = rand::seq::index::sample(&mut seeded_rng(seed), live.len(), count)
+            .into_iter()
+            .map(|i| live[i])
+            .collect();
+        active_rows.sort_unstable();
+        if keep_header {
+            active_rows.insert(0, 0usize);
+        }
+
+        self.active_row_set = active_rows.iter().cloned().collect();
+        self.active_rows = active_rows;
+        self.is_filtered = true;
+        self.filter_string = format!("Filtered (sample of {})", count);
+        (count, live.len())
+    }
+
+    /// Swap the visible and hidden data rows of the current filter, keeping the header
+    pub fn invert(&mut self, table: &Table, keep_header: bool) {
+        if !self.is_filtered {
+            return;
+        }
+
+        let start = if keep_header { 1 } else { 0 };
+        let mut active_rows: Vec<usize> = (start..table.row_count())
+            .filter(|row| !self.active_row_set.contains(row))
+            .collect();
+        if keep_header {
+            active_rows.insert(0, 0usize);
+        }
+
+        self.active_row_set = active_rows.iter().cloned().collect();
+        self.active_rows = active_rows;
+        self.filter_string = match self.filter_string.strip_prefix("NOT ") {
+            Some(original) => original.to_string(),
+            None => format!("NOT {}", self.filter_string),
+        };
+    }
+
     pub fn remove_filter(&mut self) {
         self.active_rows = Vec::new();
         self.active_row_set = HashSet::new();
@@ -125,6 +207,44 @@ impl RowManager {
         self.filter_string = String::new();
     }
 
+    /// Shift the shown rows after count rows were inserted at idx; the new rows are shown
+    pub fn rows_inserted(&mut self, idx: usize, count: usize) {
+        if !self.is_filtered || count == 0 {
+            return;
+        }
+        let split = self.active_rows.partition_point(|&row| row < idx);
+        let later: Vec<usize> = self.active_rows.drain(split..).map(|row| row + count).collect();
+        self.active_rows.extend(idx..idx + count);
+        self.active_rows.extend(later);
+        self.active_row_set = self.active_rows.iter().cloned().collect();
+    }
+
+    /// Shift the shown rows after count rows were deleted at idx; the deleted rows drop out
+    pub fn rows_deleted(&mut self, idx: usize, count: usize) {
+        if !self.is_filtered || count == 0 {
+            return;
+        }
+        self.active_rows = self.active_rows.iter()
+            .filter(|&&row| row < idx || row >= idx + count)
+            .map(|&row| if row >= idx + count { row - count } else { row })
+            .collect();
+        self.active_row_set = self.active_rows.iter().cloned().collect();
+    }
+
+    /// Keep the same rows shown after the rows are reordered, e.g. by a sort
+    /// permutation[i] = j means row i now holds what was row j
+    pub fn apply_permutation(&mut self, permutation: &[usize]) {
+        if !self.is_filtered {
+            return;
+        }
+        self.active_rows = permutation.iter()
+            .enumerate()
+            .filter(|(_, old)| self.active_row_set.contains(old))
+            .map(|(new, _)| new)
+            .collect();
+        self.active_row_set = self.active_rows.iter().cloned().collect();
+    }
+
     /// Capture current filter state for undo/redo
     pub fn snapshot(&self) -> FilterState {
         FilterState {
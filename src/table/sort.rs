@@ -2,8 +2,9 @@
 
 use rayon::prelude::*;
 
-use crate::numeric::format::parse_numeric;
+use crate::numeric::format::{parse_date, parse_numeric};
 use crate::util::ColumnType;
+use super::operations::{SortKey, compare_sort_keys};
 use super::table::{Table, CHUNK_SIZE};
 
 /// Threshold for using parallel processing
@@ -16,16 +17,51 @@ pub enum SortDirection {
     Descending,
 }
 
+impl SortDirection {
+    pub fn reversed(self) -> Self {
+        match self {
+            SortDirection::Ascending => SortDirection::Descending,
+            SortDirection::Descending => SortDirection::Ascending,
+        }
+    }
+}
+
+/// Parameters of a sort, remembered so `:sort!` can repeat it in the opposite direction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortSpec {
+    Rows { keys: Vec<(usize, SortDirection)>, as_type: Option<ColumnType> },  // Rows ordered by one or more columns
+    Cols { row: usize, direction: SortDirection },  // Columns ordered by a row
+}
+
+impl SortSpec {
+    /// Rows ordered by a single column
+    pub fn by_column(col: usize, direction: SortDirection, as_type: Option<ColumnType>) -> Self {
+        SortSpec::Rows { keys: vec![(col, direction)], as_type }
+    }
+
+    /// Same sort with every key's direction flipped
+    pub fn reversed(&self) -> Self {
+        match self {
+            SortSpec::Rows { keys, as_type } => SortSpec::Rows {
+                keys: keys.iter().map(|&(col, dir)| (col, dir.reversed())).collect(),
+                as_type: *as_type,
+            },
+            SortSpec::Cols { row, direction } => SortSpec::Cols { row: *row, direction: direction.reversed() },
+        }
+    }
+}
+
 /// Maximum cells to sample for type detection
 const TYPE_PROBE_SAMPLE_SIZE: usize = 20;
 
 impl Table {
-    /// Probe a column to determine if it's numeric or text
+    /// Probe a column to determine if it holds dates, numbers or text
     /// Samples up to TYPE_PROBE_SAMPLE_SIZE non-empty cells for efficiency
     /// Recognizes formatted numbers (currency, percentages, etc.)
     pub fn probe_column_type(&self, col: usize, skip_header: bool) -> ColumnType {
         let start_row = if skip_header { 1 } else { 0 };
         let mut numeric_count = 0;
+        let mut date_count = 0;
         let mut total_count = 0;
 
         for row_idx in start_row..self.row_count() {
@@ -36,21 +72,91 @@ impl Table {
                 let trimmed = cell.trim();
                 if !trimmed.is_empty() {
                     total_count += 1;
-                    if parse_numeric(trimmed).is_some() {
+                    if parse_date(trimmed).is_some() {
+                        date_count += 1;
+                    } else if parse_numeric(trimmed).is_some() {
                         numeric_count += 1;
                     }
                 }
             }
         }
 
+        // Dates are checked first since "05.01.2023" also reads as a number
         // If more than half are numeric (or all are numeric), treat as numeric
-        if total_count > 0 && numeric_count * 2 >= total_count {
+        if total_count > 0 && date_count * 2 >= total_count {
+            ColumnType::Date
+        } else if total_count > 0 && numeric_count * 2 >= total_count {
             ColumnType::Numeric
         } else {
             ColumnType::Text
         }
     }
 
+    /// Guess whether a column holds identifiers rather than amounts (years, ZIP codes, IDs),
+    /// which are shown without thousands separators
+    /// Either the header names one, or every sampled cell is a plain integer of one fixed width
+    /// (at least 4 digits) or with leading zeros. Columns declared numeric with :type never are.
+    pub fn looks_like_identifiers(&self, col: usize, skip_header: bool) -> bool {
+        const ID_WORDS: [&str; 11] = ["id", "year", "yr", "zip", "postal", "postcode", "code", "phone", "no", "number", "sku"];
+        if self.declared_type(col) == Some(ColumnType::Numeric) {
+            return false;
+        }
+        if skip_header {
+            let header = self.get_cell(0, col).map(|h| h.to_lowercase()).unwrap_or_default();
+            if header.split(|c: char| !c.is_alphanumeric()).any(|word| ID_WORDS.contains(&word)) {
+                return true;
+            }
+        }
+
+        let start_row = if skip_header { 1 } else { 0 };
+        let mut widths = Vec::new();
+        let mut zero_padded = false;
+        for row_idx in start_row..self.row_count() {
+            if widths.len() >= TYPE_PROBE_SAMPLE_SIZE {
+                break;
+            }
+            let Some(cell) = self.get_cell(row_idx, col).map(|c| c.trim()) else { continue };
+            if cell.is_empty() {
+                continue;
+            }
+            if !cell.bytes().all(|b| b.is_ascii_digit()) {
+                return false;
+            }
+            zero_padded |= cell.len() > 1 && cell.starts_with('0');
+            widths.push(cell.len());
+        }
+        zero_padded || (widths.first().is_some_and(|&w| w >= 4) && widths.iter().all(|&w| w == widths[0]))
+    }
+
+    /// Type declared for a column with :type, if any
+    pub fn declared_type(&self, col: usize) -> Option<ColumnType> {
+        self.col_types.get(col).copied().flatten()
+    }
+
+    /// Declare a column's type (None goes back to probing)
+    pub fn set_declared_type(&mut self, col: usize, column_type: Option<ColumnType>) {
+        if self.col_types.len() <= col {
+            self.col_types.resize(col + 1, None);
+        }
+        self.col_types[col] = column_type;
+    }
+
+    /// Declared type of a column, falling back to probing its contents
+    pub fn column_type(&self, col: usize, skip_header: bool) -> ColumnType {
+        self.declared_type(col)
+            .unwrap_or_else(|| self.probe_column_type(col, skip_header))
+    }
+
+    /// Type used to sort a column
+    /// A requested natural sort only applies to text columns; other requested types always apply
+    pub fn probe_sort_type(&self, col: usize, skip_header: bool, as_type: Option<ColumnType>) -> ColumnType {
+        match (self.column_type(col, skip_header), as_type) {
+            (ColumnType::Text, Some(ColumnType::Natural)) => ColumnType::Natural,
+            (_, Some(forced)) if forced != ColumnType::Natural => forced,
+            (sort_type, _) => sort_type,
+        }
+    }
+
     /// Probe a row to determine if it's numeric or text
     /// Samples up to TYPE_PROBE_SAMPLE_SIZE non-empty cells for efficiency
     /// Recognizes formatted numbers (currency, percentages, etc.)
@@ -83,121 +189,75 @@ impl Table {
 
     /// Sort rows by a specific column, returns the sorted indices
     /// skip_header: if true, row 0 is not included in sorting
-    /// Uses parallel processing for large tables
+    /// Rows with equal keys keep their original relative order
     pub fn get_sorted_row_indices(
         &self,
         sort_col: usize,
         direction: SortDirection,
         skip_header: bool,
     ) -> Vec<usize> {
-        let sort_type = self.probe_column_type(sort_col, skip_header);
+        self.get_sorted_row_indices_multi(&[(sort_col, direction)], skip_header, None)
+    }
+
+    /// Sort rows by several columns in priority order, returns the sorted indices
+    /// Each key only breaks ties left by the keys before it, and the original
+    /// row index breaks any remaining ties so the result is stable
+    /// `as_type` overrides the column types as described for probe_sort_type
+    /// Uses parallel processing for large tables
+    pub fn get_sorted_row_indices_multi(
+        &self,
+        keys: &[(usize, SortDirection)],
+        skip_header: bool,
+        as_type: Option<ColumnType>,
+    ) -> Vec<usize> {
         let start_row = if skip_header { 1 } else { 0 };
         let row_count = self.row_count();
         let use_parallel = row_count >= PARALLEL_THRESHOLD;
 
+        let sort_types: Vec<ColumnType> = keys.iter()
+            .map(|&(col, _)| self.probe_sort_type(col, skip_header, as_type))
+            .collect();
+        let directions: Vec<SortDirection> = keys.iter().map(|&(_, dir)| dir).collect();
+
         let mut indices: Vec<usize> = if skip_header {
             vec![0]
         } else {
             Vec::new()
         };
 
-        match sort_type {
-            ColumnType::Numeric => {
-                // Build keyed vector (parallel for large tables)
-                let mut keyed: Vec<(usize, f64)> = if use_parallel {
-                    // Need to collect cell references first for parallel access
-                    let cells: Vec<Option<&String>> = (start_row..row_count)
-                        .map(|row| self.get_cell(row, sort_col))
-                        .collect();
-
-                    cells.into_par_iter()
-                        .enumerate()
-                        .map(|(i, cell)| {
-                            let row = start_row + i;
-                            let val = parse_numeric(
-                                cell.map(|x| x.as_str().trim()).unwrap_or("")
-                            ).unwrap_or(f64::NAN);
-                            (row, val)
-                        })
-                        .collect()
-                } else {
-                    (start_row..row_count)
-                        .map(|row| {
-                            let val = parse_numeric(
-                                self.get_cell(row, sort_col).map(|x| x.as_str().trim()).unwrap_or("")
-                            ).unwrap_or(f64::NAN);
-                            (row, val)
-                        })
-                        .collect()
-                };
-
-                // Sort (parallel for large tables)
-                let cmp_fn = |&(idx_a, num_a): &(usize, f64), &(idx_b, num_b): &(usize, f64)| -> std::cmp::Ordering {
-                    let base = match (num_a.is_nan(), num_b.is_nan()) {
-                        (true, true) => std::cmp::Ordering::Equal,
-                        (true, false) => std::cmp::Ordering::Greater,
-                        (false, true) => std::cmp::Ordering::Less,
-                        (false, false) => num_a.partial_cmp(&num_b).unwrap_or(std::cmp::Ordering::Equal),
-                    };
-                    match direction {
-                        SortDirection::Ascending => base.then(idx_a.cmp(&idx_b)),
-                        SortDirection::Descending => base.reverse().then(idx_a.cmp(&idx_b)),
-                    }
-                };
-
-                if use_parallel {
-                    keyed.par_sort_unstable_by(cmp_fn);
-                } else {
-                    keyed.sort_unstable_by(cmp_fn);
-                }
-
-                indices.extend(keyed.into_iter().map(|(row, _)| row));
-            }
-            ColumnType::Text => {
-                // Build keyed vector (parallel for large tables)
-                let mut keyed: Vec<(usize, String)> = if use_parallel {
-                    let cells: Vec<Option<&String>> = (start_row..row_count)
-                        .map(|row| self.get_cell(row, sort_col))
-                        .collect();
-
-                    cells.into_par_iter()
-                        .enumerate()
-                        .map(|(i, cell)| {
-                            let row = start_row + i;
-                            let val = cell.map(|s| s.to_lowercase().trim().to_owned())
-                                .unwrap_or_default();
-                            (row, val)
-                        })
-                        .collect()
-                } else {
-                    (start_row..row_count)
-                        .map(|row| {
-                            let val = self.get_cell(row, sort_col)
-                                .map(|s| s.to_lowercase().trim().to_owned())
-                                .unwrap_or_default();
-                            (row, val)
-                        })
-                        .collect()
-                };
+        let row_keys = |row: usize| -> Vec<SortKey> {
+            keys.iter()
+                .zip(sort_types.iter())
+                .map(|(&(col, _), &sort_type)| {
+                    SortKey::from_cell(self.get_cell(row, col).map(|x| x.as_str()).unwrap_or(""), sort_type)
+                })
+                .collect()
+        };
 
-                // Sort (parallel for large tables)
-                let cmp_fn = |&(ref i, ref a): &(usize, String), &(ref j, ref b): &(usize, String)| -> std::cmp::Ordering {
-                    match direction {
-                        SortDirection::Ascending => a.cmp(b).then(i.cmp(j)),
-                        SortDirection::Descending => a.cmp(b).reverse().then(i.cmp(j)),
-                    }
-                };
+        // Build keyed vector (parallel for large tables)
+        let mut keyed: Vec<(usize, Vec<SortKey>)> = if use_parallel {
+            (start_row..row_count)
+                .into_par_iter()
+                .map(|row| (row, row_keys(row)))
+                .collect()
+        } else {
+            (start_row..row_count)
+                .map(|row| (row, row_keys(row)))
+                .collect()
+        };
 
-                if use_parallel {
-                    keyed.par_sort_unstable_by(cmp_fn);
-                } else {
-                    keyed.sort_unstable_by(cmp_fn);
-                }
+        let cmp_fn = |(idx_a, keys_a): &(usize, Vec<SortKey>), (idx_b, keys_b): &(usize, Vec<SortKey>)| -> std::cmp::Ordering {
+            compare_sort_keys(keys_a, keys_b, &directions).then(idx_a.cmp(idx_b))
+        };
 
-                indices.extend(keyed.into_iter().map(|(row, _)| row));
-            }
+        // Sort (parallel for large tables)
+        if use_parallel {
+            keyed.par_sort_unstable_by(cmp_fn);
+        } else {
+            keyed.sort_unstable_by(cmp_fn);
         }
 
+        indices.extend(keyed.into_iter().map(|(row, _)| row));
         indices
     }
 
@@ -269,7 +329,7 @@ impl Table {
 
                 indices.extend(keyed.into_iter().map(|(col, _)| col));
             }
-            ColumnType::Text => {
+            ColumnType::Text | ColumnType::Natural | ColumnType::Date => {  // Rows are only probed as numeric or text
                 let mut keyed: Vec<(usize, String)> = if use_parallel {
                     let cells: Vec<Option<&String>> = (start_col..col_count)
                         .map(|col| self.get_cell(sort_row, col))
@@ -362,6 +422,13 @@ impl Table {
         }
         
         self.col_widths.lock().unwrap().apply_permutation(permutation);
+
+        if !self.col_types.is_empty() {
+            let old_types = std::mem::take(&mut self.col_types);
+            self.col_types = permutation.iter()
+                .map(|&src_col| old_types.get(src_col).copied().flatten())
+                .collect();
+        }
     }
 
     /// Get the permutation needed to sort rows by a column
@@ -382,6 +449,24 @@ impl Table {
         Some(new_order)
     }
 
+    /// Get the permutation needed to sort rows by several columns
+    /// Returns None if already sorted
+    pub fn get_multi_sort_permutation(
+        &self,
+        keys: &[(usize, SortDirection)],
+        skip_header: bool,
+        as_type: Option<ColumnType>,
+    ) -> Option<Vec<usize>> {
+        let new_order = self.get_sorted_row_indices_multi(keys, skip_header, as_type);
+
+        // Check if already sorted
+        if new_order.iter().enumerate().all(|(i, &idx)| i == idx) {
+            return None;
+        }
+
+        Some(new_order)
+    }
+
     /// Get the permutation needed to sort columns by a row
     /// Returns None if already sorted
     pub fn get_col_sort_permutation(
@@ -3,6 +3,7 @@ use std::cmp;
 use std::sync::{Arc, Mutex};
 
 use crate::table::columnwidths::ColumnWidths;
+use crate::util::ColumnType;
 
 /// Number of rows per chunk for memory-efficient storage
 pub const CHUNK_SIZE: usize = 1024;
@@ -20,7 +21,9 @@ pub struct Table {
     /// Number of columns
     col_count: usize,
     /// Cached column widths (max length of any cell in each column)
-    pub(crate) col_widths: Arc<Mutex<ColumnWidths>>
+    pub(crate) col_widths: Arc<Mutex<ColumnWidths>>,
+    /// Column types declared with :type (None = probe the data)
+    pub(crate) col_types: Vec<Option<ColumnType>>,
 }
 
 impl Table {
@@ -58,6 +61,11 @@ impl Table {
         self.chunks.iter().flat_map(|chunk| chunk.iter())
     }
 
+    /// Iterator over the cells of one column, top to bottom (empty string past a short row)
+    pub fn col_iter(&self, col: usize) -> impl Iterator<Item = &str> {
+        self.rows_iter().map(move |row| row.get(col).map(|s| s.as_str()).unwrap_or(""))
+    }
+
     pub fn new(cells: Vec<Vec<String>>) -> Self {
         let total_rows = cells.len();
         let col_count = cells.first().map(|r| r.len()).unwrap_or(0);
@@ -71,7 +79,8 @@ impl Table {
             chunks,
             total_rows,
             col_count,
-            col_widths: Arc::new(Mutex::new(ColumnWidths::new()))
+            col_widths: Arc::new(Mutex::new(ColumnWidths::new())),
+            col_types: Vec::new(),
         };
         table.recompute_col_widths();
         table
@@ -85,18 +94,34 @@ impl Table {
             chunks,
             total_rows,
             col_count,
-            col_widths: Arc::new(Mutex::new(ColumnWidths::new()))
+            col_widths: Arc::new(Mutex::new(ColumnWidths::new())),
+            col_types: Vec::new(),
         };
         table.recompute_col_widths();
         table
     }
 
+    /// Create a table from pre-chunked data, leaving column widths to be computed on first use
+    /// Used when loading large files so the load doesn't pay for a full width scan
+    pub fn from_chunks_lazy(chunks: Vec<Vec<Vec<String>>>, col_count: usize) -> Self {
+        let total_rows: usize = chunks.iter().map(|c| c.len()).sum();
+        Self {
+            chunks,
+            total_rows,
+            col_count,
+            col_widths: Arc::new(Mutex::new(ColumnWidths::new())),
+            col_types: Vec::new(),
+        }
+    }
+
+    #[allow(dead_code)]
     pub fn from_empty() -> Self {
         Self {
             chunks: Vec::new(),
             total_rows: 0,
             col_count: 0,
-            col_widths: Arc::new(Mutex::new(ColumnWidths::new()))
+            col_widths: Arc::new(Mutex::new(ColumnWidths::new())),
+            col_types: Vec::new(),
         }
     }
 
@@ -109,6 +134,7 @@ impl Table {
         self.col_count = col_count;
     }
 
+    #[allow(dead_code)]
     pub fn add_chunk(&mut self, chunk: Vec<Vec<String>>, col_count: usize) {
         if col_count > self.col_count {
             self.expand_columns(col_count);
@@ -117,6 +143,45 @@ impl Table {
         self.chunks.push(chunk);
     }
 
+    /// Replace the whole table with the given rows, rebuilding chunks
+    /// col_count becomes the widest row; ragged rows are kept as-is
+    pub fn restore_from_rows(&mut self, rows: Vec<Vec<String>>) {
+        self.col_count = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        self.total_rows = rows.len();
+        self.chunks = rows
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+        self.mark_widths_dirty();
+    }
+
+    /// Swap rows and columns, padding ragged rows to col_count first
+    /// Returns the original rows and declared column types for undo
+    pub fn transpose(&mut self) -> (Vec<Vec<String>>, Vec<Option<ColumnType>>) {
+        let old_rows = self.clone_all_rows();
+        let col_count = self.col_count;
+
+        let mut transposed: Vec<Vec<String>> = (0..col_count)
+            .map(|_| Vec::with_capacity(self.total_rows))
+            .collect();
+        for row in old_rows.iter() {
+            for (col, out) in transposed.iter_mut().enumerate() {
+                out.push(row.get(col).cloned().unwrap_or_default());
+            }
+        }
+
+        if transposed.is_empty() {
+            transposed.push(vec![String::new()]);
+        }
+
+        // Columns become rows, so declared types no longer apply
+        let old_types = std::mem::take(&mut self.col_types);
+
+        self.restore_from_rows(transposed);
+        (old_rows, old_types)
+    }
+
+    #[allow(dead_code)]
     pub fn is_empty(&self) -> bool {
         self.chunks.is_empty()
     }
@@ -128,6 +193,14 @@ impl Table {
         self.col_widths.lock().unwrap().col_widths(&self, size >= PARALLEL_THRESHOLD && self.col_count > 1).to_vec()
     }
 
+    /// Cached width of one column, recomputing stale widths first (0 past the last column)
+    pub fn col_width(&mut self, col: usize) -> usize {
+        let size = self.total_rows * self.col_count;
+
+        let mut widths = self.col_widths.lock().unwrap();
+        widths.col_widths(self, size >= PARALLEL_THRESHOLD && self.col_count > 1).get(col).copied().unwrap_or(0)
+    }
+
     pub fn max_col_width(&self) -> usize {
         self.col_widths.lock().unwrap().max_col_width
     }
@@ -158,6 +231,23 @@ impl Table {
         self.col_widths.lock().unwrap().update_col_width(col, new_len);
     }
 
+    /// Reset a column's width, e.g. to drop growth from text typed and then deleted
+    pub fn set_col_width(&mut self, col: usize, width: usize) {
+        self.col_widths.lock().unwrap().set_col_width(col, width);
+    }
+
+    /// Rescan one column's width after a cell of old_len was replaced by a narrower value
+    /// Skipped unless that cell was the widest, since then the width can't have changed
+    pub fn maybe_shrink_col_width(&mut self, col: usize, old_len: usize) {
+        let mut widths = self.col_widths.lock().unwrap();
+        let Some(current) = widths.width_of(col) else { return; };
+        if old_len.min(widths.max_col_width) < current {
+            return;
+        }
+        let width = self.col_iter(col).map(crate::util::display_width).max().unwrap_or(0);
+        widths.set_col_width(col, width);
+    }
+
     pub fn get_cell(&self, row: usize, col: usize) -> Option<&String> {
         self.get_chunk(row)?
             .get(Self::row_in_chunk(row))?
@@ -523,6 +613,7 @@ impl Table {
         }
         self.col_count += 1;
         self.col_widths.lock().unwrap().insert_at(idx, 3);
+        self.shift_col_types(idx);
     }
 
     pub fn delete_col_at(&mut self, idx: usize) -> Option<Vec<String>> {
@@ -551,6 +642,9 @@ impl Table {
         }
         self.col_count -= 1;
         self.col_widths.lock().unwrap().remove_at(idx);
+        if idx < self.col_types.len() {
+            self.col_types.remove(idx);
+        }
         Some(col)
     }
 
@@ -643,6 +737,14 @@ impl Table {
         }
         self.col_count += 1;
         self.col_widths.lock().unwrap().insert_at(idx, max_width);
+        self.shift_col_types(idx);
+    }
+
+    /// Keep declared types attached to their columns when a column is inserted at idx
+    fn shift_col_types(&mut self, idx: usize) {
+        if idx < self.col_types.len() {
+            self.col_types.insert(idx, None);
+        }
     }
 }
 
@@ -652,7 +754,8 @@ impl Default for Table {
             chunks: vec![vec![vec![String::new()]]],
             total_rows: 1,
             col_count: 1,
-            col_widths: Arc::new(Mutex::new(ColumnWidths::new()))
+            col_widths: Arc::new(Mutex::new(ColumnWidths::new())),
+            col_types: Vec::new(),
         }
     }
 }
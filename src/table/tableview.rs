@@ -1,10 +1,12 @@
 use std::cmp;
+use std::collections::HashSet;
 use std::rc::Rc;
 use std::cell::RefCell;
 
 use crate::table::table::Table;
 use crate::mode::Mode;
 use crate::table::rowmanager::RowManager;
+use crate::util::letters_from_col;
 
 /// View state for the table (cursor, viewport, selection)
 #[derive(Debug, Clone)]
@@ -25,6 +27,12 @@ pub struct TableView {
     pub viewport_height: usize,
     pub viewport_width: usize,
 
+    // Leading columns that stay pinned at the left while scrolling horizontally
+    pub frozen_cols: usize,
+
+    // Columns left out of the display and skipped by navigation; the data is untouched
+    pub hidden_cols: HashSet<usize>,
+
     pub row_manager: Rc<RefCell<RowManager>>
 }
 
@@ -37,13 +45,15 @@ impl TableView {
             viewport_col: 0,
             viewport_height: 20,
             viewport_width: 10,
+            frozen_cols: 0,
+            hidden_cols: HashSet::new(),
             support_row: 0,
             support_col: 0,
             row_manager
         }
     }
 
-    pub fn is_selected(&mut self, row_idx: usize, col_idx: usize, mode: Mode) -> bool {
+    pub fn is_selected(&self, row_idx: usize, col_idx: usize, mode: Mode) -> bool {
         let mut row_valid = true;
         let mut col_valid = true;
         if mode != Mode::VisualCol {
@@ -74,13 +84,59 @@ impl TableView {
         )
     }
 
+    /// Describe the size of the current selection for the status bar, None outside visual modes
+    pub fn selection_summary(&self, mode: Mode) -> Option<String> {
+        let (start_row, end_row, start_col, end_col) = self.get_selection_bounds();
+        let rows = end_row - start_row + 1;
+        let cols = end_col - start_col + 1;
+        match mode {
+            Mode::Visual => Some(format!("{}x{} block ({} cells)", rows, cols, rows * cols)),
+            Mode::VisualRow => Some(format!("{} row{}", rows, if rows == 1 { "" } else { "s" })),
+            Mode::VisualCol => Some(format!("{} col{}", cols, if cols == 1 { "" } else { "s" })),
+            _ => None,
+        }
+    }
+
     /// Ensure cursor is within table bounds
     pub fn clamp_cursor(&mut self, table: &Table) {
         if table.row_count() > 0 {
             self.cursor_row = self.cursor_row.min(table.row_count() - 1);
+            // In a filtered view, move off a row the filter hides (e.g. after deleting rows)
+            let row_manager = self.row_manager.borrow();
+            if !row_manager.is_row_live(self.cursor_row) {
+                if let Some(row) = row_manager.get_successor(self.cursor_row)
+                    .or_else(|| row_manager.get_predecessor(self.cursor_row)) {
+                    self.cursor_row = row;
+                }
+            }
         }
         if table.col_count() > 0 {
             self.cursor_col = self.cursor_col.min(table.col_count() - 1);
+            self.snap_to_visible_col(table);
+        }
+    }
+
+    pub fn is_col_hidden(&self, col: usize) -> bool {
+        self.hidden_cols.contains(&col)
+    }
+
+    /// Nearest unhidden column left of col
+    fn prev_visible_col(&self, col: usize) -> Option<usize> {
+        (0..col).rev().find(|c| !self.is_col_hidden(*c))
+    }
+
+    /// Nearest unhidden column right of col
+    fn next_visible_col(&self, col: usize, table: &Table) -> Option<usize> {
+        (col + 1..table.col_count()).find(|c| !self.is_col_hidden(*c))
+    }
+
+    /// Move the cursor off a hidden column, preferring the next column to the right
+    pub fn snap_to_visible_col(&mut self, table: &Table) {
+        if self.is_col_hidden(self.cursor_col) {
+            if let Some(col) = self.next_visible_col(self.cursor_col, table)
+                .or_else(|| self.prev_visible_col(self.cursor_col)) {
+                self.cursor_col = col;
+            }
         }
     }
 
@@ -93,25 +149,70 @@ impl TableView {
             self.viewport_row = self.row_manager.borrow().jump_up(self.cursor_row, self.viewport_height-1);
         }
 
-        // Horizontal scrolling
-        if self.cursor_col < self.viewport_col {
+        // Horizontal scrolling, only the columns right of the frozen panel scroll
+        let frozen = self.frozen_cols;
+        let scroll_width = self.viewport_width.saturating_sub(frozen).max(1);
+        if self.viewport_col < frozen {
+            self.viewport_col = frozen;
+        }
+        if self.cursor_col < frozen {
+            // Frozen columns are always visible
+        } else if self.cursor_col < self.viewport_col {
             self.viewport_col = self.cursor_col;
-        } else if self.cursor_col >= self.viewport_col + self.viewport_width {
-            self.viewport_col = self.cursor_col.saturating_sub(self.viewport_width - 1);
+        } else if self.cursor_col >= self.viewport_col + scroll_width {
+            self.viewport_col = self.cursor_col.saturating_sub(scroll_width - 1).max(frozen);
+        }
+    }
+
+    /// Lay out the columns that fit in available_width, returns (col, x offset) pairs
+    /// Frozen columns come first at the left edge, followed by the columns from viewport_col
+    /// At least one scrolling column is always included so the cursor can't hide behind the panel
+    pub fn visible_columns(&self, col_widths: &[usize], available_width: u16) -> Vec<(usize, u16)> {
+        let col_count = col_widths.len();
+        let frozen = self.frozen_cols.min(col_count);
+        let mut layout = Vec::new();
+        let mut x = 0u16;
+
+        for col in (0..frozen).filter(|c| !self.is_col_hidden(*c)) {
+            let width = Self::display_width(col_widths, col);
+            if x + width > available_width && !layout.is_empty() {
+                break;
+            }
+            layout.push((col, x));
+            x += width;
+        }
+
+        let scrolling = (self.viewport_col.max(frozen)..col_count).filter(|c| !self.is_col_hidden(*c));
+        for (scrolled, col) in scrolling.enumerate() {
+            let width = Self::display_width(col_widths, col);
+            if x + width > available_width && scrolled > 0 {
+                break;
+            }
+            layout.push((col, x));
+            x += width;
         }
+
+        layout
+    }
+
+    /// Rendered width of a column, including padding and room for its letter header
+    pub fn display_width(col_widths: &[usize], col: usize) -> u16 {
+        let w = col_widths.get(col).copied().unwrap_or(3);
+        let header_w = letters_from_col(col).len();
+        w.max(header_w) as u16 + 2
     }
 
     // Navigation methods
     pub fn move_left(&mut self) {
-        if self.cursor_col > 0 {
-            self.cursor_col -= 1;
+        if let Some(col) = self.prev_visible_col(self.cursor_col) {
+            self.cursor_col = col;
             self.scroll_to_cursor();
         }
     }
 
     pub fn move_right(&mut self, table: &Table) {
-        if self.cursor_col + 1 < table.col_count() {
-            self.cursor_col += 1;
+        if let Some(col) = self.next_visible_col(self.cursor_col, table) {
+            self.cursor_col = col;
             self.scroll_to_cursor();
         }
     }
@@ -131,6 +232,61 @@ impl TableView {
         }
     }
 
+    /// Move to the next visible cell, wrapping to the first column of the next row
+    /// Returns false (without moving) at the last cell
+    pub fn next_cell(&mut self, table: &Table) -> bool {
+        if let Some(col) = self.next_visible_col(self.cursor_col, table) {
+            self.cursor_col = col;
+        } else {
+            let next_row = self.row_manager.borrow().get_successor(self.cursor_row);
+            match next_row {
+                Some(row) if row < table.row_count() => {
+                    self.cursor_row = row;
+                    self.cursor_col = 0;
+                    self.snap_to_visible_col(table);
+                }
+                _ => return false,
+            }
+        }
+        self.scroll_to_cursor();
+        true
+    }
+
+    /// Move to the previous visible cell, wrapping to the last column of the previous row
+    /// Returns false (without moving) at the first cell
+    pub fn prev_cell(&mut self, table: &Table) -> bool {
+        if let Some(col) = self.prev_visible_col(self.cursor_col) {
+            self.cursor_col = col;
+        } else {
+            let prev_row = self.row_manager.borrow().get_predecessor(self.cursor_row);
+            match prev_row {
+                Some(row) if table.col_count() > 0 => {
+                    self.cursor_row = row;
+                    self.cursor_col = table.col_count() - 1;
+                    self.snap_to_visible_col(table);
+                }
+                _ => return false,
+            }
+        }
+        self.scroll_to_cursor();
+        true
+    }
+
+    /// Move to the nth row (0-based) that is visible, clamped to the last one
+    pub fn goto_row(&mut self, n: usize, table: &Table) {
+        if table.row_count() == 0 {
+            return;
+        }
+        let row_manager = self.row_manager.borrow();
+        self.cursor_row = if row_manager.is_filtered {
+            row_manager.active_rows.get(n).copied().unwrap_or_else(|| row_manager.get_end(table))
+        } else {
+            n.min(table.row_count() - 1)
+        };
+        drop(row_manager);
+        self.scroll_to_cursor();
+    }
+
     pub fn move_to_top(&mut self) {
         self.cursor_row = 0;
         self.scroll_to_cursor();
@@ -144,13 +300,14 @@ impl TableView {
     }
 
     pub fn move_to_first_col(&mut self) {
-        self.cursor_col = 0;
+        self.cursor_col = (0..=self.cursor_col).find(|c| !self.is_col_hidden(*c)).unwrap_or(self.cursor_col);
         self.scroll_to_cursor();
     }
 
     pub fn move_to_last_col(&mut self, table: &Table) {
         if table.col_count() > 0 {
             self.cursor_col = table.col_count() - 1;
+            self.snap_to_visible_col(table);
             self.scroll_to_cursor();
         }
     }
@@ -181,12 +338,22 @@ impl TableView {
 
     // Movement with count
     pub fn move_left_n(&mut self, n: usize) {
-        self.cursor_col = self.cursor_col.saturating_sub(n);
+        for _ in 0..n {
+            match self.prev_visible_col(self.cursor_col) {
+                Some(col) => self.cursor_col = col,
+                None => break,
+            }
+        }
         self.scroll_to_cursor();
     }
 
     pub fn move_right_n(&mut self, n: usize, table: &Table) {
-        self.cursor_col = (self.cursor_col + n).min(table.col_count().saturating_sub(1));
+        for _ in 0..n {
+            match self.next_visible_col(self.cursor_col, table) {
+                Some(col) => self.cursor_col = col,
+                None => break,
+            }
+        }
         self.scroll_to_cursor();
     }
 
@@ -212,92 +379,66 @@ impl TableView {
             .unwrap_or(false)
     }
 
-    pub fn jump_left(&mut self, table: &Table) {
-        if self.cursor_col == 0 {
-            return;
-        }
-
-        let mut target = self.cursor_col - 1;
-
-        let is_occupied = Self::is_cell_occupied(table, self.cursor_row, target);
-
-        while target > 0 && (Self::is_cell_occupied(table, self.cursor_row, target-1) == is_occupied) {
-            target -= 1;
+    /// Target of an occupancy jump from start along one axis (indices 0..=last)
+    /// Inside a run (start and its neighbor occupied) this is the far end of the run;
+    /// otherwise it is the next occupied cell, or the edge if there is none
+    fn occupancy_jump(start: usize, last: usize, forward: bool, occupied: impl Fn(usize) -> bool) -> usize {
+        let step = |i: usize| if forward { (i < last).then_some(i + 1) } else { i.checked_sub(1) };
+        let Some(mut target) = step(start) else {
+            return start;
+        };
+
+        if occupied(start) && occupied(target) {
+            while let Some(next) = step(target).filter(|&next| occupied(next)) {
+                target = next;
+            }
+        } else {
+            while !occupied(target) {
+                match step(target) {
+                    Some(next) => target = next,
+                    None => break,
+                }
+            }
         }
+        target
+    }
 
-        if target > 0 && !is_occupied {
-            target -= 1;
-        }
+    pub fn jump_left(&mut self, table: &Table) {
+        let row = self.cursor_row;
+        let last = table.col_count().saturating_sub(1);
+        let target = Self::occupancy_jump(self.cursor_col, last, false, |col| Self::is_cell_occupied(table, row, col));
 
         self.cursor_col = target;
+        if self.is_col_hidden(target) {
+            self.cursor_col = self.prev_visible_col(target)
+                .or_else(|| self.next_visible_col(target, table))
+                .unwrap_or(target);
+        }
 
         self.scroll_to_cursor();
     }
 
     pub fn jump_right(&mut self, table: &Table) {
-        let max_col = table.col_count().saturating_sub(1);
-        if self.cursor_col >= max_col {
-            return;
-        }
-
-        let mut target = self.cursor_col + 1;
-
-        let is_occupied = Self::is_cell_occupied(table, self.cursor_row, target);
-
-        while target < max_col && (Self::is_cell_occupied(table, self.cursor_row, target+1) == is_occupied) {
-            target += 1;
-        }
-
-        if target < max_col && !is_occupied {
-            target += 1;
-        }
-
-        self.cursor_col = target;
+        let row = self.cursor_row;
+        let last = table.col_count().saturating_sub(1);
+        self.cursor_col = Self::occupancy_jump(self.cursor_col, last, true, |col| Self::is_cell_occupied(table, row, col));
+        self.snap_to_visible_col(table);
 
         self.scroll_to_cursor();
     }
 
     pub fn jump_up(&mut self, table: &Table) {
-        if self.cursor_row == 0 {
-            return;
-        }
-
-        let mut target = self.cursor_row - 1;
-
-        let is_occupied = Self::is_cell_occupied(table, target, self.cursor_col);
-
-        while target > 0 && (Self::is_cell_occupied(table, target-1, self.cursor_col) == is_occupied) {
-            target -= 1;
-        }
-
-        if target > 0 && !is_occupied {
-            target -= 1;
-        }
-
-        self.cursor_row = target;
+        let col = self.cursor_col;
+        let last = table.row_count().saturating_sub(1);
+        self.cursor_row = Self::occupancy_jump(self.cursor_row, last, false, |row| Self::is_cell_occupied(table, row, col));
 
         self.scroll_to_cursor();
     }
 
     pub fn jump_down(&mut self, table: &Table) {
-        let max_row = table.row_count().saturating_sub(1);
-        if self.cursor_row >= max_row {
-            return;
-        }
-
-        let mut target = self.cursor_row + 1;
-
-        let is_occupied = Self::is_cell_occupied(table, target, self.cursor_col);
-
-        while target < max_row && (Self::is_cell_occupied(table, target+1, self.cursor_col) == is_occupied) {
-            target += 1;
-        }
-
-        if target < max_row && !is_occupied {
-            target += 1;
-        }
-
-        self.cursor_row = target;
+        let col = self.cursor_col;
+        let last = table.row_count().saturating_sub(1);
+        self.cursor_row = Self::occupancy_jump(self.cursor_row, last, true, |row| Self::is_cell_occupied(table, row, col));
 
         self.scroll_to_cursor();
     }
@@ -491,6 +491,169 @@ fn predicate_filter_empty_result_keeps_header() {
     assert_eq!(rm.active_rows, vec![0]);
 }
 
+#[test]
+fn predicate_filter_regex() {
+    let table = make_table(vec![
+        vec!["Name"],
+        vec!["foobar"],
+        vec!["barfoo"],
+        vec!["food"],
+    ]);
+
+    let mut rm = RowManager::new();
+    rm.predicate_filter(&table, 0, Predicate::Regex("^foo".to_string()), ColumnType::Text, true);
+
+    assert_eq!(rm.active_rows, vec![0, 1, 3]);
+    assert_eq!(rm.filter_string, "Filtered (A =~ ^foo)");
+}
+
+#[test]
+fn predicate_filter_blank_and_not_blank() {
+    let table = make_table(vec![
+        vec!["Name", "Note"],
+        vec!["a", ""],
+        vec!["b", "   "],
+        vec!["c", "filled"],
+        vec!["d", " x "],
+    ]);
+
+    // Blank checks apply regardless of the probed column type
+    let mut rm = RowManager::new();
+    rm.predicate_filter(&table, 1, Predicate::Blank, ColumnType::Numeric, true);
+    assert_eq!(rm.active_rows, vec![0, 1, 2]);
+    assert_eq!(rm.filter_string, "Filtered (B is blank)");
+
+    let mut rm = RowManager::new();
+    rm.predicate_filter(&table, 1, Predicate::NotBlank, ColumnType::Text, true);
+    assert_eq!(rm.active_rows, vec![0, 3, 4]);
+
+    // Chains with an existing filter
+    let pred = Predicate::Comparator {
+        op: crate::numeric::predicate::Op::Ne,
+        val: "c".to_string(),
+    };
+    rm.predicate_filter(&table, 0, pred, ColumnType::Text, true);
+    assert_eq!(rm.active_rows, vec![0, 4]);
+}
+
+#[test]
+fn invert_filter_twice_restores_active_rows() {
+    let table = make_table(vec![
+        vec!["Name", "Score"],
+        vec!["Alice", "95"],
+        vec!["Bob", "87"],
+        vec!["Carol", "92"],
+        vec!["Dave", "50"],
+    ]);
+
+    let mut rm = RowManager::new();
+    let pred = Predicate::Comparator {
+        op: crate::numeric::predicate::Op::Gt,
+        val: "90".to_string(),
+    };
+    rm.predicate_filter(&table, 1, pred, ColumnType::Numeric, true);
+    let original = rm.snapshot();
+
+    rm.invert(&table, true);
+    assert_eq!(rm.active_rows, vec![0, 2, 4]);
+    assert!(rm.is_row_live(0));
+    assert!(!rm.is_row_live(1));
+    assert_eq!(rm.filter_string, format!("NOT {}", original.filter_string));
+
+    rm.invert(&table, true);
+    assert_eq!(rm.snapshot(), original);
+}
+
+#[test]
+fn filter_to_range_keeps_header_and_selection() {
+    let table = make_table(vec![
+        vec!["H"], vec!["a"], vec!["b"], vec!["c"], vec!["d"], vec!["e"],
+    ]);
+    let mut rm = RowManager::new();
+    rm.filter_to_range(2, 4, true);
+
+    assert!(rm.is_filtered);
+    assert_eq!(rm.active_rows, vec![0, 2, 3, 4]);
+    assert_eq!(rm.filter_string, "Filtered (manual selection)");
+    assert_eq!(rm.get_successor(0), Some(2));
+    assert_eq!(rm.get_end(&table), 4);
+}
+
+#[test]
+fn filter_to_range_chains_with_existing_filter() {
+    let mut rm = RowManager::new();
+    rm.restore(FilterState {
+        is_filtered: true,
+        active_rows: vec![0, 1, 3, 5],
+        filter_string: "Filtered (A = x)".to_string(),
+    });
+    rm.filter_to_range(1, 4, false);
+
+    assert_eq!(rm.active_rows, vec![1, 3]);
+    assert!(!rm.is_row_live(0));
+}
+
+#[test]
+fn sample_keeps_header_and_is_reproducible() {
+    let table = Table::new(std::iter::once("H".to_string())
+        .chain((1..=50).map(|i| i.to_string()))
+        .map(|c| vec![c])
+        .collect());
+    let mut rm = RowManager::new();
+    assert_eq!(rm.sample(&table, SampleSize::Rows(10), Some(5), true), (10, 50));
+    assert_eq!(rm.active_rows.len(), 11);
+    assert_eq!(rm.active_rows[0], 0);
+    assert!(rm.active_rows.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(rm.filter_string, "Filtered (sample of 10)");
+
+    let mut again = RowManager::new();
+    again.sample(&table, SampleSize::Rows(10), Some(5), true);
+    assert_eq!(again.active_rows, rm.active_rows);
+
+    // Sampling again draws from the rows still visible
+    let sampled = rm.active_rows.clone();
+    assert_eq!(rm.sample(&table, SampleSize::Percent(50), Some(1), true), (5, 10));
+    assert!(rm.active_rows.iter().all(|row| sampled.contains(row)));
+
+    let mut all = RowManager::new();
+    assert_eq!(all.sample(&table, SampleSize::Rows(100), None, true), (50, 50));
+}
+
+#[test]
+fn invert_without_filter_does_nothing() {
+    let table = make_table(vec![vec!["a"], vec!["b"]]);
+    let mut rm = RowManager::new();
+    rm.invert(&table, true);
+    assert!(!rm.is_filtered);
+    assert!(rm.active_rows.is_empty());
+}
+
+#[test]
+fn predicate_filter_regex_chains_and_restores() {
+    let table = make_table(vec![
+        vec!["Name", "Score"],
+        vec!["foobar", "10"],
+        vec!["food", "90"],
+        vec!["bar", "95"],
+    ]);
+
+    let mut rm = RowManager::new();
+    let pred = Predicate::Comparator {
+        op: crate::numeric::predicate::Op::Gt,
+        val: "50".to_string(),
+    };
+    rm.predicate_filter(&table, 1, pred, ColumnType::Numeric, true);
+    let before = rm.snapshot();
+
+    rm.predicate_filter(&table, 0, Predicate::Regex("o{2}".to_string()), ColumnType::Text, true);
+    assert_eq!(rm.active_rows, vec![0, 2]);
+
+    // Undoing the SetFilter restores the numeric filter alone
+    rm.restore(before);
+    assert_eq!(rm.active_rows, vec![0, 2, 3]);
+    assert!(rm.is_row_live(3));
+}
+
 // === Table basic operations ===
 
 #[test]
@@ -626,6 +789,37 @@ fn test_get_row() {
     assert_eq!(table.get_row(2), None);
 }
 
+#[test]
+fn test_duplicate_rows_copies_current_row_below() {
+    let mut table = make_table(vec![vec!["a", "1"], vec!["b", "2"], vec!["c", "3"]]);
+    let mut view = TableView::new(row_manager());
+    view.cursor_row = 1;
+
+    let (txn, first_copy) = duplicate_rows(2, &table, &view, None).unwrap();
+    assert_eq!(first_copy, 2);
+    txn.apply(&mut table);
+
+    assert_eq!(table.row_count(), 5);
+    assert_eq!(row(&table, 2), vec!["b", "2"]);
+    assert_eq!(row(&table, 3), vec!["b", "2"]);
+    assert_eq!(row(&table, 4), vec!["c", "3"]);
+}
+
+#[test]
+fn test_duplicate_rows_copies_visual_row_selection() {
+    let mut table = make_table(vec![vec!["a"], vec!["b"], vec!["c"]]);
+    let mut view = TableView::new(row_manager());
+    view.support_row = 0;
+    view.cursor_row = 1;
+
+    let (txn, first_copy) = duplicate_rows(1, &table, &view, Some(Mode::VisualRow)).unwrap();
+    assert_eq!(first_copy, 2);
+    txn.apply(&mut table);
+
+    let cells: Vec<String> = (0..table.row_count()).map(|r| cell(&table, r, 0)).collect();
+    assert_eq!(cells, vec!["a", "b", "a", "b", "c"]);
+}
+
 // === Column operations ===
 
 #[test]
@@ -926,7 +1120,22 @@ fn test_tableview_current_cell() {
         vec!["hello", "world"],
     ]);
 
-    assert_eq!(current_cell(&view, &table), "hello");
+    assert_eq!(current_cell(&view, &table).map(String::as_str), Some("hello"));
+}
+
+#[test]
+fn test_current_cell_with_stale_cursor() {
+    let mut view = TableView::new(row_manager());
+    let table = make_table(vec![
+        vec!["a", "b"],
+        vec!["c", "d"],
+    ]);
+
+    view.cursor_row = 5;
+    assert_eq!(current_cell(&view, &table), None);
+    view.cursor_row = 1;
+    view.cursor_col = 2;
+    assert_eq!(current_cell(&view, &table), None);
 }
 
 #[test]
@@ -988,6 +1197,127 @@ fn test_tableview_is_selected_visual_col() {
     assert!(!view.is_selected(0, 3, Mode::VisualCol));
 }
 
+#[test]
+fn test_tableview_next_and_prev_cell_wrap_rows() {
+    let table = make_table(vec![
+        vec!["a", "b", "c"],
+        vec!["d", "e", "f"],
+    ]);
+    let mut view = TableView::new(row_manager());
+    view.hidden_cols.insert(1);
+    view.cursor_col = 2;
+
+    assert!(view.next_cell(&table));
+    assert_eq!((view.cursor_row, view.cursor_col), (1, 0));
+    // Hidden column B is skipped
+    assert!(view.next_cell(&table));
+    assert_eq!((view.cursor_row, view.cursor_col), (1, 2));
+    assert!(!view.next_cell(&table));
+    assert_eq!((view.cursor_row, view.cursor_col), (1, 2));
+
+    assert!(view.prev_cell(&table));
+    assert!(view.prev_cell(&table));
+    assert_eq!((view.cursor_row, view.cursor_col), (0, 2));
+    view.cursor_col = 0;
+    assert!(!view.prev_cell(&table));
+}
+
+#[test]
+fn test_tableview_jump_left_and_right_from_each_column() {
+    let table = make_table(vec![vec!["a", "b", "", "c"]]);
+    let mut view = TableView::new(row_manager());
+
+    // (start, jump_left lands on, jump_right lands on)
+    let expected = [(0, 0, 1), (1, 0, 3), (2, 1, 3), (3, 1, 3)];
+    for (start, left, right) in expected {
+        view.cursor_col = start;
+        view.jump_left(&table);
+        assert_eq!(view.cursor_col, left, "jump_left from column {}", start);
+
+        view.cursor_col = start;
+        view.jump_right(&table);
+        assert_eq!(view.cursor_col, right, "jump_right from column {}", start);
+    }
+}
+
+#[test]
+fn test_tableview_jump_up_and_down_from_each_row() {
+    let table = make_table(vec![vec!["a"], vec!["b"], vec![""], vec!["c"]]);
+    let mut view = TableView::new(row_manager());
+
+    // (start, jump_up lands on, jump_down lands on)
+    let expected = [(0, 0, 1), (1, 0, 3), (2, 1, 3), (3, 1, 3)];
+    for (start, up, down) in expected {
+        view.cursor_row = start;
+        view.jump_up(&table);
+        assert_eq!(view.cursor_row, up, "jump_up from row {}", start);
+
+        view.cursor_row = start;
+        view.jump_down(&table);
+        assert_eq!(view.cursor_row, down, "jump_down from row {}", start);
+    }
+}
+
+#[test]
+fn test_tableview_jump_through_empty_region_to_edge() {
+    let table = make_table(vec![vec!["", "", "a", "", ""]]);
+    let mut view = TableView::new(row_manager());
+
+    view.cursor_col = 0;
+    view.jump_right(&table);
+    assert_eq!(view.cursor_col, 2);
+    view.jump_right(&table);
+    assert_eq!(view.cursor_col, 4);
+    view.jump_left(&table);
+    assert_eq!(view.cursor_col, 2);
+    view.jump_left(&table);
+    assert_eq!(view.cursor_col, 0);
+}
+
+#[test]
+fn test_maybe_shrink_col_width_after_widest_cell_shortened() {
+    let mut table = make_table(vec![
+        vec!["id", "a fairly long value"],
+        vec!["1", "short"],
+    ]);
+    assert_eq!(table.col_widths()[1], 19);
+
+    table.set_cell(0, 1, "tiny".to_string());
+    assert_eq!(table.col_widths()[1], 19);  // set_cell never shrinks
+    table.maybe_shrink_col_width(1, 19);
+    assert_eq!(table.col_widths()[1], 5);
+}
+
+#[test]
+fn test_col_width_before_widths_are_computed() {
+    // Built chunk by chunk as the loader does, so no widths have been computed yet
+    let mut table = Table::from_empty();
+    table.add_chunk(vec![
+        vec!["id".to_string(), "name".to_string()],
+        vec!["1".to_string(), "a longer name".to_string()],
+    ], 2);
+
+    assert_eq!(table.col_width(1), 13);
+    assert_eq!(table.col_width(5), 0);
+
+    table.expand_col_width(0, 8);
+    assert_eq!(table.col_widths(), vec![8, 13]);
+}
+
+#[test]
+fn test_maybe_shrink_col_width_skips_narrower_cells() {
+    let mut table = make_table(vec![
+        vec!["a fairly long value"],
+        vec!["medium text"],
+    ]);
+    assert_eq!(table.col_widths()[0], 19);
+
+    // The edited cell wasn't the widest, so the width stays
+    table.set_cell(1, 0, "x".to_string());
+    table.maybe_shrink_col_width(0, 11);
+    assert_eq!(table.col_widths()[0], 19);
+}
+
 #[test]
 fn test_tableview_page_navigation() {
     let mut view = TableView::new(row_manager());
@@ -1011,6 +1341,142 @@ fn test_tableview_page_navigation() {
     assert_eq!(view.cursor_row, 9);
 }
 
+#[test]
+fn test_selection_summary() {
+    let mut view = TableView::new(row_manager());
+    view.support_row = 2;
+    view.support_col = 1;
+    view.cursor_row = 4;
+    view.cursor_col = 4;
+
+    assert_eq!(view.selection_summary(Mode::Visual), Some("3x4 block (12 cells)".to_string()));
+    assert_eq!(view.selection_summary(Mode::VisualRow), Some("3 rows".to_string()));
+    assert_eq!(view.selection_summary(Mode::VisualCol), Some("4 cols".to_string()));
+    assert_eq!(view.selection_summary(Mode::Normal), None);
+
+    view.cursor_row = 2;
+    assert_eq!(view.selection_summary(Mode::VisualRow), Some("1 row".to_string()));
+}
+
+#[test]
+fn test_visible_columns_frozen_offsets() {
+    let mut view = TableView::new(row_manager());
+    view.frozen_cols = 1;
+    view.viewport_col = 3;
+
+    // Every column is 5 wide including padding
+    let widths = vec![3; 6];
+    let layout = view.visible_columns(&widths, 15);
+
+    // Column A stays at the left edge, scrolling resumes at D
+    assert_eq!(layout, vec![(0, 0), (3, 5), (4, 10)]);
+}
+
+#[test]
+fn test_visible_columns_skip_hidden() {
+    let mut view = TableView::new(row_manager());
+    view.frozen_cols = 2;
+    view.hidden_cols.insert(1);
+    view.hidden_cols.insert(3);
+
+    let widths = vec![3; 6];
+    let layout = view.visible_columns(&widths, 30);
+
+    assert_eq!(layout, vec![(0, 0), (2, 5), (4, 10), (5, 15)]);
+}
+
+#[test]
+fn test_navigation_hops_over_hidden_columns() {
+    let table = make_table(vec![vec!["a", "b", "c", "d", "e"]]);
+    let mut view = TableView::new(row_manager());
+    view.hidden_cols.insert(1);
+    view.hidden_cols.insert(2);
+    view.hidden_cols.insert(4);
+
+    view.move_right(&table);
+    assert_eq!(view.cursor_col, 3);
+    // Nothing visible further right
+    view.move_right(&table);
+    assert_eq!(view.cursor_col, 3);
+    view.move_left();
+    assert_eq!(view.cursor_col, 0);
+
+    view.move_right_n(5, &table);
+    assert_eq!(view.cursor_col, 3);
+    view.move_left_n(1);
+    assert_eq!(view.cursor_col, 0);
+
+    view.move_to_last_col(&table);
+    assert_eq!(view.cursor_col, 3);
+}
+
+#[test]
+fn test_snap_to_visible_col_prefers_right() {
+    let table = make_table(vec![vec!["a", "b", "c"]]);
+    let mut view = TableView::new(row_manager());
+    view.cursor_col = 1;
+    view.hidden_cols.insert(1);
+    view.snap_to_visible_col(&table);
+    assert_eq!(view.cursor_col, 2);
+
+    view.hidden_cols.insert(2);
+    view.snap_to_visible_col(&table);
+    assert_eq!(view.cursor_col, 0);
+}
+
+#[test]
+fn test_visible_columns_unfrozen_offsets() {
+    let mut view = TableView::new(row_manager());
+    view.viewport_col = 2;
+
+    let widths = vec![3, 3, 8, 3];
+    let layout = view.visible_columns(&widths, 20);
+
+    assert_eq!(layout, vec![(2, 0), (3, 10)]);
+}
+
+#[test]
+fn test_visible_columns_keeps_scrolling_column_when_frozen_is_wide() {
+    let mut view = TableView::new(row_manager());
+    view.frozen_cols = 2;
+    view.viewport_col = 2;
+
+    // Frozen columns alone fill the width; one scrolling column is still shown
+    let widths = vec![8, 8, 3];
+    let layout = view.visible_columns(&widths, 20);
+
+    assert_eq!(layout, vec![(0, 0), (1, 10), (2, 20)]);
+}
+
+#[test]
+fn test_scroll_with_frozen_columns() {
+    let table = make_table(vec![vec!["a", "b", "c", "d", "e", "f", "g", "h"]]);
+    let mut view = TableView::new(row_manager());
+    view.frozen_cols = 2;
+    view.viewport_width = 4; // 2 frozen + 2 scrolling
+
+    // Moving right never scrolls a column under the frozen panel
+    for _ in 0..5 {
+        view.move_right(&table);
+    }
+    assert_eq!(view.cursor_col, 5);
+    assert_eq!(view.viewport_col, 4);
+
+    // Moving back into the frozen region leaves the viewport alone
+    view.move_to_first_col();
+    assert_eq!(view.viewport_col, 4);
+
+    // Leaving the frozen region scrolls back to the first scrolling column
+    view.move_right(&table);
+    view.move_right(&table);
+    assert_eq!(view.cursor_col, 2);
+    assert_eq!(view.viewport_col, 2);
+
+    view.jump_right(&table);
+    assert_eq!(view.cursor_col, 7);
+    assert_eq!(view.viewport_col, 6);
+}
+
 // === Sorting tests ===
 
 #[test]
@@ -1028,6 +1494,23 @@ fn test_probe_column_type_numeric() {
     assert_eq!(table.probe_column_type(1, true), ColumnType::Numeric);
 }
 
+#[test]
+fn test_looks_like_identifiers() {
+    let mut table = make_table(vec![
+        vec!["Year", "Amount", "Zip", "Total", "Ref"],
+        vec!["2023", "1200", "02134", "1500.5", "1234"],
+        vec!["2024", "98000", "10001", "12", "5678"],
+    ]);
+    assert!(table.looks_like_identifiers(0, true));
+    assert!(!table.looks_like_identifiers(1, true));
+    assert!(table.looks_like_identifiers(2, true));
+    assert!(!table.looks_like_identifiers(3, true));
+    // Same-width whole numbers look like codes until declared numeric
+    assert!(table.looks_like_identifiers(4, true));
+    table.set_declared_type(4, Some(ColumnType::Numeric));
+    assert!(!table.looks_like_identifiers(4, true));
+}
+
 #[test]
 fn test_probe_column_type_mixed() {
     let table = make_table(vec![
@@ -1344,39 +1827,632 @@ fn test_sort_negative_numbers_no_data_loss_parallel() {
     assert_sort_preserves_rows(rows, 0, SortDirection::Ascending, false);
 }
 
-// === Bulk row operations ===
-
 #[test]
-fn test_delete_rows_bulk_single_chunk() {
-    let mut table = make_table(vec![
-        vec!["a", "1"],
-        vec!["b", "2"],
-        vec!["c", "3"],
-        vec!["d", "4"],
-        vec!["e", "5"],
+fn test_sort_preserves_order_of_equal_keys() {
+    let table = make_table(vec![
+        vec!["b", "x1"],
+        vec!["a", "x2"],
+        vec!["b", "x3"],
+        vec!["c", "x4"],
+        vec!["b", "x5"],
     ]);
 
-    let deleted = table.delete_rows_bulk(1, 2);
+    // The three "b" rows must keep their input order in both directions
+    let asc = table.get_sorted_row_indices(0, SortDirection::Ascending, false);
+    assert_eq!(asc, vec![1, 0, 2, 4, 3]);
 
-    assert_eq!(deleted.len(), 2);
-    assert_eq!(deleted[0], vec!["b", "2"]);
-    assert_eq!(deleted[1], vec!["c", "3"]);
-    assert_eq!(table.row_count(), 3);
-    assert_eq!(row(&table, 0), vec!["a", "1"]);
-    assert_eq!(row(&table, 1), vec!["d", "4"]);
-    assert_eq!(row(&table, 2), vec!["e", "5"]);
+    let desc = table.get_sorted_row_indices(0, SortDirection::Descending, false);
+    assert_eq!(desc, vec![3, 0, 2, 4, 1]);
 }
 
 #[test]
-fn test_delete_rows_bulk_at_start() {
-    let mut table = make_table(vec![
-        vec!["a"],
-        vec!["b"],
-        vec!["c"],
-        vec!["d"],
+fn test_sort_all_equal_keys_is_identity() {
+    let table = make_table(vec![
+        vec!["Key", "Val"],
+        vec!["7", "c"],
+        vec!["7", "a"],
+        vec!["7", "b"],
     ]);
 
-    let deleted = table.delete_rows_bulk(0, 2);
+    assert!(table.get_sort_permutation(0, SortDirection::Ascending, true).is_none());
+    assert!(table.get_sort_permutation(0, SortDirection::Descending, true).is_none());
+}
+
+#[test]
+fn test_get_sorted_row_indices_multi_tiebreak() {
+    let table = make_table(vec![
+        vec!["Region", "Revenue"],
+        vec!["West", "100"],
+        vec!["East", "300"],
+        vec!["West", "50"],
+        vec!["East", "20"],
+    ]);
+
+    // Region ascending, then revenue ascending within region
+    let keys = [(0, SortDirection::Ascending), (1, SortDirection::Ascending)];
+    let indices = table.get_sorted_row_indices_multi(&keys, true, None);
+
+    // Expected: header, East 20, East 300, West 50, West 100
+    assert_eq!(indices, vec![0, 4, 2, 3, 1]);
+}
+
+#[test]
+fn test_get_sorted_row_indices_multi_mixed_directions() {
+    let table = make_table(vec![
+        vec!["West", "100"],
+        vec!["East", "300"],
+        vec!["West", "50"],
+        vec!["East", "20"],
+    ]);
+
+    // Region ascending, then revenue descending, no header
+    let keys = [(0, SortDirection::Ascending), (1, SortDirection::Descending)];
+    let indices = table.get_sorted_row_indices_multi(&keys, false, None);
+
+    assert_eq!(indices, vec![1, 3, 0, 2]);
+}
+
+#[test]
+fn test_multi_sort_permutation_already_sorted() {
+    let table = make_table(vec![
+        vec!["Region", "Revenue"],
+        vec!["East", "20"],
+        vec!["East", "300"],
+        vec!["West", "50"],
+    ]);
+
+    let keys = [(0, SortDirection::Ascending), (1, SortDirection::Ascending)];
+    assert!(table.get_multi_sort_permutation(&keys, true, None).is_none());
+}
+
+#[test]
+fn test_sort_by_columns_records_single_permutation() {
+    let mut table = make_table(vec![
+        vec!["Region", "Revenue"],
+        vec!["West", "100"],
+        vec!["East", "300"],
+        vec!["East", "20"],
+    ]);
+    let mut view_state = crate::viewstate::ViewState::new();
+
+    let keys = [(0, SortDirection::Ascending), (1, SortDirection::Ascending)];
+    let txn = sort_by_columns(&keys, true, None, &mut table, &mut view_state)
+        .expect("table is not already sorted");
+
+    assert!(matches!(txn, crate::transaction::transaction::Transaction::PermuteRows { .. }));
+    assert_eq!(row(&table, 0), vec!["Region", "Revenue"]);
+    assert_eq!(row(&table, 1), vec!["East", "20"]);
+    assert_eq!(row(&table, 2), vec!["East", "300"]);
+    assert_eq!(row(&table, 3), vec!["West", "100"]);
+}
+
+#[test]
+fn test_cancelled_background_sort_leaves_table_unchanged() {
+    let rows: Vec<Vec<String>> = (0..60_000).rev().map(|i| vec![i.to_string()]).collect();
+    let mut table = Table::new(rows);
+    let mut view_state = crate::viewstate::ViewState::new();
+
+    assert!(sort_by_columns(&[(0, SortDirection::Ascending)], false, None, &mut table, &mut view_state).is_none());
+    assert!(view_state.bg_receiver.is_some());
+
+    assert_eq!(view_state.cancel_background().as_deref(), Some("Sort cancelled"));
+    assert!(view_state.bg_receiver.is_none() && view_state.bg_handle.is_none() && view_state.bg_cancel.is_none());
+    assert!(view_state.progress.is_none());
+    assert_eq!(view_state.cancel_background(), None);
+
+    let mut history = crate::transaction::history::History::new();
+    assert_eq!(view_state.poll_background_result(&mut table, &mut history), (None, crate::viewstate::BackgroundOutcome::Unchanged));
+    assert_eq!(cell(&table, 0, 0), "59999");
+}
+
+#[test]
+fn test_cancelled_background_sort_sends_no_result() {
+    let rows: Vec<Vec<String>> = (0..60_000).rev().map(|i| vec![i.to_string()]).collect();
+    let mut table = Table::new(rows);
+    let mut view_state = crate::viewstate::ViewState::new();
+
+    assert!(sort_by_columns(&[(0, SortDirection::Ascending)], false, None, &mut table, &mut view_state).is_none());
+    let receiver = view_state.bg_receiver.take().expect("sort runs in the background");
+    let handle = view_state.bg_handle.take().expect("sort runs in the background");
+    view_state.bg_cancel.take().unwrap().store(true, std::sync::atomic::Ordering::Relaxed);
+    handle.join().unwrap();
+
+    assert!(receiver.try_recv().is_err());
+}
+
+#[test]
+fn test_natural_sort_orders_embedded_numbers() {
+    let mut table = make_table(vec![
+        vec!["Name"],
+        vec!["file10"],
+        vec!["file2"],
+        vec!["file1"],
+        vec!["file"],
+    ]);
+    let mut view_state = crate::viewstate::ViewState::new();
+
+    // Plain text sort puts "file10" before "file2"
+    assert_eq!(table.get_sorted_row_indices(0, SortDirection::Ascending, true), vec![0, 4, 3, 1, 2]);
+
+    sort_by_columns(&[(0, SortDirection::Ascending)], true, Some(ColumnType::Natural), &mut table, &mut view_state)
+        .expect("table is not already sorted");
+    let names: Vec<String> = (0..5).map(|r| cell(&table, r, 0)).collect();
+    assert_eq!(names, vec!["Name", "file", "file1", "file2", "file10"]);
+}
+
+#[test]
+fn test_natural_sort_ignores_case() {
+    let table = make_table(vec![
+        vec!["Item12"],
+        vec!["item3"],
+        vec!["ITEM3b"],
+        vec!["apple007"],
+        vec!["Apple8"],
+    ]);
+
+    let indices = table.get_sorted_row_indices_multi(&[(0, SortDirection::Ascending)], false, Some(ColumnType::Natural));
+    // apple007, Apple8, item3, ITEM3b, Item12
+    assert_eq!(indices, vec![3, 4, 1, 2, 0]);
+}
+
+#[test]
+fn test_probe_sort_type_natural_only_for_text() {
+    let table = make_table(vec![
+        vec!["a1", "1"],
+        vec!["a2", "2"],
+    ]);
+    assert_eq!(table.probe_sort_type(0, false, Some(ColumnType::Natural)), ColumnType::Natural);
+    assert_eq!(table.probe_sort_type(0, false, None), ColumnType::Text);
+    assert_eq!(table.probe_sort_type(1, false, Some(ColumnType::Natural)), ColumnType::Numeric);
+}
+
+#[test]
+fn test_declared_type_overrides_probe() {
+    // Mostly numeric codes: probing calls this column numeric
+    let mut table = make_table(vec![
+        vec!["10"],
+        vec!["9"],
+        vec!["x"],
+    ]);
+    assert_eq!(table.column_type(0, false), ColumnType::Numeric);
+    assert_eq!(table.get_sorted_row_indices(0, SortDirection::Ascending, false), vec![1, 0, 2]);
+
+    table.set_declared_type(0, Some(ColumnType::Text));
+    assert_eq!(table.column_type(0, false), ColumnType::Text);
+    assert_eq!(table.get_sorted_row_indices_multi(&[(0, SortDirection::Ascending)], false, None), vec![0, 1, 2]);
+
+    table.set_declared_type(0, None);
+    assert_eq!(table.declared_type(0), None);
+    assert_eq!(table.column_type(0, false), ColumnType::Numeric);
+}
+
+#[test]
+fn test_declared_type_follows_column_moves() {
+    let mut table = make_table(vec![
+        vec!["a", "b", "c"],
+    ]);
+    table.set_declared_type(0, Some(ColumnType::Numeric));
+
+    table.apply_col_permutation(&[2, 0, 1]);
+    assert_eq!(table.declared_type(0), None);
+    assert_eq!(table.declared_type(1), Some(ColumnType::Numeric));
+
+    table.insert_col_at(0);
+    assert_eq!(table.declared_type(2), Some(ColumnType::Numeric));
+
+    table.delete_col_at(1);
+    assert_eq!(table.declared_type(1), Some(ColumnType::Numeric));
+}
+
+#[test]
+fn test_sort_spec_reversed_flips_every_key() {
+    let spec = SortSpec::Rows {
+        keys: vec![(0, SortDirection::Ascending), (2, SortDirection::Descending)],
+        as_type: Some(ColumnType::Natural),
+    };
+    assert_eq!(spec.reversed(), SortSpec::Rows {
+        keys: vec![(0, SortDirection::Descending), (2, SortDirection::Ascending)],
+        as_type: Some(ColumnType::Natural),
+    });
+    assert_eq!(spec.reversed().reversed(), spec);
+
+    let spec = SortSpec::Cols { row: 3, direction: SortDirection::Ascending };
+    assert_eq!(spec.reversed(), SortSpec::Cols { row: 3, direction: SortDirection::Descending });
+}
+
+#[test]
+fn test_reversed_sort_is_undoable_permutation() {
+    let mut table = make_table(vec![
+        vec!["Name"],
+        vec!["b"],
+        vec!["c"],
+        vec!["a"],
+    ]);
+    let mut view_state = crate::viewstate::ViewState::new();
+    let names = |table: &Table| -> Vec<String> { (0..4).map(|r| cell(table, r, 0)).collect() };
+
+    let spec = SortSpec::by_column(0, SortDirection::Ascending, None);
+    let SortSpec::Rows { keys, as_type } = &spec else { unreachable!() };
+    sort_by_columns(keys, true, *as_type, &mut table, &mut view_state).unwrap();
+    assert_eq!(names(&table), vec!["Name", "a", "b", "c"]);
+
+    let SortSpec::Rows { keys, as_type } = spec.reversed() else { unreachable!() };
+    let txn = sort_by_columns(&keys, true, as_type, &mut table, &mut view_state).unwrap();
+    assert_eq!(names(&table), vec!["Name", "c", "b", "a"]);
+
+    txn.inverse().apply(&mut table);
+    assert_eq!(names(&table), vec!["Name", "a", "b", "c"]);
+}
+
+// === Replace tests ===
+
+fn replace_cmd(pattern: &str, replacement: &str, global: bool, regex: bool) -> crate::mode::command::ReplaceCommand {
+    crate::mode::command::ReplaceCommand {
+        pattern: pattern.to_string(),
+        replacement: replacement.to_string(),
+        global,
+        regex,
+        smart_case: false,
+        count_only: false,
+        confirm: false,
+        scope: crate::mode::command::ReplaceScope::All,
+    }
+}
+
+#[test]
+fn test_replace_regex_capture_groups() {
+    let mut table = make_table(vec![
+        vec!["alice@example", "bob@test"],
+    ]);
+    let mut view = TableView::new(row_manager());
+
+    let (txn, _) = replace(replace_cmd(r"(\w+)@(\w+)", "$2:$1", true, true), &mut table, &mut view, None);
+    txn.unwrap().apply(&mut table);
+
+    assert_eq!(cell(&table, 0, 0), "example:alice");
+    assert_eq!(cell(&table, 0, 1), "test:bob");
+}
+
+#[test]
+fn test_replace_literal_ignores_regex_syntax() {
+    let mut table = make_table(vec![
+        vec!["a.b", "axb"],
+    ]);
+    let mut view = TableView::new(row_manager());
+
+    let (txn, _) = replace(replace_cmd("a.b", "z", true, false), &mut table, &mut view, None);
+    txn.unwrap().apply(&mut table);
+
+    assert_eq!(cell(&table, 0, 0), "z");
+    assert_eq!(cell(&table, 0, 1), "axb");
+}
+
+#[test]
+fn test_replace_regex_empty_match_terminates() {
+    let mut table = make_table(vec![
+        vec!["ab"],
+    ]);
+    let mut view = TableView::new(row_manager());
+
+    let (txn, _) = replace(replace_cmd("x*", "-", true, true), &mut table, &mut view, None);
+    txn.unwrap().apply(&mut table);
+
+    assert_eq!(cell(&table, 0, 0), "-a-b-");
+}
+
+#[test]
+fn test_replace_invalid_regex_reports_error() {
+    let mut table = make_table(vec![
+        vec!["ab"],
+    ]);
+    let mut view = TableView::new(row_manager());
+
+    let (txn, msg) = replace(replace_cmd("(", "-", true, true), &mut table, &mut view, None);
+
+    assert!(txn.is_none());
+    assert!(msg.unwrap().starts_with("Invalid regex:"));
+}
+
+#[test]
+fn test_replace_smart_case_follows_each_match() {
+    let mut table = make_table(vec![
+        vec!["color", "Color", "COLOR", "cOLOR"],
+        vec!["the color of COLOR", "", "", ""],
+    ]);
+    let mut view = TableView::new(row_manager());
+    let mut cmd = replace_cmd("color", "colour", true, false);
+    cmd.smart_case = true;
+
+    let (txn, _) = replace(cmd, &mut table, &mut view, None);
+    txn.unwrap().apply(&mut table);
+
+    assert_eq!(row(&table, 0), vec!["colour", "Colour", "COLOUR", "colour"]);
+    assert_eq!(cell(&table, 1, 0), "the colour of COLOUR");
+}
+
+#[test]
+fn test_replace_smart_case_expands_regex_groups() {
+    let mut table = make_table(vec![vec!["GRAY-cat", "Gray-dog"]]);
+    let mut view = TableView::new(row_manager());
+    let mut cmd = replace_cmd(r"gray-(\w+)", "grey $1", true, true);
+    cmd.smart_case = true;
+
+    let (txn, _) = replace(cmd, &mut table, &mut view, None);
+    txn.unwrap().apply(&mut table);
+
+    // The whole match decides the case: GRAY-cat is mixed, Gray-dog is Titlecase
+    assert_eq!(row(&table, 0), vec!["grey cat", "Grey dog"]);
+}
+
+#[test]
+fn test_replace_count_only_leaves_table_alone() {
+    let mut table = make_table(vec![
+        vec!["a-a-a", "b"],
+        vec!["a", "A"],
+    ]);
+    let mut view = TableView::new(row_manager());
+    let mut cmd = replace_cmd("a", "z", true, false);
+    cmd.count_only = true;
+
+    let (txn, msg) = replace(cmd.clone(), &mut table, &mut view, None);
+    assert!(txn.is_none());
+    assert_eq!(msg.as_deref(), Some("4 matches across 2 cells"));
+    assert_eq!(row(&table, 0), vec!["a-a-a", "b"]);
+
+    // Without /g only the first cell counts, once; /i matches any case
+    cmd.global = false;
+    cmd.smart_case = true;
+    let (_, msg) = replace(cmd, &mut table, &mut view, None);
+    assert_eq!(msg.as_deref(), Some("1 matches across 1 cells"));
+}
+
+#[test]
+fn test_replace_count_only_matches_real_replace() {
+    let table = make_table(vec![
+        vec!["a-a", "a"],
+        vec!["a", "b"],
+    ]);
+    let mut view = TableView::new(row_manager());
+    let cases = [(false, "1 matches across 1 cells", vec!["z-a", "a", "a", "b"]),
+                 (true, "4 matches across 3 cells", vec!["z-z", "z", "z", "b"])];
+    for (global, counted, replaced_cells) in cases {
+        let mut dry_run = replace_cmd("a", "z", global, false);
+        dry_run.count_only = true;
+        let (_, msg) = replace(dry_run, &mut table.clone(), &mut view, None);
+        assert_eq!(msg.as_deref(), Some(counted));
+
+        let mut replaced = table.clone();
+        let (txn, _) = replace(replace_cmd("a", "z", global, false), &mut replaced, &mut view, None);
+        txn.unwrap().apply(&mut replaced);
+        let cells: Vec<String> = replaced.rows_iter().flatten().cloned().collect();
+        assert_eq!(cells, replaced_cells);
+    }
+}
+
+#[test]
+fn test_confirmed_replace_applies_only_accepted_matches() {
+    let mut table = make_table(vec![
+        vec!["a-a", "b"],
+        vec!["xa", "a"],
+    ]);
+    let view = TableView::new(row_manager());
+    let matches = find_replace_matches(&replace_cmd("a", "Z", true, false), &table, &view, None).unwrap();
+    let positions: Vec<(usize, usize, usize)> = matches.iter().map(|m| (m.row, m.col, m.range.start)).collect();
+    assert_eq!(positions, vec![(0, 0, 0), (0, 0, 2), (1, 0, 1), (1, 1, 0)]);
+
+    let mut confirm = ReplaceConfirm::new(matches);
+    confirm.answer(false);
+    confirm.answer(true);
+    assert_eq!(confirm.current().map(|m| (m.row, m.col)), Some((1, 0)));
+    confirm.accept_rest();
+    assert!(confirm.current().is_none());
+    assert_eq!(confirm.accepted_count(), 3);
+
+    confirm.into_transaction(&table).unwrap().apply(&mut table);
+    assert_eq!(row(&table, 0), vec!["a-Z", "b"]);
+    assert_eq!(row(&table, 1), vec!["xZ", "Z"]);
+}
+
+#[test]
+fn test_confirmed_replace_without_g_offers_what_replace_changes() {
+    let table = make_table(vec![
+        vec!["a-a", "a"],
+        vec!["a", "b"],
+    ]);
+    let view = TableView::new(row_manager());
+    let matches = find_replace_matches(&replace_cmd("a", "z", false, false), &table, &view, None).unwrap();
+    let positions: Vec<(usize, usize, usize)> = matches.iter().map(|m| (m.row, m.col, m.range.start)).collect();
+    assert_eq!(positions, vec![(0, 0, 0)]);
+}
+
+#[test]
+fn test_replace_skips_filtered_rows() {
+    let mut table = make_table(vec![vec!["a"], vec!["a"], vec!["a"]]);
+    let mut view = TableView::new(row_manager_filtered(vec![0, 2]));
+
+    let matches = find_replace_matches(&replace_cmd("a", "b", true, false), &table, &view, None).unwrap();
+    assert_eq!(matches.iter().map(|m| m.row).collect::<Vec<_>>(), vec![0, 2]);
+
+    let mut dry_run = replace_cmd("a", "b", true, false);
+    dry_run.count_only = true;
+    let (_, msg) = replace(dry_run, &mut table, &mut view, None);
+    assert_eq!(msg.as_deref(), Some("2 matches across 2 cells"));
+
+    let (txn, _) = replace(replace_cmd("a", "b", true, false), &mut table, &mut view, None);
+    txn.unwrap().apply(&mut table);
+    assert_eq!(table.get_col_cloned(0).unwrap(), vec!["b", "a", "b"]);
+}
+
+// === Aggregate tests ===
+
+#[test]
+fn test_describe_numeric_column() {
+    let table = make_table(vec![
+        vec!["Amount"], vec!["4"], vec!["$2"], vec![""], vec!["8"], vec!["n/a"], vec!["6"],
+    ]);
+    let view = TableView::new(row_manager());
+    let lines = describe_column(&table, 0, &view, true);
+    let get = |label: &str| lines.iter().find(|(l, _)| *l == label).map(|(_, v)| v.as_str());
+
+    assert_eq!(get("count"), Some("5"));
+    assert_eq!(get("numeric"), Some("4"));
+    assert_eq!(get("blank"), Some("1"));
+    assert_eq!(get("min"), Some("2"));
+    assert_eq!(get("max"), Some("8"));
+    assert_eq!(get("mean"), Some("5"));
+    assert_eq!(get("median"), Some("5"));
+    assert!(get("std").unwrap().starts_with("2.58"));
+    assert_eq!(get("distinct"), None);
+}
+
+#[test]
+fn test_describe_text_column_respects_filter() {
+    let table = make_table(vec![
+        vec!["City"], vec!["Oslo"], vec!["Rome"], vec!["Rome"], vec!["Oslo"], vec!["Oslo"],
+    ]);
+    let view = TableView::new(row_manager_filtered(vec![0, 1, 2, 3]));
+    let lines = describe_column(&table, 0, &view, true);
+    let get = |label: &str| lines.iter().find(|(l, _)| *l == label).map(|(_, v)| v.as_str());
+
+    assert_eq!(get("count"), Some("3"));
+    assert_eq!(get("distinct"), Some("2"));
+    assert_eq!(get("top"), Some("Rome"));
+    assert_eq!(get("freq"), Some("2"));
+    assert_eq!(get("mean"), None);
+}
+
+#[test]
+fn test_group_by_counts_sorted_with_blank_group() {
+    let table = make_table(vec![
+        vec!["City", "Sales"],
+        vec!["Oslo", "10"],
+        vec!["Rome", "5"],
+        vec!["", "1"],
+        vec!["Rome", "$7"],
+        vec!["Paris", "n/a"],
+        vec!["Rome", "3"],
+        vec!["  ", "2"],
+    ]);
+    let view = TableView::new(row_manager());
+
+    let groups = group_by(&table, 0, Some(1), &view, true);
+    let summary: Vec<(&str, usize, Option<f64>)> = groups.iter().map(|g| (g.value.as_str(), g.count, g.sum)).collect();
+    assert_eq!(summary, vec![
+        ("Rome", 3, Some(15.0)),
+        ("(blank)", 2, Some(3.0)),
+        ("Oslo", 1, Some(10.0)),
+        ("Paris", 1, Some(0.0)),
+    ]);
+
+    let counts = group_by(&table, 0, None, &view, false);
+    assert_eq!(counts.len(), 5);
+    assert_eq!(counts[0].sum, None);
+    assert!(counts.iter().any(|g| g.value == "City" && g.count == 1));
+}
+
+#[test]
+fn test_group_by_respects_filter() {
+    let table = make_table(vec![
+        vec!["City"], vec!["Oslo"], vec!["Rome"], vec!["Rome"], vec!["Oslo"], vec!["Oslo"],
+    ]);
+    let view = TableView::new(row_manager_filtered(vec![0, 1, 2, 3]));
+    let groups = group_by(&table, 0, None, &view, true);
+    let counts: Vec<(&str, usize)> = groups.iter().map(|g| (g.value.as_str(), g.count)).collect();
+    assert_eq!(counts, vec![("Rome", 2), ("Oslo", 1)]);
+}
+
+#[test]
+fn test_aggregate_column_skips_header_and_text() {
+    let table = make_table(vec![
+        vec!["Amount"],
+        vec!["$1,000"],
+        vec!["n/a"],
+        vec!["234.5"],
+        vec![""],
+    ]);
+    let view = TableView::new(row_manager());
+
+    let msg = aggregate(crate::mode::command::AggKind::Sum, &table, &view, None, true);
+    assert_eq!(msg, "sum=1234.5 (2/3 numeric)");
+
+    let msg = aggregate(crate::mode::command::AggKind::Max, &table, &view, None, true);
+    assert_eq!(msg, "max=1000 (2/3 numeric)");
+}
+
+#[test]
+fn test_aggregate_respects_filter() {
+    let table = make_table(vec![
+        vec!["1"],
+        vec!["2"],
+        vec!["3"],
+    ]);
+    let view = TableView::new(row_manager_filtered(vec![0, 2]));
+
+    let msg = aggregate(crate::mode::command::AggKind::Avg, &table, &view, None, false);
+    assert_eq!(msg, "avg=2 (2/2 numeric)");
+}
+
+#[test]
+fn test_aggregate_visual_selection() {
+    let table = make_table(vec![
+        vec!["1", "2", "100"],
+        vec!["3", "4", "100"],
+    ]);
+    let mut view = TableView::new(row_manager());
+    view.support_row = 0;
+    view.support_col = 0;
+    view.cursor_row = 1;
+    view.cursor_col = 1;
+
+    let msg = aggregate(crate::mode::command::AggKind::Min, &table, &view, Some(Mode::Visual), false);
+    assert_eq!(msg, "min=1 (4/4 numeric)");
+}
+
+#[test]
+fn test_aggregate_no_numeric_cells() {
+    let table = make_table(vec![
+        vec!["a"],
+        vec!["b"],
+    ]);
+    let view = TableView::new(row_manager());
+
+    let msg = aggregate(crate::mode::command::AggKind::Sum, &table, &view, None, false);
+    assert_eq!(msg, "sum: no numeric cells (2 skipped)");
+}
+
+// === Bulk row operations ===
+
+#[test]
+fn test_delete_rows_bulk_single_chunk() {
+    let mut table = make_table(vec![
+        vec!["a", "1"],
+        vec!["b", "2"],
+        vec!["c", "3"],
+        vec!["d", "4"],
+        vec!["e", "5"],
+    ]);
+
+    let deleted = table.delete_rows_bulk(1, 2);
+
+    assert_eq!(deleted.len(), 2);
+    assert_eq!(deleted[0], vec!["b", "2"]);
+    assert_eq!(deleted[1], vec!["c", "3"]);
+    assert_eq!(table.row_count(), 3);
+    assert_eq!(row(&table, 0), vec!["a", "1"]);
+    assert_eq!(row(&table, 1), vec!["d", "4"]);
+    assert_eq!(row(&table, 2), vec!["e", "5"]);
+}
+
+#[test]
+fn test_delete_rows_bulk_at_start() {
+    let mut table = make_table(vec![
+        vec!["a"],
+        vec!["b"],
+        vec!["c"],
+        vec!["d"],
+    ]);
+
+    let deleted = table.delete_rows_bulk(0, 2);
 
     assert_eq!(deleted.len(), 2);
     // Verify deleted rows are in correct order
@@ -1691,6 +2767,39 @@ fn row_manager_filtered(active_rows: Vec<usize>) -> Rc<RefCell<RowManager>> {
     Rc::new(RefCell::new(rm))
 }
 
+#[test]
+fn test_row_manager_follows_inserted_and_deleted_rows() {
+    let rm = row_manager_filtered(vec![0, 2, 5, 7]);
+    let mut rm = rm.borrow_mut();
+
+    rm.rows_deleted(4, 2);
+    assert_eq!(rm.active_rows, vec![0, 2, 5]);
+    assert!(rm.is_row_live(5) && !rm.is_row_live(7));
+
+    rm.rows_inserted(2, 2);
+    assert_eq!(rm.active_rows, vec![0, 2, 3, 4, 7]);
+    assert!(rm.is_row_live(3) && rm.is_row_live(7));
+
+    let mut unfiltered = RowManager::new();
+    unfiltered.rows_deleted(0, 3);
+    assert!(!unfiltered.is_filtered && unfiltered.active_rows.is_empty());
+}
+
+#[test]
+fn test_row_manager_follows_permuted_rows() {
+    let rm = row_manager_filtered(vec![0, 2, 3]);
+    let mut rm = rm.borrow_mut();
+
+    // Row 0 stays, old row 3 moves to 1 and old row 2 to 3
+    rm.apply_permutation(&[0, 3, 1, 2]);
+    assert_eq!(rm.active_rows, vec![0, 1, 3]);
+    assert!(rm.is_row_live(1) && !rm.is_row_live(2));
+
+    let mut unfiltered = RowManager::new();
+    unfiltered.apply_permutation(&[1, 0]);
+    assert!(!unfiltered.is_filtered && unfiltered.active_rows.is_empty());
+}
+
 #[test]
 fn test_move_down_with_filter() {
     // Table has 10 rows, but only rows 0, 2, 5, 8 are active
@@ -1789,6 +2898,23 @@ fn test_move_down_n_with_filter() {
     assert_eq!(view.cursor_row, 6);
 }
 
+#[test]
+fn test_goto_row_counts_live_rows() {
+    let rm = row_manager_filtered(vec![0, 3, 6, 9, 12]);
+    let mut view = TableView::new(rm);
+    let table = make_table(vec![vec!["x"]; 15]);
+
+    view.goto_row(2, &table);
+    assert_eq!(view.cursor_row, 6);
+    // Past the last live row clamps to it
+    view.goto_row(40, &table);
+    assert_eq!(view.cursor_row, 12);
+
+    let mut view = TableView::new(row_manager());
+    view.goto_row(40, &table);
+    assert_eq!(view.cursor_row, 14);
+}
+
 #[test]
 fn test_move_up_n_with_filter() {
     let rm = row_manager_filtered(vec![0, 3, 6, 9, 12]);
@@ -1846,3 +2972,260 @@ fn test_scroll_to_cursor_with_filter() {
     assert!(view.viewport_row <= view.cursor_row);
     assert!(view.cursor_row < view.viewport_row + view.viewport_height + 50);
 }
+
+#[test]
+fn move_rows_swaps_block_with_neighbour() {
+    let mut table = make_table(vec![vec!["a"], vec!["b"], vec!["c"], vec!["d"]]);
+
+    // Rows 1-2 move down past row 3
+    let txn = move_rows(&table, 1, 2, true, false).unwrap();
+    txn.apply(&mut table);
+    let cells: Vec<String> = (0..table.row_count()).map(|r| cell(&table, r, 0)).collect();
+    assert_eq!(cells, vec!["a", "d", "b", "c"]);
+
+    txn.inverse().apply(&mut table);
+    let cells: Vec<String> = (0..table.row_count()).map(|r| cell(&table, r, 0)).collect();
+    assert_eq!(cells, vec!["a", "b", "c", "d"]);
+
+    move_rows(&table, 1, 2, false, false).unwrap().apply(&mut table);
+    let cells: Vec<String> = (0..table.row_count()).map(|r| cell(&table, r, 0)).collect();
+    assert_eq!(cells, vec!["b", "c", "a", "d"]);
+
+    // Nothing to swap with at the edges
+    assert!(move_rows(&table, 0, 1, false, false).is_none());
+    assert!(move_rows(&table, 2, 3, true, false).is_none());
+}
+
+#[test]
+fn move_rows_keeps_header_in_place() {
+    let table = make_table(vec![vec!["h"], vec!["a"], vec!["b"]]);
+    assert!(move_rows(&table, 1, 1, false, true).is_none());
+    assert!(move_rows(&table, 0, 1, true, true).is_none());
+    assert!(move_rows(&table, 1, 1, true, true).is_some());
+    assert!(move_rows(&table, 1, 1, false, false).is_some());
+}
+
+#[test]
+fn move_cols_carries_widths_and_types() {
+    let mut table = make_table(vec![
+        vec!["a", "bbbbb", "cccccccccc"],
+        vec!["1", "2", "3"],
+    ]);
+    table.set_declared_type(2, Some(ColumnType::Text));
+    let widths = table.col_widths();
+
+    // Column 2 moves to position 0 in two steps
+    move_cols(&table, 2, 2, false).unwrap().apply(&mut table);
+    move_cols(&table, 1, 1, false).unwrap().apply(&mut table);
+    assert_eq!(row(&table, 0), vec!["cccccccccc", "a", "bbbbb"]);
+    assert_eq!(table.col_widths(), vec![widths[2], widths[0], widths[1]]);
+    assert_eq!(table.declared_type(0), Some(ColumnType::Text));
+    assert_eq!(table.declared_type(2), None);
+
+    assert!(move_cols(&table, 0, 0, false).is_none());
+    assert!(move_cols(&table, 1, 2, true).is_none());
+}
+
+#[test]
+fn reverse_rows_keeps_header_and_undoes_itself() {
+    let mut table = make_table(vec![vec!["day"], vec!["3"], vec!["2"], vec!["1"]]);
+    let txn = reverse_rows(&table, true).unwrap();
+    assert!(matches!(&txn, crate::transaction::transaction::Transaction::PermuteRows { permutation } if *permutation == vec![0, 3, 2, 1]));
+    txn.apply(&mut table);
+    let cells: Vec<String> = (0..table.row_count()).map(|r| cell(&table, r, 0)).collect();
+    assert_eq!(cells, vec!["day", "1", "2", "3"]);
+
+    // A second reverse restores the original order
+    reverse_rows(&table, true).unwrap().apply(&mut table);
+    let cells: Vec<String> = (0..table.row_count()).map(|r| cell(&table, r, 0)).collect();
+    assert_eq!(cells, vec!["day", "3", "2", "1"]);
+
+    reverse_rows(&table, false).unwrap().apply(&mut table);
+    assert_eq!(cell(&table, 0, 0), "1");
+    assert!(reverse_rows(&make_table(vec![vec!["h"], vec!["x"]]), true).is_none());
+}
+
+#[test]
+fn reverse_cols_flips_column_order() {
+    let mut table = make_table(vec![vec!["a", "b", "c"]]);
+    reverse_cols(&table).unwrap().apply(&mut table);
+    assert_eq!(row(&table, 0), vec!["c", "b", "a"]);
+    reverse_cols(&table).unwrap().apply(&mut table);
+    assert_eq!(row(&table, 0), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn shuffle_rows_is_reproducible_with_a_seed() {
+    let rows: Vec<Vec<String>> = std::iter::once("id".to_string())
+        .chain((1..=20).map(|i| i.to_string()))
+        .map(|c| vec![c])
+        .collect();
+    let mut first = Table::new(rows.clone());
+    let mut second = Table::new(rows);
+
+    shuffle_rows(&first, true, Some(7)).unwrap().apply(&mut first);
+    shuffle_rows(&second, true, Some(7)).unwrap().apply(&mut second);
+    let order = |t: &Table| t.get_col_cloned(0).unwrap();
+    assert_eq!(order(&first), order(&second));
+    assert_eq!(cell(&first, 0, 0), "id");
+
+    let mut sorted = order(&first)[1..].to_vec();
+    sorted.sort_by_key(|c| c.parse::<usize>().unwrap());
+    assert_eq!(sorted, (1..=20).map(|i| i.to_string()).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_count_mismatches() {
+    let table = make_table(vec![
+        vec!["Expected", "Actual"],
+        vec!["10", "10"],
+        vec!["20", "21"],
+        vec!["", "x"],
+        vec!["", ""],
+    ]);
+    assert_eq!(count_mismatches(&table, 0, 1, true), 2);
+    // The header row counts when header mode is off
+    assert_eq!(count_mismatches(&table, 0, 1, false), 3);
+    assert_eq!(count_mismatches(&table, 1, 1, true), 0);
+    assert!(cells_differ(&table, 2, 0, 1));
+    assert!(!cells_differ(&table, 4, 0, 1));
+}
+
+#[test]
+fn test_dedup_single_column_keeps_first() {
+    let mut table = make_table(vec![
+        vec!["id", "name"],
+        vec!["1", "a"],
+        vec!["2", "b"],
+        vec!["1", "c"],
+        vec!["", "d"],
+        vec!["", "e"],
+    ]);
+    let original = table.clone_all_rows();
+
+    let txn = dedup_rows(&table, &[0], true).expect("has duplicates");
+    txn.apply(&mut table);
+    let names: Vec<String> = (0..table.row_count()).map(|r| cell(&table, r, 1)).collect();
+    // Later "1" and the second empty key are removed; header stays on top
+    assert_eq!(names, vec!["name", "a", "b", "d"]);
+
+    txn.inverse().apply(&mut table);
+    assert_eq!(table.clone_all_rows(), original);
+}
+
+#[test]
+fn test_dedup_composite_key() {
+    let mut table = make_table(vec![
+        vec!["x", "1", "first"],
+        vec!["x", "2", "second"],
+        vec!["y", "1", "third"],
+        vec!["x", "1", "fourth"],
+    ]);
+    assert!(dedup_rows(&table, &[0], false).is_some());
+
+    let txn = dedup_rows(&table, &[0, 1], false).expect("has duplicates");
+    txn.apply(&mut table);
+    let labels: Vec<String> = (0..table.row_count()).map(|r| cell(&table, r, 2)).collect();
+    assert_eq!(labels, vec!["first", "second", "third"]);
+
+    assert!(dedup_rows(&table, &[0, 1], false).is_none());
+}
+
+#[test]
+fn test_merge_three_columns() {
+    let mut table = make_table(vec![
+        vec!["id", "first", "middle", "last"],
+        vec!["1", "Ada", "", "Lovelace"],
+        vec!["2", "Alan", "M", "Turing"],
+    ]);
+    let original = table.clone_all_rows();
+
+    let txn = merge_cols(&table, 1, 3, " - ", false);
+    txn.apply(&mut table);
+    assert_eq!(table.col_count(), 2);
+    assert_eq!(table.get_row_cloned(0).unwrap(), vec!["id", "first - middle - last"]);
+    // Empty cells still contribute a separator
+    assert_eq!(table.get_row_cloned(1).unwrap(), vec!["1", "Ada -  - Lovelace"]);
+    assert_eq!(table.get_row_cloned(2).unwrap(), vec!["2", "Alan - M - Turing"]);
+
+    txn.inverse().apply(&mut table);
+    assert_eq!(table.clone_all_rows(), original);
+}
+
+#[test]
+fn test_merge_skip_empty() {
+    let mut table = make_table(vec![
+        vec!["Ada", "", "Lovelace"],
+        vec!["", "", ""],
+    ]);
+    merge_cols(&table, 0, 2, " ", true).apply(&mut table);
+    assert_eq!(cell(&table, 0, 0), "Ada Lovelace");
+    assert_eq!(cell(&table, 1, 0), "");
+}
+
+#[test]
+fn test_index_column_with_header() {
+    let mut table = make_table(vec![
+        vec!["name"],
+        vec!["b"],
+        vec!["a"],
+    ]);
+    let txn = index_column(&table, 1, "#", true);
+    txn.apply(&mut table);
+    assert_eq!(table.get_col_cloned(0).unwrap(), vec!["#", "1", "2"]);
+    assert_eq!(table.get_col_cloned(1).unwrap(), vec!["name", "b", "a"]);
+
+    txn.inverse().apply(&mut table);
+    assert_eq!(table.col_count(), 1);
+    assert_eq!(table.get_col_cloned(0).unwrap(), vec!["name", "b", "a"]);
+}
+
+#[test]
+fn test_index_column_zero_based_without_header() {
+    let mut table = make_table(vec![vec!["x"], vec!["y"], vec!["z"]]);
+    index_column(&table, 0, "#", false).apply(&mut table);
+    assert_eq!(table.get_col_cloned(0).unwrap(), vec!["0", "1", "2"]);
+}
+
+#[test]
+fn test_probe_detects_date_column() {
+    let table = make_table(vec![
+        vec!["When", "Amount"],
+        vec!["2023-12-31", "5"],
+        vec!["05.01.2023", "7"],
+        vec!["01/02/2023", "9"],
+    ]);
+    assert_eq!(table.probe_column_type(0, true), ColumnType::Date);
+    assert_eq!(table.probe_column_type(1, true), ColumnType::Numeric);
+}
+
+#[test]
+fn test_date_sort_is_chronological() {
+    let mut table = make_table(vec![
+        vec!["When"],
+        vec!["2023-12-31"],
+        vec!["2023-1-5"],
+        vec!["n/a"],
+        vec!["2023-02-01"],
+    ]);
+    let mut view_state = crate::viewstate::ViewState::new();
+
+    // As text, "2023-1-5" would sort after "2023-02-01" and "2023-12-31"
+    sort_by_columns(&[(0, SortDirection::Ascending)], true, None, &mut table, &mut view_state)
+        .expect("table is not already sorted");
+    let dates: Vec<String> = (0..5).map(|r| cell(&table, r, 0)).collect();
+    assert_eq!(dates, vec!["When", "2023-1-5", "2023-02-01", "2023-12-31", "n/a"]);
+}
+
+#[test]
+fn test_forced_date_sort_overrides_probe() {
+    let table = make_table(vec![
+        vec!["x"],
+        vec!["y"],
+        vec!["31.12.2023"],
+        vec!["2023-1-5"],
+    ]);
+    assert_eq!(table.probe_sort_type(0, false, Some(ColumnType::Date)), ColumnType::Date);
+    let indices = table.get_sorted_row_indices_multi(&[(0, SortDirection::Ascending)], false, Some(ColumnType::Date));
+    assert_eq!(indices, vec![3, 2, 0, 1]);
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use crate::fileio::split_delimited;
 use crate::table::table::Table;
 use crate::transaction::transaction::Transaction;
 
@@ -48,16 +49,47 @@ impl RegisterContent {
             anchor: PasteAnchor::Cursor,
         }
     }
+
+    /// Add another yank to this one: rows and spans go below, columns to the right
+    /// Fails without changing anything if the shapes don't line up
+    pub fn append(&mut self, other: RegisterContent) -> Result<(), String> {
+        let width = |data: &Vec<Vec<String>>| data.first().map(|r| r.len()).unwrap_or(0);
+        match (self.anchor, other.anchor) {
+            (PasteAnchor::ColStart, PasteAnchor::ColStart) => {
+                if self.data.len() != other.data.len() {
+                    return Err(format!(
+                        "Cannot append {} rows of columns to a register with {}",
+                        other.data.len(), self.data.len()
+                    ));
+                }
+                for (row, extra) in self.data.iter_mut().zip(other.data) {
+                    row.extend(extra);
+                }
+                Ok(())
+            }
+            (PasteAnchor::RowStart, PasteAnchor::RowStart) | (PasteAnchor::Cursor, PasteAnchor::Cursor) => {
+                if width(&self.data) != width(&other.data) {
+                    return Err(format!(
+                        "Cannot append {} columns to a register {} wide",
+                        width(&other.data), width(&self.data)
+                    ));
+                }
+                self.data.extend(other.data);
+                Ok(())
+            }
+            _ => Err("Cannot append a different kind of yank (rows, columns or cells) to the register".to_string()),
+        }
+    }
 }
 
 /// Vim-style register system
 ///
 /// Supported registers:
 /// - `"` (unnamed) - default register, used when no register specified
-/// - `a`-`z` - named registers for user storage
+/// - `a`-`z` - named registers for user storage (`A`-`Z` append to them)
 /// - `0` - yank register, stores last yank (not affected by delete)
 /// - `_` - black hole register, discards everything written to it
-/// - `+` - system clipboard register
+/// - `+` (or `*`) - system clipboard register
 pub struct Clipboard {
     /// Named registers (a-z) and special registers
     pub registers: HashMap<char, RegisterContent>,
@@ -67,6 +99,8 @@ pub struct Clipboard {
     pub yank_register: Option<RegisterContent>,
     /// Currently selected register for next operation (None = unnamed)
     pub selected: Option<char>,
+    /// Visual-mode pastes drop whatever falls outside the selection (:set clippaste)
+    pub clip_paste: bool,
 }
 
 impl Clipboard {
@@ -75,7 +109,8 @@ impl Clipboard {
             registers: HashMap::new(),
             unnamed: None,
             yank_register: None,
-            selected: None
+            selected: None,
+            clip_paste: false,
         }
     }
 
@@ -83,8 +118,14 @@ impl Clipboard {
     /// Returns error message if register is invalid
     pub fn select_register(&mut self, reg: char) -> Result<(), String> {
         match reg {
+            // Uppercase stays selected as-is so store() appends to the lowercase register
             'a'..='z' | 'A'..='Z' | '0' | '_' | '+' | '"' => {
-                self.selected = if reg == '"' { None } else { Some(reg.to_ascii_lowercase()) };
+                self.selected = if reg == '"' { None } else { Some(reg) };
+                Ok(())
+            }
+            // There is no separate primary selection, so * is the system clipboard too
+            '*' => {
+                self.selected = Some('+');
                 Ok(())
             }
             _ => Err(format!("Invalid register: {}", reg)),
@@ -103,13 +144,14 @@ impl Clipboard {
     /// Store content in the appropriate register
     /// - If black hole selected, discards the content
     /// - If yank=true, also updates register 0
+    /// - If an uppercase register is selected, appends to the lowercase one
     /// - Always updates unnamed register (unless black hole)
-    pub fn store(&mut self, content: RegisterContent, is_yank: bool) {
+    pub fn store(&mut self, content: RegisterContent, is_yank: bool) -> Result<(), String> {
         let reg = self.selected.take();
 
         // Black hole register - discard everything
         if reg == Some('_') {
-            return;
+            return Ok(());
         }
 
         // Update yank register if this is a yank operation
@@ -137,10 +179,28 @@ impl Clipboard {
                 self.registers.insert(c, content.clone());
                 self.unnamed = Some(content);
             }
+            Some(c) if c.is_ascii_uppercase() => {
+                // Append to named register; on a shape mismatch only the unnamed register gets the content
+                let name = c.to_ascii_lowercase();
+                let combined = match self.registers.get(&name) {
+                    Some(existing) => {
+                        let mut existing = existing.clone();
+                        if let Err(e) = existing.append(content.clone()) {
+                            self.unnamed = Some(content);
+                            return Err(e);
+                        }
+                        existing
+                    }
+                    None => content,
+                };
+                self.registers.insert(name, combined.clone());
+                self.unnamed = Some(combined);
+            }
             _ => {
                 self.unnamed = Some(content);
             }
         }
+        Ok(())
     }
 
     /// Retrieve content from the appropriate register
@@ -159,31 +219,47 @@ impl Clipboard {
                 }
             }
             Some('0') => self.yank_register.clone(),
-            Some(c) if c.is_ascii_lowercase() => {
-                self.registers.get(&c).cloned()
+            Some(c) if c.is_ascii_alphabetic() => {
+                self.registers.get(&c.to_ascii_lowercase()).cloned()
             }
             _ => self.unnamed.clone(),
         }
     }
 
     /// Convenience: yank multiple rows
-    pub fn yank_rows(&mut self, rows: Vec<Vec<String>>) {
-        self.store(RegisterContent::from_rows(rows), true);
+    pub fn yank_rows(&mut self, rows: Vec<Vec<String>>) -> Result<(), String> {
+        self.store(RegisterContent::from_rows(rows), true)
     }
 
     /// Convenience: yank multiple columns
-    pub fn yank_cols(&mut self, cols: Vec<Vec<String>>) {
-        self.store(RegisterContent::from_cols(cols), true);
+    pub fn yank_cols(&mut self, cols: Vec<Vec<String>>) -> Result<(), String> {
+        self.store(RegisterContent::from_cols(cols), true)
     }
 
     /// Convenience: yank a span
-    pub fn yank_span(&mut self, span: Vec<Vec<String>>) {
-        self.store(RegisterContent::from_span(span), true);
+    pub fn yank_span(&mut self, span: Vec<Vec<String>>) -> Result<(), String> {
+        self.store(RegisterContent::from_span(span), true)
+    }
+
+    /// Yank a single cell; with the system register selected it is copied there as well
+    pub fn yank_cell(&mut self, value: String) -> Result<String, String> {
+        let system = self.selected == Some('+');
+        if system {
+            // Yank into the unnamed register and copy it out with to_system, which reports failures
+            self.selected = None;
+        }
+        self.yank_span(vec![vec![value]])?;
+        if system {
+            self.to_system().map(|_| "Cell yanked to system clipboard".to_string())
+        } else {
+            Ok("Cell yanked".to_string())
+        }
     }
 
     /// Store deleted content (goes to unnamed but not yank register)
+    /// A failed append still leaves the content in the unnamed register, so deletes go ahead
     pub fn store_deleted(&mut self, content: RegisterContent) {
-        self.store(content, false);
+        let _ = self.store(content, false);
     }
 
     /// Create a paste transaction from the current register
@@ -198,7 +274,138 @@ impl Clipboard {
             None => return ("Nothing to paste".to_string(), None),
         };
 
-        if content.data.is_empty() {
+        Self::span_transaction(content, cursor_row, cursor_col, table)
+    }
+
+    /// Create a paste transaction repeating the current register over a selection
+    /// (start_row, end_row, start_col, end_col). Yanked rows repeat down, yanked columns
+    /// across and spans both ways; a selection that isn't a whole number of copies
+    /// gets a single paste at its corner and a warning
+    /// With clip_paste, cells that would land outside the selection are dropped
+    pub fn paste_fill_as_transaction(
+        &mut self,
+        bounds: (usize, usize, usize, usize),
+        table: &Table,
+    ) -> (String, Option<Transaction>) {
+        let content = match self.retrieve() {
+            Some(c) => c,
+            None => return ("Nothing to paste".to_string(), None),
+        };
+        let (start_row, end_row, start_col, end_col) = bounds;
+        let reg_rows = content.data.len();
+        let reg_cols = content.data.first().map(|r| r.len()).unwrap_or(0);
+        if reg_rows == 0 || reg_cols == 0 {
+            return ("Nothing to paste".to_string(), None);
+        }
+
+        let copies = |selected: usize, yanked: usize| selected.is_multiple_of(yanked).then_some(selected / yanked);
+        let sel_rows = end_row - start_row + 1;
+        let sel_cols = end_col - start_col + 1;
+        let (down, across) = match content.anchor {
+            PasteAnchor::RowStart => (copies(sel_rows, reg_rows), Some(1)),
+            PasteAnchor::ColStart => (Some(1), copies(sel_cols, reg_cols)),
+            PasteAnchor::Cursor => (copies(sel_rows, reg_rows), copies(sel_cols, reg_cols)),
+        };
+
+        let (content, warning) = match (down, across) {
+            (Some(down), Some(across)) => {
+                let data: Vec<Vec<String>> = content.data.iter()
+                    .cycle()
+                    .take(reg_rows * down)
+                    .map(|row| row.iter().cycle().take(reg_cols * across).cloned().collect())
+                    .collect();
+                (RegisterContent::new(data, content.anchor), None)
+            }
+            _ => (content, Some(format!("Selection is not a multiple of the yanked {}x{} block; pasted once", reg_rows, reg_cols))),
+        };
+
+        if self.clip_paste {
+            if let Some((clipped, row, col)) = Self::clip_to_selection(&content, bounds) {
+                let rows = clipped.data.len();
+                let cols = clipped.data.first().map(|r| r.len()).unwrap_or(0);
+                let (_, txn) = Self::span_transaction(clipped, row, col, table);
+                return (format!("Pasted {}x{} clipped to the selection", rows, cols), txn);
+            }
+        }
+
+        let (msg, txn) = Self::span_transaction(content, start_row, start_col, table);
+        match (warning, &txn) {
+            (Some(warning), Some(_)) => (warning, txn),
+            _ => (msg, txn),
+        }
+    }
+
+    /// The part of `content` pasted at the selection's corner that lands inside the selection,
+    /// with the cell it starts at; None if nothing falls outside it
+    fn clip_to_selection(content: &RegisterContent, bounds: (usize, usize, usize, usize)) -> Option<(RegisterContent, usize, usize)> {
+        let (start_row, end_row, start_col, end_col) = bounds;
+        let (origin_row, origin_col) = match content.anchor {
+            PasteAnchor::RowStart => (start_row, 0),
+            PasteAnchor::ColStart => (0, start_col),
+            PasteAnchor::Cursor => (start_row, start_col),
+        };
+        let rows = content.data.len();
+        let cols = content.data.first().map(|r| r.len()).unwrap_or(0);
+        let inside = origin_row >= start_row && origin_col >= start_col
+            && origin_row + rows <= end_row + 1 && origin_col + cols <= end_col + 1;
+        if inside {
+            return None;
+        }
+
+        let (row, col) = (origin_row.max(start_row), origin_col.max(start_col));
+        let data: Vec<Vec<String>> = content.data.iter()
+            .skip(row - origin_row)
+            .take((end_row + 1).saturating_sub(row))
+            .map(|r| r.iter().skip(col - origin_col).take((end_col + 1).saturating_sub(col)).cloned().collect())
+            .collect();
+        Some((RegisterContent::from_span(data), row, col))
+    }
+
+    /// Create a paste transaction from the current register with rows and columns swapped
+    /// Yanked rows are pasted as columns and yanked columns as rows
+    pub fn paste_transposed_as_transaction(
+        &mut self,
+        cursor_row: usize,
+        cursor_col: usize,
+        table: &Table,
+    ) -> (String, Option<Transaction>) {
+        let content = match self.retrieve() {
+            Some(c) => c,
+            None => return ("Nothing to paste".to_string(), None),
+        };
+
+        let width = content.data.iter().map(|r| r.len()).max().unwrap_or(0);
+        let data: Vec<Vec<String>> = (0..width)
+            .map(|c| {
+                content.data
+                    .iter()
+                    .map(|r| r.get(c).cloned().unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+
+        let anchor = match content.anchor {
+            PasteAnchor::RowStart => PasteAnchor::ColStart,
+            PasteAnchor::ColStart => PasteAnchor::RowStart,
+            PasteAnchor::Cursor => PasteAnchor::Cursor,
+        };
+
+        let (msg, txn) = Self::span_transaction(RegisterContent { data, anchor }, cursor_row, cursor_col, table);
+        if txn.is_some() {
+            (format!("{} (transposed)", msg), txn)
+        } else {
+            (msg, txn)
+        }
+    }
+
+    /// Build the SetSpan transaction that writes `content` relative to the cursor
+    fn span_transaction(
+        content: RegisterContent,
+        cursor_row: usize,
+        cursor_col: usize,
+        table: &Table,
+    ) -> (String, Option<Transaction>) {
+        if content.data.is_empty() || content.data[0].is_empty() {
             return ("Nothing to paste".to_string(), None);
         }
 
@@ -267,6 +474,34 @@ impl Clipboard {
         self.write_to_system(&content)
     }
 
+    /// Parse clipboard text as a block of cells: tab-separated if it has tabs, otherwise CSV
+    pub fn parse_block(text: &str) -> Result<Vec<Vec<String>>, String> {
+        let delimiter = if text.contains('\t') { b'\t' } else { b',' };
+        split_delimited(text.as_bytes(), delimiter)
+            .map_err(|e| format!("Could not parse clipboard: {}", e))
+    }
+
+    /// Create a transaction writing the system clipboard as a block at the cursor
+    pub fn paste_system_as_transaction(
+        &self,
+        cursor_row: usize,
+        cursor_col: usize,
+        table: &Table,
+    ) -> (String, Option<Transaction>) {
+        let data = match paste_from_system_clipboard().and_then(|text| Self::parse_block(&text)) {
+            Ok(data) => data,
+            Err(e) => return (e, None),
+        };
+        let rows = data.len();
+        let cols = data.first().map(|r| r.len()).unwrap_or(0);
+
+        let (msg, txn) = Self::span_transaction(RegisterContent::from_span(data), cursor_row, cursor_col, table);
+        match txn {
+            Some(txn) => (format!("Pasted {}x{} from system clipboard", rows, cols), Some(txn)),
+            None => (msg, None),
+        }
+    }
+
     /// Paste from system clipboard into unnamed register
     pub fn from_system(&mut self) -> Result<String, String> {
         let content = self.read_from_system()?;
@@ -277,29 +512,21 @@ impl Clipboard {
         Ok(format!("Yanked {}x{} from system clipboard", rows, cols))
     }
 
-    /// List non-empty registers (for :registers command)
-    #[allow(dead_code)]
-    pub fn list_registers(&self) -> Vec<(String, String)> {
-        let mut result = Vec::new();
-
-        // Unnamed register
-        if let Some(ref content) = self.unnamed {
-            result.push(("\"\"".to_string(), Self::preview_content(content)));
-        }
-
-        // Yank register
-        if let Some(ref content) = self.yank_register {
-            result.push(("\"0".to_string(), Self::preview_content(content)));
-        }
-
-        // Named registers (sorted)
-        let mut named: Vec<_> = self.registers.iter().collect();
+    /// Non-empty registers in display order: unnamed, yank (0), then named a-z
+    pub fn iter_registers(&self) -> impl Iterator<Item = (char, &RegisterContent)> {
+        let mut named: Vec<_> = self.registers.iter().map(|(&k, v)| (k, v)).collect();
         named.sort_by_key(|(k, _)| *k);
-        for (reg, content) in named {
-            result.push((format!("\"{}",reg), Self::preview_content(content)));
-        }
 
-        result
+        self.unnamed.iter().map(|c| ('"', c))
+            .chain(self.yank_register.iter().map(|c| ('0', c)))
+            .chain(named)
+    }
+
+    /// List non-empty registers with a preview of each (for :reg)
+    pub fn list_registers(&self) -> Vec<(String, String)> {
+        self.iter_registers()
+            .map(|(reg, content)| (format!("\"{}", reg), Self::preview_content(content)))
+            .collect()
     }
 
     fn preview_content(content: &RegisterContent) -> String {
@@ -309,11 +536,12 @@ impl Clipboard {
         // Show first cell as preview
         let preview = content.data.first()
             .and_then(|r| r.first())
+            .map(|s| s.replace('\n', "\\n"))
             .map(|s| {
                 if s.chars().count() > 20 {
                     format!("{}...", s.chars().take(20).collect::<String>())
                 } else {
-                    s.clone()
+                    s
                 }
             })
             .unwrap_or_default();
@@ -1,17 +1,61 @@
+use std::time::{Duration, Instant};
+
 use super::transaction::Transaction;
 
+/// Edits to the same cell recorded within this window undo as one step
+const MERGE_WINDOW: Duration = Duration::from_secs(2);
+
+/// Default maximum number of undo steps kept
+pub const DEFAULT_UNDO_LIMIT: usize = 1000;
+
 /// Manages undo/redo history
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct History {
     undo_stack: Vec<Transaction>,
     redo_stack: Vec<Transaction>,
+    /// When the top of the undo stack was last recorded (None after undo/redo)
+    last_recorded: Option<Instant>,
+    /// Same-cell edits closer together than this are merged (zero disables merging)
+    merge_window: Duration,
+    /// Maximum number of undo steps kept; the oldest are dropped beyond this
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl History {
     pub fn new() -> Self {
+        Self::with_merge_window(MERGE_WINDOW)
+    }
+
+    /// Create a history that merges same-cell edits recorded within `merge_window`
+    pub fn with_merge_window(merge_window: Duration) -> Self {
         Self {
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
+            last_recorded: None,
+            merge_window,
+            limit: DEFAULT_UNDO_LIMIT,
+        }
+    }
+
+    /// Set the maximum number of undo steps, dropping the oldest if over the limit
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.enforce_limit();
+        self.undo_stack.shrink_to_fit();
+    }
+
+    /// Drop the oldest undo steps beyond the limit and release their memory
+    /// The redo stack only holds steps newer than the undo stack, so it is unaffected
+    fn enforce_limit(&mut self) {
+        if self.undo_stack.len() > self.limit {
+            let excess = self.undo_stack.len() - self.limit;
+            self.undo_stack.drain(..excess);
         }
     }
 
@@ -21,12 +65,26 @@ impl History {
             // cannot undo/redo these for obvious reasons
             return;
         }
-        self.undo_stack.push(txn);
         self.redo_stack.clear();
+
+        let now = Instant::now();
+        let recent = !self.merge_window.is_zero()
+            && self.last_recorded.is_some_and(|t| now.duration_since(t) <= self.merge_window);
+        self.last_recorded = Some(now);
+        if recent {
+            if let Some(last) = self.undo_stack.last_mut() {
+                if last.merge_with(&txn) {
+                    return;
+                }
+            }
+        }
+        self.undo_stack.push(txn);
+        self.enforce_limit();
     }
 
     /// Undo the last transaction, returns the inverse for application
     pub fn undo(&mut self) -> Option<Transaction> {
+        self.last_recorded = None;
         self.undo_stack.pop().map(|txn| {
             let inverse = txn.inverse();
             self.redo_stack.push(txn);
@@ -36,8 +94,10 @@ impl History {
 
     /// Redo the last undone transaction
     pub fn redo(&mut self) -> Option<Transaction> {
+        self.last_recorded = None;
         self.redo_stack.pop().map(|txn| {
             self.undo_stack.push(txn.clone());
+            self.enforce_limit();
             txn
         })
     }
@@ -52,6 +112,13 @@ impl History {
         !self.redo_stack.is_empty()
     }
 
+    /// Remove the last recorded transaction without undoing it or offering it for redo
+    /// Used to withdraw part of an edit that was cancelled; the caller reverts the table
+    pub fn pop_last(&mut self) -> Option<Transaction> {
+        self.last_recorded = None;
+        self.undo_stack.pop()
+    }
+
     /// Peek at the next undo transaction without removing it
     pub fn peek_undo(&self) -> Option<&Transaction> {
         self.undo_stack.last()
@@ -66,5 +133,6 @@ impl History {
     pub fn clear(&mut self) {
         self.undo_stack.clear();
         self.redo_stack.clear();
+        self.last_recorded = None;
     }
 }
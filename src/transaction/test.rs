@@ -2,7 +2,10 @@ use super::transaction::*;
 use super::history::*;
 use super::clipboard::*;
 
+use std::time::Duration;
+
 use crate::table::table::Table;
+use crate::util::ColumnType;
 
 
 fn make_table(rows: usize, cols: usize) -> Table {
@@ -208,6 +211,41 @@ fn test_delete_col_inverse_restores() {
     assert_eq!(row(&table, 1), vec!["d", "e", "f"]);
 }
 
+#[test]
+fn test_delete_only_col_recorded_as_clear() {
+    let mut table = make_table_with_data(vec![vec!["a"], vec!["b"], vec!["c"]]);
+    let txn = Transaction::DeleteCol {
+        idx: 0,
+        data: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+    };
+
+    let applied = txn.apply_effective(&mut table);
+    assert!(matches!(applied, Transaction::SetSpan { .. }));
+    assert_eq!(table.col_count(), 1);
+    assert_eq!(cell(&table, 1, 0), "");
+
+    applied.inverse().apply(&mut table);
+    assert_eq!(table.col_count(), 1);
+    assert_eq!(table.get_col_cloned(0).unwrap(), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_delete_all_cols_in_batch_undoes_exactly() {
+    let mut table = make_table_with_data(vec![vec!["a", "b"], vec!["c", "d"]]);
+    let batch = Transaction::Batch(vec![
+        Transaction::DeleteCol { idx: 0, data: vec!["a".to_string(), "c".to_string()] },
+        Transaction::DeleteCol { idx: 0, data: vec!["b".to_string(), "d".to_string()] },
+    ]);
+
+    let applied = batch.apply_effective(&mut table);
+    assert_eq!(table.col_count(), 1);
+    assert_eq!(row(&table, 0), vec![""]);
+
+    applied.inverse().apply(&mut table);
+    assert_eq!(row(&table, 0), vec!["a", "b"]);
+    assert_eq!(row(&table, 1), vec!["c", "d"]);
+}
+
 // === SetSpan tests ===
 
 #[test]
@@ -407,7 +445,7 @@ fn test_history_new_action_clears_redo() {
 
 #[test]
 fn test_history_multiple_undos() {
-    let mut history = History::new();
+    let mut history = History::with_merge_window(Duration::ZERO);
     let mut table = make_table(3, 3);
 
     for i in 0..5 {
@@ -732,6 +770,111 @@ fn test_inverse_permutation_correctness() {
     }
 }
 
+#[test]
+fn test_remap_position() {
+    let insert = Transaction::InsertRowsBulk { idx: 2, count: 3 };
+    assert_eq!(insert.remap_position((1, 4)), Some((1, 4)));
+    assert_eq!(insert.remap_position((2, 4)), Some((5, 4)));
+
+    let delete = Transaction::DeleteCol { idx: 1, data: vec![] };
+    assert_eq!(delete.remap_position((0, 0)), Some((0, 0)));
+    assert_eq!(delete.remap_position((0, 1)), None);
+    assert_eq!(delete.remap_position((0, 3)), Some((0, 2)));
+
+    // Row 0 of the new table comes from old row 2
+    let permute = Transaction::PermuteRows { permutation: vec![2, 0, 1] };
+    assert_eq!(permute.remap_position((2, 0)), Some((0, 0)));
+    assert_eq!(permute.remap_position((0, 0)), Some((1, 0)));
+
+    let batch = Transaction::Batch(vec![permute, Transaction::InsertCol { idx: 0 }]);
+    assert_eq!(batch.remap_position((2, 0)), Some((0, 1)));
+}
+
+#[test]
+fn test_remap_col() {
+    let delete = Transaction::DeleteCol { idx: 1, data: vec![] };
+    assert_eq!(delete.remap_col(0), Some(0));
+    assert_eq!(delete.remap_col(1), None);
+    assert_eq!(delete.remap_col(2), Some(1));
+
+    // Column 0 of the new table comes from old column 2
+    let permute = Transaction::PermuteCols { permutation: vec![2, 0, 1] };
+    assert_eq!(permute.remap_col(2), Some(0));
+
+    let batch = Transaction::Batch(vec![permute, Transaction::InsertCol { idx: 0 }]);
+    assert_eq!(batch.remap_col(2), Some(1));
+    assert_eq!(Transaction::Transpose { old_rows: vec![], old_types: vec![] }.remap_col(0), None);
+}
+
+// === Transpose tests ===
+
+#[test]
+fn test_transpose_apply_and_undo() {
+    let mut table = make_table_with_data(vec![
+        vec!["a", "b", "c"],
+        vec!["1", "2", "3"],
+    ]);
+    let original = table.clone_all_rows();
+    table.set_declared_type(1, Some(ColumnType::Numeric));
+
+    let (old_rows, old_types) = table.transpose();
+    let txn = Transaction::Transpose { old_rows, old_types };
+    assert_eq!(table.declared_type(1), None);
+
+    assert_eq!(table.row_count(), 3);
+    assert_eq!(table.col_count(), 2);
+    assert_eq!(row(&table, 0), vec!["a", "1"]);
+    assert_eq!(row(&table, 2), vec!["c", "3"]);
+
+    txn.inverse().apply(&mut table);
+    assert_eq!(table.clone_all_rows(), original);
+    assert_eq!(table.col_count(), 3);
+    assert_eq!(table.declared_type(1), Some(ColumnType::Numeric));
+
+    // Redo transposes again
+    txn.apply(&mut table);
+    assert_eq!(row(&table, 1), vec!["b", "2"]);
+}
+
+#[test]
+fn test_replace_rows_apply_and_undo() {
+    let mut table = make_table_with_data(vec![
+        vec!["a;b"],
+        vec!["1;2"],
+    ]);
+    let old_rows = table.clone_all_rows();
+    let new_rows = vec![
+        vec!["a".to_string(), "b".to_string()],
+        vec!["1".to_string(), "2".to_string()],
+    ];
+    let txn = Transaction::ReplaceRows { old_rows: old_rows.clone(), new_rows: new_rows.clone() };
+
+    txn.apply(&mut table);
+    assert_eq!(table.clone_all_rows(), new_rows);
+    assert_eq!(table.col_count(), 2);
+
+    txn.inverse().apply(&mut table);
+    assert_eq!(table.clone_all_rows(), old_rows);
+    assert_eq!(table.col_count(), 1);
+}
+
+#[test]
+fn test_transpose_pads_ragged_rows() {
+    let mut table = make_table_with_data(vec![
+        vec!["a", "b"],
+    ]);
+    table.add_chunk(vec![vec!["x".to_string()]], 2);
+    table.add_chunk(vec![vec!["p".to_string(), "q".to_string(), "r".to_string()]], 3);
+
+    table.transpose();
+
+    assert_eq!(table.row_count(), 3);
+    assert_eq!(table.col_count(), 3);
+    assert_eq!(row(&table, 0), vec!["a", "x", "p"]);
+    assert_eq!(row(&table, 1), vec!["b", "", "q"]);
+    assert_eq!(row(&table, 2), vec!["", "", "r"]);
+}
+
 #[test]
 fn test_clipboard_new() {
     let clipboard = Clipboard::new();
@@ -769,3 +912,357 @@ fn test_select_valid_registers() {
     assert!(clipboard.select_register('+').is_ok());
     assert!(clipboard.select_register('"').is_ok());
 }
+
+#[test]
+fn test_star_register_is_system_clipboard() {
+    let mut clipboard = Clipboard::new();
+    assert!(clipboard.select_register('*').is_ok());
+    assert_eq!(clipboard.selected, Some('+'));
+}
+
+#[test]
+fn test_yank_cell_stores_one_by_one_span() {
+    let mut clipboard = Clipboard::new();
+    clipboard.select_register('a').unwrap();
+    assert_eq!(clipboard.yank_cell("x".to_string()), Ok("Cell yanked".to_string()));
+    assert_eq!(clipboard.registers.get(&'a').unwrap().data, vec![vec!["x".to_string()]]);
+    assert_eq!(clipboard.yank_register.as_ref().unwrap().anchor, PasteAnchor::Cursor);
+}
+
+#[test]
+fn test_paste_transposed_rows_become_columns() {
+    let mut clipboard = Clipboard::new();
+    clipboard.yank_rows(vec![
+        vec!["a".into(), "b".into(), "c".into()],
+        vec!["d".into(), "e".into(), "f".into()],
+    ]).unwrap();
+    let mut table = make_table(2, 2);
+
+    let (msg, txn) = clipboard.paste_transposed_as_transaction(1, 1, &table);
+    assert_eq!(msg, "2 column(s) pasted (transposed)");
+    txn.unwrap().apply(&mut table);
+
+    // Rows become columns anchored at row 0, growing the table to fit
+    assert_eq!(table.row_count(), 3);
+    assert_eq!(table.col_count(), 3);
+    assert_eq!(cell(&table, 0, 1), "a");
+    assert_eq!(cell(&table, 2, 1), "c");
+    assert_eq!(cell(&table, 0, 2), "d");
+    assert_eq!(cell(&table, 2, 2), "f");
+}
+
+#[test]
+fn test_paste_transposed_span_at_cursor() {
+    let mut clipboard = Clipboard::new();
+    clipboard.yank_span(vec![
+        vec!["1".into(), "2".into()],
+        vec!["3".into()],
+    ]).unwrap();
+    let mut table = make_table(3, 3);
+
+    let (msg, txn) = clipboard.paste_transposed_as_transaction(1, 0, &table);
+    assert_eq!(msg, "Span pasted (transposed)");
+    txn.unwrap().apply(&mut table);
+
+    assert_eq!(row(&table, 1)[..2], ["1".to_string(), "3".to_string()]);
+    assert_eq!(row(&table, 2)[..2], ["2".to_string(), String::new()]);
+}
+
+#[test]
+fn test_paste_transposed_honors_selected_register() {
+    let mut clipboard = Clipboard::new();
+    clipboard.select_register('a').unwrap();
+    clipboard.yank_cols(vec![vec!["x".into()], vec!["y".into()]]).unwrap();
+    clipboard.yank_span(vec![vec!["other".into()]]).unwrap();
+    let table = make_table(2, 2);
+
+    clipboard.select_register('a').unwrap();
+    let (msg, txn) = clipboard.paste_transposed_as_transaction(1, 1, &table);
+    assert_eq!(msg, "1 row(s) pasted (transposed)");
+    match txn.unwrap() {
+        Transaction::SetSpan { row, col, new_data, .. } => {
+            assert_eq!((row, col), (1, 0));
+            assert_eq!(new_data, vec![vec!["x".to_string(), "y".to_string()]]);
+        }
+        other => panic!("expected SetSpan, got {:?}", other),
+    }
+}
+
+// === Cursor restoration tests ===
+
+#[test]
+fn test_affected_cursor_cell_and_span() {
+    let set = Transaction::SetCell { row: 3, col: 2, old_value: String::new(), new_value: "x".into() };
+    assert_eq!(set.affected_cursor(), Some((3, 2)));
+    assert_eq!(set.inverse().affected_cursor(), Some((3, 2)));
+
+    let span = Transaction::SetSpan { row: 1, col: 4, old_data: vec![], new_data: vec![] };
+    assert_eq!(span.affected_cursor(), Some((1, 4)));
+}
+
+#[test]
+fn test_affected_cursor_rows_and_cols() {
+    assert_eq!(Transaction::InsertRow { idx: 5 }.affected_cursor(), Some((5, 0)));
+    assert_eq!(Transaction::DeleteRowsBulk { idx: 2, data: vec![] }.affected_cursor(), Some((2, 0)));
+    assert_eq!(Transaction::InsertCol { idx: 3 }.affected_cursor(), Some((0, 3)));
+    assert_eq!(Transaction::DeleteCol { idx: 1, data: vec![] }.affected_cursor(), Some((0, 1)));
+    assert_eq!(Transaction::PermuteRows { permutation: vec![1, 0] }.affected_cursor(), None);
+}
+
+#[test]
+fn test_affected_cursor_batch_uses_first_located() {
+    let batch = Transaction::Batch(vec![
+        Transaction::PermuteCols { permutation: vec![0] },
+        Transaction::SetCell { row: 7, col: 1, old_value: String::new(), new_value: "a".into() },
+        Transaction::SetCell { row: 9, col: 2, old_value: String::new(), new_value: "b".into() },
+    ]);
+    assert_eq!(batch.affected_cursor(), Some((7, 1)));
+    assert_eq!(Transaction::Batch(vec![]).affected_cursor(), None);
+}
+
+// === Edit coalescing tests ===
+
+#[test]
+fn test_merge_with_same_cell() {
+    let mut first = Transaction::SetCell { row: 1, col: 2, old_value: "a".into(), new_value: "b".into() };
+    let second = Transaction::SetCell { row: 1, col: 2, old_value: "b".into(), new_value: "c".into() };
+    assert!(first.merge_with(&second));
+    assert_eq!(first, Transaction::SetCell { row: 1, col: 2, old_value: "a".into(), new_value: "c".into() });
+
+    let other_cell = Transaction::SetCell { row: 0, col: 2, old_value: String::new(), new_value: "x".into() };
+    assert!(!first.merge_with(&other_cell));
+    assert!(!first.merge_with(&Transaction::InsertRow { idx: 1 }));
+}
+
+#[test]
+fn test_history_coalesces_same_cell_edits() {
+    let mut history = History::new();
+    let mut table = make_table(2, 2);
+
+    for (old, new) in [("", "h"), ("h", "hi")] {
+        let txn = Transaction::SetCell { row: 0, col: 0, old_value: old.into(), new_value: new.into() };
+        txn.apply(&mut table);
+        history.record(txn);
+    }
+    assert_eq!(cell(&table, 0, 0), "hi");
+
+    // One undo reverts the whole editing session on the cell
+    history.undo().unwrap().apply(&mut table);
+    assert_eq!(cell(&table, 0, 0), "");
+    assert!(!history.can_undo());
+
+    // Redo restores the final value in one step
+    history.redo().unwrap().apply(&mut table);
+    assert_eq!(cell(&table, 0, 0), "hi");
+}
+
+#[test]
+fn test_history_does_not_coalesce_across_cells_or_undo() {
+    let mut history = History::new();
+
+    history.record(Transaction::SetCell { row: 0, col: 0, old_value: String::new(), new_value: "a".into() });
+    history.record(Transaction::SetCell { row: 0, col: 1, old_value: String::new(), new_value: "b".into() });
+    history.record(Transaction::SetCell { row: 0, col: 1, old_value: "b".into(), new_value: "c".into() });
+    history.undo();
+    // After an undo, a new edit of the same cell starts a fresh step
+    history.record(Transaction::SetCell { row: 0, col: 0, old_value: "a".into(), new_value: "d".into() });
+
+    // Stack holds the (0,0) edit, then the fresh (0,0) edit
+    assert!(history.undo().is_some());
+    assert!(history.undo().is_some());
+    assert!(history.undo().is_none());
+}
+
+// === Undo limit tests ===
+
+fn numbered_cell(i: usize) -> Transaction {
+    Transaction::SetCell { row: i, col: 0, old_value: String::new(), new_value: i.to_string() }
+}
+
+#[test]
+fn test_history_limit_drops_oldest() {
+    let mut history = History::new();
+    history.set_limit(3);
+    for i in 0..5 {
+        history.record(numbered_cell(i));
+    }
+
+    // Only the three newest steps remain, newest first
+    for i in (2..5).rev() {
+        assert_eq!(history.undo(), Some(numbered_cell(i).inverse()));
+    }
+    assert!(!history.can_undo());
+}
+
+#[test]
+fn test_history_set_limit_trims_and_keeps_redo() {
+    let mut history = History::new();
+    for i in 0..4 {
+        history.record(numbered_cell(i));
+    }
+    history.undo();
+
+    history.set_limit(2);
+    assert_eq!(history.peek_undo(), Some(&numbered_cell(2)));
+
+    // Redo still replays the undone step and respects the limit
+    assert_eq!(history.redo(), Some(numbered_cell(3)));
+    assert!(history.undo().is_some());
+    assert!(history.undo().is_some());
+    assert!(history.undo().is_none());
+}
+
+#[test]
+fn test_history_default_records() {
+    let mut history = History::default();
+    history.record(numbered_cell(0));
+    assert_eq!(history.undo(), Some(numbered_cell(0).inverse()));
+}
+
+#[test]
+fn test_parse_block_detects_tabs_and_commas() {
+    let tsv = Clipboard::parse_block("a\tb\nc\td\n").unwrap();
+    assert_eq!(tsv, vec![vec!["a", "b"], vec!["c", "d"]]);
+
+    let csv = Clipboard::parse_block("1,\"x, y\",3\r\n").unwrap();
+    assert_eq!(csv, vec![vec!["1", "x, y", "3"]]);
+}
+
+#[test]
+fn test_parse_block_single_value() {
+    assert_eq!(Clipboard::parse_block("hello\n").unwrap(), vec![vec!["hello"]]);
+}
+
+#[test]
+fn test_list_registers_in_display_order() {
+    let mut clipboard = Clipboard::new();
+    clipboard.select_register('b').unwrap();
+    clipboard.yank_span(vec![vec!["x".repeat(30)]]).unwrap();
+    clipboard.select_register('a').unwrap();
+    clipboard.yank_rows(vec![vec!["1".into(), "2".into()]]).unwrap();
+
+    let names: Vec<char> = clipboard.iter_registers().map(|(reg, _)| reg).collect();
+    assert_eq!(names, vec!['"', '0', 'a', 'b']);
+
+    let listing = clipboard.list_registers();
+    assert_eq!(listing[2], ("\"a".to_string(), "1x2: 1".to_string()));
+    assert_eq!(listing[3].1, format!("1x1: {}...", "x".repeat(20)));
+}
+
+#[test]
+fn test_uppercase_register_appends_rows() {
+    let mut clipboard = Clipboard::new();
+    clipboard.select_register('a').unwrap();
+    clipboard.yank_rows(vec![vec!["1".into(), "2".into()]]).unwrap();
+    clipboard.select_register('A').unwrap();
+    clipboard.yank_rows(vec![vec!["3".into(), "4".into()]]).unwrap();
+
+    let mut table = make_table(3, 2);
+    clipboard.select_register('a').unwrap();
+    let (msg, txn) = clipboard.paste_as_transaction(1, 0, &table);
+    assert_eq!(msg, "2 row(s) pasted");
+    txn.unwrap().apply(&mut table);
+
+    assert_eq!(table.get_cell(1, 0).unwrap(), "1");
+    assert_eq!(table.get_cell(2, 1).unwrap(), "4");
+}
+
+#[test]
+fn test_uppercase_register_starts_empty_register() {
+    let mut clipboard = Clipboard::new();
+    clipboard.select_register('B').unwrap();
+    clipboard.yank_span(vec![vec!["x".into()]]).unwrap();
+    assert_eq!(clipboard.registers.get(&'b').unwrap().data, vec![vec!["x".to_string()]]);
+}
+
+#[test]
+fn test_append_mismatched_shapes_fails() {
+    let mut clipboard = Clipboard::new();
+    clipboard.select_register('a').unwrap();
+    clipboard.yank_rows(vec![vec!["1".into(), "2".into()]]).unwrap();
+
+    clipboard.select_register('A').unwrap();
+    assert!(clipboard.yank_rows(vec![vec!["3".into()]]).is_err());
+    clipboard.select_register('A').unwrap();
+    assert!(clipboard.yank_cols(vec![vec!["3".into()], vec!["4".into()]]).is_err());
+
+    // The register keeps its old content, the unnamed register gets the new yank
+    assert_eq!(clipboard.registers.get(&'a').unwrap().data.len(), 1);
+    assert_eq!(clipboard.unnamed.as_ref().unwrap().anchor, PasteAnchor::ColStart);
+}
+
+#[test]
+fn test_append_columns_side_by_side() {
+    let mut content = RegisterContent::from_cols(vec![vec!["a".into()], vec!["b".into()]]);
+    content.append(RegisterContent::from_cols(vec![vec!["c".into()], vec!["d".into()]])).unwrap();
+    assert_eq!(content.data, vec![vec!["a", "c"], vec!["b", "d"]]);
+}
+
+#[test]
+fn test_paste_fill_repeats_row_down_selection() {
+    let mut clipboard = Clipboard::new();
+    clipboard.yank_rows(vec![vec!["a".into(), "b".into()]]).unwrap();
+
+    let mut table = make_table(4, 2);
+    let (msg, txn) = clipboard.paste_fill_as_transaction((1, 3, 0, 1), &table);
+    assert_eq!(msg, "3 row(s) pasted");
+    txn.unwrap().apply(&mut table);
+
+    assert_eq!(table.get_cell(0, 0).unwrap(), "");
+    for row in 1..4 {
+        assert_eq!(table.get_row_cloned(row).unwrap(), vec!["a", "b"]);
+    }
+}
+
+#[test]
+fn test_paste_fill_repeats_column_across_selection() {
+    let mut clipboard = Clipboard::new();
+    clipboard.yank_cols(vec![vec!["x".into()], vec!["y".into()]]).unwrap();
+
+    let mut table = make_table(2, 4);
+    let (msg, txn) = clipboard.paste_fill_as_transaction((0, 1, 1, 3), &table);
+    assert_eq!(msg, "3 column(s) pasted");
+    txn.unwrap().apply(&mut table);
+
+    assert_eq!(table.get_row_cloned(0).unwrap(), vec!["", "x", "x", "x"]);
+    assert_eq!(table.get_row_cloned(1).unwrap(), vec!["", "y", "y", "y"]);
+}
+
+#[test]
+fn test_paste_fill_tiles_span_and_warns_on_mismatch() {
+    let mut clipboard = Clipboard::new();
+    clipboard.yank_span(vec![vec!["1".into()]]).unwrap();
+    let mut table = make_table(3, 3);
+    let (_, txn) = clipboard.paste_fill_as_transaction((0, 1, 1, 2), &table);
+    txn.unwrap().apply(&mut table);
+    assert_eq!(table.get_row_cloned(0).unwrap(), vec!["", "1", "1"]);
+    assert_eq!(table.get_row_cloned(1).unwrap(), vec!["", "1", "1"]);
+    assert_eq!(table.get_row_cloned(2).unwrap(), vec!["", "", ""]);
+
+    // Two rows don't tile three selected rows: pasted once at the corner
+    clipboard.yank_span(vec![vec!["p".into()], vec!["q".into()]]).unwrap();
+    let mut table = make_table(3, 1);
+    let (msg, txn) = clipboard.paste_fill_as_transaction((0, 2, 0, 0), &table);
+    assert_eq!(msg, "Selection is not a multiple of the yanked 2x1 block; pasted once");
+    txn.unwrap().apply(&mut table);
+    assert_eq!(table.get_col_cloned(0).unwrap(), vec!["p", "q", ""]);
+}
+
+#[test]
+fn test_paste_fill_clipped_to_selection() {
+    let mut clipboard = Clipboard::new();
+    clipboard.clip_paste = true;
+    clipboard.yank_span(vec![
+        vec!["a".into(), "b".into(), "c".into()],
+        vec!["d".into(), "e".into(), "f".into()],
+        vec!["g".into(), "h".into(), "i".into()],
+    ]).unwrap();
+    let mut table = make_table(3, 3);
+    let (msg, txn) = clipboard.paste_fill_as_transaction((0, 1, 0, 1), &table);
+    assert_eq!(msg, "Pasted 2x2 clipped to the selection");
+    txn.unwrap().apply(&mut table);
+
+    assert_eq!(table.get_row_cloned(0).unwrap(), vec!["a", "b", ""]);
+    assert_eq!(table.get_row_cloned(1).unwrap(), vec!["d", "e", ""]);
+    assert_eq!(table.get_row_cloned(2).unwrap(), vec!["", "", ""]);
+}
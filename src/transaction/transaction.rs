@@ -1,5 +1,6 @@
 use crate::table::table::Table;
-use crate::table::rowmanager::FilterState;
+use crate::table::rowmanager::{FilterState, RowManager};
+use crate::util::ColumnType;
 
 /// Represents a reversible operation on the table
 #[derive(Debug, Clone, PartialEq)]
@@ -42,10 +43,19 @@ pub enum Transaction {
     PermuteRows { permutation: Vec<usize> },
     /// Reorder columns by permutation (memory-efficient for sorting)
     PermuteCols { permutation: Vec<usize> },
+    /// Swap rows and columns of the whole table (stores the original rows for undo)
+    Transpose { old_rows: Vec<Vec<String>>, old_types: Vec<Option<ColumnType>> },
+    /// Replace the whole table with the stored rows and column types (undoes Transpose)
+    RestoreRows { rows: Vec<Vec<String>>, types: Vec<Option<ColumnType>> },
+    /// Replace the whole table with new rows (stores the old rows for undo)
+    ReplaceRows { old_rows: Vec<Vec<String>>, new_rows: Vec<Vec<String>> },
     /// Change filter state (stores old and new state for undo/redo)
     /// Note: This transaction does NOT modify the table; app.rs handles
     /// applying filter state to RowManager separately.
     SetFilter { old_state: FilterState, new_state: FilterState },
+    /// Change the delimiter used when saving (stores old and new for undo/redo)
+    /// Note: like SetFilter this does NOT modify the table; app.rs applies it to FileIO.
+    SetDelimiter { old: u8, new: u8 },
     /// Multiple transactions grouped together
     Batch(Vec<Transaction>),
     Undo,
@@ -53,6 +63,86 @@ pub enum Transaction {
 }
 
 impl Transaction {
+    /// Where a cell at pos ends up after this transaction is applied
+    /// None if its row or column was deleted
+    pub fn remap_position(&self, pos: (usize, usize)) -> Option<(usize, usize)> {
+        let (row, col) = pos;
+        // Shift an index past an insertion, or drop it if inside a deletion
+        let inserted = |i: usize, idx: usize, n: usize| if i >= idx { i + n } else { i };
+        let deleted = |i: usize, idx: usize, n: usize| {
+            if i < idx {
+                Some(i)
+            } else if i < idx + n {
+                None
+            } else {
+                Some(i - n)
+            }
+        };
+        match self {
+            Transaction::InsertRow { idx } | Transaction::InsertRowWithData { idx, .. } => {
+                Some((inserted(row, *idx, 1), col))
+            }
+            Transaction::InsertRowsBulk { idx, count } => Some((inserted(row, *idx, *count), col)),
+            Transaction::InsertRowsWithDataBulk { idx, data } => Some((inserted(row, *idx, data.len()), col)),
+            Transaction::DeleteRow { idx, .. } => deleted(row, *idx, 1).map(|row| (row, col)),
+            Transaction::DeleteRowsBulk { idx, data } => deleted(row, *idx, data.len()).map(|row| (row, col)),
+            Transaction::InsertCol { idx } | Transaction::InsertColWithData { idx, .. } => {
+                Some((row, inserted(col, *idx, 1)))
+            }
+            Transaction::DeleteCol { idx, .. } => deleted(col, *idx, 1).map(|col| (row, col)),
+            Transaction::PermuteRows { permutation } => match permutation.iter().position(|&old| old == row) {
+                Some(new_row) => Some((new_row, col)),
+                None => Some(pos),
+            },
+            Transaction::PermuteCols { permutation } => match permutation.iter().position(|&old| old == col) {
+                Some(new_col) => Some((row, new_col)),
+                None => Some(pos),
+            },
+            Transaction::Transpose { .. } | Transaction::RestoreRows { .. } => Some((col, row)),
+            Transaction::Batch(txns) => txns.iter().try_fold(pos, |pos, txn| txn.remap_position(pos)),
+            Transaction::SetCell { .. }
+            | Transaction::SetSpan { .. }
+            | Transaction::ReplaceRows { .. }
+            | Transaction::SetFilter { .. }
+            | Transaction::SetDelimiter { .. }
+            | Transaction::Undo
+            | Transaction::Redo => Some(pos),
+        }
+    }
+
+    /// Where column col ends up after this transaction is applied
+    /// None if it was deleted or the whole table was rebuilt (transpose, re-split)
+    pub fn remap_col(&self, col: usize) -> Option<usize> {
+        match self {
+            Transaction::Transpose { .. }
+            | Transaction::RestoreRows { .. }
+            | Transaction::ReplaceRows { .. } => None,
+            Transaction::Batch(txns) => txns.iter().try_fold(col, |col, txn| txn.remap_col(col)),
+            _ => self.remap_position((0, col)).map(|(_, col)| col),
+        }
+    }
+
+    /// Keep a filter on the same rows across this transaction: shown rows move with
+    /// insertions, deletions and sorts, deleted rows drop out and inserted ones are shown
+    pub fn remap_filter(&self, row_manager: &mut RowManager) {
+        match self {
+            Transaction::InsertRow { idx } | Transaction::InsertRowWithData { idx, .. } => {
+                row_manager.rows_inserted(*idx, 1)
+            }
+            Transaction::InsertRowsBulk { idx, count } => row_manager.rows_inserted(*idx, *count),
+            Transaction::InsertRowsWithDataBulk { idx, data } => row_manager.rows_inserted(*idx, data.len()),
+            Transaction::DeleteRow { idx, .. } => row_manager.rows_deleted(*idx, 1),
+            Transaction::DeleteRowsBulk { idx, data } => row_manager.rows_deleted(*idx, data.len()),
+            Transaction::PermuteRows { permutation } => row_manager.apply_permutation(permutation),
+            Transaction::Batch(txns) => {
+                for txn in txns {
+                    txn.remap_filter(row_manager);
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// Estimate the size/complexity of this transaction for progress reporting
     /// Returns the number of cells or operations involved
     pub fn estimated_size(&self) -> usize {
@@ -76,7 +166,11 @@ impl Transaction {
             }
             Transaction::PermuteRows { permutation } => permutation.len(),
             Transaction::PermuteCols { permutation } => permutation.len(),
+            Transaction::Transpose { old_rows, .. } => old_rows.iter().map(|r| r.len()).sum(),
+            Transaction::RestoreRows { rows, .. } => rows.iter().map(|r| r.len()).sum(),
+            Transaction::ReplaceRows { new_rows, .. } => new_rows.iter().map(|r| r.len()).sum(),
             Transaction::SetFilter { .. } => 1, // Filter changes are instant
+            Transaction::SetDelimiter { .. } => 1,
             Transaction::Batch(txns) => txns.iter().map(|t| t.estimated_size()).sum(),
             Transaction::Undo => 1,
             Transaction::Redo => 1
@@ -96,6 +190,84 @@ impl Transaction {
         }
     }
 
+    /// The delimiter to switch to if this transaction (or one in its batch) changes it
+    pub fn delimiter(&self) -> Option<u8> {
+        match self {
+            Transaction::SetDelimiter { new, .. } => Some(*new),
+            Transaction::Batch(txns) => txns.iter().rev().find_map(|t| t.delimiter()),
+            _ => None,
+        }
+    }
+
+    /// Fold a following edit into this one so both undo as a single step
+    /// Only SetCell edits of the same cell merge; returns false if nothing changed
+    pub fn merge_with(&mut self, next: &Transaction) -> bool {
+        match (self, next) {
+            (
+                Transaction::SetCell { row, col, new_value, .. },
+                Transaction::SetCell { row: next_row, col: next_col, new_value: next_value, .. },
+            ) if row == next_row && col == next_col => {
+                *new_value = next_value.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The cell the cursor should land on after this transaction is undone or redone
+    /// Row operations point at column 0 of the row, column operations at row 0 of the column
+    pub fn affected_cursor(&self) -> Option<(usize, usize)> {
+        match self {
+            Transaction::SetCell { row, col, .. } => Some((*row, *col)),
+            Transaction::SetSpan { row, col, .. } => Some((*row, *col)),
+            Transaction::InsertRow { idx }
+            | Transaction::InsertRowWithData { idx, .. }
+            | Transaction::DeleteRow { idx, .. }
+            | Transaction::InsertRowsBulk { idx, .. }
+            | Transaction::InsertRowsWithDataBulk { idx, .. }
+            | Transaction::DeleteRowsBulk { idx, .. } => Some((*idx, 0)),
+            Transaction::InsertCol { idx }
+            | Transaction::InsertColWithData { idx, .. }
+            | Transaction::DeleteCol { idx, .. } => Some((0, *idx)),
+            Transaction::Batch(txns) => txns.iter().find_map(|t| t.affected_cursor()),
+            Transaction::PermuteRows { .. }
+            | Transaction::PermuteCols { .. }
+            | Transaction::Transpose { .. }
+            | Transaction::RestoreRows { .. }
+            | Transaction::ReplaceRows { .. }
+            | Transaction::SetFilter { .. }
+            | Transaction::SetDelimiter { .. }
+            | Transaction::Undo
+            | Transaction::Redo => None,
+        }
+    }
+
+    /// Apply the transaction and return it as it took effect, to record in the history
+    /// Deleting the only column clears it instead (a table keeps one column), so that is
+    /// recorded as clearing its cells; undo then restores them rather than inserting a column
+    pub fn apply_effective(self, table: &mut Table) -> Transaction {
+        match self {
+            Transaction::DeleteCol { .. } if table.col_count() <= 1 => {
+                let old_data: Vec<Vec<String>> = table.get_col_cloned(0)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|value| vec![value])
+                    .collect();
+                let new_data = vec![vec![String::new()]; old_data.len()];
+                let txn = Transaction::SetSpan { row: 0, col: 0, old_data, new_data };
+                txn.apply(table);
+                txn
+            }
+            Transaction::Batch(txns) => {
+                Transaction::Batch(txns.into_iter().map(|txn| txn.apply_effective(table)).collect())
+            }
+            txn => {
+                txn.apply(table);
+                txn
+            }
+        }
+    }
+
     pub fn apply(&self, table: &mut Table) {
         match self {
             Transaction::SetCell { row, col, new_value, .. } => {
@@ -152,11 +324,24 @@ impl Transaction {
             Transaction::PermuteCols { permutation } => {
                 table.apply_col_permutation(permutation);
             }
+            Transaction::Transpose { .. } => {
+                table.transpose();
+            }
+            Transaction::RestoreRows { rows, types } => {
+                table.restore_from_rows(rows.clone());
+                table.col_types = types.clone();
+            }
+            Transaction::ReplaceRows { new_rows, .. } => {
+                table.restore_from_rows(new_rows.clone());
+            }
             Transaction::SetFilter { .. } => {
                 // Filter state is not stored in the table; app.rs handles
                 // applying filter state to RowManager when this transaction
                 // is applied or inverted.
             }
+            Transaction::SetDelimiter { .. } => {
+                // The delimiter belongs to FileIO; app.rs switches it
+            }
             Transaction::Batch(txns) => {
                 for txn in txns {
                     txn.apply(table);
@@ -240,12 +425,25 @@ impl Transaction {
                     permutation: Self::inverse_permutation(permutation),
                 }
             }
+            Transaction::Transpose { old_rows, old_types } => {
+                Transaction::RestoreRows { rows: old_rows.clone(), types: old_types.clone() }
+            }
+            Transaction::RestoreRows { rows, types } => {
+                Transaction::Transpose { old_rows: rows.clone(), old_types: types.clone() }
+            }
+            Transaction::ReplaceRows { old_rows, new_rows } => {
+                Transaction::ReplaceRows {
+                    old_rows: new_rows.clone(),
+                    new_rows: old_rows.clone(),
+                }
+            }
             Transaction::SetFilter { old_state, new_state } => {
                 Transaction::SetFilter {
                     old_state: new_state.clone(),
                     new_state: old_state.clone(),
                 }
             }
+            Transaction::SetDelimiter { old, new } => Transaction::SetDelimiter { old: *new, new: *old },
             Transaction::Batch(txns) => {
                 Transaction::Batch(txns.iter().rev().map(|t| t.inverse()).collect())
             }
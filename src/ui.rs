@@ -2,3 +2,6 @@ pub mod ui;
 pub mod style;
 pub mod progress;
 pub mod canvas;
+
+#[cfg(test)]
+mod test;
@@ -79,6 +79,11 @@ pub enum CanvasItem {
     },
     /// Empty line
     Blank,
+    /// Editable text, split on line breaks, with the cursor at a character index
+    Editor {
+        text: String,
+        cursor: usize,
+    },
 }
 
 /// Canvas overlay for displaying rich content
@@ -172,6 +177,11 @@ impl Canvas {
         self.items.push(CanvasItem::Image { rows, title });
     }
 
+    /// Add an editable text region showing the cursor
+    pub fn add_editor(&mut self, text: impl Into<String>, cursor: usize) {
+        self.items.push(CanvasItem::Editor { text: text.into(), cursor });
+    }
+
     /// Add a simple box/rectangle drawing
     pub fn add_box(&mut self, width: usize, height: usize, fill_char: char) {
         let mut rows = Vec::with_capacity(height);
@@ -220,6 +230,7 @@ impl Canvas {
 
         // Build lines from items
         let mut lines: Vec<Line> = Vec::new();
+        let mut cursor_line = None;
 
         for item in &self.items {
             match item {
@@ -266,17 +277,51 @@ impl Canvas {
                         lines.push(Line::from(row.as_str()));
                     }
                 }
+                CanvasItem::Editor { text, cursor } => {
+                    let mut remaining = *cursor;
+                    for line in text.split('\n') {
+                        let len = line.chars().count();
+                        if cursor_line.is_none() && remaining <= len {
+                            // Underline the character under the cursor (a space at the end of a line)
+                            cursor_line = Some((lines.len(), remaining));
+                            let before: String = line.chars().take(remaining).collect();
+                            let at: String = line.chars().nth(remaining).map(String::from).unwrap_or_else(|| " ".to_string());
+                            let after: String = line.chars().skip(remaining + 1).collect();
+                            lines.push(Line::from(vec![
+                                Span::raw(before),
+                                Span::styled(at, Style::default().add_modifier(Modifier::UNDERLINED)),
+                                Span::raw(after),
+                            ]));
+                        } else {
+                            lines.push(Line::from(line.to_string()));
+                        }
+                        remaining = remaining.saturating_sub(len + 1);
+                    }
+                }
             }
         }
 
-        // Apply scroll offset
-        let visible_height = overlay_height.saturating_sub(2) as usize; // Account for border
-        let max_scroll = lines.len().saturating_sub(visible_height);
-        let scroll = self.scroll.min(max_scroll);
-        let visible_lines: Vec<Line> = lines.into_iter().skip(scroll).collect();
+        // Apply scroll offset, keeping an editor cursor in view
+        // Offsets count rows after wrapping, so long lines can be scrolled to their end
+        let inner_width = overlay_width.saturating_sub(2).max(1); // Account for border
+        let visible_height = overlay_height.saturating_sub(2) as usize;
+        let wrapped_rows = |lines: &[Line]| Paragraph::new(lines.to_vec()).wrap(Wrap { trim: false }).line_count(inner_width);
+        let max_scroll = wrapped_rows(&lines).saturating_sub(visible_height);
+        let scroll = match cursor_line {
+            Some((line, col)) => {
+                let row = wrapped_rows(&lines[..line]) + col / inner_width as usize;
+                row.saturating_sub(visible_height.saturating_sub(1))
+            }
+            None => self.scroll.min(max_scroll),
+        };
 
         // Create the help text for the title
-        let title_with_help = format!(" {} [q/Esc to close, j/k to scroll] ", self.title);
+        let help = if cursor_line.is_some() {
+            "Ctrl+S to save, Esc to cancel, Enter for a new line"
+        } else {
+            "q/Esc to close, j/k to scroll"
+        };
+        let title_with_help = format!(" {} [{}] ", self.title, help);
 
         let block = Block::default()
             .borders(Borders::ALL)
@@ -285,9 +330,10 @@ impl Canvas {
             .title_alignment(Alignment::Center)
             .style(Style::default().bg(Color::White).fg(Color::Black));
 
-        let paragraph = Paragraph::new(visible_lines)
+        let paragraph = Paragraph::new(lines)
             .block(block)
-            .wrap(Wrap { trim: false });
+            .wrap(Wrap { trim: false })
+            .scroll((scroll.min(u16::MAX as usize) as u16, 0));
 
         frame.render_widget(paragraph, overlay_area);
     }
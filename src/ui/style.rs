@@ -163,6 +163,9 @@ pub struct Theme {
     pub cell_cursor: ElementStyle,
     pub cell_selection: ElementStyle,
     pub cell_match: ElementStyle,
+    // Search match under the last n/N jump (cell_match in bold when missing)
+    #[serde(default)]
+    pub cell_match_current: Option<ElementStyle>,
 
     // Row/column headers
     pub header_col: ElementStyle,
@@ -216,6 +219,9 @@ impl Theme {
                 .with_bg(ThemeColor::Named(DarkGray)),
             cell_match: ElementStyle::fg(ThemeColor::Named(Black))
                 .with_bg(ThemeColor::Named(Yellow)),
+            cell_match_current: Some(ElementStyle::fg(ThemeColor::Named(Black))
+                .with_bg(ThemeColor::Named(LightRed))
+                .with_bold()),
             header_col: ElementStyle::fg(ThemeColor::Named(LightGreen)).with_bold(),
             header_row: ElementStyle::fg(ThemeColor::Named(LightCyan)).with_bold(),
             row_number: ElementStyle::fg(ThemeColor::Named(Gray)),
@@ -259,6 +265,9 @@ impl Theme {
                 .with_bg(ThemeColor::Named(LightCyan)),
             cell_match: ElementStyle::fg(ThemeColor::Named(Black))
                 .with_bg(ThemeColor::Named(Yellow)),
+            cell_match_current: Some(ElementStyle::fg(ThemeColor::Named(White))
+                .with_bg(ThemeColor::Named(Red))
+                .with_bold()),
             header_col: ElementStyle::fg(ThemeColor::Named(Blue)).with_bold(),
             header_row: ElementStyle::fg(ThemeColor::Named(Green)).with_bold(),
             row_number: ElementStyle::fg(ThemeColor::Named(Gray)),
@@ -311,6 +320,7 @@ impl Theme {
             cell_cursor: ElementStyle::fg(base03).with_bg(blue).with_bold(),
             cell_selection: ElementStyle::fg(base0).with_bg(base02),
             cell_match: ElementStyle::fg(base03).with_bg(yellow),
+            cell_match_current: Some(ElementStyle::fg(base03).with_bg(orange).with_bold()),
             header_col: ElementStyle::fg(cyan).with_bold(),
             header_row: ElementStyle::fg(green).with_bold(),
             row_number: ElementStyle::fg(base01),
@@ -359,22 +369,25 @@ impl Theme {
 /// Runtime style manager
 pub struct Style {
     pub theme: Theme,
+    pub theme_name: String,  // Name the theme was selected by, for saving settings
 }
 
 impl Style {
     pub fn new() -> Self {
         Self {
             theme: Theme::default(),
+            theme_name: "light".to_string(),
         }
     }
 
     #[allow(dead_code)]
     pub fn with_theme(theme: Theme) -> Self {
-        Self { theme }
+        Self { theme, theme_name: String::new() }
     }
 
-    pub fn set_theme(&mut self, theme: Theme) {
+    pub fn set_theme(&mut self, name: &str, theme: Theme) {
         self.theme = theme;
+        self.theme_name = name.to_string();
     }
 
     pub fn toggle_grid(&mut self) {
@@ -398,6 +411,13 @@ impl Style {
         self.theme.cell_match.to_ratatui()
     }
 
+    pub fn cell_match_current(&self) -> RatStyle {
+        match &self.theme.cell_match_current {
+            Some(style) => style.to_ratatui(),
+            None => self.theme.cell_match.clone().with_bold().to_ratatui(),
+        }
+    }
+
     pub fn header_col(&self) -> RatStyle {
         self.theme.header_col.to_ratatui()
     }
@@ -426,7 +446,7 @@ impl Style {
             Mode::Visual | Mode::VisualRow | Mode::VisualCol => {
                 self.theme.status_mode_visual.to_ratatui()
             }
-            Mode::Command | Mode::Search => self.theme.status_mode_command.to_ratatui(),
+            Mode::Command | Mode::Search | Mode::Prompt | Mode::Confirm => self.theme.status_mode_command.to_ratatui(),
         }
     }
 
@@ -443,6 +463,11 @@ impl Style {
         self.theme.message_error.to_ratatui()
     }
 
+    /// Rows where the columns compared by :diff differ (shares the error message colors)
+    pub fn cell_diff(&self) -> RatStyle {
+        self.theme.message_error.to_ratatui()
+    }
+
     pub fn filter_status(&self) -> RatStyle {
         self.theme.filter_status.to_ratatui()
     }
@@ -0,0 +1,119 @@
+use super::ui::*;
+use super::canvas::Canvas;
+use ratatui::{backend::TestBackend, Terminal};
+use crate::app::App;
+use crate::fileio::FileIO;
+use crate::table::rowmanager::RowManager;
+use crate::table::table::Table;
+use crate::table::tableview::TableView;
+use crate::util::ColumnType;
+
+fn make_table(data: Vec<Vec<&str>>) -> Table {
+    Table::new(data.into_iter().map(|r| r.into_iter().map(String::from).collect()).collect())
+}
+
+#[test]
+fn test_pad_cell_right_aligns_numeric_column() {
+    let mut table = make_table(vec![
+        vec!["Name", "Amount"],
+        vec!["kiwi", "5"],
+        vec!["banana", "120"],
+    ]);
+    let widths = table.col_widths();
+    let align = CellAlign::Auto;
+
+    // Text column: content first, padding after
+    let text_right = align.is_right(table.column_type(0, true));
+    let text_width = TableView::display_width(&widths, 0);
+    assert_eq!(pad_cell("kiwi", text_width, text_right), "kiwi   ");
+
+    // Numeric column: padding first, content last
+    let num_right = align.is_right(table.column_type(1, true));
+    let num_width = TableView::display_width(&widths, 1);
+    assert_eq!(pad_cell("5", num_width, num_right), "      5");
+    assert_eq!(pad_cell("120", num_width, num_right), "    120");
+}
+
+#[test]
+fn test_cell_align_override() {
+    assert!(CellAlign::Right.is_right(ColumnType::Text));
+    assert!(!CellAlign::Left.is_right(ColumnType::Numeric));
+    assert!(CellAlign::Auto.is_right(ColumnType::Numeric));
+    assert!(!CellAlign::Auto.is_right(ColumnType::Natural));
+    assert_eq!(CellAlign::from_name("right"), Some(CellAlign::Right));
+    assert_eq!(CellAlign::from_name("centre"), None);
+}
+
+#[test]
+fn test_pad_cell_overflow_is_unchanged() {
+    assert_eq!(pad_cell("a long value", 5, true), "a long value");
+    assert_eq!(pad_cell("", 4, false), "   ");
+}
+
+#[test]
+fn test_split_match_in_padded_cell() {
+    assert_eq!(split_match("banana   ", "banana", false, 2..4), Some(("ba", "na", "na   ")));
+    assert_eq!(split_match("   banana", "banana", true, 2..4), Some(("   ba", "na", "na")));
+    assert_eq!(split_match("ab ", "ab", false, 1..5), None);
+}
+
+#[test]
+fn test_row_number_label_counts_only_live_rows() {
+    let mut rows = RowManager::new();
+    assert_eq!(row_number_label(4, 2, false, false, &rows), "5");
+    assert_eq!(row_number_label(4, 2, true, false, &rows), "2");
+    assert_eq!(row_number_label(0, 2, true, false, &rows), "2");
+    assert_eq!(row_number_label(2, 2, true, false, &rows), "3");
+    assert_eq!(row_number_label(0, 2, true, true, &rows), "1");
+
+    // Rows 2 and 4 are filtered out
+    rows.is_filtered = true;
+    rows.active_rows = vec![0, 1, 3, 5, 6];
+    rows.active_row_set = rows.active_rows.iter().copied().collect();
+    assert_eq!(row_number_label(6, 1, true, false, &rows), "3");
+    assert_eq!(row_number_label(1, 5, true, false, &rows), "2");
+}
+
+#[test]
+fn test_relative_numbers_in_rendered_gutter() {
+    let table = make_table(vec![vec!["h"], vec!["a"], vec!["b"], vec!["c"], vec!["d"]]);
+    let mut app = App::new(table, FileIO::new(None, None, false).unwrap());
+    app.relativenumber = true;
+    app.view_state.view.cursor_row = 2;
+
+    let mut terminal = Terminal::new(TestBackend::new(20, 12)).unwrap();
+    let row_manager = app.view_state.row_manager.clone();
+    terminal.draw(|frame| render(frame, &mut app, row_manager)).unwrap();
+
+    // Gutter text of each drawn table row (below the border and the column letters)
+    let buffer = terminal.backend().buffer();
+    let gutter: Vec<String> = (2..7)
+        .map(|y| (1..5).map(|x| buffer.get(x, y).symbol().to_string()).collect::<String>().trim().to_string())
+        .collect();
+    assert_eq!(gutter, vec!["1", "1", "3", "1", "2"]);
+}
+
+#[test]
+fn test_position_label_counts_live_rows_when_filtered() {
+    let mut rows = RowManager::new();
+    assert_eq!(position_label(2, 1, 120, 5, &rows), "Ln 3/120 Col B (2/5)");
+
+    rows.is_filtered = true;
+    rows.active_rows = vec![0, 4, 9, 17];
+    rows.active_row_set = rows.active_rows.iter().copied().collect();
+    assert_eq!(position_label(9, 27, 120, 30, &rows), "Ln 3/4 Col AB (28/30)");
+}
+
+#[test]
+fn test_canvas_scrolls_to_end_of_wrapped_text() {
+    let mut canvas = Canvas::new();
+    canvas.add_text(format!("{}END", "word ".repeat(80)));
+    canvas.show();
+    canvas.scroll_down(100);
+
+    let mut terminal = Terminal::new(TestBackend::new(40, 14)).unwrap();
+    terminal.draw(|frame| canvas.render(frame, frame.size())).unwrap();
+    let buffer = terminal.backend().buffer();
+    let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+    assert!(text.contains("END"));
+}
@@ -11,10 +11,94 @@ use std::cell::RefCell;
 use std::cmp;
 
 use crate::app::App;
-use crate::numeric::format::format_display;
+use crate::numeric::format::{format_display, group_thousands};
 use crate::mode::Mode;
-use crate::util::letters_from_col;
+use crate::util::{ColumnType, display_width, letters_from_col};
 use crate::table::rowmanager::RowManager;
+use crate::table::tableview::TableView;
+use crate::table::operations::cells_differ;
+
+/// Horizontal alignment of cell text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellAlign {
+    Left,
+    Right,
+    Auto,  // Right-align numeric columns, left-align the rest
+}
+
+impl CellAlign {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CellAlign::Left => "left",
+            CellAlign::Right => "right",
+            CellAlign::Auto => "auto",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(CellAlign::Left),
+            "right" => Some(CellAlign::Right),
+            "auto" => Some(CellAlign::Auto),
+            _ => None,
+        }
+    }
+
+    /// Whether a column of the given type is drawn right-aligned
+    pub fn is_right(&self, column_type: ColumnType) -> bool {
+        match self {
+            CellAlign::Left => false,
+            CellAlign::Right => true,
+            CellAlign::Auto => column_type == ColumnType::Numeric,
+        }
+    }
+}
+
+/// Pad cell text to a column's display width, leaving a one-space right margin
+/// Right-aligned text is padded on the left, everything else on the right
+pub fn pad_cell(content: &str, col_width: u16, right: bool) -> String {
+    let pad = (col_width as usize).saturating_sub(1).saturating_sub(display_width(content));
+    if right {
+        format!("{}{}", " ".repeat(pad), content)
+    } else {
+        format!("{}{}", content, " ".repeat(pad))
+    }
+}
+
+/// Split a padded cell into the text before, inside and after the byte range of a match
+/// in its content; None if the range doesn't fit the content
+pub fn split_match<'a>(padded: &'a str, content: &str, right: bool, range: std::ops::Range<usize>) -> Option<(&'a str, &'a str, &'a str)> {
+    content.get(range.clone())?;
+    let offset = if right { padded.len().checked_sub(content.len())? } else { 0 };
+    let (start, end) = (offset + range.start, offset + range.end);
+    Some((padded.get(..start)?, padded.get(start..end)?, padded.get(end..)?))
+}
+
+/// Gutter label for a row: its 1-based number, or with relative numbering how many
+/// live rows it is from the cursor (the cursor row and the header row keep their numbers)
+pub fn row_number_label(row_idx: usize, cursor_row: usize, relative: bool, is_header_row: bool, row_manager: &RowManager) -> String {
+    if !relative || row_idx == cursor_row || is_header_row {
+        return (row_idx + 1).to_string();
+    }
+    let (low, high) = (row_idx.min(cursor_row), row_idx.max(cursor_row));
+    (low + 1..=high).filter(|&r| row_manager.is_row_live(r)).count().to_string()
+}
+
+/// Cursor position for the status bar, e.g. "Ln 3/120 Col B (2/5)"
+/// In filtered views rows are counted among the live rows only
+pub fn position_label(cursor_row: usize, cursor_col: usize, row_count: usize, col_count: usize, row_manager: &RowManager) -> String {
+    let (row_ordinal, live_rows) = if row_manager.is_filtered {
+        // active_rows is kept in row order
+        let ordinal = row_manager.active_rows.partition_point(|&r| r <= cursor_row);
+        (ordinal, row_manager.active_rows.len())
+    } else {
+        (cursor_row + 1, row_count)
+    };
+    format!(
+        "Ln {}/{} Col {} ({}/{})",
+        row_ordinal, live_rows, letters_from_col(cursor_col), cursor_col + 1, col_count
+    )
+}
 
 pub fn render(frame: &mut Frame, app: &mut App, row_manager: Rc<RefCell<RowManager>>) {
     // Apply background color if set
@@ -57,40 +141,44 @@ fn render_table(frame: &mut Frame, app: &mut App, area: Rect, row_manager: Rc<Re
     let visible_rows = available_height as usize;
 
     // Calculate how many columns fit in available width
-    let mut total_width = 0u16;
-    let mut visible_cols = 0usize;
-    for col in app.view_state.view.viewport_col..col_count {
-        let col_width = app.table.col_widths().get(col).copied().unwrap_or(3);
-        let cell_width = col_width as u16 + 2; // padding
-        if total_width + cell_width > available_width && visible_cols > 0 {
-            break;
-        }
-        total_width += cell_width;
-        visible_cols += 1;
-    }
-    app.view_state.view.viewport_width = visible_cols;
+    let table_col_widths = app.table.col_widths();
+    app.view_state.view.viewport_width = app.view_state.view.visible_columns(&table_col_widths, available_width).len();
 
     // Ensure cursor is visible
     app.view_state.view.scroll_to_cursor();
 
+    // Lay out visible columns (frozen columns first) now that the viewport is settled
+    let visible: Vec<usize> = app.view_state.view.visible_columns(&table_col_widths, available_width)
+        .into_iter()
+        .map(|(col, _)| col)
+        .collect();
+    let visible_cols = visible.len();
+    let end_col = visible.last().map(|&c| c + 1).unwrap_or(0);
+
     // Calculate column widths for visible columns
     let mut col_widths: Vec<Constraint> = Vec::with_capacity(visible_cols + 1);
     col_widths.push(Constraint::Length(row_num_width as u16 + 1)); // Row number column
 
-    let end_col = (app.view_state.view.viewport_col + visible_cols).min(col_count);
-    for col in app.view_state.view.viewport_col..end_col {
-        let w = app.table.col_widths().get(col).copied().unwrap_or(3);
-        let header_w = letters_from_col(col).len();
-        col_widths.push(Constraint::Length(w.max(header_w) as u16 + 2));
+    for &col in &visible {
+        col_widths.push(Constraint::Length(TableView::display_width(&table_col_widths, col)));
     }
 
+    // Which visible columns are drawn right-aligned (probed once per frame)
+    let right_aligned: Vec<bool> = visible.iter()
+        .map(|&col| app.view_state.align.is_right(app.table.column_type(col, app.header_mode)))
+        .collect();
+    // Which get thousands separators; years, ZIP codes and IDs are left alone
+    let grouped: Vec<bool> = visible.iter()
+        .map(|&col| app.view_state.thousands && !app.table.looks_like_identifiers(col, app.header_mode))
+        .collect();
+
     // Build header row with column letters
     let header_style = app.view_state.style.header_col();
 
     let mut header_cells: Vec<Cell> = Vec::with_capacity(visible_cols + 1);
     header_cells.push(Cell::from("").style(header_style)); // Empty corner cell
 
-    for col in app.view_state.view.viewport_col..end_col {
+    for &col in &visible {
         let letter = letters_from_col(col);
         let style = if col == app.view_state.view.cursor_col {
             app.view_state.style.row_number_cursor()
@@ -111,12 +199,17 @@ fn render_table(frame: &mut Frame, app: &mut App, area: Rect, row_manager: Rc<Re
 
     let mut end_row = 0;
 
+    // Formulas re-evaluated after an edit show their values while nothing else has changed
+    let formula_cache = app.formula_cache.as_ref().filter(|cache| cache.edits == app.edits);
+
     // Build data rows (only visible ones)
     let rows: Vec<Row> = selected_indices
         .map(|row_idx| {
             end_row = row_idx;
 
             let is_header_row = app.header_mode && row_idx == 0;
+            let is_diff_row = !is_header_row
+                && app.diff_cols.is_some_and(|(a, b)| cells_differ(&app.table, row_idx, a, b));
 
             let mut cells: Vec<Cell> = Vec::with_capacity(visible_cols + 1);
 
@@ -126,31 +219,40 @@ fn render_table(frame: &mut Frame, app: &mut App, area: Rect, row_manager: Rc<Re
             } else {
                 app.view_state.style.row_number()
             };
-            cells.push(Cell::from(format!("{}", row_idx + 1)).style(row_num_style));
+            let label = row_number_label(row_idx, app.view_state.view.cursor_row, app.relativenumber, is_header_row, &row_manager.borrow());
+            cells.push(Cell::from(label).style(row_num_style));
+
+            // The text :s///c is asking about in this row, if any
+            let confirm_match = app.confirm_match()
+                .filter(|m| app.mode == Mode::Confirm && m.row == row_idx);
 
             // Data cells (only visible columns)
-            for col_idx in app.view_state.view.viewport_col..end_col {
-                let raw_content = app.table.get_cell(row_idx, col_idx)
-                    .map(|s| s.as_str())
+            for ((&col_idx, &right), &group) in visible.iter().zip(&right_aligned).zip(&grouped) {
+                let raw_content = formula_cache.and_then(|cache| cache.value(row_idx, col_idx))
+                    .or_else(|| app.table.get_cell(row_idx, col_idx).map(|s| s.as_str()))
                     .unwrap_or("");
                 // Apply precision formatting for display
                 let content = format_display(raw_content, app.view_state.precision);
+                let content = if group && !is_header_row { group_thousands(&content) } else { content };
 
                 let is_cursor = row_idx == app.view_state.view.cursor_row && col_idx == app.view_state.view.cursor_col;
                 let is_selected = matches!(app.mode, Mode::Visual | Mode::VisualCol | Mode::VisualRow)
                     && app.view_state.view.is_selected(row_idx, col_idx, app.mode);
 
                 // Check if this cell matches the search pattern
-                let is_search_match = app.search_pattern()
-                    .map(|p| content.to_lowercase().contains(&p.to_lowercase()))
-                    .unwrap_or(false);
+                let is_search_match = app.is_search_match(col_idx, raw_content);
+                let is_current_match = is_search_match && app.is_current_match(row_idx, col_idx);
 
                 let style = if is_cursor {
                     app.view_state.style.cell_cursor()
                 } else if is_selected {
                     app.view_state.style.cell_selection()
+                } else if is_current_match {
+                    app.view_state.style.cell_match_current()
                 } else if is_search_match {
                     app.view_state.style.cell_match()
+                } else if is_diff_row {
+                    app.view_state.style.cell_diff()
                 } else if is_header_row {
                     app.view_state.style.header_row()
                 } else {
@@ -195,7 +297,21 @@ fn render_table(frame: &mut Frame, app: &mut App, area: Rect, row_manager: Rc<Re
                     ];
                     Line::from(spans)
                 } else {
-                    Line::from(vec![Span::raw(content.to_string())])
+                    let col_width = TableView::display_width(&table_col_widths, col_idx);
+                    let right = right && !is_header_row;
+                    let padded = pad_cell(&content, col_width, right);
+                    // Match ranges index the stored text, so only highlight when it is shown as is
+                    let split = confirm_match
+                        .filter(|m| m.col == col_idx && app.table.get_cell(row_idx, col_idx) == Some(&content))
+                        .and_then(|m| split_match(&padded, &content, right, m.range.clone()));
+                    match split {
+                        Some((before, found, after)) => Line::from(vec![
+                            Span::raw(before.to_string()),
+                            Span::styled(found.to_string(), app.view_state.style.cell_match_current()),
+                            Span::raw(after.to_string()),
+                        ]),
+                        None => Line::from(vec![Span::raw(padded)]),
+                    }
                 };
 
                 cells.push(Cell::from(display_content).style(style));
@@ -244,17 +360,40 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
 
     let filter_status = if row_manager.is_filtered { row_manager.filter_string.clone() } else { String::new() };
 
-    let file_name = app
-        .file_io
-        .file_path
-        .as_ref()
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_else(|| "[No File]".to_string());
+    let mut hidden: Vec<usize> = app.view_state.view.hidden_cols.iter().copied().collect();
+    hidden.sort_unstable();
+    let hidden_status = if hidden.is_empty() {
+        String::new()
+    } else {
+        let letters: Vec<String> = hidden.into_iter().map(letters_from_col).collect();
+        format!("Hidden: {} ", letters.join(","))
+    };
+
+    let partial_status = if app.file_io.is_partial() {
+        format!("showing {} of ? rows (partial) ", app.table.row_count())
+    } else {
+        String::new()
+    };
+
+    let file_name = if app.buffer_count() > 1 {
+        app.buffer_label()
+    } else {
+        app.file_io
+            .file_path
+            .as_ref()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| "[No File]".to_string())
+    };
 
     let position = format!(
-        "{}{} ",
-        letters_from_col(app.view_state.view.cursor_col),
-        app.view_state.view.cursor_row + 1
+        "{} ",
+        position_label(
+            app.view_state.view.cursor_row,
+            app.view_state.view.cursor_col,
+            app.table.row_count(),
+            app.table.col_count(),
+            &row_manager,
+        )
     );
 
     let key_buffer = app.key_buffer_display();
@@ -264,24 +403,34 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         format!("{} ", key_buffer)
     };
 
-    let right_side_len = position.len() + key_buffer_display.len();
+    let selection_display = app.view_state.view.selection_summary(app.mode)
+        .map(|summary| format!("{} ", summary))
+        .unwrap_or_default();
+
+    let mode_label = if app.mode == Mode::Insert && app.insert_handler.append {
+        " APPEND ".to_string()
+    } else {
+        format!(" {} ", app.mode.display_name())
+    };
+
+    // Pad between the two sides so the position segment stays pinned to the right edge
+    let left_side_len = display_width(&mode_label) + display_width(&file_name) + display_width(&filter_status)
+        + display_width(&hidden_status) + display_width(&partial_status) + dirty_indicator.len() + 3;
+    let right_side_len = display_width(&position) + display_width(&key_buffer_display) + display_width(&selection_display);
+    let padding = (area.width as usize).saturating_sub(left_side_len + right_side_len).max(1);
 
     let status = Line::from(vec![
-        Span::styled(
-            format!(" {} ", app.mode.display_name()),
-            mode_style,
-        ),
+        Span::styled(mode_label, mode_style),
         Span::raw(" "),
         Span::raw(file_name),
         Span::raw(" "),
         Span::styled(filter_status, app.view_state.style.filter_status()),
         Span::raw(" "),
+        Span::styled(hidden_status, app.view_state.style.filter_status()),
+        Span::styled(partial_status, app.view_state.style.message_error()),
         Span::styled(dirty_indicator, app.view_state.style.message_error()),
-        Span::raw(" ".repeat(
-            area.width
-                .saturating_sub(30)
-                .saturating_sub(right_side_len as u16) as usize,
-        )),
+        Span::raw(" ".repeat(padding)),
+        Span::raw(selection_display),
         Span::styled(key_buffer_display, app.view_state.style.status_mode(&app.mode)),
         Span::raw(position),
     ]);
@@ -308,6 +457,17 @@ fn render_command_line(frame: &mut Frame, app: &App, area: Rect) {
             ]);
             (line, app.view_state.style.command_line())
         }
+        Mode::Prompt => {
+            let line = Line::from(vec![
+                Span::styled(format!("{} ", app.prompt_question()), app.view_state.style.command_prompt()),
+                Span::styled(app.command_buffer(), app.view_state.style.command_line()),
+            ]);
+            (line, app.view_state.style.command_line())
+        }
+        Mode::Confirm => {
+            let line = Line::from(Span::styled(app.confirm_question(), app.view_state.style.command_prompt()));
+            (line, app.view_state.style.command_line())
+        }
         _ => {
             // Check for active progress first
             if let Some((ref op_name, ref progress)) = app.view_state.progress {
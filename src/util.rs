@@ -1,8 +1,10 @@
 use regex::Regex;
 use std::num::ParseIntError;
 use std::fmt;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct CellRef {
     pub row: usize,
     pub col: usize,
@@ -32,6 +34,29 @@ impl fmt::Display for CalcError {
 pub enum ColumnType {
     Numeric,
     Text,
+    Natural,  // Text with embedded numbers compared by value ("file2" < "file10")
+    Date,     // Dates compared chronologically (see format::parse_date)
+}
+
+impl ColumnType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColumnType::Numeric => "numeric",
+            ColumnType::Text => "text",
+            ColumnType::Natural => "natural",
+            ColumnType::Date => "date",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "numeric" | "number" => Some(ColumnType::Numeric),
+            "text" => Some(ColumnType::Text),
+            "natural" => Some(ColumnType::Natural),
+            "date" => Some(ColumnType::Date),
+            _ => None,
+        }
+    }
 }
 
 impl From<ParseIntError> for CalcError {
@@ -60,6 +85,18 @@ pub fn col_from_letters(letters: &str) -> usize {
     result - 1
 }
 
+/// Parse user-typed column letters, rejecting anything that isn't purely alphabetic
+/// Inverse of letters_from_col, so ":col C" and filter labels agree on naming
+pub fn parse_col_letters(letters: &str) -> Option<usize> {
+    if letters.is_empty() || !letters.bytes().all(is_ascii_alpha) {
+        return None;
+    }
+    // Checked so a long run of letters is rejected instead of overflowing
+    letters.bytes().try_fold(0usize, |acc, b| {
+        acc.checked_mul(26)?.checked_add((b.to_ascii_uppercase() - b'A' + 1) as usize)
+    }).map(|n| n - 1)
+}
+
 pub fn letters_from_col(mut col: usize) -> String {
     col += 1;
     let mut buf = Vec::new();
@@ -166,6 +203,14 @@ pub fn parse_cell_ref(s: &str) -> Option<CellRef> {
     Some(CellRef { row: row - 1, col }) // Convert to 0-indexed
 }
 
+/// RNG for shuffling and sampling: reproducible with a seed, seeded from entropy otherwise
+pub fn seeded_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
 // === Unicode utilities ===
 
 use unicode_width::UnicodeWidthStr;
@@ -236,6 +281,18 @@ mod tests {
         assert_eq!(col_from_letters("AAB"), 703);
     }
 
+    #[test]
+    fn test_parse_col_letters() {
+        assert_eq!(parse_col_letters("C"), Some(2));
+        assert_eq!(parse_col_letters("aa"), Some(26));
+        assert_eq!(parse_col_letters(&letters_from_col(730)), Some(730));
+        assert_eq!(parse_col_letters("3A"), None);
+        assert_eq!(parse_col_letters("A3"), None);
+        assert_eq!(parse_col_letters(""), None);
+        assert_eq!(parse_col_letters("ZZZZZZZZZZZZZZ"), None);
+        assert_eq!(parse_col_letters(&"A".repeat(40)), None);
+    }
+
     // === letters_from_col tests ===
 
     #[test]
@@ -1,6 +1,8 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::thread::JoinHandle;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver};
 
 use crate::table::tableview::TableView;
@@ -13,6 +15,7 @@ use crate::util::ColumnType;
 use crate::transaction::transaction::Transaction;
 use crate::ui::canvas::Canvas;
 use crate::ui::style::Style;
+use crate::ui::ui::CellAlign;
 
 
 /// Result from a background operation
@@ -21,8 +24,21 @@ pub enum BackgroundResult {
         permutation: Vec<usize>,
         direction: SortDirection,
         sort_type: ColumnType,
+        key_count: usize,  // Number of sort keys, more than one for multi-column sorts
         is_column_sort: bool,
     },
+    SaveComplete {
+        result: Result<(), String>,
+        edits: u64,  // App::edits when the save started
+    },
+}
+
+/// What a finished background operation means for the buffer
+#[derive(Debug, PartialEq)]
+pub enum BackgroundOutcome {
+    Unchanged,
+    Changed,  // The table was changed and the change recorded as the latest undo step
+    Saved { edits: u64 },  // The file was written with the table as of `edits`
 }
 
 /// Pending operation to be executed after the next render
@@ -31,6 +47,8 @@ pub enum PendingOp {
     Undo,
     Redo,
     Calc { formula_count: usize },
+    ColFormula { col: usize, template: String },
+    Paste { txn: Transaction, message: Option<String> },
 }
 
 pub struct ViewState {
@@ -38,6 +56,8 @@ pub struct ViewState {
     pub style: Style,
     pub row_manager: Rc<RefCell<RowManager>>,
     pub precision: Option<usize>,  // Display precision for numbers (None = auto)
+    pub align: CellAlign,  // Horizontal alignment of cell text
+    pub thousands: bool,  // Show thousands separators in numbers (display only)
     pub canvas: Canvas,  // Canvas overlay for displaying text/images
     pub progress: Option<(String, Progress)>,  // Optional progress indicator (operation name, progress)
     pub(crate) pending_op: Option<PendingOp>,  // Pending operation to execute after next render
@@ -45,6 +65,7 @@ pub struct ViewState {
     pub(crate) bg_receiver: Option<Receiver<BackgroundResult>>,
     #[allow(dead_code)]
     pub(crate) bg_handle: Option<JoinHandle<()>>,
+    pub(crate) bg_cancel: Option<Arc<AtomicBool>>,  // Set to ask the background thread to stop
 
     pub message: Option<String>
 }
@@ -60,10 +81,13 @@ impl ViewState {
             row_manager,
             canvas: Canvas::new(),
             precision: None,
+            align: CellAlign::Auto,
+            thousands: false,
             progress: None,
             pending_op: None,
             bg_receiver: None,
             bg_handle: None,
+            bg_cancel: None,
             message: None
         }
     }
@@ -80,14 +104,42 @@ impl ViewState {
         self.progress = None;
     }
 
+    /// Ask the running background operation to stop and drop its result
+    /// Returns "Sort cancelled", or None if nothing cancellable was running
+    /// (only sorts can be cancelled; saves run to completion)
+    pub fn cancel_background(&mut self) -> Option<String> {
+        self.bg_receiver.as_ref()?;
+        self.bg_cancel.take()?.store(true, Ordering::Relaxed);
+        self.bg_receiver = None;
+        self.bg_handle = None;
+        self.progress = None;
+        Some("Sort cancelled".to_string())
+    }
+
+    /// Whether a background operation is still running
+    pub fn background_busy(&self) -> bool {
+        self.bg_receiver.is_some()
+    }
+
+    /// Block until a running save has written the file
+    /// Cancellable operations (sorts) are simply abandoned
+    pub fn wait_for_background_save(&mut self) {
+        if self.bg_cancel.is_none() {
+            if let Some(handle) = self.bg_handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
     /// Check for and handle completed background operations
-    pub fn poll_background_result(&mut self, table: &mut Table, history: &mut History) -> (Option<String>, bool) {
+    pub fn poll_background_result(&mut self, table: &mut Table, history: &mut History) -> (Option<String>, BackgroundOutcome) {
         if let Some(ref receiver) = self.bg_receiver {
             match receiver.try_recv() {
                 Ok(result) => {
                     let output = self.handle_background_result(result, table, history);
                     self.bg_receiver = None;
                     self.bg_handle = None;
+                    self.bg_cancel = None;
                     self.clear_progress();
 
                     return output;
@@ -99,21 +151,28 @@ impl ViewState {
                     // Thread died unexpectedly
                     self.bg_receiver = None;
                     self.bg_handle = None;
+                    self.bg_cancel = None;
                     self.clear_progress();
-                    return (Some("Operation failed".to_string()), false);
+                    return (Some("Operation failed".to_string()), BackgroundOutcome::Unchanged);
                 }
             }
         }
-        (None, false)
+        (None, BackgroundOutcome::Unchanged)
     }
 
     /// Handle a completed background operation
-    pub fn handle_background_result(&mut self, result: BackgroundResult, table: &mut Table, history: &mut History) -> (Option<String>, bool) {
+    pub fn handle_background_result(&mut self, result: BackgroundResult, table: &mut Table, history: &mut History) -> (Option<String>, BackgroundOutcome) {
         match result {
-            BackgroundResult::SortComplete { permutation, direction, sort_type, is_column_sort } => {
+            BackgroundResult::SaveComplete { result: Ok(()), edits } => {
+                (None, BackgroundOutcome::Saved { edits })
+            }
+            BackgroundResult::SaveComplete { result: Err(e), .. } => {
+                (Some(format!("Error saving: {}", e)), BackgroundOutcome::Unchanged)
+            }
+            BackgroundResult::SortComplete { permutation, direction, sort_type, key_count, is_column_sort } => {
                 // Empty permutation means already sorted
                 if permutation.is_empty() {
-                    return (Some("Already sorted".to_string()), false);
+                    return (Some("Already sorted".to_string()), BackgroundOutcome::Unchanged);
                 }
 
                 if is_column_sort {
@@ -126,18 +185,18 @@ impl ViewState {
                     history.record(txn);
                 }
 
-                let type_str = match sort_type {
-                    ColumnType::Numeric => "numeric",
-                    ColumnType::Text => "text",
-                };
+                let type_str = sort_type.name();
                 let dir_str = match direction {
                     SortDirection::Ascending => "ascending",
                     SortDirection::Descending => "descending",
                 };
+                if key_count > 1 {
+                    return (Some(format!("Sorted by {} columns", key_count)), BackgroundOutcome::Changed);
+                }
                 if is_column_sort {
-                    return (Some(format!("Columns sorted {} ({})", dir_str, type_str)), true);
+                    return (Some(format!("Columns sorted {} ({})", dir_str, type_str)), BackgroundOutcome::Changed);
                 } else {
-                    return (Some(format!("Sorted {} ({})", dir_str, type_str)), true);
+                    return (Some(format!("Sorted {} ({})", dir_str, type_str)), BackgroundOutcome::Changed);
                 }
             }
         }